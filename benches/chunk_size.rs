@@ -1,9 +1,8 @@
 #![allow(missing_docs)]
 
-use std::{fs, path::PathBuf, sync::LazyLock};
+use std::{fs, sync::LazyLock};
 
 use ahash::AHashMap;
-use cached_path::Cache;
 use divan::AllocProfiler;
 
 #[global_allocator]
@@ -18,12 +17,13 @@ fn main() {
 
 /// Downloads a remote file to the cache directory if it doensn't already exist,
 /// and returns the path to the cached file.
-fn download_file_to_cache(src: &str) -> PathBuf {
+#[cfg(feature = "rust-tokenizers")]
+fn download_file_to_cache(src: &str) -> std::path::PathBuf {
     let mut cache_dir = dirs::home_dir().unwrap();
     cache_dir.push(".cache");
     cache_dir.push(".text-splitter");
 
-    Cache::builder()
+    cached_path::Cache::builder()
         .dir(cache_dir)
         .build()
         .unwrap()
@@ -121,6 +121,60 @@ mod text {
     }
 }
 
+#[divan::bench_group]
+mod parse {
+    use divan::{black_box_drop, counter::BytesCount, Bencher};
+    use text_splitter::TextSplitter;
+
+    use crate::{FILES, TEXT_FILENAMES};
+
+    /// Compare with and without the `simd` feature
+    #[divan::bench(args = TEXT_FILENAMES)]
+    fn line_breaks(bencher: Bencher<'_, '_>, filename: &str) {
+        // Large enough that the text is only parsed and measured
+        let splitter = TextSplitter::new(usize::MAX);
+        bencher
+            .with_inputs(|| FILES.get(filename).unwrap().repeat(10))
+            .input_counter(BytesCount::of_str)
+            .bench_refs(|text| splitter.chunks(text).for_each(black_box_drop));
+    }
+}
+
+/// Number of repetitions for adversarial inputs, to compare how they scale
+const REPETITIONS: [usize; 3] = [1_000, 10_000, 100_000];
+
+#[divan::bench_group]
+mod adversarial {
+    use divan::{black_box_drop, counter::BytesCount, Bencher};
+    use text_splitter::TextSplitter;
+
+    use crate::REPETITIONS;
+
+    /// Many identical short lines, with lots of ranges of a single level
+    #[divan::bench(args = REPETITIONS)]
+    fn identical_lines(bencher: Bencher<'_, '_>, n: usize) {
+        let splitter = TextSplitter::new(200);
+        bencher
+            .with_inputs(|| "abc def\n".repeat(n))
+            .input_counter(BytesCount::of_str)
+            .bench_refs(|text| splitter.chunks(text).for_each(black_box_drop));
+    }
+
+    /// Many paragraphs before a single heading, which is a level that is
+    /// much larger than the chunks
+    #[cfg(feature = "markdown")]
+    #[divan::bench(args = REPETITIONS)]
+    fn paragraphs_before_one_heading(bencher: Bencher<'_, '_>, n: usize) {
+        use text_splitter::MarkdownSplitter;
+
+        let splitter = MarkdownSplitter::new(200);
+        bencher
+            .with_inputs(|| format!("{}# Heading\n\nend", "abc def\n\n".repeat(n)))
+            .input_counter(BytesCount::of_str)
+            .bench_refs(|text| splitter.chunks(text).for_each(black_box_drop));
+    }
+}
+
 #[cfg(feature = "markdown")]
 #[divan::bench_group]
 mod markdown {
@@ -205,6 +259,33 @@ mod code {
         });
     }
 
+    /// Generated code with deeply nested array literals, where every level
+    /// of nesting is its own semantic level
+    fn nested_code() -> String {
+        let literal = (0..200).fold("1".to_string(), |inner, i| format!("[{inner}, {i}]"));
+        format!("let data = {literal};\n").repeat(50)
+    }
+
+    #[divan::bench(consts = CHUNK_SIZES)]
+    fn nested_literals<const N: usize>(bencher: Bencher<'_, '_>) {
+        let splitter = CodeSplitter::new(tree_sitter_rust::LANGUAGE, N).unwrap();
+        bencher
+            .with_inputs(nested_code)
+            .input_counter(BytesCount::of_str)
+            .bench_refs(|text| splitter.chunks(text).for_each(black_box_drop));
+    }
+
+    #[divan::bench(consts = CHUNK_SIZES)]
+    fn nested_literals_with_max_depth<const N: usize>(bencher: Bencher<'_, '_>) {
+        let splitter = CodeSplitter::new(tree_sitter_rust::LANGUAGE, N)
+            .unwrap()
+            .with_max_depth(8);
+        bencher
+            .with_inputs(nested_code)
+            .input_counter(BytesCount::of_str)
+            .bench_refs(|text| splitter.chunks(text).for_each(black_box_drop));
+    }
+
     #[cfg(feature = "tiktoken-rs")]
     #[divan::bench(args = CODE_FILENAMES, consts = CHUNK_SIZES)]
     fn tiktoken<const N: usize>(bencher: Bencher<'_, '_>, filename: &str) {
@@ -244,3 +325,42 @@ mod code {
         });
     }
 }
+
+#[cfg(feature = "baseline")]
+#[divan::bench_group]
+mod baseline {
+    use divan::{black_box_drop, counter::BytesCount, Bencher};
+    use text_splitter::baseline::{FixedSizeSplitter, RecursiveCharacterSplitter};
+
+    use crate::{CHUNK_SIZES, FILES, TEXT_FILENAMES};
+
+    #[divan::bench(args = TEXT_FILENAMES, consts = CHUNK_SIZES)]
+    fn fixed_size<const N: usize>(bencher: Bencher<'_, '_>, filename: &str) {
+        bencher
+            .with_inputs(|| {
+                (
+                    FixedSizeSplitter::new(N),
+                    FILES.get(filename).unwrap().clone(),
+                )
+            })
+            .input_counter(|(_, text)| BytesCount::of_str(text))
+            .bench_values(|(splitter, text)| {
+                splitter.chunks(&text).for_each(black_box_drop);
+            });
+    }
+
+    #[divan::bench(args = TEXT_FILENAMES, consts = CHUNK_SIZES)]
+    fn recursive_character<const N: usize>(bencher: Bencher<'_, '_>, filename: &str) {
+        bencher
+            .with_inputs(|| {
+                (
+                    RecursiveCharacterSplitter::new(N),
+                    FILES.get(filename).unwrap().clone(),
+                )
+            })
+            .input_counter(|(_, text)| BytesCount::of_str(text))
+            .bench_values(|(splitter, text)| {
+                splitter.chunks(&text).for_each(black_box_drop);
+            });
+    }
+}
@@ -1,4 +1,28 @@
-from typing import Callable, List, Tuple, Union, final
+from os import PathLike
+from types import TracebackType
+from typing import IO, Any, Callable, Dict, Iterator, List, Optional, Tuple, Type, Union, final
+
+# Capacity of a chunk, either a max size, or a `(desired, max)` range of sizes.
+_Capacity = Union[int, Tuple[int, int]]
+
+@final
+class ChunkFileIterator:
+    """Iterator over the chunks of a file, returned by `TextSplitter.chunk_file`. The file is read a block at a time, and only the text that hasn't been chunked yet is kept in memory.
+
+    Can be used as a context manager to close the file once done, even if not all chunks were consumed.
+    """
+
+    def __iter__(self) -> Iterator[str]: ...
+    def __next__(self) -> str: ...
+    def __enter__(self) -> ChunkFileIterator: ...
+    def __exit__(
+        self,
+        exc_type: Optional[Type[BaseException]],
+        exc_value: Optional[BaseException],
+        traceback: Optional[TracebackType],
+    ) -> bool: ...
+    def close(self) -> None:
+        """Stop reading the file, and close it if it was opened from a path. File objects passed in are left open."""
 
 @final
 class TextSplitter:
@@ -88,12 +112,12 @@ class TextSplitter:
     """
 
     def __init__(
-        self, capacity: Union[int, Tuple[int, int]], overlap: int = 0, trim: bool = True
+        self, capacity: _Capacity, overlap: int = 0, trim: bool = True
     ) -> None: ...
     @staticmethod
     def from_huggingface_tokenizer(
         tokenizer,
-        capacity: Union[int, Tuple[int, int]],
+        capacity: _Capacity,
         overlap: int = 0,
         trim: bool = True,
     ) -> TextSplitter:
@@ -120,7 +144,7 @@ class TextSplitter:
     @staticmethod
     def from_huggingface_tokenizer_str(
         json: str,
-        capacity: Union[int, Tuple[int, int]],
+        capacity: _Capacity,
         overlap: int = 0,
         trim: bool = True,
     ) -> TextSplitter:
@@ -147,7 +171,7 @@ class TextSplitter:
     @staticmethod
     def from_huggingface_tokenizer_file(
         path: str,
-        capacity: Union[int, Tuple[int, int]],
+        capacity: _Capacity,
         overlap: int = 0,
         trim: bool = True,
     ) -> TextSplitter:
@@ -174,7 +198,7 @@ class TextSplitter:
     @staticmethod
     def from_tiktoken_model(
         model: str,
-        capacity: Union[int, Tuple[int, int]],
+        capacity: _Capacity,
         overlap: int = 0,
         trim: bool = True,
     ) -> TextSplitter:
@@ -200,7 +224,7 @@ class TextSplitter:
     @staticmethod
     def from_callback(
         callback: Callable[[str], int],
-        capacity: Union[int, Tuple[int, int]],
+        capacity: _Capacity,
         overlap: int = 0,
         trim: bool = True,
     ) -> TextSplitter:
@@ -224,7 +248,12 @@ class TextSplitter:
             The new text splitter
         """
 
-    def chunks(self, text: str) -> List[str]:
+    def chunks(
+        self,
+        text: str,
+        cancel: Optional[Callable[[], bool]] = None,
+        on_progress: Optional[Callable[[int, int, int], None]] = None,
+    ) -> List[str]:
         """Generate a list of chunks from a given text. Each chunk will be up to the `capacity`.
 
 
@@ -246,21 +275,39 @@ class TextSplitter:
 
         Splitting doesn't occur below the character level, otherwise you could get partial bytes of a char, which may not be a valid unicode str.
 
+        Chunking can be interrupted with Ctrl+C, which raises a `KeyboardInterrupt`.
+
         Args:
             text (str): Text to split.
+            cancel (Callable[[], bool], optional): Called regularly while chunking, such as
+                `threading.Event.is_set`. Once it returns `True`, chunking stops and a
+                `concurrent.futures.CancelledError` is raised.
+            on_progress (Callable[[int, int, int], None], optional): Called after each chunk with
+                the number of bytes of the UTF-8 encoded text processed so far, the total number
+                of bytes, and the number of chunks generated so far, such as for a progress bar.
+                Chunking stops if it raises an exception.
 
         Returns:
             A list of strings, one for each chunk. If `trim` was specified in the text
             splitter, then each chunk will already be trimmed as well.
         """
 
-    def chunk_indices(self, text: str) -> List[Tuple[int, str]]:
+    def chunk_indices(
+        self,
+        text: str,
+        cancel: Optional[Callable[[], bool]] = None,
+        on_progress: Optional[Callable[[int, int, int], None]] = None,
+    ) -> List[Tuple[int, str]]:
         """Generate a list of chunks from a given text, along with their character offsets in the original text. Each chunk will be up to the `capacity`.
 
         See `chunks` for more information.
 
         Args:
             text (str): Text to split.
+            cancel (Callable[[], bool], optional): Called regularly while chunking. Once it
+                returns `True`, chunking stops and a `concurrent.futures.CancelledError` is raised.
+            on_progress (Callable[[int, int, int], None], optional): Called after each chunk with
+                the bytes processed, total bytes, and number of chunks so far. See `chunks`.
 
         Returns:
             A list of tuples, one for each chunk. The first item will be the character offset relative
@@ -269,10 +316,30 @@ class TextSplitter:
             trimmed as well.
         """
 
+    def chunk_spans(self, text: str) -> List[Tuple[int, str, int]]:
+        """Generate a list of chunks from a given text, along with their character offsets in the original text and how much of each chunk overlaps with the previous one. Each chunk will be up to the `capacity`.
+
+        This is useful if `overlap` was specified, and you want to strip the duplicated prefix of each chunk when displaying results.
+
+        See `chunks` for more information.
+
+        Args:
+            text (str): Text to split.
+
+        Returns:
+            A list of tuples, one for each chunk. The first item will be the character offset relative
+            to the original text. The second item is the chunk itself. The third item is the number
+            of characters at the start of the chunk that are also part of the previous chunk.
+            If `trim` was specified in the text splitter, then each chunk will already be
+            trimmed as well.
+        """
+
     def chunk_all(self, texts: List[str]) -> List[List[str]]:
         """
         Generate a list of chunks for a given set of texts. Each chunk will be up to the `capacity`.
 
+        The texts are split in parallel, and the GIL is released for the whole batch.
+
         See `chunks` for more information.
 
         Args:
@@ -288,6 +355,8 @@ class TextSplitter:
         """
         Generate a list of chunks for a given set of text, along with their character offsets in the original text. Each chunk will be up to the `capacity`.
 
+        The texts are split in parallel, and the GIL is released for the whole batch.
+
         See `chunks` for more information.
 
         Args:
@@ -301,6 +370,39 @@ class TextSplitter:
             trimmed as well.
         """
 
+    def chunk_file(
+        self,
+        path_or_fileobj: Union[str, PathLike, IO[str], IO[bytes]],
+        block_size: int = 1048576,
+    ) -> ChunkFileIterator:
+        """Generate chunks from a file lazily, without reading the whole file into memory. Each chunk will be up to the `capacity`.
+
+        The file is read in blocks, and only the text that hasn't been chunked yet is kept in memory, so this can be used for very large files. The chunks are the same as calling `chunks` on the contents of the whole file, unless a chunk would need more than a block of text to decide where it ends.
+
+        ```python
+        from semantic_text_splitter import TextSplitter
+
+        splitter = TextSplitter(1000)
+
+        with splitter.chunk_file("large_document.txt") as chunks:
+            for chunk in chunks:
+                print(chunk)
+        ```
+
+        See `chunks` for more information.
+
+        Args:
+            path_or_fileobj (str | os.PathLike | file object): Path of a UTF-8 encoded file, or an
+                open file object in text or binary mode. File objects are not closed.
+            block_size (int, optional): Number of bytes (or characters, for files in text mode)
+                to read at a time. Defaults to 1 MiB.
+
+        Returns:
+            An iterator of strings, one for each chunk, that can also be used as a context
+            manager to close the file. If `trim` was specified in the text splitter, then each
+            chunk will already be trimmed as well.
+        """
+
 @final
 class MarkdownSplitter:
     """Markdown splitter. Recursively splits chunks into the largest semantic units that fit within the chunk size. Also will attempt to merge neighboring chunks if they can fit within the given chunk size.
@@ -391,12 +493,12 @@ class MarkdownSplitter:
     """
 
     def __init__(
-        self, capacity: Union[int, Tuple[int, int]], overlap: int = 0, trim: bool = True
+        self, capacity: _Capacity, overlap: int = 0, trim: bool = True
     ) -> None: ...
     @staticmethod
     def from_huggingface_tokenizer(
         tokenizer,
-        capacity: Union[int, Tuple[int, int]],
+        capacity: _Capacity,
         overlap: int = 0,
         trim: bool = True,
     ) -> MarkdownSplitter:
@@ -423,7 +525,7 @@ class MarkdownSplitter:
     @staticmethod
     def from_huggingface_tokenizer_str(
         json: str,
-        capacity: Union[int, Tuple[int, int]],
+        capacity: _Capacity,
         overlap: int = 0,
         trim: bool = True,
     ) -> MarkdownSplitter:
@@ -450,7 +552,7 @@ class MarkdownSplitter:
     @staticmethod
     def from_huggingface_tokenizer_file(
         path: str,
-        capacity: Union[int, Tuple[int, int]],
+        capacity: _Capacity,
         overlap: int = 0,
         trim: bool = True,
     ) -> MarkdownSplitter:
@@ -477,7 +579,7 @@ class MarkdownSplitter:
     @staticmethod
     def from_tiktoken_model(
         model: str,
-        capacity: Union[int, Tuple[int, int]],
+        capacity: _Capacity,
         overlap: int = 0,
         trim: bool = True,
     ) -> MarkdownSplitter:
@@ -503,7 +605,7 @@ class MarkdownSplitter:
     @staticmethod
     def from_callback(
         callback: Callable[[str], int],
-        capacity: Union[int, Tuple[int, int]],
+        capacity: _Capacity,
         overlap: int = 0,
         trim: bool = True,
     ) -> MarkdownSplitter:
@@ -527,7 +629,12 @@ class MarkdownSplitter:
             The new markdown splitter
         """
 
-    def chunks(self, text: str) -> List[str]:
+    def chunks(
+        self,
+        text: str,
+        cancel: Optional[Callable[[], bool]] = None,
+        on_progress: Optional[Callable[[int, int, int], None]] = None,
+    ) -> List[str]:
         """Generate a list of chunks from a given text. Each chunk will be up to the `capacity`.
 
         ## Method
@@ -552,21 +659,39 @@ class MarkdownSplitter:
 
         Markdown is parsed according to the Commonmark spec, along with some optional features such as GitHub Flavored Markdown.
 
+        Chunking can be interrupted with Ctrl+C, which raises a `KeyboardInterrupt`.
+
         Args:
             text (str): Text to split.
+            cancel (Callable[[], bool], optional): Called regularly while chunking, such as
+                `threading.Event.is_set`. Once it returns `True`, chunking stops and a
+                `concurrent.futures.CancelledError` is raised.
+            on_progress (Callable[[int, int, int], None], optional): Called after each chunk with
+                the number of bytes of the UTF-8 encoded text processed so far, the total number
+                of bytes, and the number of chunks generated so far, such as for a progress bar.
+                Chunking stops if it raises an exception.
 
         Returns:
             A list of strings, one for each chunk. If `trim` was specified in the text
             splitter, then each chunk will already be trimmed as well.
         """
 
-    def chunk_indices(self, text: str) -> List[Tuple[int, str]]:
+    def chunk_indices(
+        self,
+        text: str,
+        cancel: Optional[Callable[[], bool]] = None,
+        on_progress: Optional[Callable[[int, int, int], None]] = None,
+    ) -> List[Tuple[int, str]]:
         """Generate a list of chunks from a given text, along with their character offsets in the original text. Each chunk will be up to the `capacity`.
 
         See `chunks` for more information.
 
         Args:
             text (str): Text to split.
+            cancel (Callable[[], bool], optional): Called regularly while chunking. Once it
+                returns `True`, chunking stops and a `concurrent.futures.CancelledError` is raised.
+            on_progress (Callable[[int, int, int], None], optional): Called after each chunk with
+                the bytes processed, total bytes, and number of chunks so far. See `chunks`.
 
         Returns:
             A list of tuples, one for each chunk. The first item will be the character offset relative
@@ -575,10 +700,30 @@ class MarkdownSplitter:
             trimmed as well.
         """
 
+    def chunk_spans(self, text: str) -> List[Tuple[int, str, int]]:
+        """Generate a list of chunks from a given text, along with their character offsets in the original text and how much of each chunk overlaps with the previous one. Each chunk will be up to the `capacity`.
+
+        This is useful if `overlap` was specified, and you want to strip the duplicated prefix of each chunk when displaying results.
+
+        See `chunks` for more information.
+
+        Args:
+            text (str): Text to split.
+
+        Returns:
+            A list of tuples, one for each chunk. The first item will be the character offset relative
+            to the original text. The second item is the chunk itself. The third item is the number
+            of characters at the start of the chunk that are also part of the previous chunk.
+            If `trim` was specified in the text splitter, then each chunk will already be
+            trimmed as well.
+        """
+
     def chunk_all(self, texts: List[str]) -> List[List[str]]:
         """
         Generate a list of chunks for a given set of texts. Each chunk will be up to the `capacity`.
 
+        The texts are split in parallel, and the GIL is released for the whole batch.
+
         See `chunks` for more information.
 
         Args:
@@ -594,6 +739,8 @@ class MarkdownSplitter:
         """
         Generate a list of chunks for a given set of text, along with their character offsets in the original text. Each chunk will be up to the `capacity`.
 
+        The texts are split in parallel, and the GIL is released for the whole batch.
+
         See `chunks` for more information.
 
         Args:
@@ -695,7 +842,8 @@ class CodeSplitter:
 
     Args:
         language (object): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
-            to use for parsing the code.
+            to use for parsing the code, such as `tree_sitter_python.language()` or a
+            `tree_sitter.Language` from py-tree-sitter 0.22 to 0.25.
         capacity (int | (int, int)): The capacity of characters in each chunk. If a
             single int, then chunks will be filled up as much as possible, without going over
             that number. If a tuple of two integers is provided, a chunk will be considered
@@ -711,23 +859,39 @@ class CodeSplitter:
     def __init__(
         self,
         language: object,
-        capacity: Union[int, Tuple[int, int]],
+        capacity: _Capacity,
         overlap: int = 0,
         trim: bool = True,
     ) -> None: ...
     @staticmethod
+    def unsafe_language_from_pointer(pointer: int) -> object:
+        """Wrap a pointer to a tree-sitter language, as returned as an int by older tree-sitter
+        grammar packages, so that it can be passed to `CodeSplitter`.
+
+        This is unsafe: the pointer can't be checked, and anything other than a pointer
+        to a `TSLanguage` that stays alive for as long as the splitter is used can crash
+        the interpreter. Prefer passing `language()` of the grammar package directly.
+
+        Args:
+            pointer (int): The address of a `TSLanguage`.
+
+        Returns:
+            A capsule for the language, to pass to `CodeSplitter`.
+        """
+    @staticmethod
     def from_huggingface_tokenizer(
-        language: int,
+        language: object,
         tokenizer,
-        capacity: Union[int, Tuple[int, int]],
+        capacity: _Capacity,
         overlap: int = 0,
         trim: bool = True,
     ) -> MarkdownSplitter:
         """Instantiate a new code splitter from a Hugging Face Tokenizer instance.
 
         Args:
-            language (int): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
-                to use for parsing the code.
+            language (object): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
+                to use for parsing the code, such as `tree_sitter_python.language()` or a
+                `tree_sitter.Language` from py-tree-sitter 0.22 to 0.25.
             tokenizer (Tokenizer): A `tokenizers.Tokenizer` you want to use to count tokens for each
                 chunk.
             capacity (int | (int, int)): The capacity of tokens in each chunk. If a
@@ -747,17 +911,18 @@ class CodeSplitter:
 
     @staticmethod
     def from_huggingface_tokenizer_str(
-        language: int,
+        language: object,
         json: str,
-        capacity: Union[int, Tuple[int, int]],
+        capacity: _Capacity,
         overlap: int = 0,
         trim: bool = True,
     ) -> MarkdownSplitter:
         """Instantiate a new code splitter from the given Hugging Face Tokenizer JSON string.
 
         Args:
-            language (int): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
-                to use for parsing the code.
+            language (object): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
+                to use for parsing the code, such as `tree_sitter_python.language()` or a
+                `tree_sitter.Language` from py-tree-sitter 0.22 to 0.25.
             json (str): A valid JSON string representing a previously serialized
                 Hugging Face Tokenizer
             capacity (int | (int, int)): The capacity of tokens in each chunk. If a
@@ -777,17 +942,18 @@ class CodeSplitter:
 
     @staticmethod
     def from_huggingface_tokenizer_file(
-        language: int,
+        language: object,
         path: str,
-        capacity: Union[int, Tuple[int, int]],
+        capacity: _Capacity,
         overlap: int = 0,
         trim: bool = True,
     ) -> MarkdownSplitter:
         """Instantiate a new code splitter from the Hugging Face tokenizer file at the given path.
 
         Args:
-            language (int): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
-                to use for parsing the code.
+            language (object): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
+                to use for parsing the code, such as `tree_sitter_python.language()` or a
+                `tree_sitter.Language` from py-tree-sitter 0.22 to 0.25.
             path (str): A path to a local JSON file representing a previously serialized
                 Hugging Face tokenizer.
             capacity (int | (int, int)): The capacity of tokens in each chunk. If a
@@ -807,17 +973,18 @@ class CodeSplitter:
 
     @staticmethod
     def from_tiktoken_model(
-        language: int,
+        language: object,
         model: str,
-        capacity: Union[int, Tuple[int, int]],
+        capacity: _Capacity,
         overlap: int = 0,
         trim: bool = True,
     ) -> MarkdownSplitter:
         """Instantiate a new code splitter based on an OpenAI Tiktoken tokenizer.
 
         Args:
-            language (int): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
-                to use for parsing the code.
+            language (object): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
+                to use for parsing the code, such as `tree_sitter_python.language()` or a
+                `tree_sitter.Language` from py-tree-sitter 0.22 to 0.25.
             model (str): The OpenAI model name you want to retrieve a tokenizer for.
             capacity (int | (int, int)): The capacity of tokens in each chunk. If a
                 single int, then chunks will be filled up as much as possible, without going over
@@ -836,17 +1003,18 @@ class CodeSplitter:
 
     @staticmethod
     def from_callback(
-        language: int,
+        language: object,
         callback: Callable[[str], int],
-        capacity: Union[int, Tuple[int, int]],
+        capacity: _Capacity,
         overlap: int = 0,
         trim: bool = True,
     ) -> MarkdownSplitter:
         """Instantiate a code text splitter based on a custom callback.
 
         Args:
-            language (int): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
-                to use for parsing the code.
+            language (object): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
+                to use for parsing the code, such as `tree_sitter_python.language()` or a
+                `tree_sitter.Language` from py-tree-sitter 0.22 to 0.25.
             callback (Callable[[str], int]): A lambda or other function that can be called. It will be
                 provided a piece of text, and it should return an integer value for the size.
             capacity (int | (int, int)): The capacity of each chunk. If a
@@ -864,7 +1032,12 @@ class CodeSplitter:
             The new code splitter
         """
 
-    def chunks(self, text: str) -> List[str]:
+    def chunks(
+        self,
+        text: str,
+        cancel: Optional[Callable[[], bool]] = None,
+        on_progress: Optional[Callable[[int, int, int], None]] = None,
+    ) -> List[str]:
         """Generate a list of chunks from a given text. Each chunk will be up to the `capacity`.
 
         ## Method
@@ -883,21 +1056,39 @@ class CodeSplitter:
         4. [Unicode Sentence Boundaries](https://www.unicode.org/reports/tr29/#Sentence_Boundaries)
         5. Ascending depth of the syntax tree. So function would have a higher level than a statement inside of the function, and so on.
 
+        Chunking can be interrupted with Ctrl+C, which raises a `KeyboardInterrupt`.
+
         Args:
             text (str): Text to split.
+            cancel (Callable[[], bool], optional): Called regularly while chunking, such as
+                `threading.Event.is_set`. Once it returns `True`, chunking stops and a
+                `concurrent.futures.CancelledError` is raised.
+            on_progress (Callable[[int, int, int], None], optional): Called after each chunk with
+                the number of bytes of the UTF-8 encoded text processed so far, the total number
+                of bytes, and the number of chunks generated so far, such as for a progress bar.
+                Chunking stops if it raises an exception.
 
         Returns:
             A list of strings, one for each chunk. If `trim` was specified in the text
             splitter, then each chunk will already be trimmed as well.
         """
 
-    def chunk_indices(self, text: str) -> List[Tuple[int, str]]:
+    def chunk_indices(
+        self,
+        text: str,
+        cancel: Optional[Callable[[], bool]] = None,
+        on_progress: Optional[Callable[[int, int, int], None]] = None,
+    ) -> List[Tuple[int, str]]:
         """Generate a list of chunks from a given text, along with their character offsets in the original text. Each chunk will be up to the `capacity`.
 
         See `chunks` for more information.
 
         Args:
             text (str): Text to split.
+            cancel (Callable[[], bool], optional): Called regularly while chunking. Once it
+                returns `True`, chunking stops and a `concurrent.futures.CancelledError` is raised.
+            on_progress (Callable[[int, int, int], None], optional): Called after each chunk with
+                the bytes processed, total bytes, and number of chunks so far. See `chunks`.
 
         Returns:
             A list of tuples, one for each chunk. The first item will be the character offset relative
@@ -906,10 +1097,30 @@ class CodeSplitter:
             trimmed as well.
         """
 
+    def chunk_spans(self, text: str) -> List[Tuple[int, str, int]]:
+        """Generate a list of chunks from a given text, along with their character offsets in the original text and how much of each chunk overlaps with the previous one. Each chunk will be up to the `capacity`.
+
+        This is useful if `overlap` was specified, and you want to strip the duplicated prefix of each chunk when displaying results.
+
+        See `chunks` for more information.
+
+        Args:
+            text (str): Text to split.
+
+        Returns:
+            A list of tuples, one for each chunk. The first item will be the character offset relative
+            to the original text. The second item is the chunk itself. The third item is the number
+            of characters at the start of the chunk that are also part of the previous chunk.
+            If `trim` was specified in the text splitter, then each chunk will already be
+            trimmed as well.
+        """
+
     def chunk_all(self, texts: List[str]) -> List[List[str]]:
         """
         Generate a list of chunks for a given set of texts. Each chunk will be up to the `capacity`.
 
+        The texts are split in parallel, and the GIL is released for the whole batch.
+
         See `chunks` for more information.
 
         Args:
@@ -925,6 +1136,8 @@ class CodeSplitter:
         """
         Generate a list of chunks for a given set of text, along with their character offsets in the original text. Each chunk will be up to the `capacity`.
 
+        The texts are split in parallel, and the GIL is released for the whole batch.
+
         See `chunks` for more information.
 
         Args:
@@ -937,3 +1150,28 @@ class CodeSplitter:
             If `trim` was specified in the text splitter, then each chunk will already be
             trimmed as well.
         """
+
+def test_vectors() -> List[Dict[str, Any]]:
+    """Canonical test vectors: texts, configurations, and the chunks they are expected to produce,
+    so you can check that chunking behaves the same in your environment as in the Rust crate.
+
+    ```python
+    from semantic_text_splitter import MarkdownSplitter, TextSplitter, test_vectors
+
+    for vector in test_vectors():
+        config = vector["config"]
+        capacity = config["capacity"]
+        if config["max_capacity"] is not None:
+            capacity = (capacity, config["max_capacity"])
+        splitter_class = MarkdownSplitter if vector["format"] == "markdown" else TextSplitter
+        splitter = splitter_class(capacity, overlap=config["overlap"], trim=config["trim"])
+        expected = [(chunk["char_start"], chunk["text"]) for chunk in vector["chunks"]]
+        assert splitter.chunk_indices(vector["text"]) == expected, vector["name"]
+    ```
+
+    Returns:
+        A list of dicts, one for each test vector, with its `name`, `format` (`"text"` or
+        `"markdown"`), `text`, `config` (`capacity`, `max_capacity`, `overlap`, and `trim`),
+        and expected `chunks` (`start` byte offset, `char_start` character offset, and `text`).
+        Chunk sizes are measured in characters.
+    """
@@ -3,18 +3,27 @@
 // pyo3 uses these
 #![allow(elided_lifetimes_in_paths, unsafe_op_in_unsafe_fn)]
 
-use std::str::FromStr;
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::Read,
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
 
 use pyo3::{
     exceptions::{PyException, PyTypeError, PyValueError},
-    ffi,
+    import_exception,
     prelude::*,
     pybacked::PyBackedStr,
+    types::{PyBytes, PyCapsule, PyInt, PyString},
 };
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use text_splitter::{
-    Characters, ChunkCapacity, ChunkCapacityError, ChunkConfig, ChunkConfigError, ChunkSizer,
-    CodeSplitter, CodeSplitterError, MarkdownSplitter, TextSplitter,
+    BudgetExhausted, Characters, ChunkCapacity, ChunkCapacityError, ChunkConfig, ChunkConfigError,
+    ChunkProgress, ChunkSizer, ChunkingBudget, CodeSplitter, CodeSplitterError, DynChunkConfig,
+    DynChunkSizer, MarkdownSplitter, SemanticRange, SplitterExt, TextSplitter,
 };
 use tiktoken_rs::get_bpe_from_model;
 use tokenizers::Tokenizer;
@@ -99,6 +108,120 @@ impl ChunkSizer for CustomCallback {
     }
 }
 
+import_exception!(concurrent.futures, CancelledError);
+
+/// Stops chunking once Python has a pending signal, such as a
+/// `KeyboardInterrupt`, once the `cancel` callable returns `True`, or once
+/// the `on_progress` callable raised an exception.
+struct PyCancellation {
+    budget: ChunkingBudget,
+    /// Error to raise once chunking has stopped
+    error: Arc<Mutex<Option<PyErr>>>,
+}
+
+impl PyCancellation {
+    fn new(cancel: Option<PyObject>) -> Self {
+        let error = Arc::new(Mutex::new(None));
+        let check_error = Arc::clone(&error);
+        let budget = ChunkingBudget::new().with_cancellation_check(move || {
+            let mut error = check_error.lock().expect("Lock poisoned");
+            if error.is_some() {
+                return true;
+            }
+            Python::with_gil(|py| {
+                let cancelled = py.check_signals().and_then(|()| match &cancel {
+                    Some(cancel) if cancel.bind(py).call0()?.is_truthy()? => {
+                        Err(CancelledError::new_err("Chunking was cancelled"))
+                    }
+                    _ => Ok(()),
+                });
+                match cancelled {
+                    Ok(()) => false,
+                    Err(err) => {
+                        *error = Some(err);
+                        true
+                    }
+                }
+            })
+        });
+        Self { budget, error }
+    }
+
+    /// Progress callback that passes the bytes processed, total bytes, and
+    /// number of chunks to `on_progress`, and stops chunking if it raises.
+    fn progress(&self, on_progress: PyObject) -> Box<dyn FnMut(ChunkProgress)> {
+        let error = Arc::clone(&self.error);
+        Box::new(move |progress| {
+            let result = Python::with_gil(|py| {
+                on_progress.call1(
+                    py,
+                    (
+                        progress.bytes_processed,
+                        progress.total_bytes,
+                        progress.chunks,
+                    ),
+                )
+            });
+            if let Err(err) = result {
+                error.lock().expect("Lock poisoned").get_or_insert(err);
+            }
+        })
+    }
+
+    /// Collect the chunks, checking whether chunking should stop after each
+    /// one.
+    fn collect<'text>(
+        &self,
+        chunks: impl Iterator<Item = (usize, &'text str)>,
+    ) -> PyResult<Vec<(usize, &'text str)>> {
+        let mut collected = vec![];
+        for chunk in chunks {
+            if self.budget.is_exhausted() {
+                return Err(self.take_error());
+            }
+            collected.push(chunk);
+        }
+        Ok(collected)
+    }
+
+    /// Raise the error that stopped chunking, if it didn't finish.
+    fn finish<'text>(
+        &self,
+        chunks: Result<Vec<(usize, &'text str)>, BudgetExhausted<'text>>,
+    ) -> PyResult<Vec<(usize, &'text str)>> {
+        chunks.map_err(|_| self.take_error())
+    }
+
+    /// The error that stopped chunking.
+    fn take_error(&self) -> PyErr {
+        self.error
+            .lock()
+            .expect("Lock poisoned")
+            .take()
+            .unwrap_or_else(|| CancelledError::new_err("Chunking was cancelled"))
+    }
+
+    /// Chunks of the text, generated by `with_progress` if there is a
+    /// progress callback, and otherwise by `within_budget`, which is checked
+    /// more often.
+    fn chunk_indices<'text, I>(
+        &self,
+        on_progress: Option<PyObject>,
+        with_progress: impl FnOnce(Box<dyn FnMut(ChunkProgress)>) -> I,
+        within_budget: impl FnOnce(
+            &ChunkingBudget,
+        ) -> Result<Vec<(usize, &'text str)>, BudgetExhausted<'text>>,
+    ) -> PyResult<Vec<(usize, &'text str)>>
+    where
+        I: Iterator<Item = (usize, &'text str)>,
+    {
+        match on_progress {
+            Some(on_progress) => self.collect(with_progress(self.progress(on_progress))),
+            None => self.finish(within_budget(&self.budget)),
+        }
+    }
+}
+
 /// Keeps track of the corresponding byte to character offset in a text
 struct ByteToCharOffsetTracker<'text> {
     byte_offset: usize,
@@ -127,18 +250,203 @@ impl<'text> ByteToCharOffsetTracker<'text> {
     }
 }
 
-/// Allows for dynamically choosing between different chunk sizers
-struct Sizer(Box<dyn ChunkSizer + 'static + Send + Sync>);
+/// Map chunks to their character offsets, along with the number of characters
+/// at the start of each chunk that overlap with the previous chunk.
+fn chunk_spans<'text>(
+    text: &'text str,
+    chunks: impl Iterator<Item = (usize, &'text str)>,
+) -> Vec<(usize, &'text str, usize)> {
+    let mut offsets = ByteToCharOffsetTracker::new(text);
+    let mut prev_end = 0;
+    chunks
+        .map(|(offset, chunk)| {
+            let end = offset + chunk.len();
+            let overlap = text[offset..prev_end.clamp(offset, end)].chars().count();
+            prev_end = prev_end.max(end);
+            let (char_offset, chunk) = offsets.map_byte_to_char((offset, chunk));
+            (char_offset, chunk, overlap)
+        })
+        .collect()
+}
+
+/// Where the text of a streamed file comes from
+enum StreamSource {
+    /// A file opened from a path
+    File(File),
+    /// A Python file object, opened in either text or binary mode
+    Python(PyObject),
+}
 
-impl Sizer {
-    fn new(sizer: impl ChunkSizer + 'static + Send + Sync) -> Self {
-        Self(Box::new(sizer))
+/**
+Iterator over the chunks of a file, returned by `TextSplitter.chunk_file`. The file is read a block at a time, and only the text that hasn't been chunked yet is kept in memory.
+
+Can be used as a context manager to close the file once done, even if not all chunks were consumed.
+*/
+#[pyclass(name = "ChunkFileIterator")]
+struct PyChunkFileIterator {
+    splitter: Py<PyTextSplitter>,
+    /// `None` once the end of the file was reached, or the iterator was closed
+    source: Option<StreamSource>,
+    block_size: usize,
+    /// Bytes that were read but aren't a complete UTF-8 sequence yet
+    bytes: Vec<u8>,
+    /// Text that was read but not chunked yet
+    text: String,
+    /// Chunks that are ready to be returned
+    chunks: VecDeque<String>,
+    /// Whether `text` starts with a chunk that was already returned
+    resumed: bool,
+}
+
+impl PyChunkFileIterator {
+    /// Read the next block of the file into `text`. Returns whether the end of
+    /// the file was reached.
+    fn read_block(&mut self, py: Python<'_>) -> PyResult<bool> {
+        let read = match &mut self.source {
+            None => return Ok(true),
+            Some(StreamSource::File(file)) => {
+                let block_size = self.block_size;
+                let bytes = &mut self.bytes;
+                py.allow_threads(|| file.take(block_size as u64).read_to_end(bytes))?
+            }
+            Some(StreamSource::Python(file)) => {
+                let block = file.call_method1(py, "read", (self.block_size,))?;
+                let block = block.bind(py);
+                if let Ok(text) = block.downcast::<PyString>() {
+                    let text = text.to_cow()?;
+                    self.text.push_str(&text);
+                    text.len()
+                } else if let Ok(bytes) = block.downcast::<PyBytes>() {
+                    self.bytes.extend_from_slice(bytes.as_bytes());
+                    bytes.as_bytes().len()
+                } else {
+                    return Err(PyTypeError::new_err(
+                        "read() of the file object must return str or bytes",
+                    ));
+                }
+            }
+        };
+
+        // Keep any incomplete UTF-8 sequence at the end for the next block
+        let valid = match std::str::from_utf8(&self.bytes) {
+            Ok(text) => text.len(),
+            Err(err) if err.error_len().is_none() && read > 0 => err.valid_up_to(),
+            Err(err) => return Err(PyValueError::new_err(err.to_string())),
+        };
+        self.text
+            .push_str(std::str::from_utf8(&self.bytes[..valid]).expect("validated above"));
+        self.bytes.drain(..valid);
+        Ok(read == 0)
+    }
+
+    /// Read blocks of the file until there are chunks that can't change
+    /// anymore, or the end of the file is reached.
+    fn fill_chunks(&mut self, py: Python<'_>) -> PyResult<()> {
+        while self.chunks.is_empty() && self.source.is_some() {
+            let eof = self.read_block(py)?;
+            let splitter = &self.splitter.get().splitter;
+            let text = &self.text;
+            let skip = usize::from(self.resumed);
+            let (chunks, consumed) = py.allow_threads(|| {
+                let mut chunks = splitter.chunk_indices(text).skip(skip).collect::<Vec<_>>();
+                if eof {
+                    return (chunks, text.len());
+                }
+                let ranges = splitter.parse_ranges(text);
+                let done = chunks
+                    .iter()
+                    .rposition(|(offset, _)| {
+                        is_chunk_done(text, &ranges, *offset, splitter.chunk_config())
+                    })
+                    .map_or(0, |index| index + 1);
+                chunks.truncate(done);
+                // Chunking resumes from the last chunk that is done, which is
+                // skipped next time, so that the chunk after it overlaps with
+                // it the same way as when chunking the whole file.
+                let consumed = chunks.last().map_or(0, |(offset, _)| *offset);
+                (chunks, consumed)
+            });
+            self.resumed |= !chunks.is_empty();
+            self.chunks
+                .extend(chunks.into_iter().map(|(_, chunk)| chunk.to_owned()));
+            self.text.drain(..consumed);
+            if eof {
+                self.source = None;
+            }
+        }
+        Ok(())
     }
 }
 
-impl ChunkSizer for Sizer {
-    fn size(&self, chunk: &str) -> usize {
-        self.0.size(chunk)
+/// Whether the chunk starting at `offset` can't change anymore if more text is
+/// added to the end of `text`. Follows the same rules as lazy parsing: there
+/// must be more text than fits in a chunk, and after that, a semantic range at
+/// least as high as any before it.
+fn is_chunk_done(
+    text: &str,
+    ranges: &[SemanticRange],
+    offset: usize,
+    chunk_config: &DynChunkConfig,
+) -> bool {
+    let max = chunk_config.capacity().max();
+    let mut end = offset;
+    let mut step = max.max(1);
+    loop {
+        if end >= text.len() {
+            return false;
+        }
+        end = end.saturating_add(step).min(text.len());
+        while !text.is_char_boundary(end) {
+            end += 1;
+        }
+        if chunk_config.sizer().size(text[offset..end].trim_end()) > max {
+            break;
+        }
+        step = step.saturating_mul(2);
+    }
+
+    let max_level = ranges
+        .iter()
+        .filter(|r| r.range.end > offset && r.range.start < end)
+        .map(|r| r.level)
+        .max();
+    // A range at the very end could still grow, like a run of newlines
+    ranges.iter().any(|r| {
+        r.range.start >= end
+            && r.range.end < text.len()
+            && max_level.is_none_or(|max| r.level >= max)
+    })
+}
+
+#[pymethods]
+impl PyChunkFileIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<String>> {
+        self.fill_chunks(py)?;
+        Ok(self.chunks.pop_front())
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    #[pyo3(signature = (*_args))]
+    fn __exit__(&mut self, _args: &Bound<'_, pyo3::types::PyTuple>) -> bool {
+        self.close();
+        false
+    }
+
+    /**
+    Stop reading the file, and close it if it was opened from a path. File objects passed in are left open.
+    */
+    fn close(&mut self) {
+        self.source = None;
+        self.bytes.clear();
+        self.text.clear();
+        self.chunks.clear();
     }
 }
 
@@ -229,7 +537,7 @@ Args:
 */
 #[pyclass(frozen, name = "TextSplitter")]
 struct PyTextSplitter {
-    splitter: TextSplitter<Sizer>,
+    splitter: TextSplitter<DynChunkSizer>,
 }
 
 #[pymethods]
@@ -243,7 +551,8 @@ impl PyTextSplitter {
                     .with_overlap(overlap)
                     .map_err(PyChunkConfigError)?
                     .with_trim(trim)
-                    .with_sizer(Sizer::new(Characters)),
+                    .with_sizer(Characters)
+                    .into_dyn(),
             ),
         })
     }
@@ -286,8 +595,9 @@ impl PyTextSplitter {
                 ChunkConfig::new(ChunkCapacity::try_from(capacity)?)
                     .with_overlap(overlap)
                     .map_err(PyChunkConfigError)?
-                    .with_sizer(Sizer::new(tokenizer))
-                    .with_trim(trim),
+                    .with_sizer(tokenizer)
+                    .with_trim(trim)
+                    .into_dyn(),
             ),
         })
     }
@@ -329,8 +639,9 @@ impl PyTextSplitter {
                 ChunkConfig::new(ChunkCapacity::try_from(capacity)?)
                     .with_overlap(overlap)
                     .map_err(PyChunkConfigError)?
-                    .with_sizer(Sizer::new(tokenizer))
-                    .with_trim(trim),
+                    .with_sizer(tokenizer)
+                    .with_trim(trim)
+                    .into_dyn(),
             ),
         })
     }
@@ -370,8 +681,9 @@ impl PyTextSplitter {
                 ChunkConfig::new(ChunkCapacity::try_from(capacity)?)
                     .with_overlap(overlap)
                     .map_err(PyChunkConfigError)?
-                    .with_sizer(Sizer::new(tokenizer))
-                    .with_trim(trim),
+                    .with_sizer(tokenizer)
+                    .with_trim(trim)
+                    .into_dyn(),
             ),
         })
     }
@@ -411,8 +723,9 @@ impl PyTextSplitter {
                 ChunkConfig::new(ChunkCapacity::try_from(capacity)?)
                     .with_overlap(overlap)
                     .map_err(PyChunkConfigError)?
-                    .with_sizer(Sizer::new(tokenizer))
-                    .with_trim(trim),
+                    .with_sizer(tokenizer)
+                    .with_trim(trim)
+                    .into_dyn(),
             ),
         })
     }
@@ -450,8 +763,9 @@ impl PyTextSplitter {
                 ChunkConfig::new(ChunkCapacity::try_from(capacity)?)
                     .with_overlap(overlap)
                     .map_err(PyChunkConfigError)?
-                    .with_sizer(Sizer::new(CustomCallback(callback)))
-                    .with_trim(trim),
+                    .with_sizer(CustomCallback(callback))
+                    .with_trim(trim)
+                    .into_dyn(),
             ),
         })
     }
@@ -478,15 +792,36 @@ impl PyTextSplitter {
 
     Splitting doesn't occur below the character level, otherwise you could get partial bytes of a char, which may not be a valid unicode str.
 
+    Chunking can be interrupted with Ctrl+C, which raises a `KeyboardInterrupt`.
+
     Args:
         text (str): Text to split.
+        cancel (Callable[[], bool], optional): Called regularly while chunking, such as
+            `threading.Event.is_set`. Once it returns `True`, chunking stops and a
+            `concurrent.futures.CancelledError` is raised.
+        `on_progress` (Callable[[int, int, int], None], optional): Called after each chunk with
+            the number of bytes of the UTF-8 encoded text processed so far, the total number
+            of bytes, and the number of chunks generated so far, such as for a progress bar.
+            Chunking stops if it raises an exception.
 
     Returns:
         A list of strings, one for each chunk. If `trim` was specified in the text
         splitter, then each chunk will already be trimmed as well.
     */
-    fn chunks<'text, 'splitter: 'text>(&'splitter self, text: &'text str) -> Vec<&'text str> {
-        self.splitter.chunks(text).collect()
+    #[pyo3(signature = (text, cancel=None, on_progress=None))]
+    fn chunks<'text, 'splitter: 'text>(
+        &'splitter self,
+        text: &'text str,
+        cancel: Option<PyObject>,
+        on_progress: Option<PyObject>,
+    ) -> PyResult<Vec<&'text str>> {
+        let cancellation = PyCancellation::new(cancel);
+        let chunks = cancellation.chunk_indices(
+            on_progress,
+            |on_progress| self.splitter.chunk_indices_with_progress(text, on_progress),
+            |budget| self.splitter.chunk_indices_within_budget(text, budget),
+        )?;
+        Ok(chunks.into_iter().map(|(_, chunk)| chunk).collect())
     }
 
     /**
@@ -496,6 +831,10 @@ impl PyTextSplitter {
 
     Args:
         text (str): Text to split.
+        cancel (Callable[[], bool], optional): Called regularly while chunking. Once it
+            returns `True`, chunking stops and a `concurrent.futures.CancelledError` is raised.
+        `on_progress` (Callable[[int, int, int], None], optional): Called after each chunk with
+            the bytes processed, total bytes, and number of chunks so far. See `chunks`.
 
     Returns:
         A list of tuples, one for each chunk. The first item will be the character offset relative
@@ -503,20 +842,55 @@ impl PyTextSplitter {
         If `trim` was specified in the text splitter, then each chunk will already be
         trimmed as well.
     */
+    #[pyo3(signature = (text, cancel=None, on_progress=None))]
     fn chunk_indices<'text, 'splitter: 'text>(
         &'splitter self,
         text: &'text str,
-    ) -> Vec<(usize, &'text str)> {
+        cancel: Option<PyObject>,
+        on_progress: Option<PyObject>,
+    ) -> PyResult<Vec<(usize, &'text str)>> {
+        let cancellation = PyCancellation::new(cancel);
+        let chunks = cancellation.chunk_indices(
+            on_progress,
+            |on_progress| self.splitter.chunk_indices_with_progress(text, on_progress),
+            |budget| self.splitter.chunk_indices_within_budget(text, budget),
+        )?;
         let mut offsets = ByteToCharOffsetTracker::new(text);
-        self.splitter
-            .chunk_indices(text)
+        Ok(chunks
+            .into_iter()
             .map(|c| offsets.map_byte_to_char(c))
-            .collect()
+            .collect())
+    }
+
+    /**
+    Generate a list of chunks from a given text, along with their character offsets in the original text and how much of each chunk overlaps with the previous one. Each chunk will be up to the `capacity`.
+
+    This is useful if `overlap` was specified, and you want to strip the duplicated prefix of each chunk when displaying results.
+
+    See `chunks` for more information.
+
+    Args:
+        text (str): Text to split.
+
+    Returns:
+        A list of tuples, one for each chunk. The first item will be the character offset relative
+        to the original text. The second item is the chunk itself. The third item is the number
+        of characters at the start of the chunk that are also part of the previous chunk.
+        If `trim` was specified in the text splitter, then each chunk will already be
+        trimmed as well.
+    */
+    fn chunk_spans<'text, 'splitter: 'text>(
+        &'splitter self,
+        text: &'text str,
+    ) -> Vec<(usize, &'text str, usize)> {
+        chunk_spans(text, self.splitter.chunk_indices(text))
     }
 
     /**
     Generate a list of chunks for a given set of texts. Each chunk will be up to the `capacity`.
 
+    The texts are split in parallel, and the GIL is released for the whole batch.
+
     See `chunks` for more information.
 
     Args:
@@ -527,16 +901,20 @@ impl PyTextSplitter {
         If `trim` was specified in the text splitter, then each chunk will already be
         trimmed as well.
     */
-    fn chunk_all(&self, texts: Vec<String>) -> Vec<Vec<String>> {
-        texts
-            .into_par_iter()
-            .map(|text| self.splitter.chunks(&text).map(ToOwned::to_owned).collect())
-            .collect()
+    fn chunk_all(&self, py: Python<'_>, texts: Vec<String>) -> Vec<Vec<String>> {
+        py.allow_threads(|| {
+            texts
+                .into_par_iter()
+                .map(|text| self.splitter.chunks(&text).map(ToOwned::to_owned).collect())
+                .collect()
+        })
     }
 
     /**
     Generate a list of chunks for a given set of text, along with their character offsets in the original text. Each chunk will be up to the `capacity`.
 
+    The texts are split in parallel, and the GIL is released for the whole batch.
+
     See `chunks` for more information.
 
     Args:
@@ -549,18 +927,76 @@ impl PyTextSplitter {
         If `trim` was specified in the text splitter, then each chunk will already be
         trimmed as well.
     */
-    fn chunk_all_indices(&self, texts: Vec<String>) -> Vec<Vec<(usize, String)>> {
-        texts
-            .into_par_iter()
-            .map(|text| {
-                let mut offsets = ByteToCharOffsetTracker::new(&text);
-                self.splitter
-                    .chunk_indices(&text)
-                    .map(|c| offsets.map_byte_to_char(c))
-                    .map(|(i, c)| (i, c.to_owned()))
-                    .collect()
-            })
-            .collect()
+    fn chunk_all_indices(&self, py: Python<'_>, texts: Vec<String>) -> Vec<Vec<(usize, String)>> {
+        py.allow_threads(|| {
+            texts
+                .into_par_iter()
+                .map(|text| {
+                    let mut offsets = ByteToCharOffsetTracker::new(&text);
+                    self.splitter
+                        .chunk_indices(&text)
+                        .map(|c| offsets.map_byte_to_char(c))
+                        .map(|(i, c)| (i, c.to_owned()))
+                        .collect()
+                })
+                .collect()
+        })
+    }
+
+    /**
+    Generate chunks from a file lazily, without reading the whole file into memory. Each chunk will be up to the `capacity`.
+
+    The file is read in blocks, and only the text that hasn't been chunked yet is kept in memory, so this can be used for very large files. The chunks are the same as calling `chunks` on the contents of the whole file, unless a chunk would need more than a block of text to decide where it ends.
+
+    ```python
+    from semantic_text_splitter import TextSplitter
+
+    splitter = TextSplitter(1000)
+
+    with splitter.chunk_file("large_document.txt") as chunks:
+        for chunk in chunks:
+            print(chunk)
+    ```
+
+    See `chunks` for more information.
+
+    Args:
+        `path_or_fileobj` (str | os.PathLike | file object): Path of a UTF-8 encoded file, or an
+            open file object in text or binary mode. File objects are not closed.
+        `block_size` (int, optional): Number of bytes (or characters, for files in text mode)
+            to read at a time. Defaults to 1 MiB.
+
+    Returns:
+        An iterator of strings, one for each chunk, that can also be used as a context
+        manager to close the file. If `trim` was specified in the text splitter, then each
+        chunk will already be trimmed as well.
+    */
+    #[pyo3(signature = (path_or_fileobj, block_size=1_048_576))]
+    fn chunk_file(
+        slf: Py<Self>,
+        path_or_fileobj: &Bound<'_, PyAny>,
+        block_size: usize,
+    ) -> PyResult<PyChunkFileIterator> {
+        let source = match path_or_fileobj.extract::<PathBuf>() {
+            Ok(path) => StreamSource::File(File::open(path)?),
+            Err(_) if path_or_fileobj.hasattr("read")? => {
+                StreamSource::Python(path_or_fileobj.clone().unbind())
+            }
+            Err(_) => {
+                return Err(PyTypeError::new_err(
+                    "Expected a path or a file object with a read() method",
+                ))
+            }
+        };
+        Ok(PyChunkFileIterator {
+            splitter: slf,
+            source: Some(source),
+            block_size: block_size.max(1),
+            bytes: vec![],
+            text: String::new(),
+            chunks: VecDeque::new(),
+            resumed: false,
+        })
     }
 }
 
@@ -653,7 +1089,7 @@ Args:
 */
 #[pyclass(frozen, name = "MarkdownSplitter")]
 struct PyMarkdownSplitter {
-    splitter: MarkdownSplitter<Sizer>,
+    splitter: MarkdownSplitter<DynChunkSizer>,
 }
 
 #[pymethods]
@@ -666,8 +1102,9 @@ impl PyMarkdownSplitter {
                 ChunkConfig::new(ChunkCapacity::try_from(capacity)?)
                     .with_overlap(overlap)
                     .map_err(PyChunkConfigError)?
-                    .with_sizer(Sizer::new(Characters))
-                    .with_trim(trim),
+                    .with_sizer(Characters)
+                    .with_trim(trim)
+                    .into_dyn(),
             ),
         })
     }
@@ -710,8 +1147,9 @@ impl PyMarkdownSplitter {
                 ChunkConfig::new(ChunkCapacity::try_from(capacity)?)
                     .with_overlap(overlap)
                     .map_err(PyChunkConfigError)?
-                    .with_sizer(Sizer::new(tokenizer))
-                    .with_trim(trim),
+                    .with_sizer(tokenizer)
+                    .with_trim(trim)
+                    .into_dyn(),
             ),
         })
     }
@@ -753,8 +1191,9 @@ impl PyMarkdownSplitter {
                 ChunkConfig::new(ChunkCapacity::try_from(capacity)?)
                     .with_overlap(overlap)
                     .map_err(PyChunkConfigError)?
-                    .with_sizer(Sizer::new(tokenizer))
-                    .with_trim(trim),
+                    .with_sizer(tokenizer)
+                    .with_trim(trim)
+                    .into_dyn(),
             ),
         })
     }
@@ -794,8 +1233,9 @@ impl PyMarkdownSplitter {
                 ChunkConfig::new(ChunkCapacity::try_from(capacity)?)
                     .with_overlap(overlap)
                     .map_err(PyChunkConfigError)?
-                    .with_sizer(Sizer::new(tokenizer))
-                    .with_trim(trim),
+                    .with_sizer(tokenizer)
+                    .with_trim(trim)
+                    .into_dyn(),
             ),
         })
     }
@@ -835,8 +1275,9 @@ impl PyMarkdownSplitter {
                 ChunkConfig::new(ChunkCapacity::try_from(capacity)?)
                     .with_overlap(overlap)
                     .map_err(PyChunkConfigError)?
-                    .with_sizer(Sizer::new(tokenizer))
-                    .with_trim(trim),
+                    .with_sizer(tokenizer)
+                    .with_trim(trim)
+                    .into_dyn(),
             ),
         })
     }
@@ -874,8 +1315,9 @@ impl PyMarkdownSplitter {
                 ChunkConfig::new(ChunkCapacity::try_from(capacity)?)
                     .with_overlap(overlap)
                     .map_err(PyChunkConfigError)?
-                    .with_sizer(Sizer::new(CustomCallback(callback)))
-                    .with_trim(trim),
+                    .with_sizer(CustomCallback(callback))
+                    .with_trim(trim)
+                    .into_dyn(),
             ),
         })
     }
@@ -905,15 +1347,36 @@ impl PyMarkdownSplitter {
 
     Markdown is parsed according to the Commonmark spec, along with some optional features such as GitHub Flavored Markdown.
 
+    Chunking can be interrupted with Ctrl+C, which raises a `KeyboardInterrupt`.
+
     Args:
         text (str): Text to split.
+        cancel (Callable[[], bool], optional): Called regularly while chunking, such as
+            `threading.Event.is_set`. Once it returns `True`, chunking stops and a
+            `concurrent.futures.CancelledError` is raised.
+        `on_progress` (Callable[[int, int, int], None], optional): Called after each chunk with
+            the number of bytes of the UTF-8 encoded text processed so far, the total number
+            of bytes, and the number of chunks generated so far, such as for a progress bar.
+            Chunking stops if it raises an exception.
 
     Returns:
         A list of strings, one for each chunk. If `trim` was specified in the text
         splitter, then each chunk will already be trimmed as well.
     */
-    fn chunks<'text, 'splitter: 'text>(&'splitter self, text: &'text str) -> Vec<&'text str> {
-        self.splitter.chunks(text).collect()
+    #[pyo3(signature = (text, cancel=None, on_progress=None))]
+    fn chunks<'text, 'splitter: 'text>(
+        &'splitter self,
+        text: &'text str,
+        cancel: Option<PyObject>,
+        on_progress: Option<PyObject>,
+    ) -> PyResult<Vec<&'text str>> {
+        let cancellation = PyCancellation::new(cancel);
+        let chunks = cancellation.chunk_indices(
+            on_progress,
+            |on_progress| self.splitter.chunk_indices_with_progress(text, on_progress),
+            |budget| self.splitter.chunk_indices_within_budget(text, budget),
+        )?;
+        Ok(chunks.into_iter().map(|(_, chunk)| chunk).collect())
     }
 
     /**
@@ -923,6 +1386,10 @@ impl PyMarkdownSplitter {
 
     Args:
         text (str): Text to split.
+        cancel (Callable[[], bool], optional): Called regularly while chunking. Once it
+            returns `True`, chunking stops and a `concurrent.futures.CancelledError` is raised.
+        `on_progress` (Callable[[int, int, int], None], optional): Called after each chunk with
+            the bytes processed, total bytes, and number of chunks so far. See `chunks`.
 
     Returns:
         A list of tuples, one for each chunk. The first item will be the character offset relative
@@ -930,20 +1397,55 @@ impl PyMarkdownSplitter {
         If `trim` was specified in the text splitter, then each chunk will already be
         trimmed as well.
     */
+    #[pyo3(signature = (text, cancel=None, on_progress=None))]
     fn chunk_indices<'text, 'splitter: 'text>(
         &'splitter self,
         text: &'text str,
-    ) -> Vec<(usize, &'text str)> {
+        cancel: Option<PyObject>,
+        on_progress: Option<PyObject>,
+    ) -> PyResult<Vec<(usize, &'text str)>> {
+        let cancellation = PyCancellation::new(cancel);
+        let chunks = cancellation.chunk_indices(
+            on_progress,
+            |on_progress| self.splitter.chunk_indices_with_progress(text, on_progress),
+            |budget| self.splitter.chunk_indices_within_budget(text, budget),
+        )?;
         let mut offsets = ByteToCharOffsetTracker::new(text);
-        self.splitter
-            .chunk_indices(text)
+        Ok(chunks
+            .into_iter()
             .map(|c| offsets.map_byte_to_char(c))
-            .collect()
+            .collect())
+    }
+
+    /**
+    Generate a list of chunks from a given text, along with their character offsets in the original text and how much of each chunk overlaps with the previous one. Each chunk will be up to the `capacity`.
+
+    This is useful if `overlap` was specified, and you want to strip the duplicated prefix of each chunk when displaying results.
+
+    See `chunks` for more information.
+
+    Args:
+        text (str): Text to split.
+
+    Returns:
+        A list of tuples, one for each chunk. The first item will be the character offset relative
+        to the original text. The second item is the chunk itself. The third item is the number
+        of characters at the start of the chunk that are also part of the previous chunk.
+        If `trim` was specified in the text splitter, then each chunk will already be
+        trimmed as well.
+    */
+    fn chunk_spans<'text, 'splitter: 'text>(
+        &'splitter self,
+        text: &'text str,
+    ) -> Vec<(usize, &'text str, usize)> {
+        chunk_spans(text, self.splitter.chunk_indices(text))
     }
 
     /**
     Generate a list of chunks for a given set of texts. Each chunk will be up to the `capacity`.
 
+    The texts are split in parallel, and the GIL is released for the whole batch.
+
     See `chunks` for more information.
 
     Args:
@@ -954,16 +1456,20 @@ impl PyMarkdownSplitter {
         If `trim` was specified in the text splitter, then each chunk will already be
         trimmed as well.
     */
-    fn chunk_all(&self, texts: Vec<String>) -> Vec<Vec<String>> {
-        texts
-            .into_par_iter()
-            .map(|text| self.splitter.chunks(&text).map(ToOwned::to_owned).collect())
-            .collect()
+    fn chunk_all(&self, py: Python<'_>, texts: Vec<String>) -> Vec<Vec<String>> {
+        py.allow_threads(|| {
+            texts
+                .into_par_iter()
+                .map(|text| self.splitter.chunks(&text).map(ToOwned::to_owned).collect())
+                .collect()
+        })
     }
 
     /**
     Generate a list of chunks for a given set of text, along with their character offsets in the original text. Each chunk will be up to the `capacity`.
 
+    The texts are split in parallel, and the GIL is released for the whole batch.
+
     See `chunks` for more information.
 
     Args:
@@ -976,18 +1482,20 @@ impl PyMarkdownSplitter {
         If `trim` was specified in the text splitter, then each chunk will already be
         trimmed as well.
     */
-    fn chunk_all_indices(&self, texts: Vec<String>) -> Vec<Vec<(usize, String)>> {
-        texts
-            .into_par_iter()
-            .map(|text| {
-                let mut offsets = ByteToCharOffsetTracker::new(&text);
-                self.splitter
-                    .chunk_indices(&text)
-                    .map(|c| offsets.map_byte_to_char(c))
-                    .map(|(i, c)| (i, c.to_owned()))
-                    .collect()
-            })
-            .collect()
+    fn chunk_all_indices(&self, py: Python<'_>, texts: Vec<String>) -> Vec<Vec<(usize, String)>> {
+        py.allow_threads(|| {
+            texts
+                .into_par_iter()
+                .map(|text| {
+                    let mut offsets = ByteToCharOffsetTracker::new(&text);
+                    self.splitter
+                        .chunk_indices(&text)
+                        .map(|c| offsets.map_byte_to_char(c))
+                        .map(|(i, c)| (i, c.to_owned()))
+                        .collect()
+                })
+                .collect()
+        })
     }
 }
 
@@ -1077,8 +1585,9 @@ chunks = splitter.chunks("# Header\n\nyour document text")
 ```
 
 Args:
-    language (int): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
-        to use for parsing the code.
+    language (object): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
+        to use for parsing the code, such as `tree_sitter_python.language()` or a
+        `tree_sitter.Language` from py-tree-sitter 0.22 to 0.25.
     capacity (int | (int, int)): The capacity of characters in each chunk. If a
         single int, then chunks will be filled up as much as possible, without going over
         that number. If a tuple of two integers is provided, a chunk will be considered
@@ -1092,30 +1601,117 @@ Args:
 */
 #[pyclass(frozen, name = "CodeSplitter")]
 struct PyCodeSplitter {
-    splitter: CodeSplitter<Sizer>,
+    splitter: CodeSplitter<DynChunkSizer>,
 }
 
 impl PyCodeSplitter {
-    /// Converts the output of a Python tree-sitter language object into a `Language` struct.
+    /// Name of the capsules that tree-sitter grammar packages return from `language()`
+    const LANGUAGE_CAPSULE: &'static std::ffi::CStr = c"tree_sitter.Language";
+
+    /// Versions of py-tree-sitter whose `Language` objects hash to the pointer of their
+    /// `TSLanguage`, as (major, minor), from the first supported to the first unsupported.
+    const TREE_SITTER_VERSIONS: ((u32, u32), (u32, u32)) = ((0, 22), (0, 26));
+
+    /// Converts a Python tree-sitter language into a `Language` struct. Accepts the
+    /// capsule returned by `language()` in tree-sitter grammar packages, or a
+    /// `tree_sitter.Language` object from a supported version of py-tree-sitter.
     fn load_language(language: &Bound<'_, PyAny>) -> PyResult<Language> {
-        unsafe {
-            if ffi::PyCapsule_CheckExact(language.as_ptr()) > 0 {
-                let pointer = ffi::PyCapsule_GetPointer(
-                    language.as_ptr(),
-                    ffi::PyCapsule_GetName(language.as_ptr()),
-                );
-                Ok(Language::from_raw(pointer.cast::<TSLanguage>()))
-            } else {
-                Err(PyTypeError::new_err(
-                "Expected a pointer for the language. Try calling `language()` on the tree-sitter language.",
-                ))
+        let pointer = if let Ok(capsule) = language.downcast::<PyCapsule>() {
+            if capsule.name()? != Some(Self::LANGUAGE_CAPSULE) {
+                return Err(PyTypeError::new_err(
+                    "Expected a `tree_sitter.Language` capsule. Try calling `language()` on the tree-sitter language.",
+                ));
             }
+            capsule.pointer() as usize
+        } else if Self::is_tree_sitter_language(language)? {
+            Self::check_tree_sitter_version(language.py())?;
+            // py-tree-sitter hashes a `Language` by the pointer to its `TSLanguage` in
+            // the supported versions
+            usize::from_ne_bytes(language.hash()?.to_ne_bytes())
+        } else if language.is_instance_of::<PyInt>() {
+            return Err(PyTypeError::new_err(
+                "Pointers to a tree-sitter language aren't accepted directly, since they can't be checked. Pass `language()` of the tree-sitter language instead, or wrap the pointer with `CodeSplitter.unsafe_language_from_pointer`.",
+            ));
+        } else {
+            return Err(PyTypeError::new_err(
+                "Expected a tree-sitter language. Try calling `language()` on the tree-sitter language.",
+            ));
+        };
+
+        let pointer = pointer as *const TSLanguage;
+        if pointer.is_null() || !pointer.is_aligned() {
+            return Err(PyValueError::new_err(
+                "Expected a pointer to a tree-sitter language.",
+            ));
+        }
+        // SAFETY: The pointer comes from a capsule that was named as a tree-sitter
+        // language, or from a py-tree-sitter `Language`, which both point to the
+        // static `TSLanguage` of a grammar. Anything else was rejected above, since
+        // even reading the ABI version of the language dereferences the pointer.
+        Ok(unsafe { Language::from_raw(pointer) })
+    }
+
+    /// Checks that the installed py-tree-sitter hashes its `Language` objects by their
+    /// pointer, which isn't part of its public API.
+    fn check_tree_sitter_version(py: Python<'_>) -> PyResult<()> {
+        let version = py
+            .import("importlib.metadata")?
+            .call_method1("version", ("tree-sitter",))?
+            .extract::<String>()?;
+        let mut parts = version.split('.').map(|part| part.parse::<u32>().ok());
+        let (first, end) = Self::TREE_SITTER_VERSIONS;
+        match (parts.next().flatten(), parts.next().flatten()) {
+            (Some(major), Some(minor)) if (first..end).contains(&(major, minor)) => Ok(()),
+            _ => Err(PyTypeError::new_err(format!(
+                "`tree_sitter.Language` objects are only supported for py-tree-sitter {}.{} up to {}.{}, but {version} is installed. Pass `language()` of the tree-sitter language instead.",
+                first.0, first.1, end.0, end.1
+            ))),
         }
     }
+
+    /// Whether the object is a `tree_sitter.Language` from the py-tree-sitter package.
+    fn is_tree_sitter_language(language: &Bound<'_, PyAny>) -> PyResult<bool> {
+        let language_type = language.get_type();
+        Ok(language_type.module()?.to_cow()? == "tree_sitter"
+            && language_type.name()?.to_cow()? == "Language")
+    }
 }
 
 #[pymethods]
 impl PyCodeSplitter {
+    /**
+    Wrap a pointer to a tree-sitter language, as returned as an int by older tree-sitter
+    grammar packages, so that it can be passed to `CodeSplitter`.
+
+    This is unsafe: the pointer can't be checked, and anything other than a pointer
+    to a `TSLanguage` that stays alive for as long as the splitter is used can crash
+    the interpreter. Prefer passing `language()` of the grammar package directly.
+
+    Args:
+        pointer (int): The address of a `TSLanguage`.
+
+    Returns:
+        A capsule for the language, to pass to `CodeSplitter`.
+    */
+    #[staticmethod]
+    fn unsafe_language_from_pointer(py: Python<'_>, pointer: usize) -> PyResult<PyObject> {
+        if pointer == 0 || !(pointer as *const TSLanguage).is_aligned() {
+            return Err(PyValueError::new_err(
+                "Expected a pointer to a tree-sitter language.",
+            ));
+        }
+        // SAFETY: The capsule only stores the pointer and has no destructor. The
+        // caller promises that it points to a valid `TSLanguage`.
+        unsafe {
+            let capsule = pyo3::ffi::PyCapsule_New(
+                pointer as *mut std::ffi::c_void,
+                Self::LANGUAGE_CAPSULE.as_ptr(),
+                None,
+            );
+            PyObject::from_owned_ptr_or_err(py, capsule)
+        }
+    }
+
     #[new]
     #[pyo3(signature = (language, capacity, overlap=0, trim=true))]
     fn new(
@@ -1130,8 +1726,9 @@ impl PyCodeSplitter {
                 ChunkConfig::new(ChunkCapacity::try_from(capacity)?)
                     .with_overlap(overlap)
                     .map_err(PyChunkConfigError)?
-                    .with_sizer(Sizer::new(Characters))
-                    .with_trim(trim),
+                    .with_sizer(Characters)
+                    .with_trim(trim)
+                    .into_dyn(),
             )
             .map_err(PyCodeSplitterError)?,
         })
@@ -1141,8 +1738,9 @@ impl PyCodeSplitter {
     Instantiate a new code splitter from a Hugging Face Tokenizer instance.
 
     Args:
-        language (int): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
-            to use for parsing the code.
+        language (object): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
+            to use for parsing the code, such as `tree_sitter_python.language()` or a
+            `tree_sitter.Language` from py-tree-sitter 0.22 to 0.25.
         tokenizer (Tokenizer): A `tokenizers.Tokenizer` you want to use to count tokens for each
             chunk.
         capacity (int | (int, int)): The capacity of tokens in each chunk. If a
@@ -1179,8 +1777,9 @@ impl PyCodeSplitter {
                 ChunkConfig::new(ChunkCapacity::try_from(capacity)?)
                     .with_overlap(overlap)
                     .map_err(PyChunkConfigError)?
-                    .with_sizer(Sizer::new(tokenizer))
-                    .with_trim(trim),
+                    .with_sizer(tokenizer)
+                    .with_trim(trim)
+                    .into_dyn(),
             )
             .map_err(PyCodeSplitterError)?,
         })
@@ -1190,8 +1789,9 @@ impl PyCodeSplitter {
     Instantiate a new code splitter from the given Hugging Face Tokenizer JSON string.
 
     Args:
-        language (int): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
-            to use for parsing the code.
+        language (object): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
+            to use for parsing the code, such as `tree_sitter_python.language()` or a
+            `tree_sitter.Language` from py-tree-sitter 0.22 to 0.25.
         json (str): A valid JSON string representing a previously serialized
             Hugging Face Tokenizer
         capacity (int | (int, int)): The capacity of tokens in each chunk. If a
@@ -1227,8 +1827,9 @@ impl PyCodeSplitter {
                 ChunkConfig::new(ChunkCapacity::try_from(capacity)?)
                     .with_overlap(overlap)
                     .map_err(PyChunkConfigError)?
-                    .with_sizer(Sizer::new(tokenizer))
-                    .with_trim(trim),
+                    .with_sizer(tokenizer)
+                    .with_trim(trim)
+                    .into_dyn(),
             )
             .map_err(PyCodeSplitterError)?,
         })
@@ -1238,8 +1839,9 @@ impl PyCodeSplitter {
     Instantiate a new code splitter from the Hugging Face tokenizer file at the given path.
 
     Args:
-        language (int): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
-            to use for parsing the code.
+        language (object): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
+            to use for parsing the code, such as `tree_sitter_python.language()` or a
+            `tree_sitter.Language` from py-tree-sitter 0.22 to 0.25.
         path (str): A path to a local JSON file representing a previously serialized
             Hugging Face tokenizer.
         capacity (int | (int, int)): The capacity of tokens in each chunk. If a
@@ -1273,8 +1875,9 @@ impl PyCodeSplitter {
                 ChunkConfig::new(ChunkCapacity::try_from(capacity)?)
                     .with_overlap(overlap)
                     .map_err(PyChunkConfigError)?
-                    .with_sizer(Sizer::new(tokenizer))
-                    .with_trim(trim),
+                    .with_sizer(tokenizer)
+                    .with_trim(trim)
+                    .into_dyn(),
             )
             .map_err(PyCodeSplitterError)?,
         })
@@ -1284,8 +1887,9 @@ impl PyCodeSplitter {
     Instantiate a new code splitter based on an OpenAI Tiktoken tokenizer.
 
     Args:
-        language (int): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
-            to use for parsing the code.
+        language (object): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
+            to use for parsing the code, such as `tree_sitter_python.language()` or a
+            `tree_sitter.Language` from py-tree-sitter 0.22 to 0.25.
         model (str): The OpenAI model name you want to retrieve a tokenizer for.
         capacity (int | (int, int)): The capacity of tokens in each chunk. If a
             single int, then chunks will be filled up as much as possible, without going over
@@ -1319,8 +1923,9 @@ impl PyCodeSplitter {
                 ChunkConfig::new(ChunkCapacity::try_from(capacity)?)
                     .with_overlap(overlap)
                     .map_err(PyChunkConfigError)?
-                    .with_sizer(Sizer::new(tokenizer))
-                    .with_trim(trim),
+                    .with_sizer(tokenizer)
+                    .with_trim(trim)
+                    .into_dyn(),
             )
             .map_err(PyCodeSplitterError)?,
         })
@@ -1330,8 +1935,9 @@ impl PyCodeSplitter {
     Instantiate a code text splitter based on a custom callback.
 
     Args:
-        language (int): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
-            to use for parsing the code.
+        language (object): The [tree-sitter language](https://tree-sitter.github.io/tree-sitter/#parsers)
+            to use for parsing the code, such as `tree_sitter_python.language()` or a
+            `tree_sitter.Language` from py-tree-sitter 0.22 to 0.25.
         callback (Callable[[str], int]): A lambda or other function that can be called. It will be
             provided a piece of text, and it should return an integer value for the size.
         capacity (int | (int, int)): The capacity of each chunk. If a
@@ -1363,8 +1969,9 @@ impl PyCodeSplitter {
                 ChunkConfig::new(ChunkCapacity::try_from(capacity)?)
                     .with_overlap(overlap)
                     .map_err(PyChunkConfigError)?
-                    .with_sizer(Sizer::new(CustomCallback(callback)))
-                    .with_trim(trim),
+                    .with_sizer(CustomCallback(callback))
+                    .with_trim(trim)
+                    .into_dyn(),
             )
             .map_err(PyCodeSplitterError)?,
         })
@@ -1389,15 +1996,36 @@ impl PyCodeSplitter {
     4. [Unicode Sentence Boundaries](https://www.unicode.org/reports/tr29/#Sentence_Boundaries)
     5. Ascending depth of the syntax tree. So function would have a higher level than a statement inside of the function, and so on.
 
+    Chunking can be interrupted with Ctrl+C, which raises a `KeyboardInterrupt`.
+
     Args:
         text (str): Text to split.
+        cancel (Callable[[], bool], optional): Called regularly while chunking, such as
+            `threading.Event.is_set`. Once it returns `True`, chunking stops and a
+            `concurrent.futures.CancelledError` is raised.
+        `on_progress` (Callable[[int, int, int], None], optional): Called after each chunk with
+            the number of bytes of the UTF-8 encoded text processed so far, the total number
+            of bytes, and the number of chunks generated so far, such as for a progress bar.
+            Chunking stops if it raises an exception.
 
     Returns:
         A list of strings, one for each chunk. If `trim` was specified in the text
         splitter, then each chunk will already be trimmed as well.
     */
-    fn chunks<'text, 'splitter: 'text>(&'splitter self, text: &'text str) -> Vec<&'text str> {
-        self.splitter.chunks(text).collect()
+    #[pyo3(signature = (text, cancel=None, on_progress=None))]
+    fn chunks<'text, 'splitter: 'text>(
+        &'splitter self,
+        text: &'text str,
+        cancel: Option<PyObject>,
+        on_progress: Option<PyObject>,
+    ) -> PyResult<Vec<&'text str>> {
+        let cancellation = PyCancellation::new(cancel);
+        let chunks = cancellation.chunk_indices(
+            on_progress,
+            |on_progress| self.splitter.chunk_indices_with_progress(text, on_progress),
+            |budget| self.splitter.chunk_indices_within_budget(text, budget),
+        )?;
+        Ok(chunks.into_iter().map(|(_, chunk)| chunk).collect())
     }
 
     /**
@@ -1407,6 +2035,10 @@ impl PyCodeSplitter {
 
     Args:
         text (str): Text to split.
+        cancel (Callable[[], bool], optional): Called regularly while chunking. Once it
+            returns `True`, chunking stops and a `concurrent.futures.CancelledError` is raised.
+        `on_progress` (Callable[[int, int, int], None], optional): Called after each chunk with
+            the bytes processed, total bytes, and number of chunks so far. See `chunks`.
 
     Returns:
         A list of tuples, one for each chunk. The first item will be the character offset relative
@@ -1414,20 +2046,55 @@ impl PyCodeSplitter {
         If `trim` was specified in the text splitter, then each chunk will already be
         trimmed as well.
     */
+    #[pyo3(signature = (text, cancel=None, on_progress=None))]
     fn chunk_indices<'text, 'splitter: 'text>(
         &'splitter self,
         text: &'text str,
-    ) -> Vec<(usize, &'text str)> {
+        cancel: Option<PyObject>,
+        on_progress: Option<PyObject>,
+    ) -> PyResult<Vec<(usize, &'text str)>> {
+        let cancellation = PyCancellation::new(cancel);
+        let chunks = cancellation.chunk_indices(
+            on_progress,
+            |on_progress| self.splitter.chunk_indices_with_progress(text, on_progress),
+            |budget| self.splitter.chunk_indices_within_budget(text, budget),
+        )?;
         let mut offsets = ByteToCharOffsetTracker::new(text);
-        self.splitter
-            .chunk_indices(text)
+        Ok(chunks
+            .into_iter()
             .map(|c| offsets.map_byte_to_char(c))
-            .collect()
+            .collect())
+    }
+
+    /**
+    Generate a list of chunks from a given text, along with their character offsets in the original text and how much of each chunk overlaps with the previous one. Each chunk will be up to the `capacity`.
+
+    This is useful if `overlap` was specified, and you want to strip the duplicated prefix of each chunk when displaying results.
+
+    See `chunks` for more information.
+
+    Args:
+        text (str): Text to split.
+
+    Returns:
+        A list of tuples, one for each chunk. The first item will be the character offset relative
+        to the original text. The second item is the chunk itself. The third item is the number
+        of characters at the start of the chunk that are also part of the previous chunk.
+        If `trim` was specified in the text splitter, then each chunk will already be
+        trimmed as well.
+    */
+    fn chunk_spans<'text, 'splitter: 'text>(
+        &'splitter self,
+        text: &'text str,
+    ) -> Vec<(usize, &'text str, usize)> {
+        chunk_spans(text, self.splitter.chunk_indices(text))
     }
 
     /**
     Generate a list of chunks for a given set of texts. Each chunk will be up to the `capacity`.
 
+    The texts are split in parallel, and the GIL is released for the whole batch.
+
     See `chunks` for more information.
 
     Args:
@@ -1438,16 +2105,20 @@ impl PyCodeSplitter {
         If `trim` was specified in the text splitter, then each chunk will already be
         trimmed as well.
     */
-    fn chunk_all(&self, texts: Vec<String>) -> Vec<Vec<String>> {
-        texts
-            .into_par_iter()
-            .map(|text| self.splitter.chunks(&text).map(ToOwned::to_owned).collect())
-            .collect()
+    fn chunk_all(&self, py: Python<'_>, texts: Vec<String>) -> Vec<Vec<String>> {
+        py.allow_threads(|| {
+            texts
+                .into_par_iter()
+                .map(|text| self.splitter.chunks(&text).map(ToOwned::to_owned).collect())
+                .collect()
+        })
     }
 
     /**
     Generate a list of chunks for a given set of text, along with their character offsets in the original text. Each chunk will be up to the `capacity`.
 
+    The texts are split in parallel, and the GIL is released for the whole batch.
+
     See `chunks` for more information.
 
     Args:
@@ -1460,24 +2131,60 @@ impl PyCodeSplitter {
         If `trim` was specified in the text splitter, then each chunk will already be
         trimmed as well.
     */
-    fn chunk_all_indices(&self, texts: Vec<String>) -> Vec<Vec<(usize, String)>> {
-        texts
-            .into_par_iter()
-            .map(|text| {
-                let mut offsets = ByteToCharOffsetTracker::new(&text);
-                self.splitter
-                    .chunk_indices(&text)
-                    .map(|c| offsets.map_byte_to_char(c))
-                    .map(|(i, c)| (i, c.to_owned()))
-                    .collect()
-            })
-            .collect()
+    fn chunk_all_indices(&self, py: Python<'_>, texts: Vec<String>) -> Vec<Vec<(usize, String)>> {
+        py.allow_threads(|| {
+            texts
+                .into_par_iter()
+                .map(|text| {
+                    let mut offsets = ByteToCharOffsetTracker::new(&text);
+                    self.splitter
+                        .chunk_indices(&text)
+                        .map(|c| offsets.map_byte_to_char(c))
+                        .map(|(i, c)| (i, c.to_owned()))
+                        .collect()
+                })
+                .collect()
+        })
     }
 }
 
+/**
+Canonical test vectors: texts, configurations, and the chunks they are expected to produce,
+so you can check that chunking behaves the same in your environment as in the Rust crate.
+
+```python
+from semantic_text_splitter import MarkdownSplitter, TextSplitter, test_vectors
+
+for vector in test_vectors():
+    config = vector["config"]
+    capacity = config["capacity"]
+    if config["max_capacity"] is not None:
+        capacity = (capacity, config["max_capacity"])
+    splitter_class = MarkdownSplitter if vector["format"] == "markdown" else TextSplitter
+    splitter = splitter_class(capacity, overlap=config["overlap"], trim=config["trim"])
+    expected = [(chunk["char_start"], chunk["text"]) for chunk in vector["chunks"]]
+    assert splitter.chunk_indices(vector["text"]) == expected, vector["name"]
+```
+
+Returns:
+    A list of dicts, one for each test vector, with its `name`, `format` (`"text"` or
+    `"markdown"`), `text`, `config` (`capacity`, `max_capacity`, `overlap`, and `trim`),
+    and expected `chunks` (`start` byte offset, `char_start` character offset, and `text`).
+    Chunk sizes are measured in characters.
+*/
+#[pyfunction]
+fn test_vectors(py: Python<'_>) -> PyResult<Bound<'_, PyAny>> {
+    py.import("json")?
+        .call_method1("loads", (text_splitter::test_vectors::json(),))
+}
+
 #[doc = include_str!("../README.md")]
 #[pymodule]
 mod semantic_text_splitter {
+    #[pymodule_export]
+    use super::test_vectors;
+    #[pymodule_export]
+    use super::PyChunkFileIterator;
     #[pymodule_export]
     use super::PyCodeSplitter;
     #[pymodule_export]
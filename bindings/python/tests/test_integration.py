@@ -1,6 +1,12 @@
+from concurrent.futures import CancelledError
+import io
+from pathlib import Path
+
 import pytest
+import semantic_text_splitter
 from semantic_text_splitter import CodeSplitter, MarkdownSplitter, TextSplitter
 from tokenizers import Tokenizer  # type: ignore
+import tree_sitter
 import tree_sitter_python
 
 
@@ -38,6 +44,27 @@ def test_chunk_overlap_indices() -> None:
     ]
 
 
+def test_chunk_overlap_spans() -> None:
+    splitter = TextSplitter(capacity=4, overlap=2)
+    text = "12345678é0"
+
+    spans = splitter.chunk_spans(text)
+    assert spans == [
+        (0, "1234", 0),
+        (2, "3456", 2),
+        (4, "5678", 2),
+        (6, "78é0", 2),
+    ]
+    assert "".join(chunk[overlap:] for _, chunk, overlap in spans) == text
+
+
+def test_chunk_spans_without_overlap() -> None:
+    splitter = MarkdownSplitter(capacity=4)
+    text = "123\n123"
+
+    assert splitter.chunk_spans(text) == [(0, "123", 0), (4, "123", 0)]
+
+
 def test_chunks_trim() -> None:
     splitter = TextSplitter(capacity=4)
     text = "123\n123"
@@ -279,6 +306,22 @@ def test_invalid_language_type() -> None:
         CodeSplitter(tree_sitter_python.language, 40)  # type: ignore
 
 
+def test_code_splitter_tree_sitter_language() -> None:
+    language = tree_sitter.Language(tree_sitter_python.language())
+    splitter = CodeSplitter(language, 40)
+    assert splitter.chunks("def foo():\n    return 42") == ["def foo():\n    return 42"]
+
+
+def test_int_language_pointer_rejected() -> None:
+    with pytest.raises(TypeError):
+        CodeSplitter(0, 40)
+
+
+def test_invalid_language_pointer() -> None:
+    with pytest.raises(ValueError):
+        CodeSplitter.unsafe_language_from_pointer(0)
+
+
 def test_code_char_indices() -> None:
     splitter = CodeSplitter(tree_sitter_python.language(), capacity=4)
     text = "123\n456\n789"
@@ -314,6 +357,13 @@ def test_chunk_all_indices() -> None:
     assert chunks == [[(0, "123"), (4, "123")], [(0, "456"), (4, "456")]]
 
 
+def test_chunk_all_indices_callback() -> None:
+    splitter = TextSplitter.from_callback(lambda text: len(text), 4)
+    texts = ["é23\n123", "456\n45é"] * 50
+    chunks = splitter.chunk_all_indices(texts)
+    assert chunks == [[(0, "é23"), (4, "123")], [(0, "456"), (4, "45é")]] * 50
+
+
 def test_chunk_all_markdown() -> None:
     splitter = MarkdownSplitter(4)
     texts = ["123\n123", "456\n456"]
@@ -340,3 +390,84 @@ def test_chunk_all_indices_code() -> None:
     texts = ["123\n123", "456\n456"]
     chunks = splitter.chunk_all_indices(texts)
     assert chunks == [[(0, "123"), (4, "123")], [(0, "456"), (4, "456")]]
+
+
+def test_chunks_cancel() -> None:
+    splitter = TextSplitter(4)
+    text = "123\n123"
+    assert splitter.chunks(text, cancel=lambda: False) == ["123", "123"]
+    with pytest.raises(CancelledError):
+        splitter.chunks(text, cancel=lambda: True)
+
+
+def test_chunk_indices_cancel_markdown() -> None:
+    splitter = MarkdownSplitter(4)
+    with pytest.raises(CancelledError):
+        splitter.chunk_indices("123\n123", cancel=lambda: True)
+
+
+def test_chunks_progress() -> None:
+    splitter = TextSplitter(4)
+    progress = []
+    chunks = splitter.chunks(
+        "123\n123 ", on_progress=lambda *args: progress.append(args)
+    )
+    assert chunks == ["123", "123"]
+    assert progress == [(3, 8, 1), (7, 8, 2), (8, 8, 2)]
+
+
+def test_chunk_indices_progress_error() -> None:
+    def on_progress(bytes_processed: int, total_bytes: int, chunks: int) -> None:
+        raise ValueError("Stop")
+
+    splitter = CodeSplitter(tree_sitter_python.language(), 4)
+    with pytest.raises(ValueError):
+        splitter.chunk_indices("123\n123", on_progress=on_progress)
+
+
+def test_chunk_file(tmp_path: Path) -> None:
+    splitter = TextSplitter(20)
+    text = "Some text.\n\nWith paragraphs ü and sentences. " * 200
+    path = tmp_path / "text.txt"
+    path.write_text(text, encoding="utf-8")
+    with splitter.chunk_file(path, block_size=64) as chunks:
+        assert list(chunks) == splitter.chunks(text)
+
+
+def test_chunk_file_object() -> None:
+    splitter = TextSplitter(20)
+    text = "Some text.\n\nWith paragraphs ü and sentences. " * 200
+    binary = io.BytesIO(text.encode("utf-8"))
+    assert list(splitter.chunk_file(binary, block_size=7)) == splitter.chunks(text)
+    assert list(splitter.chunk_file(io.StringIO(text))) == splitter.chunks(text)
+
+
+def test_chunk_file_close() -> None:
+    splitter = TextSplitter(4)
+    with splitter.chunk_file(io.StringIO("123\n123\n123")) as chunks:
+        assert next(chunks) == "123"
+    assert list(chunks) == []
+
+
+def test_chunk_file_invalid_type() -> None:
+    splitter = TextSplitter(4)
+    with pytest.raises(TypeError):
+        splitter.chunk_file(123)  # type: ignore
+
+
+def test_test_vectors() -> None:
+    vectors = semantic_text_splitter.test_vectors()
+    assert vectors
+    for vector in vectors:
+        config = vector["config"]
+        capacity = config["capacity"]
+        if config["max_capacity"] is not None:
+            capacity = (capacity, config["max_capacity"])
+        splitter_class = (
+            MarkdownSplitter if vector["format"] == "markdown" else TextSplitter
+        )
+        splitter = splitter_class(
+            capacity, overlap=config["overlap"], trim=config["trim"]
+        )
+        expected = [(chunk["char_start"], chunk["text"]) for chunk in vector["chunks"]]
+        assert splitter.chunk_indices(vector["text"]) == expected, vector["name"]
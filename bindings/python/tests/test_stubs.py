@@ -0,0 +1,88 @@
+"""Checks that the type stubs match the signatures of the compiled module."""
+
+import ast
+import inspect
+from pathlib import Path
+from typing import Dict, List, Tuple
+
+import pytest
+import semantic_text_splitter
+
+STUB_PATH = Path(__file__).parent.parent / "semantic_text_splitter.pyi"
+
+
+def stub_classes() -> Dict[str, Dict[str, ast.FunctionDef]]:
+    """The functions of each class in the stub file, by name."""
+    tree = ast.parse(STUB_PATH.read_text())
+    return {
+        node.name: {
+            item.name: item for item in node.body if isinstance(item, ast.FunctionDef)
+        }
+        for node in tree.body
+        if isinstance(node, ast.ClassDef)
+    }
+
+
+def stub_parameters(function: ast.FunctionDef) -> List[Tuple[str, object]]:
+    """Names and default values of the parameters of a stub function."""
+    args = function.args.args
+    defaults = [inspect.Parameter.empty] * (
+        len(args) - len(function.args.defaults)
+    ) + [ast.literal_eval(default) for default in function.args.defaults]
+    return [
+        (arg.arg, default)
+        for arg, default in zip(args, defaults)
+        if arg.arg not in ("self", "cls")
+    ]
+
+
+def runtime_parameters(function: object) -> List[Tuple[str, object]]:
+    """Names and default values of the parameters of a compiled function."""
+    try:
+        signature = inspect.signature(function)  # type: ignore
+    except ValueError:
+        pytest.skip(f"No runtime signature for {function}")
+    return [
+        (name, parameter.default)
+        for name, parameter in signature.parameters.items()
+        if name not in ("self", "cls")
+    ]
+
+
+RUNTIME_CLASSES = {
+    name: cls
+    for name, cls in inspect.getmembers(semantic_text_splitter, inspect.isclass)
+    if not name.startswith("_")
+}
+
+RUNTIME_METHODS = [
+    (class_name, method_name)
+    for class_name, cls in RUNTIME_CLASSES.items()
+    for method_name, _ in inspect.getmembers(cls, callable)
+    if not method_name.startswith("_")
+]
+
+
+def test_all_classes_have_stubs() -> None:
+    assert sorted(RUNTIME_CLASSES) == sorted(stub_classes())
+
+
+@pytest.mark.parametrize(("class_name", "method_name"), RUNTIME_METHODS)
+def test_method_stubs_match_runtime(class_name: str, method_name: str) -> None:
+    stub = stub_classes()[class_name].get(method_name)
+    assert stub is not None, f"{class_name}.{method_name} is missing from the stubs"
+
+    method = getattr(RUNTIME_CLASSES[class_name], method_name)
+    assert stub_parameters(stub) == runtime_parameters(method)
+
+
+@pytest.mark.parametrize("class_name", sorted(RUNTIME_CLASSES))
+def test_constructor_stubs_match_runtime(class_name: str) -> None:
+    stub = stub_classes()[class_name].get("__init__")
+    if stub is None:
+        # Classes without a constructor can only be returned by other methods
+        with pytest.raises(TypeError):
+            RUNTIME_CLASSES[class_name]()
+        return
+
+    assert stub_parameters(stub) == runtime_parameters(RUNTIME_CLASSES[class_name])
@@ -0,0 +1,293 @@
+/*!
+# Arrow chunking
+
+Chunk a string column of an [Arrow](https://docs.rs/arrow-array) array, for
+dataframe-native ingestion pipelines. Query engines built on Arrow, such as
+[DataFusion](https://docs.rs/datafusion) and
+[Polars](https://docs.rs/polars), can wrap an [`ArrowChunker`] in a
+user-defined function.
+
+- [`ArrowChunker::chunk_lists`] returns one list of chunks per row, with the
+  same length as the input, as a scalar UDF has to. Use the engine's
+  `unnest` or `explode` to turn the lists into rows.
+- [`ArrowChunker::chunk_rows`] explodes the column into one row per chunk
+  directly, with the index of the row each chunk came from, to join back
+  onto the other columns.
+
+Each chunk has its index within the row, the byte offsets of its start and
+end within the text of the row, and its text. Null rows don't have any
+chunks.
+
+```
+use arrow_array::{cast::AsArray, types::UInt64Type, StringArray};
+use text_splitter::{arrow::ArrowChunker, TextSplitter};
+
+let chunker = ArrowChunker::new(TextSplitter::new(10));
+let column = StringArray::from(vec![Some("Some text\n\nfrom a\ndocument"), None, Some("More")]);
+let rows = chunker.chunk_rows(&column).unwrap();
+
+assert_eq!(4, rows.num_rows());
+assert_eq!(
+    vec![0, 0, 0, 2],
+    rows["row"].as_primitive::<UInt64Type>().values().to_vec()
+);
+assert_eq!("from a", rows["text"].as_string::<i32>().value(1));
+```
+*/
+
+use std::sync::Arc;
+
+use arrow_array::{
+    builder::StringBuilder, cast::AsArray, Array, ArrayRef, ListArray, RecordBatch, StructArray,
+    UInt64Array,
+};
+use arrow_buffer::OffsetBuffer;
+use arrow_schema::{ArrowError, DataType, Field, Fields, Schema, SchemaRef};
+use thiserror::Error;
+
+use crate::{ChunkSizer, DocumentSplitter};
+
+/// Indicates the column could not be chunked.
+#[derive(Error, Debug)]
+#[error(transparent)]
+pub struct ArrowChunkerError(#[from] ArrowChunkerErrorRepr);
+
+/// Private error and free to change across minor version of the crate.
+#[derive(Error, Debug)]
+enum ArrowChunkerErrorRepr {
+    #[error("Expected a string column, found {0}")]
+    UnsupportedType(DataType),
+    #[error(transparent)]
+    Arrow(#[from] ArrowError),
+}
+
+/// Chunks string columns of Arrow arrays with the given splitter.
+#[derive(Debug)]
+pub struct ArrowChunker<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Splitter to chunk the text of each row with
+    splitter: DocumentSplitter<Sizer>,
+}
+
+impl<Sizer> ArrowChunker<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Creates a new [`ArrowChunker`], which chunks the text of each row with
+    /// the given splitter.
+    #[must_use]
+    pub fn new(splitter: impl Into<DocumentSplitter<Sizer>>) -> Self {
+        Self {
+            splitter: splitter.into(),
+        }
+    }
+
+    /// Fields of each chunk: its `index` within the row, the byte offsets of
+    /// its `start` and `end` within the text of the row, and its `text`.
+    #[must_use]
+    pub fn chunk_fields() -> Fields {
+        Fields::from(vec![
+            Field::new("index", DataType::UInt64, false),
+            Field::new("start", DataType::UInt64, false),
+            Field::new("end", DataType::UInt64, false),
+            Field::new("text", DataType::Utf8, false),
+        ])
+    }
+
+    /// Data type returned by [`Self::chunk_lists`], a list of structs with
+    /// the [`Self::chunk_fields`], such as for the return type of a scalar
+    /// UDF.
+    #[must_use]
+    pub fn list_data_type() -> DataType {
+        DataType::new_list(DataType::Struct(Self::chunk_fields()), true)
+    }
+
+    /// Schema of the batches returned by [`Self::chunk_rows`]: the index of
+    /// the `row` each chunk came from, followed by the
+    /// [`Self::chunk_fields`].
+    #[must_use]
+    pub fn row_schema() -> SchemaRef {
+        let mut fields = vec![Arc::new(Field::new("row", DataType::UInt64, false))];
+        fields.extend(Self::chunk_fields().iter().cloned());
+        Arc::new(Schema::new(fields))
+    }
+
+    /// Chunk each row of a string column, and return a list of the chunks of
+    /// each row. Null rows are null lists.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the column isn't a `Utf8`, `LargeUtf8`, or
+    /// `Utf8View` column.
+    pub fn chunk_lists(&self, column: &dyn Array) -> Result<ListArray, ArrowChunkerError> {
+        let (_, lengths, columns) = self.chunk_column(column)?.into_columns();
+        let values = StructArray::try_new(Self::chunk_fields(), columns, None)
+            .map_err(ArrowChunkerErrorRepr::Arrow)?;
+        let offsets = OffsetBuffer::from_lengths(lengths);
+        Ok(ListArray::try_new(
+            Arc::new(Field::new_list_field(values.data_type().clone(), true)),
+            offsets,
+            Arc::new(values),
+            column.logical_nulls(),
+        )
+        .map_err(ArrowChunkerErrorRepr::Arrow)?)
+    }
+
+    /// Chunk each row of a string column, and return a batch with one row
+    /// per chunk, in the [`Self::row_schema`].
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the column isn't a `Utf8`, `LargeUtf8`, or
+    /// `Utf8View` column.
+    pub fn chunk_rows(&self, column: &dyn Array) -> Result<RecordBatch, ArrowChunkerError> {
+        let (rows, _, chunk_columns) = self.chunk_column(column)?.into_columns();
+        let mut columns = vec![Arc::new(UInt64Array::from(rows)) as ArrayRef];
+        columns.extend(chunk_columns);
+        Ok(RecordBatch::try_new(Self::row_schema(), columns)
+            .map_err(ArrowChunkerErrorRepr::Arrow)?)
+    }
+
+    /// Chunk the text of each row of the column.
+    fn chunk_column(&self, column: &dyn Array) -> Result<ChunkColumns, ArrowChunkerError> {
+        let texts: Box<dyn Iterator<Item = Option<&str>>> = match column.data_type() {
+            DataType::Utf8 => Box::new(column.as_string::<i32>().iter()),
+            DataType::LargeUtf8 => Box::new(column.as_string::<i64>().iter()),
+            DataType::Utf8View => Box::new(column.as_string_view().iter()),
+            data_type => Err(ArrowChunkerErrorRepr::UnsupportedType(data_type.clone()))?,
+        };
+
+        let mut chunks = ChunkColumns::default();
+        for (row, text) in texts.enumerate() {
+            let mut length = 0;
+            for (index, (offset, chunk)) in self
+                .splitter
+                .chunk_indices(text.unwrap_or_default())
+                .enumerate()
+            {
+                chunks.rows.push(row as u64);
+                chunks.indices.push(index as u64);
+                chunks.starts.push(offset as u64);
+                chunks.ends.push((offset + chunk.len()) as u64);
+                chunks.texts.append_value(chunk);
+                length += 1;
+            }
+            chunks.lengths.push(length);
+        }
+        Ok(chunks)
+    }
+}
+
+/// Columns of the chunks of every row, in order.
+#[derive(Default)]
+struct ChunkColumns {
+    /// Index of the row of each chunk
+    rows: Vec<u64>,
+    /// Number of chunks in each row
+    lengths: Vec<usize>,
+    /// Index of each chunk within its row
+    indices: Vec<u64>,
+    /// Start byte offset of each chunk
+    starts: Vec<u64>,
+    /// End byte offset of each chunk
+    ends: Vec<u64>,
+    /// Text of each chunk
+    texts: StringBuilder,
+}
+
+impl ChunkColumns {
+    /// The row of each chunk, the number of chunks in each row, and the
+    /// arrays in the order of the [`ArrowChunker::chunk_fields`].
+    fn into_columns(mut self) -> (Vec<u64>, Vec<usize>, Vec<ArrayRef>) {
+        let columns = vec![
+            Arc::new(UInt64Array::from(self.indices)) as ArrayRef,
+            Arc::new(UInt64Array::from(self.starts)),
+            Arc::new(UInt64Array::from(self.ends)),
+            Arc::new(self.texts.finish()),
+        ];
+        (self.rows, self.lengths, columns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{types::UInt64Type, Int32Array, LargeStringArray, StringArray};
+
+    use crate::TextSplitter;
+
+    use super::*;
+
+    #[test]
+    fn chunk_lists_match_input_length() {
+        let chunker = ArrowChunker::new(TextSplitter::new(10));
+        let column = LargeStringArray::from(vec![None, Some("Some text\n\nfrom a"), Some("")]);
+
+        let lists = chunker.chunk_lists(&column).unwrap();
+
+        assert_eq!(
+            lists.data_type(),
+            &ArrowChunker::<crate::Characters>::list_data_type()
+        );
+        assert_eq!(lists.len(), 3);
+        assert!(lists.is_null(0));
+        assert!(lists.is_valid(2));
+        assert_eq!(lists.value_offsets(), &[0, 0, 2, 2]);
+
+        let chunks = lists.value(1);
+        let chunks = chunks.as_struct();
+        assert_eq!(
+            chunks
+                .column_by_name("start")
+                .unwrap()
+                .as_primitive::<UInt64Type>()
+                .values()
+                .to_vec(),
+            vec![0, 11]
+        );
+        assert_eq!(
+            chunks
+                .column_by_name("end")
+                .unwrap()
+                .as_primitive::<UInt64Type>()
+                .values()
+                .to_vec(),
+            vec![9, 17]
+        );
+        assert_eq!(
+            chunks
+                .column_by_name("text")
+                .unwrap()
+                .as_string::<i32>()
+                .value(1),
+            "from a"
+        );
+    }
+
+    #[test]
+    fn chunk_rows_match_schema() {
+        let chunker = ArrowChunker::new(TextSplitter::new(10));
+        let column = StringArray::from(vec!["Some text", "from a\ndocument"]);
+
+        let rows = chunker.chunk_rows(&column).unwrap();
+
+        assert_eq!(
+            rows.schema(),
+            ArrowChunker::<crate::Characters>::row_schema()
+        );
+        assert_eq!(
+            rows["index"].as_primitive::<UInt64Type>().values().to_vec(),
+            vec![0, 0, 1]
+        );
+    }
+
+    #[test]
+    fn non_string_columns_are_rejected() {
+        let chunker = ArrowChunker::new(TextSplitter::new(10));
+
+        let error = chunker.chunk_rows(&Int32Array::from(vec![1])).unwrap_err();
+
+        assert_eq!(error.to_string(), "Expected a string column, found Int32");
+    }
+}
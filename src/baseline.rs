@@ -0,0 +1,298 @@
+/*!
+# Baseline splitters
+
+Simple, non-semantic splitters to compare the semantic splitters against, both
+for chunk quality and performance. They use the same [`ChunkConfig`] and the
+same API as the other splitters, so they can be swapped in and out easily.
+
+- [`FixedSizeSplitter`] fills each chunk with as many characters as fit,
+  regardless of any structure in the text.
+- [`RecursiveCharacterSplitter`] splits by a list of separators, in order,
+  and merges the pieces back together, similar to `LangChain`'s
+  `RecursiveCharacterTextSplitter`.
+
+These are not intended for production use.
+*/
+
+use std::ops::Range;
+
+use crate::{trim::Trim, ChunkConfig, ChunkSizer};
+
+/// Splits text into chunks that contain as many characters as will fit in the
+/// chunk capacity, without taking the content of the text into account.
+///
+/// ```
+/// use text_splitter::baseline::FixedSizeSplitter;
+///
+/// let splitter = FixedSizeSplitter::new(10);
+/// let chunks = splitter.chunks("Some text from a document").collect::<Vec<_>>();
+///
+/// assert_eq!(vec!["Some text", "from a doc", "ument"], chunks);
+/// ```
+#[derive(Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub struct FixedSizeSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Method of determining chunk sizes.
+    chunk_config: ChunkConfig<Sizer>,
+}
+
+impl<Sizer> FixedSizeSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Creates a new [`FixedSizeSplitter`].
+    #[must_use]
+    pub fn new(chunk_config: impl Into<ChunkConfig<Sizer>>) -> Self {
+        Self {
+            chunk_config: chunk_config.into(),
+        }
+    }
+
+    /// Generate a list of chunks from a given text. Each chunk will be up to
+    /// the max chunk capacity, unless a single character is already larger.
+    pub fn chunks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = &'text str> + 'splitter {
+        self.chunk_indices(text).map(|(_, chunk)| chunk)
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets.
+    ///
+    /// See [`FixedSizeSplitter::chunks`] for more information.
+    pub fn chunk_indices<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter {
+        let boundaries = text
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(Some(text.len()))
+            .collect::<Vec<_>>();
+        let ranges = fill_chunks(&self.chunk_config, text, &boundaries);
+        emit(self.chunk_config.trim, text, ranges)
+    }
+}
+
+/// Splits text by the first separator in a list that is found in the text,
+/// and recursively splits pieces that are still too large with the next
+/// separators. Neighboring pieces are then merged back together as long as
+/// they fit in the chunk capacity.
+///
+/// The default separators are `"\n\n"`, `"\n"`, `" "`, and `""` (which splits
+/// between characters).
+///
+/// ```
+/// use text_splitter::baseline::RecursiveCharacterSplitter;
+///
+/// let splitter = RecursiveCharacterSplitter::new(10);
+/// let chunks = splitter.chunks("Some text\n\nfrom a\ndocument").collect::<Vec<_>>();
+///
+/// assert_eq!(vec!["Some text", "from a", "document"], chunks);
+/// ```
+#[derive(Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub struct RecursiveCharacterSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Method of determining chunk sizes.
+    chunk_config: ChunkConfig<Sizer>,
+    /// Separators to try, in order.
+    separators: Vec<String>,
+}
+
+impl<Sizer> RecursiveCharacterSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Creates a new [`RecursiveCharacterSplitter`] with the default separators.
+    #[must_use]
+    pub fn new(chunk_config: impl Into<ChunkConfig<Sizer>>) -> Self {
+        Self {
+            chunk_config: chunk_config.into(),
+            separators: ["\n\n", "\n", " ", ""].map(String::from).to_vec(),
+        }
+    }
+
+    /// Use a custom list of separators, tried in order. Separators are kept
+    /// at the start of the piece that follows them.
+    #[must_use]
+    pub fn with_separators(mut self, separators: &[&str]) -> Self {
+        self.separators = separators.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    /// Generate a list of chunks from a given text. Each chunk will be up to
+    /// the max chunk capacity, unless a piece couldn't be split by any of the
+    /// separators.
+    pub fn chunks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = &'text str> + 'splitter {
+        self.chunk_indices(text).map(|(_, chunk)| chunk)
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets.
+    ///
+    /// See [`RecursiveCharacterSplitter::chunks`] for more information.
+    pub fn chunk_indices<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter {
+        let mut boundaries = vec![0];
+        self.split(text, 0..text.len(), &self.separators, &mut boundaries);
+        let ranges = fill_chunks(&self.chunk_config, text, &boundaries);
+        emit(self.chunk_config.trim, text, ranges)
+    }
+
+    /// Collect the end offset of each piece of the given range.
+    fn split(
+        &self,
+        text: &str,
+        range: Range<usize>,
+        separators: &[String],
+        boundaries: &mut Vec<usize>,
+    ) {
+        let piece = &text[range.clone()];
+        let Some(index) = separators
+            .iter()
+            .position(|s| s.is_empty() || piece.contains(s.as_str()))
+        else {
+            boundaries.push(range.end);
+            return;
+        };
+        let separator = &separators[index];
+
+        let mut starts = if separator.is_empty() {
+            piece
+                .char_indices()
+                .map(|(i, _)| i)
+                .skip(1)
+                .collect::<Vec<_>>()
+        } else {
+            piece
+                .match_indices(separator.as_str())
+                .map(|(i, _)| i)
+                .filter(|&i| i > 0)
+                .collect()
+        };
+        starts.push(piece.len());
+
+        let mut start = 0;
+        for end in starts {
+            let sub_range = range.start + start..range.start + end;
+            let size = self.chunk_config.sizer.size(&text[sub_range.clone()]);
+            if size > self.chunk_config.capacity.max && index + 1 < separators.len() {
+                self.split(text, sub_range, &separators[index + 1..], boundaries);
+            } else {
+                boundaries.push(sub_range.end);
+            }
+            start = end;
+        }
+    }
+}
+
+/// Greedily merge the pieces between the given boundaries into chunks that fit
+/// within the capacity, keeping trailing pieces that fit in the overlap.
+fn fill_chunks<Sizer: ChunkSizer>(
+    config: &ChunkConfig<Sizer>,
+    text: &str,
+    boundaries: &[usize],
+) -> Vec<Range<usize>> {
+    let size = |range: Range<usize>| config.sizer.size(&text[range]);
+    let mut ranges = vec![];
+    let mut start = 0;
+
+    while start + 1 < boundaries.len() {
+        // Largest end that fits, but always include at least one piece
+        let end = start
+            + 1
+            + boundaries[start + 2..]
+                .partition_point(|&b| size(boundaries[start]..b) <= config.capacity.max);
+        ranges.push(boundaries[start]..boundaries[end]);
+
+        if end + 1 == boundaries.len() {
+            break;
+        }
+        // Move back as far as the overlap allows, but always move forward
+        start = start
+            + 1
+            + boundaries[start + 1..end]
+                .partition_point(|&b| size(b..boundaries[end]) > config.overlap);
+    }
+
+    ranges
+}
+
+/// Turn chunk ranges into chunks, applying trimming settings.
+fn emit(trim: bool, text: &str, ranges: Vec<Range<usize>>) -> impl Iterator<Item = (usize, &str)> {
+    let trim = if trim { Trim::All } else { Trim::None };
+    ranges
+        .into_iter()
+        .map(move |range| trim.trim(range.start, &text[range]))
+        .filter(|(_, chunk)| !chunk.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_size_fills_chunks() {
+        let splitter = FixedSizeSplitter::new(ChunkConfig::new(4).with_trim(false));
+        let chunks = splitter.chunk_indices("abcdéfghi").collect::<Vec<_>>();
+
+        assert_eq!(vec![(0, "abcd"), (4, "éfgh"), (9, "i")], chunks);
+    }
+
+    #[test]
+    fn fixed_size_overlap() {
+        let config = ChunkConfig::new(4)
+            .with_trim(false)
+            .with_overlap(2)
+            .unwrap();
+        let chunks = FixedSizeSplitter::new(config)
+            .chunks("abcdefgh")
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec!["abcd", "cdef", "efgh"], chunks);
+    }
+
+    #[test]
+    fn fixed_size_empty_text() {
+        let splitter = FixedSizeSplitter::new(4);
+
+        assert_eq!(splitter.chunks("").count(), 0);
+    }
+
+    #[test]
+    fn recursive_falls_back_to_later_separators() {
+        let splitter = RecursiveCharacterSplitter::new(ChunkConfig::new(8).with_trim(false));
+        let chunks = splitter
+            .chunks("aaaa bbbb\n\ncccccccccc")
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec!["aaaa", " bbbb\n\nc", "cccccccc", "c"], chunks);
+    }
+
+    #[test]
+    fn recursive_custom_separators() {
+        let splitter = RecursiveCharacterSplitter::new(ChunkConfig::new(5).with_trim(false))
+            .with_separators(&[";"]);
+        let chunks = splitter.chunks("a;b;c;dddddddd").collect::<Vec<_>>();
+
+        assert_eq!(vec!["a;b;c", ";dddddddd"], chunks);
+    }
+
+    #[test]
+    fn recursive_chunks_join_to_text() {
+        let text = "Some text\n\nfrom a\ndocument with some more words in it.";
+        let splitter = RecursiveCharacterSplitter::new(ChunkConfig::new(12).with_trim(false));
+
+        assert_eq!(splitter.chunks(text).collect::<String>(), text);
+    }
+}
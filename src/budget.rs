@@ -0,0 +1,191 @@
+/*!
+# [`ChunkingBudget`]
+Limit how long chunking a single text may take, so that a pathological
+document with an expensive chunk sizer can't stall the thread chunking it.
+*/
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use thiserror::Error;
+
+/// A time limit and/or cancellation hooks for chunking a text, for use with
+/// methods like `chunk_indices_within_budget`.
+///
+/// The budget is checked cooperatively, before each chunk is generated and
+/// before each step of the binary search for the end of a chunk, so chunking
+/// stops within a single call to the chunk sizer after the budget is
+/// exhausted.
+///
+/// ```
+/// use std::{sync::{atomic::AtomicBool, Arc}, time::Duration};
+///
+/// use text_splitter::ChunkingBudget;
+///
+/// let cancelled = Arc::new(AtomicBool::new(false));
+/// let budget = ChunkingBudget::new()
+///     .with_deadline(Duration::from_secs(1))
+///     .with_cancellation(Arc::clone(&cancelled));
+///
+/// assert!(!budget.is_exhausted());
+/// ```
+#[derive(Clone, Default)]
+pub struct ChunkingBudget {
+    /// When chunking has to stop, if there is a time limit
+    deadline: Option<Instant>,
+    /// Flag that can be set from another thread to stop chunking
+    cancelled: Option<Arc<AtomicBool>>,
+    /// Callback that returns `true` once chunking should stop
+    check: Option<Arc<dyn Fn() -> bool + Send + Sync>>,
+}
+
+impl fmt::Debug for ChunkingBudget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChunkingBudget")
+            .field("deadline", &self.deadline)
+            .field("cancelled", &self.cancelled)
+            .field("check", &self.check.is_some())
+            .finish()
+    }
+}
+
+impl ChunkingBudget {
+    /// Creates a new [`ChunkingBudget`] without any limits.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop chunking once the given duration has passed, counting from when
+    /// this method is called.
+    #[must_use]
+    pub fn with_deadline(mut self, duration: Duration) -> Self {
+        self.deadline = Instant::now().checked_add(duration);
+        self
+    }
+
+    /// Stop chunking once the flag is set to `true`, such as from another
+    /// thread when the request the text came from was cancelled.
+    #[must_use]
+    pub fn with_cancellation(mut self, cancelled: Arc<AtomicBool>) -> Self {
+        self.cancelled = Some(cancelled);
+        self
+    }
+
+    /// Stop chunking once the callback returns `true`, such as to check a
+    /// cancellation token of an async runtime, or for pending signals like
+    /// an interrupt. The callback is called often, so it should be cheap.
+    ///
+    /// ```
+    /// use text_splitter::{ChunkingBudget, SplitterExt, TextSplitter};
+    ///
+    /// let budget = ChunkingBudget::new().with_cancellation_check(|| true);
+    /// let exhausted = TextSplitter::new(10)
+    ///     .chunk_indices_within_budget("Some text", &budget)
+    ///     .unwrap_err();
+    ///
+    /// assert!(exhausted.chunks().is_empty());
+    /// ```
+    #[must_use]
+    pub fn with_cancellation_check(
+        mut self,
+        check: impl Fn() -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.check = Some(Arc::new(check));
+        self
+    }
+
+    /// Whether the deadline has passed or chunking was cancelled.
+    #[must_use]
+    pub fn is_exhausted(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+            || self
+                .cancelled
+                .as_ref()
+                .is_some_and(|cancelled| cancelled.load(Ordering::Relaxed))
+            || self.check.as_ref().is_some_and(|check| check())
+    }
+}
+
+/// Indicates the [`ChunkingBudget`] was exhausted before the whole text was
+/// chunked. Holds the chunks that were generated before that, so callers can
+/// use the partial results, or chunk the rest of the text from
+/// [`BudgetExhausted::offset`] with a cheaper splitter.
+#[derive(Error, Debug, Eq, PartialEq)]
+#[error("The chunking budget was exhausted after {} chunks", chunks.len())]
+pub struct BudgetExhausted<'text> {
+    /// Chunks generated before the budget was exhausted, and their byte
+    /// offsets
+    chunks: Vec<(usize, &'text str)>,
+    /// Byte offset of the end of the last chunk
+    offset: usize,
+}
+
+impl<'text> BudgetExhausted<'text> {
+    /// Creates a new error from the chunks that were generated in time.
+    pub(crate) fn new(chunks: Vec<(usize, &'text str)>) -> Self {
+        let offset = chunks
+            .last()
+            .map_or(0, |(offset, chunk)| offset + chunk.len());
+        Self { chunks, offset }
+    }
+
+    /// Chunks generated before the budget was exhausted, and their byte
+    /// offsets.
+    #[must_use]
+    pub fn chunks(&self) -> &[(usize, &'text str)] {
+        &self.chunks
+    }
+
+    /// Take the chunks generated before the budget was exhausted.
+    #[must_use]
+    pub fn into_chunks(self) -> Vec<(usize, &'text str)> {
+        self.chunks
+    }
+
+    /// Byte offset of the end of the last chunk that was generated, from
+    /// which the rest of the text still has to be chunked.
+    #[must_use]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_without_limits_is_never_exhausted() {
+        assert!(!ChunkingBudget::new().is_exhausted());
+    }
+
+    #[test]
+    fn budget_is_exhausted_after_deadline_or_cancellation() {
+        assert!(ChunkingBudget::new()
+            .with_deadline(Duration::ZERO)
+            .is_exhausted());
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let budget = ChunkingBudget::new().with_cancellation(Arc::clone(&cancelled));
+        assert!(!budget.is_exhausted());
+        cancelled.store(true, Ordering::Relaxed);
+        assert!(budget.is_exhausted());
+    }
+
+    #[test]
+    fn offset_is_end_of_last_chunk() {
+        assert_eq!(BudgetExhausted::new(vec![]).offset(), 0);
+        assert_eq!(
+            BudgetExhausted::new(vec![(0, "Some"), (5, "text")]).offset(),
+            9
+        );
+    }
+}
@@ -2,22 +2,37 @@ use std::{
     cmp::Ordering,
     fmt,
     ops::{Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive},
+    sync::Arc,
 };
 
 use ahash::AHashMap;
 use itertools::Itertools;
 use thiserror::Error;
 
+mod approx;
 mod characters;
+#[cfg(feature = "aho-corasick")]
+mod dictionary;
 #[cfg(feature = "tokenizers")]
 mod huggingface;
+mod pooled;
 #[cfg(feature = "rust-tokenizers")]
 mod rust_tokenizers;
+mod syntax;
 #[cfg(feature = "tiktoken-rs")]
 mod tiktoken;
+mod weighted;
 
 use crate::trim::Trim;
+pub use approx::ApproxTokens;
 pub use characters::Characters;
+#[cfg(feature = "aho-corasick")]
+pub use dictionary::{AhoCorasickSizer, AhoCorasickSizerError};
+#[cfg(feature = "tokenizers")]
+pub use huggingface::{ModelTokenizer, ModelTokenizerError};
+pub use pooled::PooledSizer;
+pub use syntax::{SyntaxClass, SyntaxSizer, SyntaxSpan, SyntaxWeighted};
+pub use weighted::WeightedCharacters;
 
 /// Indicates there was an error with the chunk capacity configuration.
 /// The `Display` implementation will provide a human-readable error message to
@@ -190,8 +205,122 @@ impl From<RangeToInclusive<usize>> for ChunkCapacity {
 pub trait ChunkSizer {
     /// Determine the size of a given chunk to use for validation
     fn size(&self, chunk: &str) -> usize;
+
+    /// The largest chunk size the model behind this sizer can handle, such as
+    /// the max sequence length of a tokenizer, if known. Used by
+    /// [`ChunkConfig::validate`].
+    fn max_size(&self) -> Option<usize> {
+        None
+    }
+
+    /// Whether the size of a chunk grows linearly with its length and is cheap
+    /// enough to calculate that it is faster to measure it again than to look
+    /// it up, such as a count of characters.
+    ///
+    /// If `true`, sizes are measured on every check instead of being cached
+    /// while searching for the next chunk. Defaults to `false`, which is
+    /// always correct.
+    fn is_linear(&self) -> bool {
+        false
+    }
+
+    /// This sizer as a [`SyntaxSizer`], if it takes the syntax of source code
+    /// into account, such as [`SyntaxWeighted`]. The `CodeSplitter` then
+    /// measures chunks with [`SyntaxSizer::size_with_syntax`] instead of
+    /// [`ChunkSizer::size`]. Defaults to `None`.
+    fn syntax_sizer(&self) -> Option<&dyn SyntaxSizer> {
+        None
+    }
+
+    /// This sizer as a [`ChunkSizerBatch`], if it can measure several chunks
+    /// at once faster than one by one, such as a tokenizer that encodes
+    /// batches in parallel. Splitters then measure the candidates for the
+    /// next chunk in batches. Defaults to `None`.
+    fn batch_sizer(&self) -> Option<&dyn ChunkSizerBatch> {
+        None
+    }
+}
+
+/// A chunk sizer that can measure several chunks at once, such as a tokenizer
+/// that encodes batches in parallel.
+///
+/// Splitters measure a batch of candidates for the next chunk at once with
+/// sizers that return themselves from [`ChunkSizer::batch_sizer`], instead of
+/// measuring one candidate after the other. Some of the candidates of a batch
+/// may end up not being needed, so this only pays off if measuring a batch is
+/// much faster than measuring each chunk in it.
+pub trait ChunkSizerBatch: ChunkSizer {
+    /// Determine the size of each of the given chunks, in the same order.
+    /// Each size must be the same as [`ChunkSizer::size`] of that chunk.
+    fn sizes(&self, chunks: &[&str]) -> Vec<usize>;
 }
 
+impl<T> ChunkSizer for Box<T>
+where
+    T: ChunkSizer + ?Sized,
+{
+    fn size(&self, chunk: &str) -> usize {
+        (**self).size(chunk)
+    }
+
+    fn max_size(&self) -> Option<usize> {
+        (**self).max_size()
+    }
+
+    fn is_linear(&self) -> bool {
+        (**self).is_linear()
+    }
+
+    fn syntax_sizer(&self) -> Option<&dyn SyntaxSizer> {
+        (**self).syntax_sizer()
+    }
+
+    fn batch_sizer(&self) -> Option<&dyn ChunkSizerBatch> {
+        (**self).batch_sizer()
+    }
+}
+
+impl<T> ChunkSizer for Arc<T>
+where
+    T: ChunkSizer + ?Sized,
+{
+    fn size(&self, chunk: &str) -> usize {
+        (**self).size(chunk)
+    }
+
+    fn max_size(&self) -> Option<usize> {
+        (**self).max_size()
+    }
+
+    fn is_linear(&self) -> bool {
+        (**self).is_linear()
+    }
+
+    fn syntax_sizer(&self) -> Option<&dyn SyntaxSizer> {
+        (**self).syntax_sizer()
+    }
+
+    fn batch_sizer(&self) -> Option<&dyn ChunkSizerBatch> {
+        (**self).batch_sizer()
+    }
+}
+
+impl fmt::Debug for dyn ChunkSizer + Send + Sync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("dyn ChunkSizer")
+    }
+}
+
+/// A chunk sizer that is chosen at runtime, such as based on user
+/// configuration. Every chunk sizer can be turned into one with
+/// [`Box::new`].
+pub type DynChunkSizer = Box<dyn ChunkSizer + Send + Sync>;
+
+/// Configuration with a chunk sizer that is chosen at runtime, so that
+/// splitters with different chunk sizers have the same type. Created with
+/// [`ChunkConfig::into_dyn`].
+pub type DynChunkConfig = ChunkConfig<DynChunkSizer>;
+
 /// Indicates there was an error with the chunk configuration.
 /// The `Display` implementation will provide a human-readable error message to
 /// help debug the issue that caused the error.
@@ -204,10 +333,89 @@ pub struct ChunkConfigError(#[from] ChunkConfigErrorRepr);
 enum ChunkConfigErrorRepr {
     #[error("The overlap is larger than or equal to the desired chunk capacity")]
     OverlapLargerThanCapacity,
+    #[error("The max chunk capacity of {max} is larger than the max size of {limit} supported by the chunk sizer")]
+    CapacityLargerThanSizerMax { max: usize, limit: usize },
+}
+
+/// How to choose the end of a chunk when several possible chunks all have a
+/// size within the chunk capacity.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum TieBreaking {
+    /// Use the smallest chunk that is within the capacity. Leaves more room
+    /// for semantic units in the following chunks.
+    #[default]
+    PreferSmaller,
+    /// Use the largest chunk that is still within the capacity, which
+    /// results in fewer chunks.
+    PreferLarger,
+    /// Use the chunk that ends on the boundary of the highest semantic level,
+    /// such as the end of a paragraph rather than the end of a sentence. If
+    /// several chunks end on a boundary of the same level, the largest one is
+    /// used.
+    PreferHigherLevel,
+}
+
+/// How sentence boundaries are found, whenever text needs to be split at the
+/// sentence level.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SentenceBoundaries {
+    /// [Unicode Sentence Boundaries](https://www.unicode.org/reports/tr29/#Sentence_Boundaries)
+    #[default]
+    Unicode,
+    /// Unicode sentence boundaries, adjusted for scientific text. Common
+    /// abbreviations like `Fig.`, `Eq.`, `et al.`, or `e.g.` never end a
+    /// sentence, and bracketed citations like `[12, 13]` that come right
+    /// after the end of a sentence stay with that sentence.
+    Scientific,
+}
+
+/// Version of the chunking algorithm, so that chunk boundaries can stay stable
+/// across releases of the crate.
+///
+/// Improvements to the algorithm can move chunk boundaries, which would
+/// invalidate anything cached or indexed by chunk. Pinning a version keeps
+/// producing the same boundaries as that version, while
+/// [`ChunkingVersion::Latest`] always uses the newest algorithm.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ChunkingVersion {
+    /// Always use the newest version of the algorithm. Currently
+    /// [`ChunkingVersion::V3`].
+    #[default]
+    Latest,
+    /// The algorithm as released in version 0.22 of the crate.
+    V1,
+    /// Chunks no longer end right after a bidi control character that
+    /// applies to the text after it, such as a right-to-left mark, unless
+    /// the chunk would be empty otherwise. Sections much larger than the
+    /// capacity are only measured until they no longer fit, which can move
+    /// boundaries for sizers where a longer text can be smaller, such as
+    /// some tokenizers.
+    V2,
+    /// When falling back to splitting by chars, only the grapheme cluster
+    /// that is too large for a chunk on its own is split into chars, so ZWJ
+    /// emoji and flag sequences after it stay whole.
+    V3,
+}
+
+impl ChunkingVersion {
+    /// Whether this version includes the changes made in `version`.
+    pub(crate) fn includes(self, version: Self) -> bool {
+        let number = |version| match version {
+            Self::Latest => u8::MAX,
+            Self::V1 => 1,
+            Self::V2 => 2,
+            Self::V3 => 3,
+        };
+        number(self) >= number(version)
+    }
 }
 
 /// Configuration for how chunks should be created
 #[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct ChunkConfig<Sizer>
 where
     Sizer: ChunkSizer,
@@ -218,6 +426,19 @@ where
     pub(crate) overlap: usize,
     /// The chunk sizer to use for determining the size of each chunk
     pub(crate) sizer: Sizer,
+    /// How to pick between multiple chunks that are all within the capacity
+    pub(crate) tie_breaking: TieBreaking,
+    /// How to find sentence boundaries
+    pub(crate) sentence_boundaries: SentenceBoundaries,
+    /// Version of the chunking algorithm to use
+    pub(crate) algorithm_version: ChunkingVersion,
+    /// Whether closing punctuation right after a chunk is pulled into it
+    pub(crate) sticky_punctuation: bool,
+    /// Whether words are only split at whitespace, unless a single word
+    /// doesn't fit
+    pub(crate) whole_words: bool,
+    /// Whether chunks end right after a newline, if the chunk contains one
+    pub(crate) line_aligned: bool,
     /// Whether whitespace will be trimmed from the beginning and end of each chunk
     pub(crate) trim: bool,
 }
@@ -236,6 +457,12 @@ impl ChunkConfig<Characters> {
             capacity: capacity.into(),
             overlap: 0,
             sizer: Characters,
+            tie_breaking: TieBreaking::default(),
+            sentence_boundaries: SentenceBoundaries::default(),
+            algorithm_version: ChunkingVersion::default(),
+            sticky_punctuation: false,
+            whole_words: false,
+            line_aligned: false,
             trim: true,
         }
     }
@@ -271,6 +498,29 @@ where
         }
     }
 
+    /// Check that the max chunk capacity isn't larger than the max size
+    /// supported by the chunk sizer, such as the max sequence length of a
+    /// tokenizer. Otherwise chunks could be generated that the model would
+    /// silently truncate.
+    ///
+    /// Always succeeds if the sizer doesn't know its max size.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the max chunk capacity is larger than the max
+    /// size of the chunk sizer.
+    pub fn validate(&self) -> Result<(), ChunkConfigError> {
+        match self.sizer.max_size() {
+            Some(limit) if self.capacity.max > limit => Err(ChunkConfigError(
+                ChunkConfigErrorRepr::CapacityLargerThanSizerMax {
+                    max: self.capacity.max,
+                    limit,
+                },
+            )),
+            _ => Ok(()),
+        }
+    }
+
     /// Retrieve a reference to the chunk sizer for this configuration.
     pub fn sizer(&self) -> &Sizer {
         &self.sizer
@@ -289,10 +539,193 @@ where
             capacity: self.capacity,
             overlap: self.overlap,
             sizer,
+            tie_breaking: self.tie_breaking,
+            sentence_boundaries: self.sentence_boundaries,
+            algorithm_version: self.algorithm_version,
+            sticky_punctuation: self.sticky_punctuation,
+            whole_words: self.whole_words,
+            line_aligned: self.line_aligned,
             trim: self.trim,
         }
     }
 
+    /// Retrieve the tie-breaking policy for this configuration.
+    pub fn tie_breaking(&self) -> TieBreaking {
+        self.tie_breaking
+    }
+
+    /// Set how to choose between several possible chunks whose sizes all fall
+    /// within the chunk capacity. This only makes a difference if the
+    /// capacity is a range, or if several chunks have exactly the same size.
+    ///
+    /// ```
+    /// use text_splitter::{ChunkConfig, TextSplitter, TieBreaking};
+    ///
+    /// let text = "Some text from a document";
+    ///
+    /// let config = ChunkConfig::new(5..20);
+    /// let chunks = TextSplitter::new(config).chunks(text).collect::<Vec<_>>();
+    /// assert_eq!(vec!["Some text", "from a document"], chunks);
+    ///
+    /// let config = ChunkConfig::new(5..20).with_tie_breaking(TieBreaking::PreferLarger);
+    /// let chunks = TextSplitter::new(config).chunks(text).collect::<Vec<_>>();
+    /// assert_eq!(vec!["Some text from a", "document"], chunks);
+    /// ```
+    #[must_use]
+    pub fn with_tie_breaking(mut self, tie_breaking: TieBreaking) -> Self {
+        self.tie_breaking = tie_breaking;
+        self
+    }
+
+    /// Retrieve how sentence boundaries are found for this configuration.
+    pub fn sentence_boundaries(&self) -> SentenceBoundaries {
+        self.sentence_boundaries
+    }
+
+    /// Set how sentence boundaries are found, whenever text needs to be split
+    /// at the sentence level. Use [`SentenceBoundaries::Scientific`] for text
+    /// with citations and abbreviations like `Fig. 3` or `et al.`.
+    ///
+    /// ```
+    /// use text_splitter::{ChunkConfig, SentenceBoundaries, TextSplitter};
+    ///
+    /// let text = "As shown in Fig. 2, it works.[3] Smith et al. agree.";
+    ///
+    /// let config = ChunkConfig::new(35).with_sentence_boundaries(SentenceBoundaries::Scientific);
+    /// let chunks = TextSplitter::new(config).chunks(text).collect::<Vec<_>>();
+    /// assert_eq!(vec!["As shown in Fig. 2, it works.[3]", "Smith et al. agree."], chunks);
+    /// ```
+    #[must_use]
+    pub fn with_sentence_boundaries(mut self, sentence_boundaries: SentenceBoundaries) -> Self {
+        self.sentence_boundaries = sentence_boundaries;
+        self
+    }
+
+    /// Retrieve the version of the chunking algorithm for this configuration.
+    pub fn algorithm_version(&self) -> ChunkingVersion {
+        self.algorithm_version
+    }
+
+    /// Pin the version of the chunking algorithm, so that chunk boundaries
+    /// don't change when the algorithm is improved in a later release.
+    /// Defaults to [`ChunkingVersion::Latest`].
+    ///
+    /// ```
+    /// use text_splitter::{ChunkConfig, ChunkingVersion};
+    ///
+    /// let config = ChunkConfig::new(512).with_algorithm_version(ChunkingVersion::V1);
+    /// ```
+    #[must_use]
+    pub fn with_algorithm_version(mut self, algorithm_version: ChunkingVersion) -> Self {
+        self.algorithm_version = algorithm_version;
+        self
+    }
+
+    /// Whether closing punctuation right after a chunk is pulled into it.
+    pub fn sticky_punctuation(&self) -> bool {
+        self.sticky_punctuation
+    }
+
+    /// Specify whether closing punctuation, such as quotes, brackets, or a
+    /// period, that directly follows the end of a chunk should be pulled into
+    /// that chunk, as long as it still fits within the capacity. Otherwise
+    /// the next chunk can start with orphaned punctuation like `")`.
+    ///
+    /// Defaults to `false`.
+    ///
+    /// ```
+    /// use text_splitter::{ChunkConfig, TextSplitter};
+    ///
+    /// let text = "She wrote: \"It works.\" Nobody agreed.";
+    ///
+    /// let config = ChunkConfig::new(6..=12);
+    /// let chunks = TextSplitter::new(config).chunks(text).collect::<Vec<_>>();
+    /// assert_eq!(vec!["She wrote", ": \"It works", ".\"", "Nobody", "agreed."], chunks);
+    ///
+    /// let config = ChunkConfig::new(6..=12).with_sticky_punctuation(true);
+    /// let chunks = TextSplitter::new(config).chunks(text).collect::<Vec<_>>();
+    /// assert_eq!(vec!["She wrote:", "\"It works.\"", "Nobody", "agreed."], chunks);
+    /// ```
+    #[must_use]
+    pub fn with_sticky_punctuation(mut self, sticky_punctuation: bool) -> Self {
+        self.sticky_punctuation = sticky_punctuation;
+        self
+    }
+
+    /// Whether words are only split at whitespace, unless a single word
+    /// doesn't fit.
+    pub fn whole_words(&self) -> bool {
+        self.whole_words
+    }
+
+    /// Specify whether chunks should only end at whitespace when text has to
+    /// be split by words. By default, words are found with the Unicode word
+    /// boundaries, which also split at punctuation within words, such as in
+    /// `state-of-the-art` or `example.com`. If enabled, everything between
+    /// whitespace is kept together, and chunks are only split within it if
+    /// it doesn't fit in a chunk by itself. The same goes for sentence
+    /// boundaries. Chunks may end up smaller than they could otherwise be.
+    ///
+    /// This is not useful for scripts that don't use spaces between words,
+    /// which will be split by grapheme clusters instead.
+    ///
+    /// Defaults to `false`.
+    ///
+    /// ```
+    /// use text_splitter::{ChunkConfig, TextSplitter};
+    ///
+    /// let text = "A state-of-the-art result";
+    ///
+    /// let config = ChunkConfig::new(16);
+    /// let chunks = TextSplitter::new(config).chunks(text).collect::<Vec<_>>();
+    /// assert_eq!(vec!["A state-of-the-", "art result"], chunks);
+    ///
+    /// let config = ChunkConfig::new(16).with_whole_words(true);
+    /// let chunks = TextSplitter::new(config).chunks(text).collect::<Vec<_>>();
+    /// assert_eq!(vec!["A", "state-of-the-art", "result"], chunks);
+    /// ```
+    #[must_use]
+    pub fn with_whole_words(mut self, whole_words: bool) -> Self {
+        self.whole_words = whole_words;
+        self
+    }
+
+    /// Whether chunks end right after a newline, if the chunk contains one.
+    pub fn line_aligned(&self) -> bool {
+        self.line_aligned
+    }
+
+    /// Specify whether chunks should end at a line boundary, right after a
+    /// newline, whenever possible. If the chunk that would otherwise be
+    /// generated doesn't end at a line boundary, a newline directly after it
+    /// is pulled into the chunk if it still fits within the capacity.
+    /// Otherwise, it is cut short after the last newline within it. Chunks
+    /// without a newline, or where the newline is within a range that can't be
+    /// split, are left as they are. This is mostly useful without trimming, for
+    /// tooling that diffs the chunks line by line. Chunks may end up smaller
+    /// than they could otherwise be.
+    ///
+    /// Defaults to `false`.
+    ///
+    /// ```
+    /// use text_splitter::{ChunkConfig, TextSplitter};
+    ///
+    /// let text = "a b c\nd e f g h i j k\nl m";
+    ///
+    /// let config = ChunkConfig::new(21).with_trim(false);
+    /// let chunks = TextSplitter::new(config).chunks(text).collect::<Vec<_>>();
+    /// assert_eq!(vec!["a b c\nd e f g h i j k", "\nl m"], chunks);
+    ///
+    /// let config = ChunkConfig::new(21).with_trim(false).with_line_aligned(true);
+    /// let chunks = TextSplitter::new(config).chunks(text).collect::<Vec<_>>();
+    /// assert_eq!(vec!["a b c\n", "d e f g h i j k\nl m"], chunks);
+    /// ```
+    #[must_use]
+    pub fn with_line_aligned(mut self, line_aligned: bool) -> Self {
+        self.line_aligned = line_aligned;
+        self
+    }
+
     /// Whether chunkd should have whitespace trimmed from the beginning and end or not.
     pub fn trim(&self) -> bool {
         self.trim
@@ -317,6 +750,42 @@ where
     }
 }
 
+impl<Sizer> ChunkConfig<Sizer>
+where
+    Sizer: ChunkSizer + Send + Sync + 'static,
+{
+    /// Box the chunk sizer, so that configurations with different chunk
+    /// sizers have the same type.
+    ///
+    /// ```
+    /// use text_splitter::{ApproxTokens, ChunkConfig, DynChunkConfig, TextSplitter};
+    ///
+    /// let approximate = true;
+    /// let config: DynChunkConfig = if approximate {
+    ///     ChunkConfig::new(512).with_sizer(ApproxTokens).into_dyn()
+    /// } else {
+    ///     ChunkConfig::new(2048).into_dyn()
+    /// };
+    /// let splitter = TextSplitter::new(config);
+    /// ```
+    #[must_use]
+    pub fn into_dyn(self) -> DynChunkConfig {
+        let sizer: DynChunkSizer = Box::new(self.sizer);
+        ChunkConfig {
+            capacity: self.capacity,
+            overlap: self.overlap,
+            sizer,
+            tie_breaking: self.tie_breaking,
+            sentence_boundaries: self.sentence_boundaries,
+            algorithm_version: self.algorithm_version,
+            sticky_punctuation: self.sticky_punctuation,
+            whole_words: self.whole_words,
+            line_aligned: self.line_aligned,
+            trim: self.trim,
+        }
+    }
+}
+
 impl<T> From<T> for ChunkConfig<Characters>
 where
     T: Into<ChunkCapacity>,
@@ -334,8 +803,22 @@ pub struct MemoizedChunkSizer<'sizer, Sizer>
 where
     Sizer: ChunkSizer,
 {
+    /// Number of times the size of a chunk was requested
+    lookups: usize,
+    /// Number of times the sizer was called to calculate a size
+    sizer_calls: usize,
     /// Cache of chunk sizes per byte offset range for base capacity
     size_cache: AHashMap<Range<usize>, usize>,
+    /// Size of a prefix that will be added to chunks starting at or after
+    /// each offset, sorted by offset
+    prefix_sizes: Vec<(usize, usize)>,
+    /// Size the prefixes can be shortened to, to keep a section whole
+    min_prefix_size: usize,
+    /// Comments and strings in the whole text, sorted by offset, if the
+    /// sizer takes them into account
+    syntax_spans: Vec<SyntaxSpan>,
+    /// Version of the chunking algorithm to measure chunks with
+    algorithm_version: ChunkingVersion,
     /// The sizer used for caluclating chunk sizes
     sizer: &'sizer Sizer,
 }
@@ -347,19 +830,191 @@ where
     /// Wrap any chunk sizer for memoization
     pub fn new(sizer: &'sizer Sizer) -> Self {
         Self {
+            lookups: 0,
+            sizer_calls: 0,
             size_cache: AHashMap::new(),
+            prefix_sizes: Vec::new(),
+            min_prefix_size: 0,
+            syntax_spans: Vec::new(),
+            algorithm_version: ChunkingVersion::default(),
             sizer,
         }
     }
 
+    /// Measure chunks the way the given version of the chunking algorithm
+    /// did.
+    pub fn set_algorithm_version(&mut self, algorithm_version: ChunkingVersion) {
+        self.algorithm_version = algorithm_version;
+    }
+
+    /// Count the size of a prefix that will be added to each chunk, such as
+    /// the headings it is under, as part of the chunk's size. Each item is
+    /// the offset a prefix starts applying at and its size, sorted by offset.
+    ///
+    /// If the prefixes can be shortened to `min_prefix_size` for a chunk
+    /// that wouldn't fit otherwise, the level to split the text by is chosen
+    /// with that size instead, so that sections are only split if they don't
+    /// fit in a chunk with the shortest prefix.
+    pub fn set_prefix_sizes(&mut self, prefix_sizes: Vec<(usize, usize)>, min_prefix_size: usize) {
+        self.prefix_sizes = prefix_sizes;
+        self.min_prefix_size = min_prefix_size;
+    }
+
+    /// Measure chunks with the comments and strings in the whole text, if the
+    /// sizer is a [`SyntaxSizer`]. Spans must be sorted and not overlap.
+    pub fn set_syntax_spans(&mut self, syntax_spans: Vec<SyntaxSpan>) {
+        self.syntax_spans = syntax_spans;
+    }
+
+    /// Measure a chunk with the sizer, along with the syntax spans within
+    /// it, if there are any.
+    fn measure(&self, offset: usize, chunk: &str) -> usize {
+        let Some(sizer) = self
+            .sizer
+            .syntax_sizer()
+            .filter(|_| !self.syntax_spans.is_empty())
+        else {
+            return self.sizer.size(chunk);
+        };
+
+        let end = offset + chunk.len();
+        let first = self
+            .syntax_spans
+            .partition_point(|span| span.range.end <= offset);
+        let syntax = self.syntax_spans[first..]
+            .iter()
+            .take_while(|span| span.range.start < end)
+            .map(|span| SyntaxSpan {
+                range: span.range.start.max(offset) - offset..span.range.end.min(end) - offset,
+                ..span.clone()
+            })
+            .collect::<Vec<_>>();
+        sizer.size_with_syntax(chunk, &syntax)
+    }
+
+    /// Whether the size of the chunk would be measured by the sizer, rather
+    /// than looked up in the cache, and measuring it can be batched.
+    pub fn needs_batch(&self, offset: usize, chunk: &str, trim: Trim) -> bool {
+        let (offset, chunk) = trim.trim(offset, chunk);
+        self.sizer.batch_sizer().is_some()
+            && !self.sizer.is_linear()
+            && !self
+                .size_cache
+                .contains_key(&(offset..offset + chunk.len()))
+    }
+
+    /// Measure the chunks starting at the offset and ending at each of the
+    /// given ends in a single batch, if the sizer supports it, so that later
+    /// lookups of their sizes are cached. Does nothing for linear sizers,
+    /// whose sizes aren't cached, or if syntax spans are measured.
+    pub fn prefetch(&mut self, offset: usize, text: &str, ends: &[usize], trim: Trim) {
+        let Some(sizer) = self.sizer.batch_sizer() else {
+            return;
+        };
+        if self.sizer.is_linear()
+            || (!self.syntax_spans.is_empty() && self.sizer.syntax_sizer().is_some())
+        {
+            return;
+        }
+
+        let (ranges, chunks): (Vec<_>, Vec<_>) = ends
+            .iter()
+            .map(|&end| trim.trim(offset, &text[offset..end]))
+            .map(|(offset, chunk)| (offset..offset + chunk.len(), chunk))
+            .filter(|(range, _)| !self.size_cache.contains_key(range))
+            .unique_by(|(range, _)| range.clone())
+            .unzip();
+        if chunks.len() < 2 {
+            return;
+        }
+        self.sizer_calls += chunks.len();
+        self.size_cache
+            .extend(ranges.into_iter().zip(sizer.sizes(&chunks)));
+    }
+
+    /// Size of the prefix that will be added to a chunk starting at the offset
+    fn prefix_size(&self, offset: usize) -> usize {
+        match self
+            .prefix_sizes
+            .partition_point(|(start, _)| *start <= offset)
+        {
+            0 => 0,
+            index => self.prefix_sizes[index - 1].1,
+        }
+    }
+
     /// Determine the size of a given chunk to use for validation,
     /// returning a cached value if it exists, and storing the result if not.
     pub fn chunk_size(&mut self, offset: usize, chunk: &str, trim: Trim) -> usize {
         let (offset, chunk) = trim.trim(offset, chunk);
-        *self
-            .size_cache
-            .entry(offset..(offset + chunk.len()))
-            .or_insert_with(|| self.sizer.size(chunk))
+        self.unprefixed_size(offset, chunk) + self.prefix_size(offset)
+    }
+
+    /// Size of an already trimmed chunk, without the prefix added to it
+    fn unprefixed_size(&mut self, offset: usize, chunk: &str) -> usize {
+        self.lookups += 1;
+        // Hashing the range costs about as much as measuring a linear sizer
+        if self.sizer.is_linear() {
+            self.sizer_calls += 1;
+            return self.measure(offset, chunk);
+        }
+        let range = offset..(offset + chunk.len());
+        if let Some(size) = self.size_cache.get(&range) {
+            return *size;
+        }
+        self.sizer_calls += 1;
+        let size = self.measure(offset, chunk);
+        #[cfg(feature = "tracing")]
+        tracing::trace!(offset, len = chunk.len(), size, "chunk sizer call");
+        self.size_cache.insert(range, size);
+        size
+    }
+
+    /// Size of a chunk with the shortest prefix it can have, for choosing the
+    /// level to split by.
+    fn min_chunk_size(&mut self, offset: usize, chunk: &str, trim: Trim) -> usize {
+        let (offset, chunk) = trim.trim(offset, chunk);
+        let prefix_size = self.prefix_size(offset).min(self.min_prefix_size);
+        self.unprefixed_size(offset, chunk) + prefix_size
+    }
+
+    /// Number of times the size of a chunk was requested, and the number of
+    /// times the sizer had to be called to calculate it.
+    pub fn counts(&self) -> (usize, usize) {
+        (self.lookups, self.sizer_calls)
+    }
+
+    /// Whether the chunk, with the shortest prefix it can have, is larger
+    /// than the capacity. Growing prefixes of the
+    /// chunk are measured first, so that a huge section, such as everything
+    /// before the only heading of a long document, isn't measured in full
+    /// for every chunk. [`ChunkingVersion::V1`] always measures the whole
+    /// chunk.
+    fn exceeds_capacity(
+        &mut self,
+        offset: usize,
+        chunk: &str,
+        capacity: &ChunkCapacity,
+        trim: Trim,
+    ) -> bool {
+        if !self.algorithm_version.includes(ChunkingVersion::V2) {
+            let chunk_size = self.min_chunk_size(offset, chunk, trim);
+            return capacity.fits(chunk_size).is_gt();
+        }
+        let mut end = capacity.max.max(1);
+        loop {
+            end = end.saturating_mul(2).min(chunk.len());
+            while !chunk.is_char_boundary(end) {
+                end += 1;
+            }
+            let chunk_size = self.min_chunk_size(offset, &chunk[..end], trim);
+            if capacity.fits(chunk_size).is_gt() {
+                return true;
+            }
+            if end == chunk.len() {
+                return false;
+            }
+        }
     }
 
     /// Find the best level to start splitting the text
@@ -386,19 +1041,26 @@ where
         for (level, str) in levels_with_first_chunk {
             // Skip tokenizing levels that we know are too small anyway.
             let len = str.len();
-            if len > capacity.max {
-                let chunk_size = self.chunk_size(offset, str, trim);
-                let fits = capacity.fits(chunk_size);
-                // If this no longer fits, we use the level we are at.
-                if fits.is_gt() {
-                    max_offset = Some(offset + len);
-                    break;
-                }
+            // If this no longer fits, we use the level we are at.
+            let prefix_size = self.prefix_size(offset).min(self.min_prefix_size);
+            if len.saturating_add(prefix_size) > capacity.max
+                && self.exceeds_capacity(offset, str, capacity, trim)
+            {
+                max_offset = Some(offset + len);
+                break;
             }
             // Otherwise break up the text with the next level
             semantic_level = Some(level);
         }
 
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            offset,
+            level = ?semantic_level,
+            max_offset,
+            "selected semantic level"
+        );
+
         (semantic_level, max_offset)
     }
 
@@ -407,6 +1069,18 @@ where
     pub fn clear_cache(&mut self) {
         self.size_cache.clear();
     }
+
+    /// Use an existing cache, to reuse its allocation. Any sizes in it are
+    /// cleared.
+    pub fn set_cache(&mut self, mut cache: AHashMap<Range<usize>, usize>) {
+        cache.clear();
+        self.size_cache = cache;
+    }
+
+    /// Take the cache out of the sizer, to reuse its allocation elsewhere.
+    pub fn take_cache(&mut self) -> AHashMap<Range<usize>, usize> {
+        std::mem::take(&mut self.size_cache)
+    }
 }
 
 #[cfg(test)]
@@ -583,6 +1257,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn memoized_sizer_counts_lookups_and_calls() {
+        let sizer = CountingSizer::default();
+        let mut memoized_sizer = MemoizedChunkSizer::new(&sizer);
+        for text in ["123", "123", "12345"] {
+            memoized_sizer.chunk_size(0, text, Trim::All);
+        }
+
+        assert_eq!(memoized_sizer.counts(), (3, 2));
+    }
+
     #[test]
     fn can_clear_cache_on_memoized_sizer() {
         let sizer = CountingSizer::default();
@@ -681,4 +1366,96 @@ mod tests {
             "The overlap is larger than or equal to the desired chunk capacity"
         );
     }
+
+    #[test]
+    fn validate_without_sizer_max() {
+        assert!(ChunkConfig::new(usize::MAX).validate().is_ok());
+    }
+
+    #[test]
+    fn validate_against_sizer_max() {
+        struct LimitedSizer;
+
+        impl ChunkSizer for LimitedSizer {
+            fn size(&self, chunk: &str) -> usize {
+                chunk.len()
+            }
+
+            fn max_size(&self) -> Option<usize> {
+                Some(10)
+            }
+        }
+
+        assert!(ChunkConfig::new(5..=10)
+            .with_sizer(LimitedSizer)
+            .validate()
+            .is_ok());
+        let err = ChunkConfig::new(5..=11)
+            .with_sizer(LimitedSizer)
+            .validate()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The max chunk capacity of 11 is larger than the max size of 10 supported by the chunk sizer"
+        );
+    }
+
+    #[test]
+    fn dyn_sizer_forwards_to_inner_sizer() {
+        struct LimitedSizer;
+
+        impl ChunkSizer for LimitedSizer {
+            fn size(&self, chunk: &str) -> usize {
+                chunk.len()
+            }
+
+            fn max_size(&self) -> Option<usize> {
+                Some(10)
+            }
+        }
+
+        let config = ChunkConfig::new(5..=11).with_sizer(LimitedSizer).into_dyn();
+        assert_eq!(config.sizer().size("éa"), 3);
+        assert!(config.validate().is_err());
+        assert!(ChunkConfig::new(5).into_dyn().sizer().is_linear());
+        assert!(format!("{config:?}").contains("sizer: dyn ChunkSizer"));
+    }
+
+    #[test]
+    fn batches_candidates_of_binary_search() {
+        #[derive(Default)]
+        struct BatchSizer {
+            batches: AtomicUsize,
+        }
+
+        impl ChunkSizer for BatchSizer {
+            fn size(&self, chunk: &str) -> usize {
+                Characters.size(chunk)
+            }
+
+            fn batch_sizer(&self) -> Option<&dyn ChunkSizerBatch> {
+                Some(self)
+            }
+        }
+
+        impl ChunkSizerBatch for BatchSizer {
+            fn sizes(&self, chunks: &[&str]) -> Vec<usize> {
+                self.batches.fetch_add(1, atomic::Ordering::SeqCst);
+                chunks.iter().map(|chunk| self.size(chunk)).collect()
+            }
+        }
+
+        let text = "one two three four five six seven eight nine ten eleven twelve";
+        let sizer = Arc::new(BatchSizer::default());
+        let config = ChunkConfig::new(20).with_sizer(Arc::clone(&sizer));
+        let batched = crate::TextSplitter::new(config)
+            .chunks(text)
+            .collect::<Vec<_>>();
+        let expected = crate::TextSplitter::new(20)
+            .chunks(text)
+            .collect::<Vec<_>>();
+
+        assert_eq!(batched, expected);
+        assert!(sizer.batches.load(atomic::Ordering::SeqCst) > 0);
+    }
 }
@@ -0,0 +1,166 @@
+use crate::ChunkSizer;
+
+/// Used for splitting a piece of text into chunks based on an estimate of the
+/// number of tokens in each chunk, without the vocabulary of a tokenizer.
+///
+/// The estimate is based on how common tokenizers split text: words are
+/// about six letters per token, with a space before a word being part of the
+/// word, runs of digits are about three digits per token, and punctuation is
+/// about two marks per token. Letters outside of ASCII count for more, and
+/// ideographs, such as Chinese, Japanese or Korean characters, are a token
+/// each. For English text and code, the estimate tends to be a bit higher
+/// than the actual number of tokens.
+///
+/// Useful to plan for a token budget, or in builds where pulling in a
+/// tokenizer is too expensive, such as for WASM. Sizes are an estimate, so
+/// leave some room if you need to stay under a hard limit.
+///
+/// ```
+/// use text_splitter::{ApproxTokens, ChunkConfig, TextSplitter};
+///
+/// let splitter = TextSplitter::new(ChunkConfig::new(512).with_sizer(ApproxTokens));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ApproxTokens;
+
+/// Kinds of characters that are counted differently
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Class {
+    Whitespace,
+    Newline,
+    Word,
+    Digit,
+    Ideograph,
+    Symbol,
+}
+
+impl Class {
+    fn of(c: char) -> Self {
+        match c {
+            '\n' | '\r' => Self::Newline,
+            _ if c.is_whitespace() => Self::Whitespace,
+            '0'..='9' => Self::Digit,
+            _ if is_ideograph(c) => Self::Ideograph,
+            _ if c.is_alphanumeric() || c == '\'' => Self::Word,
+            _ => Self::Symbol,
+        }
+    }
+}
+
+/// Scripts where a tokenizer usually needs a token or more per character.
+fn is_ideograph(c: char) -> bool {
+    matches!(c,
+        '\u{1100}'..='\u{11FF}' // Hangul Jamo
+        | '\u{3040}'..='\u{30FF}' // Hiragana and Katakana
+        | '\u{3400}'..='\u{4DBF}' // CJK Extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+        | '\u{AC00}'..='\u{D7AF}' // Hangul Syllables
+        | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+        | '\u{20000}'..='\u{2FA1F}' // CJK Extensions B and later
+    )
+}
+
+/// Letters per token in a word, in quarters of a letter, so that letters
+/// outside of ASCII can weigh more.
+const WORD_UNITS_PER_TOKEN: usize = 24;
+/// Digits per token.
+const DIGITS_PER_TOKEN: usize = 3;
+/// Punctuation marks per token.
+const SYMBOLS_PER_TOKEN: usize = 2;
+
+impl ChunkSizer for ApproxTokens {
+    /// Estimate the number of tokens in a given chunk.
+    fn size(&self, chunk: &str) -> usize {
+        let mut tokens = 0;
+        let mut chars = chunk.chars().peekable();
+        while let Some(c) = chars.next() {
+            match Class::of(c) {
+                Class::Word => {
+                    let mut units = word_units(c);
+                    while let Some(c) = chars.next_if(|c| Class::of(*c) == Class::Word) {
+                        units += word_units(c);
+                    }
+                    tokens += units.div_ceil(WORD_UNITS_PER_TOKEN);
+                }
+                Class::Digit => {
+                    let mut digits: usize = 1;
+                    while chars.next_if(char::is_ascii_digit).is_some() {
+                        digits += 1;
+                    }
+                    tokens += digits.div_ceil(DIGITS_PER_TOKEN);
+                }
+                // A single space is part of the token that follows it, longer
+                // runs, such as indentation or line breaks, are a token.
+                Class::Whitespace | Class::Newline => {
+                    let mut newline = Class::of(c) == Class::Newline;
+                    let mut len = 1;
+                    while let Some(c) = chars
+                        .next_if(|c| matches!(Class::of(*c), Class::Whitespace | Class::Newline))
+                    {
+                        newline |= Class::of(c) == Class::Newline;
+                        len += 1;
+                    }
+                    if newline || len > 1 {
+                        tokens += 1;
+                    }
+                }
+                Class::Symbol => {
+                    let mut symbols: usize = 1;
+                    while chars.next_if(|c| Class::of(*c) == Class::Symbol).is_some() {
+                        symbols += 1;
+                    }
+                    tokens += symbols.div_ceil(SYMBOLS_PER_TOKEN);
+                }
+                Class::Ideograph => tokens += 1,
+            }
+        }
+        tokens
+    }
+
+    /// The estimate is cheap to calculate, and there are never more tokens
+    /// than bytes.
+    fn is_linear(&self) -> bool {
+        true
+    }
+}
+
+/// How much a letter in a word counts, in quarters of a letter.
+fn word_units(c: char) -> usize {
+    match c.len_utf8() {
+        1 => 4,
+        2 => 8,
+        _ => 12,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_words() {
+        assert_eq!(ApproxTokens.size("An apple a day"), 4);
+        assert_eq!(ApproxTokens.size("Internationalization"), 4);
+    }
+
+    #[test]
+    fn estimates_digits_and_punctuation() {
+        assert_eq!(ApproxTokens.size("1234567, right?!"), 6);
+    }
+
+    #[test]
+    fn estimates_whitespace() {
+        assert_eq!(ApproxTokens.size("a\n\n    b"), 3);
+    }
+
+    #[test]
+    fn estimates_other_scripts() {
+        assert_eq!(ApproxTokens.size("你好世界"), 4);
+        assert_eq!(ApproxTokens.size("привет"), 2);
+    }
+
+    #[test]
+    fn is_linear() {
+        assert!(ApproxTokens.is_linear());
+    }
+}
@@ -17,6 +17,12 @@ impl ChunkSizer for Characters {
     fn size(&self, chunk: &str) -> usize {
         chunk.chars().count()
     }
+
+    /// Counting characters is cheap, and there are never more characters
+    /// than bytes.
+    fn is_linear(&self) -> bool {
+        true
+    }
 }
 
 #[cfg(test)]
@@ -28,4 +34,9 @@ mod tests {
         let offsets = Characters.size("eé");
         assert_eq!(offsets, 2);
     }
+
+    #[test]
+    fn is_linear() {
+        assert!(Characters.is_linear());
+    }
 }
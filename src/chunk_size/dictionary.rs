@@ -0,0 +1,144 @@
+use aho_corasick::{AhoCorasick, BuildError, MatchKind};
+use thiserror::Error;
+
+use crate::ChunkSizer;
+
+/// Indicates there was an error with building an [`AhoCorasickSizer`] from a
+/// list of patterns.
+/// The `Display` implementation will provide a human-readable error message to
+/// help debug the issue that caused the error.
+#[derive(Error, Debug)]
+#[error(transparent)]
+pub struct AhoCorasickSizerError(#[from] AhoCorasickSizerErrorRepr);
+
+/// Private error and free to change across minor version of the crate.
+#[derive(Error, Debug)]
+enum AhoCorasickSizerErrorRepr {
+    #[error("Unable to build automaton from the patterns")]
+    Build(#[source] BuildError),
+}
+
+/// Used for splitting a piece of text into chunks based on how many terms
+/// from a dictionary, such as the names of entities in a domain, are in each
+/// chunk, rather than on the length of the chunk.
+///
+/// Terms are matched with an [`AhoCorasick`] automaton, preferring the
+/// longest term if several match at the same position. Text without any
+/// terms in it has a size of 0, so a chunk extends up until the term that
+/// would make it go over the chunk capacity.
+///
+/// ```
+/// use text_splitter::{AhoCorasickSizer, ChunkConfig, TextSplitter};
+///
+/// let sizer = AhoCorasickSizer::new(["Rust", "Python"])
+///     .unwrap()
+///     .with_word_boundaries();
+/// // At most 2 terms per chunk
+/// let splitter = TextSplitter::new(ChunkConfig::new(2).with_sizer(sizer));
+/// ```
+#[derive(Clone, Debug)]
+pub struct AhoCorasickSizer {
+    /// Automaton that finds the terms
+    automaton: AhoCorasick,
+    /// Only count terms that aren't part of a larger word
+    word_boundaries: bool,
+}
+
+impl AhoCorasickSizer {
+    /// Build a sizer that counts the given terms. If several terms match at
+    /// the same position, the longest one is counted.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the automaton can't be built, such as if the
+    /// patterns are too large.
+    pub fn new<I, P>(patterns: I) -> Result<Self, AhoCorasickSizerError>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<[u8]>,
+    {
+        let automaton = AhoCorasick::builder()
+            .match_kind(MatchKind::LeftmostLongest)
+            .build(patterns)
+            .map_err(AhoCorasickSizerErrorRepr::Build)?;
+        Ok(Self::from(automaton))
+    }
+
+    /// Only count terms that start and end at a word boundary, so that a term
+    /// like `Go` isn't counted in `Google`.
+    #[must_use]
+    pub fn with_word_boundaries(mut self) -> Self {
+        self.word_boundaries = true;
+        self
+    }
+}
+
+impl From<AhoCorasick> for AhoCorasickSizer {
+    /// Use an automaton that was built with other options, such as to match
+    /// case insensitively.
+    fn from(automaton: AhoCorasick) -> Self {
+        Self {
+            automaton,
+            word_boundaries: false,
+        }
+    }
+}
+
+impl ChunkSizer for AhoCorasickSizer {
+    /// Returns the number of terms in a given chunk.
+    fn size(&self, chunk: &str) -> usize {
+        let is_word_char = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+        self.automaton
+            .find_iter(chunk)
+            .filter(|m| {
+                !self.word_boundaries
+                    || (!is_word_char(chunk[..m.start()].chars().next_back())
+                        && !is_word_char(chunk[m.end()..].chars().next()))
+            })
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ChunkConfig, TextSplitter};
+
+    use super::*;
+
+    #[test]
+    fn counts_terms() {
+        let sizer = AhoCorasickSizer::new(["Rust", "Rust Analyzer", "Go"]).unwrap();
+
+        assert_eq!(sizer.size("Rust Analyzer and Go, not Google"), 3);
+        assert_eq!(sizer.size("Nothing to see here"), 0);
+    }
+
+    #[test]
+    fn counts_whole_words() {
+        let sizer = AhoCorasickSizer::new(["Rust", "Go"])
+            .unwrap()
+            .with_word_boundaries();
+
+        assert_eq!(sizer.size("Rust and Go, not Google or Rusty"), 2);
+    }
+
+    #[test]
+    fn chunks_by_number_of_terms() {
+        let sizer = AhoCorasickSizer::new(["Rust", "Python", "Go"])
+            .unwrap()
+            .with_word_boundaries();
+        let splitter = TextSplitter::new(ChunkConfig::new(2).with_sizer(sizer.clone()));
+        let text = "Rust is fast. Python is easy. Go is simple. Rust and Go are compiled.";
+        let chunks = splitter.chunks(text).collect::<Vec<_>>();
+
+        assert!(chunks.iter().all(|chunk| sizer.size(chunk) <= 2));
+        assert_eq!(
+            chunks,
+            [
+                "Rust is fast. Python is easy.",
+                "Go is simple.",
+                "Rust and Go are compiled."
+            ]
+        );
+    }
+}
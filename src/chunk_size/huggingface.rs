@@ -1,6 +1,112 @@
+use std::{fs, io, path::Path};
+
+use thiserror::Error;
 use tokenizers::{Encoding, Tokenizer};
 
-use crate::ChunkSizer;
+use crate::{ChunkCapacity, ChunkSizer, ChunkSizerBatch};
+
+/// Indicates there was an error with loading a [`ModelTokenizer`], or that a
+/// chunk capacity is too large for the model.
+/// The `Display` implementation will provide a human-readable error message to
+/// help debug the issue that caused the error.
+#[derive(Error, Debug)]
+#[error(transparent)]
+pub struct ModelTokenizerError(#[from] ModelTokenizerErrorRepr);
+
+/// Private error and free to change across minor version of the crate.
+#[derive(Error, Debug)]
+enum ModelTokenizerErrorRepr {
+    #[error("Unable to load tokenizer")]
+    Tokenizer(#[source] tokenizers::Error),
+    #[error("Unable to read model config")]
+    Io(#[source] io::Error),
+    #[error("Unable to parse model config")]
+    Json(#[source] serde_json::Error),
+    #[error("Max chunk capacity of {max} is larger than the model limit of {limit} tokens")]
+    CapacityTooLarge { max: usize, limit: usize },
+}
+
+/// A tokenizer for a local model, loaded from a folder with the files
+/// downloaded from the Hugging Face Hub, for use with embedding models run
+/// locally with something like ONNX Runtime or candle.
+///
+/// Truncation and padding are disabled, so the size of a chunk is always the
+/// full number of tokens in it. If the folder contains the model's
+/// `config.json`, `max_position_embeddings` is read from it so that the chunk
+/// capacity can be checked against what the model can handle.
+#[derive(Clone, Debug)]
+pub struct ModelTokenizer {
+    /// Max number of tokens the model can embed
+    max_position_embeddings: Option<usize>,
+    /// Tokenizer used for counting tokens
+    tokenizer: Tokenizer,
+}
+
+impl ModelTokenizer {
+    /// Load the `tokenizer.json` and optional `config.json` from a model
+    /// folder.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if `tokenizer.json` can't be loaded, or if
+    /// `config.json` exists but can't be read or parsed.
+    pub fn from_dir(path: impl AsRef<Path>) -> Result<Self, ModelTokenizerError> {
+        let path = path.as_ref();
+        let mut tokenizer = Tokenizer::from_file(path.join("tokenizer.json"))
+            .map_err(ModelTokenizerErrorRepr::Tokenizer)?;
+        tokenizer
+            .with_truncation(None)
+            .map_err(ModelTokenizerErrorRepr::Tokenizer)?
+            .with_padding(None);
+
+        let max_position_embeddings = match fs::read(path.join("config.json")) {
+            Ok(config) => serde_json::from_slice::<serde_json::Value>(&config)
+                .map_err(ModelTokenizerErrorRepr::Json)?
+                .get("max_position_embeddings")
+                .and_then(serde_json::Value::as_u64)
+                .and_then(|max| usize::try_from(max).ok()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(ModelTokenizerErrorRepr::Io(e).into()),
+        };
+
+        Ok(Self {
+            max_position_embeddings,
+            tokenizer,
+        })
+    }
+
+    /// The max number of tokens the model can embed, if known.
+    #[must_use]
+    pub fn max_position_embeddings(&self) -> Option<usize> {
+        self.max_position_embeddings
+    }
+
+    /// The underlying tokenizer.
+    #[must_use]
+    pub fn tokenizer(&self) -> &Tokenizer {
+        &self.tokenizer
+    }
+
+    /// Check that chunks generated with the given capacity will fit within
+    /// `max_position_embeddings`. Always succeeds if the limit is unknown.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the max chunk capacity is larger than the model
+    /// limit.
+    pub fn check_capacity(
+        &self,
+        capacity: impl Into<ChunkCapacity>,
+    ) -> Result<(), ModelTokenizerError> {
+        let max = capacity.into().max;
+        match self.max_position_embeddings {
+            Some(limit) if max > limit => {
+                Err(ModelTokenizerErrorRepr::CapacityTooLarge { max, limit }.into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
 
 /// Compute the number of tokens that exist within an entire [`Encoding`] object.
 ///
@@ -39,6 +145,35 @@ impl ChunkSizer for &Tokenizer {
         let pad_id = self.get_padding().map(|params| params.pad_id);
         num_tokens_with_overflow(&encoding, pad_id)
     }
+
+    /// The max length of the tokenizer's truncation parameters, if set.
+    fn max_size(&self) -> Option<usize> {
+        self.get_truncation().map(|params| params.max_length)
+    }
+
+    fn batch_sizer(&self) -> Option<&dyn ChunkSizerBatch> {
+        Some(self)
+    }
+}
+
+impl ChunkSizerBatch for &Tokenizer {
+    /// Returns the number of tokens in each text, tokenizing them all at once.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if you don't have a byte-level tokenizer and the splitter
+    /// encounters text it can't tokenize.
+    fn sizes(&self, chunks: &[&str]) -> Vec<usize> {
+        let encodings = self
+            .encode_batch(chunks.to_vec(), false)
+            .expect("Unable to tokenize the batch of strings");
+
+        let pad_id = self.get_padding().map(|params| params.pad_id);
+        encodings
+            .iter()
+            .map(|encoding| num_tokens_with_overflow(encoding, pad_id))
+            .collect()
+    }
 }
 
 impl ChunkSizer for Tokenizer {
@@ -51,10 +186,79 @@ impl ChunkSizer for Tokenizer {
     fn size(&self, chunk: &str) -> usize {
         (&self).size(chunk)
     }
+
+    /// The max length of the tokenizer's truncation parameters, if set.
+    fn max_size(&self) -> Option<usize> {
+        (&self).max_size()
+    }
+
+    fn batch_sizer(&self) -> Option<&dyn ChunkSizerBatch> {
+        Some(self)
+    }
+}
+
+impl ChunkSizerBatch for Tokenizer {
+    /// Returns the number of tokens in each text, tokenizing them all at once.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if you don't have a byte-level tokenizer and the splitter
+    /// encounters text it can't tokenize.
+    fn sizes(&self, chunks: &[&str]) -> Vec<usize> {
+        (&self).sizes(chunks)
+    }
+}
+
+impl ChunkSizer for &ModelTokenizer {
+    /// Returns the number of tokens in a given text after tokenization.
+    fn size(&self, chunk: &str) -> usize {
+        self.tokenizer.size(chunk)
+    }
+
+    /// The model's `max_position_embeddings`, if known.
+    fn max_size(&self) -> Option<usize> {
+        self.max_position_embeddings
+    }
+
+    fn batch_sizer(&self) -> Option<&dyn ChunkSizerBatch> {
+        Some(self)
+    }
+}
+
+impl ChunkSizerBatch for &ModelTokenizer {
+    /// Returns the number of tokens in each text, tokenizing them all at once.
+    fn sizes(&self, chunks: &[&str]) -> Vec<usize> {
+        self.tokenizer.sizes(chunks)
+    }
+}
+
+impl ChunkSizer for ModelTokenizer {
+    /// Returns the number of tokens in a given text after tokenization.
+    fn size(&self, chunk: &str) -> usize {
+        self.tokenizer.size(chunk)
+    }
+
+    /// The model's `max_position_embeddings`, if known.
+    fn max_size(&self) -> Option<usize> {
+        self.max_position_embeddings
+    }
+
+    fn batch_sizer(&self) -> Option<&dyn ChunkSizerBatch> {
+        Some(self)
+    }
+}
+
+impl ChunkSizerBatch for ModelTokenizer {
+    /// Returns the number of tokens in each text, tokenizing them all at once.
+    fn sizes(&self, chunks: &[&str]) -> Vec<usize> {
+        self.tokenizer.sizes(chunks)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::ChunkConfig;
+
     use super::*;
 
     #[test]
@@ -73,6 +277,81 @@ mod tests {
         assert_eq!(size, 3);
     }
 
+    #[test]
+    fn batch_sizes_match_single_sizes() {
+        let tokenizer =
+            tokenizers::Tokenizer::from_file("./tests/tokenizers/huggingface.json").unwrap();
+        let chunks = ["An apple a", "day keeps", "the doctor away"];
+
+        let sizes = chunks.map(|chunk| tokenizer.size(chunk));
+        assert_eq!(tokenizer.sizes(&chunks), sizes);
+    }
+
+    fn model_dir(name: &str, config: Option<&str>) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("text-splitter-{name}"));
+        fs::create_dir_all(&dir).unwrap();
+        fs::copy(
+            "./tests/tokenizers/huggingface.json",
+            dir.join("tokenizer.json"),
+        )
+        .unwrap();
+        match config {
+            Some(config) => fs::write(dir.join("config.json"), config).unwrap(),
+            None => drop(fs::remove_file(dir.join("config.json"))),
+        }
+        dir
+    }
+
+    #[test]
+    fn model_tokenizer_from_dir() {
+        let dir = model_dir("model", Some(r#"{"max_position_embeddings": 512}"#));
+        let tokenizer = ModelTokenizer::from_dir(&dir).unwrap();
+
+        assert_eq!(tokenizer.max_position_embeddings(), Some(512));
+        assert_eq!(tokenizer.size("An apple a"), 3);
+        assert!(tokenizer.check_capacity(512).is_ok());
+        assert!(tokenizer.check_capacity(256..1024).is_err());
+        assert!(ChunkConfig::new(512)
+            .with_sizer(&tokenizer)
+            .validate()
+            .is_ok());
+        assert!(ChunkConfig::new(1024)
+            .with_sizer(&tokenizer)
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn max_size_from_truncation() {
+        let mut tokenizer =
+            tokenizers::Tokenizer::from_file("./tests/tokenizers/huggingface.json").unwrap();
+        tokenizer.with_truncation(None).unwrap();
+        assert_eq!(tokenizer.max_size(), None);
+
+        tokenizer
+            .with_truncation(Some(tokenizers::TruncationParams {
+                max_length: 128,
+                ..Default::default()
+            }))
+            .unwrap();
+        assert_eq!(tokenizer.max_size(), Some(128));
+    }
+
+    #[test]
+    fn model_tokenizer_without_config() {
+        let dir = model_dir("model-no-config", None);
+        let tokenizer = ModelTokenizer::from_dir(&dir).unwrap();
+
+        assert_eq!(tokenizer.max_position_embeddings(), None);
+        assert!(tokenizer.check_capacity(usize::MAX).is_ok());
+    }
+
+    #[test]
+    fn model_tokenizer_missing_tokenizer() {
+        let dir = std::env::temp_dir().join("text-splitter-missing-model");
+        assert!(ModelTokenizer::from_dir(dir).is_err());
+    }
+
     #[test]
     fn handles_padding() {
         let tokenizer = Tokenizer::from_pretrained("thenlper/gte-small", None).unwrap();
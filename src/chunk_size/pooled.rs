@@ -0,0 +1,253 @@
+use std::{
+    fmt,
+    sync::{mpsc, Arc, Mutex},
+    thread::{self, JoinHandle},
+};
+
+use crate::ChunkSizer;
+
+/// A chunk to measure, and where to send its size
+struct Job {
+    chunk: String,
+    size: mpsc::Sender<usize>,
+}
+
+/// Wraps chunk sizers that aren't `Send` or `Sync`, such as tokenizers with
+/// internal caches or bindings to Python callbacks, so that they can be used
+/// by APIs that chunk in parallel, such as
+/// [`ChunkPipeline`](crate::pipeline::ChunkPipeline) or
+/// [`ChunkConfig::into_dyn`](crate::ChunkConfig::into_dyn).
+///
+/// The pool starts a number of worker threads, and each of them creates its
+/// own sizer with the given function and keeps it for its whole lifetime.
+/// Chunks are measured by whichever worker is free next, so every sizer of
+/// the pool needs to return the same size for the same chunk. Each chunk is
+/// copied to the worker, so this is slower than using a thread-safe sizer
+/// directly.
+///
+/// ```
+/// use std::{cell::RefCell, collections::HashMap, rc::Rc};
+///
+/// use text_splitter::{ChunkConfig, ChunkSizer, PooledSizer, TextSplitter};
+///
+/// // Caches sizes with an `Rc`, so it can't be shared between threads
+/// #[derive(Default)]
+/// struct CachingSizer(Rc<RefCell<HashMap<String, usize>>>);
+///
+/// impl ChunkSizer for CachingSizer {
+///     fn size(&self, chunk: &str) -> usize {
+///         *self.0.borrow_mut().entry(chunk.to_string()).or_insert_with(|| chunk.chars().count())
+///     }
+/// }
+///
+/// let sizer = PooledSizer::new(2, CachingSizer::default);
+/// let splitter = TextSplitter::new(ChunkConfig::new(10).with_sizer(sizer).into_dyn());
+/// let chunks = splitter.chunks("Some text from a document").collect::<Vec<_>>();
+///
+/// assert_eq!(vec!["Some text", "from a", "document"], chunks);
+/// ```
+pub struct PooledSizer {
+    /// Queue of chunks for the workers to measure
+    jobs: Option<mpsc::Sender<Job>>,
+    /// Threads that own the sizers of the pool
+    workers: Vec<JoinHandle<()>>,
+    /// Max size of the sizers, as reported by the first worker
+    max_size: Option<usize>,
+    /// Whether the sizers are linear, as reported by the first worker
+    is_linear: bool,
+}
+
+impl PooledSizer {
+    /// Creates a new [`PooledSizer`] with the given number of workers, each
+    /// with its own sizer created by `make_sizer` on the thread of the worker.
+    /// There is always at least one worker.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a worker thread can't be started, or if `make_sizer` panics
+    /// for the first worker.
+    #[must_use]
+    pub fn new<Sizer, F>(workers: usize, make_sizer: F) -> Self
+    where
+        Sizer: ChunkSizer + 'static,
+        F: Fn() -> Sizer + Send + Sync + 'static,
+    {
+        let make_sizer = Arc::new(make_sizer);
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let queue = Arc::new(Mutex::new(queue));
+        let (info_sender, info) = mpsc::channel();
+
+        let workers = (0..workers.max(1))
+            .map(|index| {
+                let make_sizer = Arc::clone(&make_sizer);
+                let queue = Arc::clone(&queue);
+                let info_sender = (index == 0).then(|| info_sender.clone());
+                thread::Builder::new()
+                    .name(format!("text-splitter-sizer-{index}"))
+                    .spawn(move || {
+                        let sizer = make_sizer();
+                        if let Some(info_sender) = info_sender {
+                            // The pool waits for this, so it can't be gone yet
+                            let _ = info_sender.send((sizer.max_size(), sizer.is_linear()));
+                        }
+                        loop {
+                            // Release the lock before measuring, so other
+                            // workers can take the next job.
+                            let job = queue
+                                .lock()
+                                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                                .recv();
+                            let Ok(Job { chunk, size }) = job else {
+                                break;
+                            };
+                            // The caller stops waiting only if it panicked
+                            let _ = size.send(sizer.size(&chunk));
+                        }
+                    })
+                    .expect("Unable to start a sizer worker thread")
+            })
+            .collect();
+        drop(info_sender);
+
+        let (max_size, is_linear) = info
+            .recv()
+            .expect("Unable to create the sizer of the first worker");
+
+        Self {
+            jobs: Some(jobs),
+            workers,
+            max_size,
+            is_linear,
+        }
+    }
+
+    /// Number of workers in the pool.
+    #[must_use]
+    pub fn workers(&self) -> usize {
+        self.workers.len()
+    }
+}
+
+impl ChunkSizer for PooledSizer {
+    /// Measures the chunk with the sizer of the next free worker.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sizer of the worker panics, or if there are no workers
+    /// left because all of their sizers panicked.
+    fn size(&self, chunk: &str) -> usize {
+        let (size, receiver) = mpsc::channel();
+        self.jobs
+            .as_ref()
+            .and_then(|jobs| {
+                jobs.send(Job {
+                    chunk: chunk.to_string(),
+                    size,
+                })
+                .ok()
+            })
+            .expect("All sizer workers have stopped");
+        receiver
+            .recv()
+            .expect("The sizer of a worker panicked while measuring a chunk")
+    }
+
+    fn max_size(&self) -> Option<usize> {
+        self.max_size
+    }
+
+    fn is_linear(&self) -> bool {
+        self.is_linear
+    }
+}
+
+impl fmt::Debug for PooledSizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PooledSizer")
+            .field("workers", &self.workers.len())
+            .field("max_size", &self.max_size)
+            .field("is_linear", &self.is_linear)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for PooledSizer {
+    /// Stops the workers once they have measured the chunks already queued.
+    fn drop(&mut self) {
+        drop(self.jobs.take());
+        for worker in self.workers.drain(..) {
+            // A worker that panicked already reported it to its caller
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use crate::{Characters, ChunkConfig, TextSplitter};
+
+    use super::*;
+
+    /// Counts how often it was called, without being `Send` or `Sync`
+    #[derive(Default)]
+    struct CountingSizer(Rc<Cell<usize>>);
+
+    impl ChunkSizer for CountingSizer {
+        fn size(&self, chunk: &str) -> usize {
+            self.0.set(self.0.get() + 1);
+            Characters.size(chunk)
+        }
+
+        fn max_size(&self) -> Option<usize> {
+            Some(100)
+        }
+    }
+
+    #[test]
+    fn measures_on_workers() {
+        let sizer = PooledSizer::new(3, CountingSizer::default);
+
+        assert_eq!(sizer.workers(), 3);
+        assert_eq!(sizer.size("four"), 4);
+        assert_eq!(sizer.max_size(), Some(100));
+        assert!(!sizer.is_linear());
+    }
+
+    #[test]
+    fn measures_from_several_threads() {
+        let sizer = PooledSizer::new(2, CountingSizer::default);
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for len in 0..50 {
+                        assert_eq!(sizer.size(&"a".repeat(len)), len);
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn chunks_like_inner_sizer() {
+        let text = "Some text\n\nfrom a document, with a few sentences. And more words.";
+        let pooled = TextSplitter::new(
+            ChunkConfig::new(16)
+                .with_sizer(PooledSizer::new(2, CountingSizer::default))
+                .into_dyn(),
+        );
+        let direct = TextSplitter::new(16);
+
+        assert_eq!(
+            pooled.chunks(text).collect::<Vec<_>>(),
+            direct.chunks(text).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn at_least_one_worker() {
+        assert_eq!(PooledSizer::new(0, CountingSizer::default).workers(), 1);
+    }
+}
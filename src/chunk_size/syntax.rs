@@ -0,0 +1,184 @@
+use std::ops::Range;
+
+use ahash::AHashMap;
+
+use crate::ChunkSizer;
+
+/// Class of syntax that a span of source code belongs to, for sizers that
+/// weigh them differently.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum SyntaxClass {
+    /// A comment, including doc comments
+    Comment,
+    /// A string literal, including docstrings
+    String,
+}
+
+/// A span of a chunk of source code that isn't regular code, such as a
+/// comment or a string literal.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SyntaxSpan {
+    /// Byte range of the span within the chunk
+    pub range: Range<usize>,
+    /// Kind of the syntax node, as named by the tree-sitter grammar of the
+    /// language, such as `line_comment` or `string_literal`
+    pub kind: &'static str,
+    /// Class of syntax the node belongs to
+    pub class: SyntaxClass,
+}
+
+/// A chunk sizer that can also take the syntax of a chunk of source code into
+/// account, such as to count comments less than code.
+///
+/// The `CodeSplitter` passes the comments and string literals of each chunk
+/// to sizers that return themselves from [`ChunkSizer::syntax_sizer`]. Other
+/// splitters only use [`ChunkSizer::size`].
+pub trait SyntaxSizer: ChunkSizer {
+    /// Determine the size of a chunk of source code, given the spans of the
+    /// chunk that are comments or string literals, sorted by their start and
+    /// without overlap. Everything outside of the spans is regular code.
+    fn size_with_syntax(&self, chunk: &str, syntax: &[SyntaxSpan]) -> usize;
+}
+
+/// Wraps another chunk sizer to weigh comments and string literals in source
+/// code differently than the rest of the code, such as for code embeddings,
+/// where comments carry less signal than code.
+///
+/// Each part of a chunk is measured separately by the inner sizer, and the
+/// sizes of comments and strings are multiplied by their weight and rounded
+/// up. For tokenizers, the sum can differ slightly from the size of the
+/// whole chunk, even with a weight of 1.
+///
+/// Only the `CodeSplitter` knows the syntax of a chunk. Other splitters
+/// measure chunks with the inner sizer.
+///
+/// ```
+/// use text_splitter::{Characters, SyntaxClass, SyntaxWeighted};
+///
+/// // Comments count for a quarter of their length
+/// let sizer = SyntaxWeighted::new(Characters).with_weight(SyntaxClass::Comment, 0.25);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SyntaxWeighted<Sizer> {
+    /// Sizer to measure each part of a chunk with
+    sizer: Sizer,
+    /// Weights of the classes of syntax that don't count as 1
+    weights: AHashMap<SyntaxClass, f64>,
+}
+
+impl<Sizer> SyntaxWeighted<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Creates a new [`SyntaxWeighted`] sizer, where every class of syntax
+    /// has a weight of 1 until set otherwise.
+    #[must_use]
+    pub fn new(sizer: Sizer) -> Self {
+        Self {
+            sizer,
+            weights: AHashMap::new(),
+        }
+    }
+
+    /// Set the weight of a class of syntax, such as `0.5` for comments to
+    /// count for half of their size. Negative weights count as 0.
+    #[must_use]
+    pub fn with_weight(mut self, class: SyntaxClass, weight: f64) -> Self {
+        self.weights.insert(class, weight.max(0.0));
+        self
+    }
+
+    /// Size of a part of a chunk, weighted by its class of syntax, if any.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    fn weighted_size(&self, part: &str, class: Option<SyntaxClass>) -> usize {
+        let size = self.sizer.size(part);
+        match class.and_then(|class| self.weights.get(&class)) {
+            Some(weight) => (size as f64 * weight).ceil() as usize,
+            None => size,
+        }
+    }
+}
+
+impl<Sizer> ChunkSizer for SyntaxWeighted<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Size of the chunk as measured by the inner sizer, for chunks without
+    /// syntax information.
+    fn size(&self, chunk: &str) -> usize {
+        self.sizer.size(chunk)
+    }
+
+    fn max_size(&self) -> Option<usize> {
+        self.sizer.max_size()
+    }
+
+    fn is_linear(&self) -> bool {
+        self.sizer.is_linear()
+    }
+
+    fn syntax_sizer(&self) -> Option<&dyn SyntaxSizer> {
+        Some(self)
+    }
+}
+
+impl<Sizer> SyntaxSizer for SyntaxWeighted<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    fn size_with_syntax(&self, chunk: &str, syntax: &[SyntaxSpan]) -> usize {
+        if syntax.is_empty() {
+            return self.sizer.size(chunk);
+        }
+
+        let mut size = 0;
+        let mut cursor = 0;
+        for span in syntax {
+            if span.range.start > cursor {
+                size += self.weighted_size(&chunk[cursor..span.range.start], None);
+            }
+            size += self.weighted_size(&chunk[span.range.clone()], Some(span.class));
+            cursor = span.range.end;
+        }
+        if cursor < chunk.len() {
+            size += self.weighted_size(&chunk[cursor..], None);
+        }
+        size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Characters;
+
+    use super::*;
+
+    #[test]
+    fn weighs_comments_and_strings() {
+        let sizer = SyntaxWeighted::new(Characters)
+            .with_weight(SyntaxClass::Comment, 0.5)
+            .with_weight(SyntaxClass::String, 0.0);
+        let chunk = "let a = \"text\"; // comment";
+        let syntax = [
+            SyntaxSpan {
+                range: 8..14,
+                kind: "string_literal",
+                class: SyntaxClass::String,
+            },
+            SyntaxSpan {
+                range: 16..26,
+                kind: "line_comment",
+                class: SyntaxClass::Comment,
+            },
+        ];
+
+        assert_eq!(sizer.size(chunk), 26);
+        assert_eq!(sizer.size_with_syntax(chunk, &syntax), 8 + 2 + 5);
+        assert_eq!(sizer.size_with_syntax(chunk, &[]), 26);
+    }
+}
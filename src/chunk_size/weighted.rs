@@ -0,0 +1,129 @@
+use ahash::AHashMap;
+
+use crate::ChunkSizer;
+
+/// Weights are stored in thousandths, so that fractional weights add up
+/// exactly.
+const UNITS_PER_CHARACTER: u64 = 1000;
+
+/// Used for splitting a piece of text into chunks based on the number of
+/// characters in each chunk, where some characters count for more or less
+/// than one character, or not at all.
+///
+/// Useful if the limit of where the chunks are stored ignores whitespace, or
+/// counts some characters differently, so that more text can be packed into
+/// each chunk. The size of a chunk is the sum of the weights of its
+/// characters, rounded up.
+///
+/// ```
+/// use text_splitter::{ChunkConfig, TextSplitter, WeightedCharacters};
+///
+/// // Whitespace doesn't count, and punctuation counts for half a character.
+/// let sizer = WeightedCharacters::new()
+///     .with_whitespace_weight(0.0)
+///     .with_weight(['.', ',', ';'], 0.5);
+/// let splitter = TextSplitter::new(ChunkConfig::new(1000).with_sizer(sizer));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WeightedCharacters {
+    /// Weight of whitespace characters, in thousandths, if it was set
+    whitespace: Option<u64>,
+    /// Weights of specific characters, in thousandths
+    weights: AHashMap<char, u64>,
+}
+
+impl WeightedCharacters {
+    /// Creates a new [`WeightedCharacters`] sizer, where every character has a
+    /// weight of 1, which counts the same as [`Characters`](crate::Characters).
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the weight of whitespace characters, such as `0.0` to ignore
+    /// whitespace. Negative weights count as 0.
+    #[must_use]
+    pub fn with_whitespace_weight(mut self, weight: f64) -> Self {
+        self.whitespace = Some(units(weight));
+        self
+    }
+
+    /// Set the weight of each of the given characters. Takes precedence over
+    /// the weight of whitespace. Negative weights count as 0.
+    #[must_use]
+    pub fn with_weight(mut self, chars: impl IntoIterator<Item = char>, weight: f64) -> Self {
+        let weight = units(weight);
+        self.weights.extend(chars.into_iter().map(|c| (c, weight)));
+        self
+    }
+
+    /// Weight of a character, in thousandths
+    fn weight(&self, c: char) -> u64 {
+        match (self.weights.get(&c), self.whitespace) {
+            (Some(&weight), _) => weight,
+            (None, Some(weight)) if c.is_whitespace() => weight,
+            _ => UNITS_PER_CHARACTER,
+        }
+    }
+}
+
+/// Convert a weight to thousandths of a character.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+fn units(weight: f64) -> u64 {
+    (weight.max(0.0) * UNITS_PER_CHARACTER as f64).round() as u64
+}
+
+impl ChunkSizer for WeightedCharacters {
+    /// Returns the sum of the weights of the characters in a given chunk,
+    /// rounded up.
+    fn size(&self, chunk: &str) -> usize {
+        let units = chunk.chars().map(|c| self.weight(c)).sum::<u64>();
+        usize::try_from(units.div_ceil(UNITS_PER_CHARACTER)).unwrap_or(usize::MAX)
+    }
+
+    /// Weighing characters is cheap, and the size only grows with the number
+    /// of characters.
+    fn is_linear(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ChunkConfig, TextSplitter};
+
+    use super::*;
+
+    #[test]
+    fn defaults_to_characters() {
+        assert_eq!(WeightedCharacters::new().size("eé a"), 4);
+    }
+
+    #[test]
+    fn weighs_characters() {
+        let sizer = WeightedCharacters::new()
+            .with_whitespace_weight(0.0)
+            .with_weight(['.'], 0.5)
+            .with_weight(['\n'], 2.0);
+
+        assert_eq!(sizer.size("a b c"), 3);
+        assert_eq!(sizer.size("a b."), 3);
+        assert_eq!(sizer.size("a b.."), 3);
+        assert_eq!(sizer.size("a\nb"), 4);
+    }
+
+    #[test]
+    fn packs_more_text_without_whitespace() {
+        let text = "one two three four five six seven eight";
+        let sizer = WeightedCharacters::new().with_whitespace_weight(0.0);
+        let chunks = TextSplitter::new(ChunkConfig::new(15).with_sizer(sizer))
+            .chunks(text)
+            .collect::<Vec<_>>();
+
+        assert_eq!(chunks, ["one two three four", "five six seven", "eight"]);
+    }
+}
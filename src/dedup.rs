@@ -0,0 +1,336 @@
+/*!
+Finding chunks that were already seen across a corpus, such as license
+headers or navigation that end up in the chunks of thousands of documents.
+*/
+
+use ahash::AHashMap;
+use itertools::Itertools;
+
+use crate::fingerprint::Fnv1a;
+
+/// Number of hash functions in the `MinHash` signature of a chunk
+const NUM_HASHES: usize = 128;
+/// Number of values of the signature in each band used to find candidates
+const ROWS_PER_BAND: usize = 4;
+
+/// A chunk that is a duplicate of a chunk seen before.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Duplicate {
+    /// Position of the first occurrence among all chunks checked by the
+    /// deduplicator, starting at 0
+    pub of: usize,
+    /// Whether the chunk is the same as the first occurrence, apart from
+    /// whitespace, rather than only similar to it
+    pub exact: bool,
+    /// Estimated share of shingles the chunks have in common, from 0 to 1.
+    /// Always 1 for exact duplicates.
+    pub similarity: f64,
+}
+
+/// Keeps track of the chunks seen across a corpus, to filter out or tag
+/// chunks that were already seen in another place.
+///
+/// By default, only exact duplicates are found, ignoring differences in
+/// whitespace. Chunks are compared by a 64-bit hash of their text, so only a
+/// hash of each distinct chunk is kept in memory.
+///
+/// With [`ChunkDeduplicator::with_near_duplicates`], chunks that share most
+/// of their shingles, runs of consecutive words, with a chunk seen before
+/// are also duplicates. The share is estimated with
+/// [`MinHash`](https://en.wikipedia.org/wiki/MinHash), and only chunks that
+/// are likely to be similar are compared, so checking a chunk doesn't get
+/// slower as more chunks are seen.
+///
+/// ```
+/// use text_splitter::{ChunkDeduplicator, TextSplitter};
+///
+/// let splitter = TextSplitter::new(40);
+/// let documents = [
+///     "Licensed under the MIT license.\n\nFirst document.",
+///     "Licensed under the MIT license.\n\nSecond document.",
+/// ];
+///
+/// let mut deduplicator = ChunkDeduplicator::new();
+/// let chunks = documents
+///     .iter()
+///     .flat_map(|document| deduplicator.filter(splitter.chunks(document)).collect::<Vec<_>>())
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(
+///     chunks,
+///     ["Licensed under the MIT license.", "First document.", "Second document."]
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ChunkDeduplicator {
+    /// Position of the first occurrence of each distinct chunk, by the hash
+    /// of its text
+    exact: AHashMap<u64, usize>,
+    /// Index of the chunks seen so far, if near duplicates are found too
+    near: Option<NearDuplicates>,
+    /// Number of chunks checked so far
+    seen: usize,
+}
+
+impl ChunkDeduplicator {
+    /// Creates a new [`ChunkDeduplicator`] that finds exact duplicates.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also find chunks that share at least `threshold` of their shingles,
+    /// from 0 to 1, with a chunk seen before. A threshold around 0.8 finds
+    /// chunks that only differ in a few words, such as a year or a name.
+    ///
+    /// Shingles are 5 words long by default, which can be changed with
+    /// [`ChunkDeduplicator::with_shingle_size`].
+    #[must_use]
+    pub fn with_near_duplicates(mut self, threshold: f64) -> Self {
+        let threshold = threshold.clamp(0.0, 1.0);
+        match &mut self.near {
+            Some(near) => near.threshold = threshold,
+            None => self.near = Some(NearDuplicates::new(threshold)),
+        }
+        self
+    }
+
+    /// Set the number of consecutive words in each shingle used to find near
+    /// duplicates. Shorter shingles find chunks with the same words in a
+    /// different order, longer shingles only find chunks that share long
+    /// runs of text. Shingles are at least one word long.
+    ///
+    /// Only has an effect if near duplicates are found as well.
+    #[must_use]
+    pub fn with_shingle_size(mut self, shingle_size: usize) -> Self {
+        if let Some(near) = &mut self.near {
+            near.shingle_size = shingle_size.max(1);
+        }
+        self
+    }
+
+    /// Check whether a chunk is a duplicate of a chunk seen before, and
+    /// remember it for the chunks that are checked after it.
+    pub fn check(&mut self, chunk: &str) -> Option<Duplicate> {
+        let position = self.seen;
+        self.seen += 1;
+
+        let mut hasher = Fnv1a::default();
+        for word in chunk.split_whitespace() {
+            hasher.write(word.as_bytes());
+            // Never part of valid UTF-8, so words can't run into each other
+            hasher.write(&[0xFF]);
+        }
+        if let Some(&of) = self.exact.get(&hasher.0) {
+            return Some(Duplicate {
+                of,
+                exact: true,
+                similarity: 1.0,
+            });
+        }
+        self.exact.insert(hasher.0, position);
+
+        self.near
+            .as_mut()
+            .and_then(|near| near.check(chunk, position))
+    }
+
+    /// Only keep the chunks that aren't a duplicate of a chunk seen before,
+    /// including the chunks earlier in the same iterator.
+    pub fn filter<'a, I>(&'a mut self, chunks: I) -> impl Iterator<Item = I::Item> + 'a
+    where
+        I: IntoIterator + 'a,
+        I::Item: AsRef<str>,
+    {
+        chunks
+            .into_iter()
+            .filter(move |chunk| self.check(chunk.as_ref()).is_none())
+    }
+
+    /// Return each chunk along with the chunk seen before it is a duplicate
+    /// of, if any, such as to store duplicates with a reference to the first
+    /// occurrence instead of embedding them again.
+    pub fn tag<'a, I>(
+        &'a mut self,
+        chunks: I,
+    ) -> impl Iterator<Item = (I::Item, Option<Duplicate>)> + 'a
+    where
+        I: IntoIterator + 'a,
+        I::Item: AsRef<str>,
+    {
+        chunks.into_iter().map(move |chunk| {
+            let duplicate = self.check(chunk.as_ref());
+            (chunk, duplicate)
+        })
+    }
+}
+
+/// `MinHash` signatures of the chunks seen so far, with locality-sensitive
+/// hashing to find the chunks that are likely to be similar to a new one.
+#[derive(Clone, Debug)]
+struct NearDuplicates {
+    /// Min share of shingles in common for a chunk to be a duplicate
+    threshold: f64,
+    /// Number of words in each shingle
+    shingle_size: usize,
+    /// Signatures of the chunks that weren't duplicates, by their position
+    signatures: AHashMap<usize, Box<[u64; NUM_HASHES]>>,
+    /// Positions of the chunks with the same values in a band, by the index
+    /// and hash of the band
+    bands: AHashMap<(usize, u64), Vec<usize>>,
+}
+
+impl NearDuplicates {
+    fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            shingle_size: 5,
+            signatures: AHashMap::new(),
+            bands: AHashMap::new(),
+        }
+    }
+
+    /// Check whether a chunk is similar to a chunk seen before, and remember
+    /// its signature if it isn't.
+    fn check(&mut self, chunk: &str, position: usize) -> Option<Duplicate> {
+        let signature = self.signature(chunk)?;
+        let bands = signature
+            .chunks(ROWS_PER_BAND)
+            .map(|band| {
+                let mut hasher = Fnv1a::default();
+                for value in band {
+                    hasher.write(&value.to_le_bytes());
+                }
+                hasher.0
+            })
+            .enumerate()
+            .collect::<Vec<_>>();
+
+        let duplicate = bands
+            .iter()
+            .filter_map(|band| self.bands.get(band))
+            .flatten()
+            .copied()
+            .sorted_unstable()
+            .dedup()
+            .map(|of| {
+                let matching = self.signatures[&of]
+                    .iter()
+                    .zip(signature.iter())
+                    .filter(|(a, b)| a == b)
+                    .count();
+                #[allow(clippy::cast_precision_loss)]
+                let similarity = matching as f64 / NUM_HASHES as f64;
+                (of, similarity)
+            })
+            .filter(|(_, similarity)| *similarity >= self.threshold)
+            // Most similar chunk, and the earliest one if there is a tie
+            .max_by(|(a_of, a), (b_of, b)| a.total_cmp(b).then(b_of.cmp(a_of)));
+
+        if let Some((of, similarity)) = duplicate {
+            return Some(Duplicate {
+                of,
+                exact: false,
+                similarity,
+            });
+        }
+
+        for band in bands {
+            self.bands.entry(band).or_default().push(position);
+        }
+        self.signatures.insert(position, signature);
+        None
+    }
+
+    /// `MinHash` signature of the shingles of a chunk, or `None` if the chunk
+    /// doesn't have any words. Chunks with fewer words than the shingle size
+    /// have a single shingle with all of their words.
+    fn signature(&self, chunk: &str) -> Option<Box<[u64; NUM_HASHES]>> {
+        let words = chunk.split_whitespace().collect::<Vec<_>>();
+        if words.is_empty() {
+            return None;
+        }
+
+        let mut signature = Box::new([u64::MAX; NUM_HASHES]);
+        for shingle in words.windows(self.shingle_size.min(words.len())) {
+            let mut hasher = Fnv1a::default();
+            for word in shingle {
+                hasher.write(word.as_bytes());
+                hasher.write(&[0xFF]);
+            }
+            for (seed, min) in (0u64..).zip(signature.iter_mut()) {
+                *min = (*min).min(mix(hasher.0 ^ seed.wrapping_mul(0x9E37_79B9_7F4A_7C15)));
+            }
+        }
+        Some(signature)
+    }
+}
+
+/// Finalizer of `SplitMix64`, to derive independent hash functions from the
+/// hash of a shingle with a different seed for each.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_duplicates_ignore_whitespace() {
+        let mut deduplicator = ChunkDeduplicator::new();
+
+        assert_eq!(deduplicator.check("Copyright 2024 Acme"), None);
+        assert_eq!(deduplicator.check("Something else"), None);
+        assert_eq!(
+            deduplicator.check("Copyright  2024\nAcme"),
+            Some(Duplicate {
+                of: 0,
+                exact: true,
+                similarity: 1.0
+            })
+        );
+        assert_eq!(deduplicator.check("Copyright 2025 Acme"), None);
+    }
+
+    #[test]
+    fn near_duplicates() {
+        let boilerplate = "This page is part of the user guide. For questions, contact the \
+            documentation team or open an issue in the tracker. Last updated in";
+        let mut deduplicator = ChunkDeduplicator::new().with_near_duplicates(0.7);
+
+        assert_eq!(deduplicator.check(&format!("{boilerplate} March.")), None);
+        assert_eq!(
+            deduplicator.check("A completely different paragraph about installing the tool."),
+            None
+        );
+        let duplicate = deduplicator
+            .check(&format!("{boilerplate} April."))
+            .unwrap();
+        assert_eq!(duplicate.of, 0);
+        assert!(!duplicate.exact);
+        assert!(duplicate.similarity >= 0.7 && duplicate.similarity < 1.0);
+    }
+
+    #[test]
+    fn tags_duplicates_across_documents() {
+        let mut deduplicator = ChunkDeduplicator::new().with_near_duplicates(0.8);
+        let tags = deduplicator
+            .tag(["Header", "First", "Header", "Second", ""])
+            .map(|(chunk, duplicate)| (chunk, duplicate.map(|d| d.of)))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            tags,
+            [
+                ("Header", None),
+                ("First", None),
+                ("Header", Some(0)),
+                ("Second", None),
+                ("", None)
+            ]
+        );
+    }
+}
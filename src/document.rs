@@ -0,0 +1,635 @@
+/*!
+# [`DocumentLoader`]
+Load documents from disk, detecting the format of each file so that the
+matching splitter can be used without every caller having to write the same
+dispatch over file extensions.
+*/
+
+use std::{
+    ffi::OsStr,
+    fs, io,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "code")]
+use ahash::AHashMap;
+#[cfg(feature = "memmap2")]
+use memmap2::Mmap;
+use thiserror::Error;
+#[cfg(feature = "code")]
+use tree_sitter::Language;
+
+#[cfg(feature = "markdown")]
+use crate::MarkdownSplitter;
+use crate::{ChunkConfig, ChunkSizer, SplitterExt, TextSplitter};
+#[cfg(feature = "code")]
+use crate::{CodeSplitter, CodeSplitterError};
+
+/// Indicates there was an error loading or splitting a document.
+/// The `Display` implementation will provide a human-readable error message to
+/// help debug the issue that caused the error.
+#[derive(Error, Debug)]
+#[error(transparent)]
+#[allow(clippy::module_name_repetitions)]
+pub struct DocumentError(#[from] DocumentErrorRepr);
+
+/// Private error and free to change across minor version of the crate.
+#[derive(Error, Debug)]
+enum DocumentErrorRepr {
+    #[error("Unable to read {path:?}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("File {path:?} is {size} bytes, which is larger than the maximum of {max} bytes")]
+    FileTooLarge { path: PathBuf, size: u64, max: u64 },
+    #[error("File {0:?} does not contain valid UTF-8 or UTF-16 text")]
+    InvalidEncoding(PathBuf),
+    #[cfg(feature = "code")]
+    #[error(transparent)]
+    CodeSplitter(#[from] CodeSplitterError),
+}
+
+/// The format of a document, which determines which splitter is used.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum DocumentFormat {
+    /// Plain text, split with a [`TextSplitter`].
+    Text,
+    /// Markdown, split with a [`MarkdownSplitter`].
+    #[cfg(feature = "markdown")]
+    Markdown,
+    /// Source code in the given language, split with a [`CodeSplitter`].
+    #[cfg(feature = "code")]
+    Code(Language),
+}
+
+/// A document read from disk, along with its detected format.
+#[derive(Debug)]
+pub struct Document {
+    /// The format detected for this document
+    format: DocumentFormat,
+    /// Where the document was loaded from
+    path: PathBuf,
+    /// The decoded contents of the document
+    contents: Contents,
+}
+
+/// Storage for the text of a document.
+#[derive(Debug)]
+enum Contents {
+    /// Text that was read or decoded into memory
+    Owned(String),
+    /// A memory-mapped file, already validated as UTF-8 from `start` onwards.
+    #[cfg(feature = "memmap2")]
+    Mapped { map: Mmap, start: usize },
+}
+
+impl Contents {
+    /// Use the memory map directly if it is UTF-8, skipping over any byte
+    /// order mark. Other encodings need to be decoded into memory.
+    #[cfg(feature = "memmap2")]
+    fn mapped(map: Mmap) -> Option<Self> {
+        let start = if map.starts_with(UTF8_BOM) {
+            UTF8_BOM.len()
+        } else if map.starts_with(&[0xFF, 0xFE]) || map.starts_with(&[0xFE, 0xFF]) {
+            return decode(map.to_vec()).map(Self::Owned);
+        } else {
+            0
+        };
+        std::str::from_utf8(&map[start..]).ok()?;
+        Some(Self::Mapped { map, start })
+    }
+
+    fn as_str(&self) -> &str {
+        match self {
+            Self::Owned(text) => text,
+            #[cfg(feature = "memmap2")]
+            Self::Mapped { map, start } => {
+                // SAFETY: validated as UTF-8 when the contents were created.
+                unsafe { std::str::from_utf8_unchecked(&map[*start..]) }
+            }
+        }
+    }
+}
+
+impl Document {
+    /// The format that was detected for this document.
+    #[must_use]
+    pub fn format(&self) -> &DocumentFormat {
+        &self.format
+    }
+
+    /// The path this document was loaded from.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The decoded text of the document. Any byte order mark has been removed.
+    #[must_use]
+    pub fn text(&self) -> &str {
+        self.contents.as_str()
+    }
+
+    /// Create a splitter suitable for the format of this document.
+    ///
+    /// ```no_run
+    /// use text_splitter::DocumentLoader;
+    ///
+    /// let document = DocumentLoader::new().load("README.md").unwrap();
+    /// let splitter = document.splitter(512).unwrap();
+    /// let chunks = splitter.chunks(document.text()).collect::<Vec<_>>();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the document is source code whose language
+    /// isn't compatible with the current version of tree-sitter.
+    pub fn splitter<Sizer>(
+        &self,
+        chunk_config: impl Into<ChunkConfig<Sizer>>,
+    ) -> Result<DocumentSplitter<Sizer>, DocumentError>
+    where
+        Sizer: ChunkSizer,
+    {
+        DocumentSplitter::new(&self.format, chunk_config)
+    }
+}
+
+/// Reads files from disk and detects their format by extension and byte
+/// order mark.
+///
+/// Files with a `.md` or `.markdown` extension are treated as Markdown (if the
+/// `markdown` feature is enabled), extensions registered with
+/// [`DocumentLoader::with_language`] are treated as code, and everything else
+/// is treated as plain text.
+#[derive(Debug, Default)]
+#[allow(clippy::module_name_repetitions)]
+pub struct DocumentLoader {
+    /// Tree-sitter languages to use for a given file extension
+    #[cfg(feature = "code")]
+    languages: AHashMap<String, Language>,
+    /// Files larger than this many bytes will not be loaded
+    max_file_size: Option<u64>,
+    /// Whether files should be memory-mapped instead of read into memory
+    #[cfg(feature = "memmap2")]
+    memory_map: bool,
+}
+
+impl DocumentLoader {
+    /// Creates a new [`DocumentLoader`] with no size limit.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum size of a file, in bytes, that will be read.
+    ///
+    /// Loading a single file that is larger returns an error, while
+    /// [`DocumentLoader::load_dir`] skips over any files that are too large.
+    #[must_use]
+    pub fn with_max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = Some(max_file_size);
+        self
+    }
+
+    /// Memory-map files instead of reading them into a `String`. UTF-8 files
+    /// are then split directly from the mapped memory, which avoids copying
+    /// very large files. Files with a UTF-16 byte order mark still need to be
+    /// decoded into memory.
+    ///
+    /// As with any memory map, the file must not be modified while the
+    /// [`Document`] is alive, or the text can change out from under it.
+    #[cfg(feature = "memmap2")]
+    #[must_use]
+    pub fn with_memory_map(mut self, memory_map: bool) -> Self {
+        self.memory_map = memory_map;
+        self
+    }
+
+    /// Treat files with the given extension as code of the given language.
+    ///
+    /// ```
+    /// use text_splitter::DocumentLoader;
+    ///
+    /// let loader = DocumentLoader::new()
+    ///     .with_language("rs", tree_sitter_rust::LANGUAGE)
+    ///     .expect("Invalid language");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the language version is too old or too new to
+    /// be compatible with the current version of the tree-sitter crate.
+    #[cfg(feature = "code")]
+    pub fn with_language(
+        mut self,
+        extension: impl Into<String>,
+        language: impl Into<Language>,
+    ) -> Result<Self, DocumentError> {
+        let language = language.into();
+        // Validate up front, so we know later on the splitter can be created.
+        CodeSplitter::new(language.clone(), 1).map_err(DocumentErrorRepr::CodeSplitter)?;
+        self.languages.insert(extension.into(), language);
+        Ok(self)
+    }
+
+    /// Detect the format of a file based on its extension.
+    #[must_use]
+    pub fn detect_format(&self, path: impl AsRef<Path>) -> DocumentFormat {
+        let extension = path.as_ref().extension().and_then(OsStr::to_str);
+        match extension {
+            #[cfg(feature = "markdown")]
+            Some("md" | "markdown") => DocumentFormat::Markdown,
+            #[cfg(feature = "code")]
+            Some(extension) if self.languages.contains_key(extension) => {
+                DocumentFormat::Code(self.languages[extension].clone())
+            }
+            _ => DocumentFormat::Text,
+        }
+    }
+
+    /// Read a single file from disk.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the file can't be read, is larger than the
+    /// maximum file size, or isn't valid UTF-8 or UTF-16 (with a byte order mark).
+    pub fn load(&self, path: impl AsRef<Path>) -> Result<Document, DocumentError> {
+        let path = path.as_ref();
+        let io_error = |source| DocumentErrorRepr::Io {
+            path: path.to_owned(),
+            source,
+        };
+
+        if let Some(max) = self.max_file_size {
+            let size = fs::metadata(path).map_err(io_error)?.len();
+            if size > max {
+                return Err(DocumentErrorRepr::FileTooLarge {
+                    path: path.to_owned(),
+                    size,
+                    max,
+                }
+                .into());
+            }
+        }
+
+        let contents = self.read(path).map_err(io_error)?;
+        let contents =
+            contents.ok_or_else(|| DocumentErrorRepr::InvalidEncoding(path.to_owned()))?;
+
+        Ok(Document {
+            format: self.detect_format(path),
+            path: path.to_owned(),
+            contents,
+        })
+    }
+
+    /// Read the contents of a file. Returns `None` if it isn't valid text.
+    #[cfg_attr(not(feature = "memmap2"), allow(clippy::unused_self))]
+    fn read(&self, path: &Path) -> io::Result<Option<Contents>> {
+        #[cfg(feature = "memmap2")]
+        if self.memory_map {
+            let file = fs::File::open(path)?;
+            // SAFETY: the caller opted in to memory mapping, and is documented
+            // to not modify the file while the document is in use.
+            let map = unsafe { Mmap::map(&file)? };
+            return Ok(Contents::mapped(map));
+        }
+
+        Ok(decode(fs::read(path)?).map(Contents::Owned))
+    }
+
+    /// Recursively read all files within a directory. Files are visited in
+    /// sorted order, and files larger than the maximum file size are skipped.
+    /// Symlinks to directories within it are skipped, since they can link
+    /// back to a parent directory and never finish.
+    ///
+    /// Errors for individual files are returned as items of the iterator, so
+    /// that one unreadable file doesn't stop the rest from being loaded.
+    pub fn load_dir(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> impl Iterator<Item = Result<Document, DocumentError>> + '_ {
+        let root = path.as_ref().to_owned();
+        let mut pending = vec![root.clone()];
+        std::iter::from_fn(move || loop {
+            let path = pending.pop()?;
+            if path.is_dir() {
+                if path != root && fs::symlink_metadata(&path).is_ok_and(|m| m.is_symlink()) {
+                    continue;
+                }
+                match sorted_entries(&path) {
+                    // Reverse so that we pop them in order
+                    Ok(entries) => pending.extend(entries.into_iter().rev()),
+                    Err(source) => return Some(Err(DocumentErrorRepr::Io { path, source }.into())),
+                }
+                continue;
+            }
+            if let Some(max) = self.max_file_size {
+                if fs::metadata(&path).is_ok_and(|m| m.len() > max) {
+                    continue;
+                }
+            }
+            return Some(self.load(&path));
+        })
+    }
+}
+
+/// Entries of a directory, sorted so that loading is deterministic.
+fn sorted_entries(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut entries = fs::read_dir(path)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    Ok(entries)
+}
+
+/// Byte order mark for UTF-8 encoded text
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+/// Decode the bytes of a file, using the byte order mark if there is one.
+/// Defaults to UTF-8 if there isn't.
+fn decode(bytes: Vec<u8>) -> Option<String> {
+    let utf16 = |bytes: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        let units = bytes
+            .chunks(2)
+            .map(|pair| pair.try_into().ok().map(from_bytes))
+            .collect::<Option<Vec<_>>>()?;
+        String::from_utf16(&units).ok()
+    };
+
+    if let Some(rest) = bytes.strip_prefix(UTF8_BOM) {
+        return String::from_utf8(rest.to_vec()).ok();
+    }
+
+    match bytes.as_slice() {
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        _ => String::from_utf8(bytes).ok(),
+    }
+}
+
+/// Splitter that uses the appropriate splitter for a given [`DocumentFormat`].
+#[derive(Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub enum DocumentSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Plain text splitter
+    Text(TextSplitter<Sizer>),
+    /// Markdown splitter
+    #[cfg(feature = "markdown")]
+    Markdown(MarkdownSplitter<Sizer>),
+    /// Code splitter
+    #[cfg(feature = "code")]
+    Code(CodeSplitter<Sizer>),
+}
+
+impl<Sizer> DocumentSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Creates a new [`DocumentSplitter`] for the given format.
+    ///
+    /// ```
+    /// use text_splitter::{DocumentFormat, DocumentSplitter};
+    ///
+    /// let splitter = DocumentSplitter::new(&DocumentFormat::Text, 512).unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the format is source code whose language
+    /// isn't compatible with the current version of tree-sitter.
+    pub fn new(
+        format: &DocumentFormat,
+        chunk_config: impl Into<ChunkConfig<Sizer>>,
+    ) -> Result<Self, DocumentError> {
+        Ok(match format {
+            DocumentFormat::Text => Self::Text(TextSplitter::new(chunk_config)),
+            #[cfg(feature = "markdown")]
+            DocumentFormat::Markdown => Self::Markdown(MarkdownSplitter::new(chunk_config)),
+            #[cfg(feature = "code")]
+            DocumentFormat::Code(language) => Self::Code(
+                CodeSplitter::new(language.clone(), chunk_config)
+                    .map_err(DocumentErrorRepr::CodeSplitter)?,
+            ),
+        })
+    }
+
+    /// The chunk configuration the splitter was created with, such as for
+    /// measuring text with the same sizer.
+    #[must_use]
+    pub fn chunk_config(&self) -> &ChunkConfig<Sizer> {
+        match self {
+            Self::Text(splitter) => splitter.chunk_config(),
+            #[cfg(feature = "markdown")]
+            Self::Markdown(splitter) => splitter.chunk_config(),
+            #[cfg(feature = "code")]
+            Self::Code(splitter) => splitter.chunk_config(),
+        }
+    }
+
+    /// Generate a list of chunks from a given text, using the splitter for
+    /// this format. Each chunk will be up to the `chunk_capacity`.
+    pub fn chunks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = &'text str> + 'splitter {
+        self.chunk_indices(text).map(|(_, t)| t)
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets,
+    /// using the splitter for this format. Each chunk will be up to the
+    /// `chunk_capacity`.
+    pub fn chunk_indices<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> Box<dyn Iterator<Item = (usize, &'text str)> + 'splitter> {
+        match self {
+            Self::Text(splitter) => Box::new(splitter.chunk_indices(text)),
+            #[cfg(feature = "markdown")]
+            Self::Markdown(splitter) => Box::new(splitter.chunk_indices(text)),
+            #[cfg(feature = "code")]
+            Self::Code(splitter) => Box::new(splitter.chunk_indices(text)),
+        }
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets,
+    /// along with the byte range of each chunk before it was trimmed, using
+    /// the splitter for this format.
+    pub fn chunks_with_untrimmed_ranges<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> Box<dyn Iterator<Item = (usize, &'text str, Range<usize>)> + 'splitter> {
+        match self {
+            Self::Text(splitter) => Box::new(splitter.chunks_with_untrimmed_ranges(text)),
+            #[cfg(feature = "markdown")]
+            Self::Markdown(splitter) => Box::new(splitter.chunks_with_untrimmed_ranges(text)),
+            #[cfg(feature = "code")]
+            Self::Code(splitter) => Box::new(splitter.chunks_with_untrimmed_ranges(text)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(unix)]
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// Directory in the system temp dir that is unique to this test run and
+    /// removed again when dropped, even if the test fails
+    #[cfg(unix)]
+    struct TempDir(PathBuf);
+
+    #[cfg(unix)]
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            static COUNTER: AtomicUsize = AtomicUsize::new(0);
+            let dir = std::env::temp_dir().join(format!(
+                "text-splitter-{name}-{}-{}",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    #[cfg(unix)]
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn detects_text_by_default() {
+        let loader = DocumentLoader::new();
+        assert_eq!(loader.detect_format("notes.txt"), DocumentFormat::Text);
+        assert_eq!(loader.detect_format("no_extension"), DocumentFormat::Text);
+    }
+
+    #[cfg(feature = "markdown")]
+    #[test]
+    fn detects_markdown() {
+        let loader = DocumentLoader::new();
+        assert_eq!(loader.detect_format("README.md"), DocumentFormat::Markdown);
+        assert_eq!(
+            loader.detect_format("notes.markdown"),
+            DocumentFormat::Markdown
+        );
+    }
+
+    #[cfg(feature = "code")]
+    #[test]
+    fn detects_registered_language() {
+        let loader = DocumentLoader::new()
+            .with_language("rs", tree_sitter_rust::LANGUAGE)
+            .unwrap();
+        assert_eq!(
+            loader.detect_format("src/lib.rs"),
+            DocumentFormat::Code(tree_sitter_rust::LANGUAGE.into())
+        );
+        assert_eq!(loader.detect_format("src/lib.py"), DocumentFormat::Text);
+    }
+
+    #[test]
+    fn decodes_utf8_bom() {
+        let text = decode(b"\xEF\xBB\xBFhello".to_vec());
+        assert_eq!(text.as_deref(), Some("hello"));
+    }
+
+    #[test]
+    fn decodes_utf16_boms() {
+        let le = decode(vec![0xFF, 0xFE, b'h', 0, b'i', 0]);
+        assert_eq!(le.as_deref(), Some("hi"));
+        let be = decode(vec![0xFE, 0xFF, 0, b'h', 0, b'i']);
+        assert_eq!(be.as_deref(), Some("hi"));
+    }
+
+    #[test]
+    fn rejects_invalid_utf8() {
+        assert_eq!(decode(vec![0xC3, 0x28]), None);
+    }
+
+    #[test]
+    fn load_file_too_large() {
+        let err = DocumentLoader::new()
+            .with_max_file_size(1)
+            .load("tests/inputs/text/romeo_and_juliet.txt")
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("larger than the maximum of 1 bytes"));
+    }
+
+    #[test]
+    fn load_dir_skips_large_files() {
+        let loader = DocumentLoader::new().with_max_file_size(1);
+        assert_eq!(loader.load_dir("tests/inputs").count(), 0);
+    }
+
+    #[test]
+    fn load_dir_is_sorted_and_recursive() {
+        let paths = DocumentLoader::new()
+            .load_dir("tests/inputs")
+            .map(|d| d.unwrap().path().to_owned())
+            .collect::<Vec<_>>();
+
+        let mut sorted = paths.clone();
+        sorted.sort();
+        assert_eq!(paths, sorted);
+        assert!(paths.iter().any(|p| p.starts_with("tests/inputs/markdown")));
+        assert!(paths.iter().any(|p| p.starts_with("tests/inputs/text")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn load_dir_skips_symlinked_dirs() {
+        let dir = TempDir::new("symlink-loop");
+        fs::create_dir_all(dir.0.join("nested")).unwrap();
+        fs::write(dir.0.join("nested/notes.txt"), "notes").unwrap();
+        std::os::unix::fs::symlink(&dir.0, dir.0.join("nested/parent")).unwrap();
+
+        let paths = DocumentLoader::new()
+            .load_dir(&dir.0)
+            .map(|d| d.unwrap().path().to_owned())
+            .collect::<Vec<_>>();
+
+        assert_eq!(paths, vec![dir.0.join("nested/notes.txt")]);
+    }
+
+    #[cfg(feature = "memmap2")]
+    #[test]
+    fn memory_map_matches_read() {
+        let path = "tests/inputs/markdown/commonmark_spec.md";
+        let read = DocumentLoader::new().load(path).unwrap();
+        let mapped = DocumentLoader::new()
+            .with_memory_map(true)
+            .load(path)
+            .unwrap();
+
+        assert!(matches!(mapped.contents, Contents::Mapped { .. }));
+        assert_eq!(read.text(), mapped.text());
+    }
+
+    #[test]
+    fn document_splitter_matches_splitter() {
+        let text = "Some text\n\nfrom a\ndocument";
+        let splitter = DocumentSplitter::new(&DocumentFormat::Text, 10).unwrap();
+        assert_eq!(
+            splitter.chunk_indices(text).collect::<Vec<_>>(),
+            TextSplitter::new(10)
+                .chunk_indices(text)
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,218 @@
+/*!
+Embedding chunks while they are generated, in batches, so that chunking and
+embedding happen in a single pass over the text.
+*/
+
+use std::{collections::VecDeque, fmt, iter::FusedIterator};
+
+/// Number of chunks embedded at once, unless set otherwise
+const DEFAULT_BATCH_SIZE: usize = 32;
+
+/// Adds [`WithEmbedder::with_embedder`] to iterators over chunks and their
+/// byte offsets, such as the one returned by `chunk_indices` of every
+/// splitter.
+pub trait WithEmbedder<'text>: Iterator<Item = (usize, &'text str)> + Sized {
+    /// Embed the chunks in batches with the given callback, and yield each
+    /// chunk along with its byte offset and embedding. The callback gets the
+    /// text of a batch of chunks, and returns an embedding for each of them,
+    /// in the same order, or an error.
+    ///
+    /// Chunks are only generated as the embeddings are needed, one batch
+    /// ahead, so embedding can start before the whole text is chunked.
+    /// Batches are 32 chunks by default, which can be changed with
+    /// [`EmbeddedChunks::with_batch_size`].
+    ///
+    /// ```
+    /// use text_splitter::{TextSplitter, WithEmbedder};
+    ///
+    /// // Stand-in for a call to an embedding model
+    /// fn embed(batch: &[&str]) -> Result<Vec<Vec<f32>>, String> {
+    ///     Ok(batch.iter().map(|chunk| vec![chunk.len() as f32]).collect())
+    /// }
+    ///
+    /// let splitter = TextSplitter::new(10);
+    /// let chunks = splitter
+    ///     .chunk_indices("Some text\n\nfrom a\ndocument")
+    ///     .with_embedder(embed)
+    ///     .with_batch_size(2)
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         (0, "Some text", vec![9.0]),
+    ///         (11, "from a", vec![6.0]),
+    ///         (18, "document", vec![8.0]),
+    ///     ],
+    ///     chunks
+    /// );
+    /// ```
+    fn with_embedder<F, E, Err>(self, embedder: F) -> EmbeddedChunks<'text, Self, F, E>
+    where
+        F: FnMut(&[&'text str]) -> Result<Vec<E>, Err>,
+    {
+        EmbeddedChunks {
+            chunks: self,
+            embedder,
+            batch_size: DEFAULT_BATCH_SIZE,
+            embedded: VecDeque::new(),
+            finished: false,
+        }
+    }
+}
+
+impl<'text, I> WithEmbedder<'text> for I where I: Iterator<Item = (usize, &'text str)> {}
+
+/// Iterator over chunks, their byte offsets, and their embeddings, as
+/// returned by [`WithEmbedder::with_embedder`].
+///
+/// If the embedder returns an error, the error is yielded in place of the
+/// chunks of that batch, and the iterator ends.
+///
+/// # Panics
+///
+/// Panics if the embedder returns a different number of embeddings than the
+/// number of chunks it was given.
+pub struct EmbeddedChunks<'text, I, F, E> {
+    /// Chunks that haven't been embedded yet
+    chunks: I,
+    /// Callback to embed a batch of chunks with
+    embedder: F,
+    /// Max number of chunks to embed at once
+    batch_size: usize,
+    /// Chunks of the current batch that were embedded but not yielded yet
+    embedded: VecDeque<(usize, &'text str, E)>,
+    /// Whether the embedder returned an error, so no more chunks are yielded
+    finished: bool,
+}
+
+impl<I, F, E> EmbeddedChunks<'_, I, F, E> {
+    /// Set the max number of chunks to embed at once. Batches are at least
+    /// one chunk.
+    #[must_use]
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+}
+
+impl<'text, I, F, E, Err> Iterator for EmbeddedChunks<'text, I, F, E>
+where
+    I: Iterator<Item = (usize, &'text str)>,
+    F: FnMut(&[&'text str]) -> Result<Vec<E>, Err>,
+{
+    type Item = Result<(usize, &'text str, E), Err>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(chunk) = self.embedded.pop_front() {
+            return Some(Ok(chunk));
+        }
+        if self.finished {
+            return None;
+        }
+
+        let (offsets, batch): (Vec<_>, Vec<_>) = self.chunks.by_ref().take(self.batch_size).unzip();
+        if batch.is_empty() {
+            self.finished = true;
+            return None;
+        }
+        let embeddings = match (self.embedder)(&batch) {
+            Ok(embeddings) => embeddings,
+            Err(err) => {
+                self.finished = true;
+                return Some(Err(err));
+            }
+        };
+        assert_eq!(
+            batch.len(),
+            embeddings.len(),
+            "The embedder returned {} embeddings for {} chunks",
+            embeddings.len(),
+            batch.len(),
+        );
+        self.embedded.extend(
+            offsets
+                .into_iter()
+                .zip(batch)
+                .zip(embeddings)
+                .map(|((offset, chunk), embedding)| (offset, chunk, embedding)),
+        );
+        self.embedded.pop_front().map(Ok)
+    }
+}
+
+impl<'text, I, F, E, Err> FusedIterator for EmbeddedChunks<'text, I, F, E>
+where
+    I: Iterator<Item = (usize, &'text str)>,
+    F: FnMut(&[&'text str]) -> Result<Vec<E>, Err>,
+{
+}
+
+impl<I, F, E> fmt::Debug for EmbeddedChunks<'_, I, F, E>
+where
+    I: fmt::Debug,
+    E: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EmbeddedChunks")
+            .field("chunks", &self.chunks)
+            .field("batch_size", &self.batch_size)
+            .field("embedded", &self.embedded)
+            .field("finished", &self.finished)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TextSplitter;
+
+    use super::*;
+
+    #[test]
+    fn embeds_in_batches() {
+        let mut batches = vec![];
+        let chunks = TextSplitter::new(4)
+            .chunk_indices("a b c d e f g")
+            .with_embedder(|batch: &[&str]| {
+                batches.push(batch.len());
+                Ok::<_, ()>(batch.iter().map(|chunk| chunk.len()).collect())
+            })
+            .with_batch_size(2)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            chunks,
+            [(0, "a b", 3), (4, "c d", 3), (8, "e f", 3), (12, "g", 1)]
+        );
+        assert_eq!(batches, [2, 2]);
+    }
+
+    #[test]
+    fn stops_after_error() {
+        let mut calls = 0;
+        let results = TextSplitter::new(4)
+            .chunk_indices("a b c d e f g")
+            .with_embedder(|batch: &[&str]| {
+                calls += 1;
+                if calls == 2 {
+                    Err("model unavailable")
+                } else {
+                    Ok(vec![(); batch.len()])
+                }
+            })
+            .with_batch_size(3)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            results,
+            [
+                Ok((0, "a b", ())),
+                Ok((4, "c d", ())),
+                Ok((8, "e f", ())),
+                Err("model unavailable")
+            ]
+        );
+    }
+}
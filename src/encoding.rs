@@ -0,0 +1,201 @@
+/*!
+# [`DecodedText`]
+Decode text in encodings other than UTF-8 so it can be split, while keeping
+track of where each piece of the decoded text came from in the original bytes.
+*/
+
+use std::ops::Range;
+
+use chardetng::EncodingDetector;
+use encoding_rs::Encoding;
+
+use crate::OffsetMap;
+
+/// Text decoded from bytes in an arbitrary encoding, such as Windows-1252 or
+/// Shift-JIS.
+///
+/// The splitters only work with UTF-8 strings, so the byte offsets of the
+/// chunks they return are relative to the decoded [`DecodedText::text`]. Use
+/// [`DecodedText::source_offset`] or [`DecodedText::source_range`] to map them
+/// back to offsets in the original bytes, or [`DecodedText::offsets`] for the
+/// full [`OffsetMap`].
+///
+/// ```
+/// use text_splitter::{DecodedText, TextSplitter};
+///
+/// // "café crème" encoded as Windows-1252
+/// let bytes = b"caf\xE9 cr\xE8me";
+/// let decoded = DecodedText::decode_with(bytes, encoding_rs::WINDOWS_1252);
+///
+/// let splitter = TextSplitter::new(5);
+/// let chunks = splitter
+///     .chunk_indices(decoded.text())
+///     .map(|(offset, chunk)| (decoded.source_range(offset..offset + chunk.len()), chunk))
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(vec![(0..4, "café"), (5..10, "crème")], chunks);
+/// ```
+#[derive(Debug)]
+pub struct DecodedText {
+    /// Maps offsets in the decoded text to offsets in the source bytes
+    offsets: OffsetMap,
+    /// The encoding the text was decoded from
+    encoding: &'static Encoding,
+    /// The decoded text
+    text: String,
+}
+
+impl DecodedText {
+    /// Detect the encoding of the given bytes and decode them.
+    ///
+    /// A byte order mark always takes precedence. Otherwise the encoding is
+    /// guessed based on the contents, which works best for larger inputs.
+    #[must_use]
+    pub fn decode(bytes: &[u8]) -> Self {
+        let encoding = Encoding::for_bom(bytes).map_or_else(
+            || {
+                let mut detector = EncodingDetector::new();
+                detector.feed(bytes, true);
+                detector.guess(None, true)
+            },
+            |(encoding, _)| encoding,
+        );
+        Self::decode_with(bytes, encoding)
+    }
+
+    /// Decode the bytes with a known encoding. If the bytes start with a byte
+    /// order mark, it is removed and the encoding it indicates is used instead.
+    ///
+    /// Malformed sequences are replaced with the REPLACEMENT CHARACTER.
+    #[must_use]
+    pub fn decode_with(bytes: &[u8], encoding: &'static Encoding) -> Self {
+        let (encoding, bom_length) = Encoding::for_bom(bytes).unwrap_or((encoding, 0));
+        let mut decoder = encoding.new_decoder_without_bom_handling();
+        let mut text = String::with_capacity(bytes.len());
+        let mut offsets = OffsetMap::new();
+        offsets.push(0, bom_length);
+        // Source offset of the start of the next decoded character
+        let mut char_start = bom_length;
+
+        // Decode one byte at a time so we know which bytes produced each
+        // character. Characters of the same length in both encodings are
+        // merged into one unchanged piece of the map.
+        for end in bom_length + 1..=bytes.len() {
+            let decoded_start = text.len();
+            text.reserve(decoder.max_utf8_buffer_length(1).unwrap_or(4));
+            let _ = decoder.decode_to_string(&bytes[end - 1..end], &mut text, end == bytes.len());
+            if text.len() == decoded_start {
+                continue;
+            }
+
+            offsets.push(text.len() - decoded_start, end - char_start);
+            char_start = end;
+        }
+
+        Self {
+            offsets,
+            encoding,
+            text,
+        }
+    }
+
+    /// The encoding the text was decoded from.
+    #[must_use]
+    pub fn encoding(&self) -> &'static Encoding {
+        self.encoding
+    }
+
+    /// The decoded text, which can be passed to any of the splitters.
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The map between byte offsets in the decoded text and in the original
+    /// bytes, which can also map ranges of the original bytes to the decoded
+    /// text with [`OffsetMap::transformed_range`].
+    #[must_use]
+    pub fn offsets(&self) -> &OffsetMap {
+        &self.offsets
+    }
+
+    /// Map a byte offset in the decoded text to the byte offset in the
+    /// original bytes. Offsets should be on character boundaries, such as the
+    /// offsets returned by `chunk_indices`.
+    #[must_use]
+    pub fn source_offset(&self, offset: usize) -> usize {
+        self.offsets.original_range(offset..offset).start
+    }
+
+    /// Map a byte range in the decoded text to the byte range in the original
+    /// bytes.
+    #[must_use]
+    pub fn source_range(&self, range: Range<usize>) -> Range<usize> {
+        self.offsets.original_range(range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utf8_is_unchanged() {
+        let text = "Some text. From a document.";
+        let decoded = DecodedText::decode(text.as_bytes());
+
+        assert_eq!(decoded.encoding(), encoding_rs::UTF_8);
+        assert_eq!(decoded.text(), text);
+        let mut offsets = OffsetMap::new();
+        offsets.push(text.len(), text.len());
+        assert_eq!(decoded.offsets(), &offsets);
+        assert_eq!(decoded.source_offset(11), 11);
+    }
+
+    #[test]
+    fn detects_bom() {
+        let decoded = DecodedText::decode(&[0xFF, 0xFE, b'h', 0, b'i', 0]);
+
+        assert_eq!(decoded.encoding(), encoding_rs::UTF_16LE);
+        assert_eq!(decoded.text(), "hi");
+        assert_eq!(decoded.source_offset(0), 2);
+        assert_eq!(decoded.source_offset(1), 4);
+        assert_eq!(decoded.source_offset(2), 6);
+        assert_eq!(decoded.offsets().transformed_range(4..6), 1..2);
+    }
+
+    #[test]
+    fn single_byte_encoding() {
+        let decoded = DecodedText::decode_with(b"\xE9t\xE9 ok", encoding_rs::WINDOWS_1252);
+
+        assert_eq!(decoded.text(), "été ok");
+        // "é" is 2 bytes in UTF-8, but 1 byte in Windows-1252
+        assert_eq!(decoded.source_offset(2), 1);
+        assert_eq!(decoded.source_offset(3), 2);
+        assert_eq!(decoded.source_offset(6), 4);
+        assert_eq!(decoded.source_range(6..8), 4..6);
+    }
+
+    #[test]
+    fn multi_byte_encoding() {
+        // "日本 語" in Shift-JIS
+        let bytes = b"\x93\xFA\x96\x7B \x8C\xEA";
+        let decoded = DecodedText::decode_with(bytes, encoding_rs::SHIFT_JIS);
+
+        assert_eq!(decoded.text(), "日本 語");
+        assert_eq!(decoded.source_offset(3), 2);
+        assert_eq!(decoded.source_offset(6), 4);
+        assert_eq!(decoded.source_offset(7), 5);
+        assert_eq!(decoded.source_offset(10), 7);
+    }
+
+    #[test]
+    fn detects_legacy_encoding() {
+        let text = "Der Bär saß im Schnee und aß ein Brötchen. Später ging er über die Brücke.";
+        let (bytes, _, _) = encoding_rs::WINDOWS_1252.encode(text);
+        let decoded = DecodedText::decode(&bytes);
+
+        assert_eq!(decoded.text(), text);
+        assert_eq!(decoded.source_offset(text.len()), bytes.len());
+    }
+}
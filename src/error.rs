@@ -0,0 +1,131 @@
+/*!
+# [`Error`]
+A single error type that every error in this crate converts into, so callers
+using several fallible APIs can use `?` without juggling several error types.
+*/
+
+use std::io;
+
+use thiserror::Error;
+
+#[cfg(feature = "arrow")]
+use crate::arrow::ArrowChunkerError;
+#[cfg(feature = "service")]
+use crate::service::ServiceError;
+#[cfg(feature = "aho-corasick")]
+use crate::AhoCorasickSizerError;
+#[cfg(feature = "code")]
+use crate::CodeSplitterError;
+#[cfg(feature = "tokenizers")]
+use crate::ModelTokenizerError;
+use crate::{
+    BudgetExhausted, ChunkCapacityError, ChunkConfigError, ChunkTemplateError, DocumentError,
+    TranscriptSplitterError,
+};
+
+/// Any error that can be returned by this crate. Each of the more specific
+/// error types converts into it, so that `?` works across the different
+/// fallible APIs.
+///
+/// ```
+/// use text_splitter::{ChunkConfig, ChunkTemplate, Error, SplitterExt, TextSplitter};
+///
+/// fn chunks(text: &str) -> Result<Vec<String>, Error> {
+///     let config = ChunkConfig::new(100).with_overlap(10)?;
+///     let template = ChunkTemplate::new("{chunk}\n\n[{index}]")?;
+///     let chunks = TextSplitter::new(config).chunks_with_template(text, &template)?;
+///     Ok(chunks.into_iter().map(|chunk| chunk.text).collect())
+/// }
+/// ```
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The chunk capacity is invalid.
+    #[error(transparent)]
+    ChunkCapacity(#[from] ChunkCapacityError),
+    /// The chunk configuration is invalid.
+    #[error(transparent)]
+    ChunkConfig(#[from] ChunkConfigError),
+    /// A chunk template couldn't be parsed or rendered.
+    #[error(transparent)]
+    ChunkTemplate(#[from] ChunkTemplateError),
+    /// A document couldn't be loaded.
+    #[error(transparent)]
+    Document(#[from] DocumentError),
+    /// The pattern of a transcript splitter is invalid.
+    #[error(transparent)]
+    TranscriptSplitter(#[from] TranscriptSplitterError),
+    /// The chunking budget was exhausted. Only the offset chunking stopped at
+    /// is kept, since the chunks generated before that borrow from the text.
+    #[error("The chunking budget was exhausted at byte {offset}")]
+    BudgetExhausted {
+        /// Byte offset of the end of the last chunk generated in time
+        offset: usize,
+    },
+    /// Reading or writing failed.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// A code splitter couldn't be created.
+    #[cfg(feature = "code")]
+    #[error(transparent)]
+    CodeSplitter(#[from] CodeSplitterError),
+    /// A model tokenizer couldn't be loaded, or the chunk capacity is too
+    /// large for it.
+    #[cfg(feature = "tokenizers")]
+    #[error(transparent)]
+    ModelTokenizer(#[from] ModelTokenizerError),
+    /// A dictionary sizer couldn't be built.
+    #[cfg(feature = "aho-corasick")]
+    #[error(transparent)]
+    AhoCorasickSizer(#[from] AhoCorasickSizerError),
+    /// An Arrow array couldn't be chunked.
+    #[cfg(feature = "arrow")]
+    #[error(transparent)]
+    ArrowChunker(#[from] ArrowChunkerError),
+    /// The chunking service failed.
+    #[cfg(feature = "service")]
+    #[error(transparent)]
+    Service(#[from] ServiceError),
+}
+
+impl From<BudgetExhausted<'_>> for Error {
+    fn from(error: BudgetExhausted<'_>) -> Self {
+        Self::BudgetExhausted {
+            offset: error.offset(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::AtomicBool, Arc};
+
+    use crate::{ChunkConfig, ChunkingBudget, SplitterExt, TextSplitter};
+
+    use super::*;
+
+    #[test]
+    fn converts_with_question_mark() {
+        fn config() -> Result<ChunkConfig<crate::Characters>, Error> {
+            Ok(ChunkConfig::new(10).with_overlap(10)?)
+        }
+
+        let err = config().unwrap_err();
+        assert!(matches!(err, Error::ChunkConfig(_)));
+        assert_eq!(
+            err.to_string(),
+            "The overlap is larger than or equal to the desired chunk capacity"
+        );
+    }
+
+    #[test]
+    fn keeps_offset_of_exhausted_budget() {
+        let budget = ChunkingBudget::new().with_cancellation(Arc::new(AtomicBool::new(true)));
+        let err: Error = TextSplitter::new(5)
+            .chunk_indices_within_budget("Some text. More text.", &budget)
+            .unwrap_err()
+            .into();
+
+        assert!(matches!(err, Error::BudgetExhausted { offset: 0 }));
+    }
+}
@@ -0,0 +1,132 @@
+/*!
+# Chunking fingerprint
+A fingerprint of everything that can change where chunks are split, to use as
+part of a cache key for chunks that were stored or indexed.
+
+Chunking doesn't depend on any random seeds, hash map iteration order, or
+the platform it runs on, so the same text and configuration produce the same
+chunks on every platform. What can change the chunks is upgrading this crate
+or the Unicode segmentation and regex crates it relies on, which is what the
+fingerprint captures.
+*/
+
+use std::sync::OnceLock;
+
+#[cfg(feature = "markdown")]
+use crate::MarkdownSplitter;
+use crate::{ChunkConfig, SentenceBoundaries, TextSplitter};
+
+/// Text to chunk when computing the fingerprint, covering the parts of
+/// chunking that depend on other crates: grapheme clusters, words in scripts
+/// without spaces, sentences, and line breaks.
+const PROBE: &str = "Mr. Smith bought cheapsite.com for 1.5 million dollars, i.e. he paid a lot for it. Did he mind? Adam Jones Jr. thinks he didn't.\r\n\r\n\
+    As shown in Fig. 3 and by Smith et al. [12, 13], it works.\n\
+    สวัสดีครับ ยินดีต้อนรับ 東京都は日本の首都です。中文没有空格！\n\n\
+    👨‍👩‍👧‍👦🇯🇵 e\u{301}te\u{301} ﷽ — \"Quoted.\" (Parenthetical!) 'Single' ...\n\
+    # A heading\n\n- A list item\n- Another `code` item\n\n```\nfn main() {}\n```\n";
+
+/// Capacities to chunk the probe text with, from splitting it into graphemes
+/// to keeping whole paragraphs together.
+const PROBE_CAPACITIES: [usize; 6] = [1, 3, 8, 20, 64, 256];
+
+/// Returns a fingerprint of the chunking algorithm and the versions of the
+/// segmentation and parsing rules it uses, such as `0.22.0-6f1c0b1e3a2d4c5b`.
+///
+/// The same text and [`ChunkConfig`] always produce the same chunks for the
+/// same fingerprint, regardless of platform. If the fingerprint changes
+/// after an upgrade, chunks may have moved, so it can be included in the key
+/// of anything cached or indexed by chunk.
+///
+/// The fingerprint depends on which features of the crate are enabled. It
+/// doesn't cover the chunk sizer, or the tree-sitter grammar used by a
+/// `CodeSplitter`, so include their versions in the key separately if they
+/// can change.
+///
+/// ```
+/// let fingerprint = text_splitter::chunking_fingerprint();
+///
+/// assert!(fingerprint.starts_with(env!("CARGO_PKG_VERSION")));
+/// ```
+#[must_use]
+pub fn chunking_fingerprint() -> &'static str {
+    static FINGERPRINT: OnceLock<String> = OnceLock::new();
+    FINGERPRINT.get_or_init(|| {
+        let mut hasher = Fnv1a::default();
+        for capacity in PROBE_CAPACITIES {
+            for sentence_boundaries in [SentenceBoundaries::Unicode, SentenceBoundaries::Scientific]
+            {
+                let splitter = TextSplitter::new(
+                    ChunkConfig::new(capacity)
+                        .with_trim(false)
+                        .with_sentence_boundaries(sentence_boundaries),
+                );
+                hasher.write_chunks(splitter.chunk_indices(PROBE));
+            }
+            #[cfg(feature = "markdown")]
+            hasher.write_chunks(
+                MarkdownSplitter::new(ChunkConfig::new(capacity).with_trim(false))
+                    .chunk_indices(PROBE),
+            );
+        }
+        format!("{}-{:016x}", env!("CARGO_PKG_VERSION"), hasher.0)
+    })
+}
+
+/// 64-bit FNV-1a hash. Unlike the hashers in the standard library, its output
+/// is specified, so it stays the same across platforms and Rust versions.
+pub(crate) struct Fnv1a(pub(crate) u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    /// Hash the boundaries of each chunk, with a fixed width so that the
+    /// hash is the same on 32-bit and 64-bit platforms.
+    fn write_chunks<'text>(&mut self, chunks: impl Iterator<Item = (usize, &'text str)>) {
+        for (offset, chunk) in chunks {
+            self.write(&(offset as u64).to_le_bytes());
+            self.write(&(chunk.len() as u64).to_le_bytes());
+        }
+        // Separate the chunks of each run
+        self.write(&[0xFF]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a_matches_reference_values() {
+        let hash = |bytes: &[u8]| {
+            let mut hasher = Fnv1a::default();
+            hasher.write(bytes);
+            hasher.0
+        };
+
+        assert_eq!(hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(hash(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn fingerprint_is_stable_within_process() {
+        let fingerprint = chunking_fingerprint();
+
+        assert_eq!(fingerprint, chunking_fingerprint());
+        assert_eq!(
+            fingerprint.len(),
+            env!("CARGO_PKG_VERSION").len() + "-".len() + 16
+        );
+    }
+}
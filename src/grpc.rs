@@ -0,0 +1,194 @@
+/*!
+# gRPC chunking service
+
+A [tonic](https://docs.rs/tonic) implementation of the `ChunkingService`
+defined in `proto/text_splitter/v1/chunking.proto`, so that services in other
+languages can generate a client from the same definition and get chunks with
+the same semantics as the Rust crate.
+
+`Chunk` accepts a `TextRequest` with the text and its configuration, and
+streams back each chunk, in order, with the same offsets and sizes as an
+[`output::Chunk`](crate::output::Chunk). Chunk sizes are measured in
+characters.
+
+```
+use text_splitter::grpc::{Chunker, ChunkingServiceServer};
+
+// Add this to a `tonic::transport::Server` with `add_service`
+let service = ChunkingServiceServer::new(Chunker);
+```
+*/
+
+// Handlers have to return tonic's `Status` as their error, however large it is
+#![allow(clippy::result_large_err)]
+
+use tonic::{codegen::tokio_stream, Request, Response, Status};
+
+use crate::{
+    output::{Chunk, ChunkingRun},
+    Characters, ChunkCapacity, ChunkConfig, DocumentFormat, DocumentSplitter,
+};
+
+/// Types generated from the proto definition.
+#[allow(clippy::all, clippy::pedantic)]
+pub mod proto {
+    include!("grpc/text_splitter.v1.rs");
+}
+
+pub use proto::chunking_service_server::{ChunkingService, ChunkingServiceServer};
+use proto::{ChunkResponse, Format, TextRequest};
+
+/// Implementation of the [`ChunkingService`], to serve with a
+/// [`ChunkingServiceServer`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Chunker;
+
+#[tonic::async_trait]
+impl ChunkingService for Chunker {
+    type ChunkStream = tokio_stream::Iter<std::vec::IntoIter<Result<ChunkResponse, Status>>>;
+
+    async fn chunk(
+        &self,
+        request: Request<TextRequest>,
+    ) -> Result<Response<Self::ChunkStream>, Status> {
+        let TextRequest { text, config } = request.into_inner();
+        let config = config.ok_or_else(|| Status::invalid_argument("config is required"))?;
+
+        let format = match Format::try_from(config.format) {
+            Ok(Format::Unspecified | Format::Text) => DocumentFormat::Text,
+            #[cfg(feature = "markdown")]
+            Ok(Format::Markdown) => DocumentFormat::Markdown,
+            #[cfg(not(feature = "markdown"))]
+            Ok(Format::Markdown) => {
+                return Err(Status::unimplemented(
+                    "Markdown requires the `markdown` feature of text-splitter",
+                ))
+            }
+            Err(_) => {
+                return Err(Status::invalid_argument(format!(
+                    "Unknown format {}",
+                    config.format
+                )))
+            }
+        };
+        let splitter = DocumentSplitter::new(&format, chunk_config(config)?)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        let run = ChunkingRun::new(
+            &text,
+            splitter.chunk_indices(&text),
+            splitter.chunk_config(),
+        );
+        let chunks = run
+            .chunks
+            .into_iter()
+            .map(|chunk| Ok(chunk.into()))
+            .collect::<Vec<_>>();
+        Ok(Response::new(tokio_stream::iter(chunks)))
+    }
+}
+
+/// Build the [`ChunkConfig`] for a request.
+fn chunk_config(config: proto::ChunkConfig) -> Result<ChunkConfig<Characters>, Status> {
+    let to_usize = |value: u64| {
+        usize::try_from(value)
+            .map_err(|_| Status::invalid_argument(format!("{value} is too large")))
+    };
+    let capacity = to_usize(config.capacity)?;
+    let capacity = ChunkCapacity::new(capacity)
+        .with_max(config.max_capacity.map_or(Ok(capacity), to_usize)?)
+        .map_err(|e| Status::invalid_argument(e.to_string()))?;
+    Ok(ChunkConfig::new(capacity)
+        .with_overlap(to_usize(config.overlap)?)
+        .map_err(|e| Status::invalid_argument(e.to_string()))?
+        .with_trim(config.trim.unwrap_or(true)))
+}
+
+impl From<Chunk> for ChunkResponse {
+    fn from(chunk: Chunk) -> Self {
+        Self {
+            index: chunk.index as u64,
+            text: chunk.text,
+            start: chunk.metadata.start as u64,
+            end: chunk.metadata.end as u64,
+            char_start: chunk.metadata.char_start as u64,
+            char_end: chunk.metadata.char_end as u64,
+            size: chunk.metadata.size as u64,
+            previous: chunk.metadata.previous.map(|index| index as u64),
+            next: chunk.metadata.next.map(|index| index as u64),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_stream::StreamExt;
+
+    use super::*;
+
+    async fn chunk(request: TextRequest) -> Result<Vec<ChunkResponse>, Status> {
+        let stream = Chunker.chunk(Request::new(request)).await?.into_inner();
+        stream.collect().await
+    }
+
+    #[tokio::test]
+    async fn streams_chunks() {
+        let chunks = chunk(TextRequest {
+            text: "Some text\n\nfrom a\ndocument".to_owned(),
+            config: Some(proto::ChunkConfig {
+                capacity: 10,
+                ..Default::default()
+            }),
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            chunks.iter().map(|c| c.text.as_str()).collect::<Vec<_>>(),
+            vec!["Some text", "from a", "document"]
+        );
+        assert_eq!(
+            (chunks[1].index, chunks[1].start, chunks[1].end),
+            (1, 11, 17)
+        );
+    }
+
+    #[cfg(feature = "markdown")]
+    #[tokio::test]
+    async fn streams_markdown_chunks() {
+        let chunks = chunk(TextRequest {
+            text: "# Header\n\nfrom a document".to_owned(),
+            config: Some(proto::ChunkConfig {
+                capacity: 20,
+                format: Format::Markdown.into(),
+                ..Default::default()
+            }),
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            chunks.iter().map(|c| c.text.as_str()).collect::<Vec<_>>(),
+            vec!["# Header", "from a document"]
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_config_is_invalid_argument() {
+        let status = chunk(TextRequest {
+            text: "Some text".to_owned(),
+            config: Some(proto::ChunkConfig {
+                capacity: 10,
+                overlap: 10,
+                ..Default::default()
+            }),
+        })
+        .await
+        .unwrap_err();
+
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        let status = chunk(TextRequest::default()).await.unwrap_err();
+        assert_eq!(status.message(), "config is required");
+    }
+}
@@ -0,0 +1,284 @@
+// This file is @generated by prost-build.
+/// A request to chunk a text.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TextRequest {
+    /// The text to chunk
+    #[prost(string, tag = "1")]
+    pub text: ::prost::alloc::string::String,
+    /// How to chunk the text
+    #[prost(message, optional, tag = "2")]
+    pub config: ::core::option::Option<ChunkConfig>,
+}
+/// How to chunk a text, mirroring the options of a `ChunkConfig`. Chunk sizes
+/// are measured in characters.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ChunkConfig {
+    /// Desired chunk capacity
+    #[prost(uint64, tag = "1")]
+    pub capacity: u64,
+    /// Max chunk capacity. Defaults to the desired capacity.
+    #[prost(uint64, optional, tag = "2")]
+    pub max_capacity: ::core::option::Option<u64>,
+    /// Overlap between chunks. Defaults to 0.
+    #[prost(uint64, tag = "3")]
+    pub overlap: u64,
+    /// Whether to trim whitespace from each chunk. Defaults to true.
+    #[prost(bool, optional, tag = "4")]
+    pub trim: ::core::option::Option<bool>,
+    /// Format of the text, which determines the splitter
+    #[prost(enumeration = "Format", tag = "5")]
+    pub format: i32,
+}
+/// A single chunk of the text.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChunkResponse {
+    /// Index of the chunk among all chunks of the text
+    #[prost(uint64, tag = "1")]
+    pub index: u64,
+    /// Text of the chunk
+    #[prost(string, tag = "2")]
+    pub text: ::prost::alloc::string::String,
+    /// Byte offset of the start of the chunk within the text
+    #[prost(uint64, tag = "3")]
+    pub start: u64,
+    /// Byte offset of the end of the chunk within the text
+    #[prost(uint64, tag = "4")]
+    pub end: u64,
+    /// Character offset of the start of the chunk within the text
+    #[prost(uint64, tag = "5")]
+    pub char_start: u64,
+    /// Character offset of the end of the chunk within the text
+    #[prost(uint64, tag = "6")]
+    pub char_end: u64,
+    /// Size of the chunk, in characters
+    #[prost(uint64, tag = "7")]
+    pub size: u64,
+    /// Index of the previous chunk of the text, if any
+    #[prost(uint64, optional, tag = "8")]
+    pub previous: ::core::option::Option<u64>,
+    /// Index of the next chunk of the text, if any
+    #[prost(uint64, optional, tag = "9")]
+    pub next: ::core::option::Option<u64>,
+}
+/// Format of the text of a request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum Format {
+    /// Plain text
+    Unspecified = 0,
+    /// Plain text, split with a `TextSplitter`
+    Text = 1,
+    /// Markdown, split with a `MarkdownSplitter`
+    Markdown = 2,
+}
+impl Format {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "FORMAT_UNSPECIFIED",
+            Self::Text => "FORMAT_TEXT",
+            Self::Markdown => "FORMAT_MARKDOWN",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "FORMAT_UNSPECIFIED" => Some(Self::Unspecified),
+            "FORMAT_TEXT" => Some(Self::Text),
+            "FORMAT_MARKDOWN" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+}
+/// Generated server implementations.
+pub mod chunking_service_server {
+    #![allow(
+        unused_variables,
+        dead_code,
+        missing_docs,
+        clippy::wildcard_imports,
+        clippy::let_unit_value,
+    )]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with ChunkingServiceServer.
+    #[async_trait]
+    pub trait ChunkingService: std::marker::Send + std::marker::Sync + 'static {
+        /// Server streaming response type for the Chunk method.
+        type ChunkStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::ChunkResponse, tonic::Status>,
+            >
+            + std::marker::Send
+            + 'static;
+        /// Split the text of the request into chunks, which are streamed back in
+        /// order.
+        async fn chunk(
+            &self,
+            request: tonic::Request<super::TextRequest>,
+        ) -> std::result::Result<tonic::Response<Self::ChunkStream>, tonic::Status>;
+    }
+    /// Splits text into chunks.
+    #[derive(Debug)]
+    pub struct ChunkingServiceServer<T> {
+        inner: Arc<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    impl<T> ChunkingServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for ChunkingServiceServer<T>
+    where
+        T: ChunkingService,
+        B: Body + std::marker::Send + 'static,
+        B::Error: Into<StdError> + std::marker::Send + 'static,
+    {
+        type Response = http::Response<tonic::body::Body>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            match req.uri().path() {
+                "/text_splitter.v1.ChunkingService/Chunk" => {
+                    #[allow(non_camel_case_types)]
+                    struct ChunkSvc<T: ChunkingService>(pub Arc<T>);
+                    impl<
+                        T: ChunkingService,
+                    > tonic::server::ServerStreamingService<super::TextRequest>
+                    for ChunkSvc<T> {
+                        type Response = super::ChunkResponse;
+                        type ResponseStream = T::ChunkStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::TextRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ChunkingService>::chunk(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ChunkSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        let mut response = http::Response::new(
+                            tonic::body::Body::default(),
+                        );
+                        let headers = response.headers_mut();
+                        headers
+                            .insert(
+                                tonic::Status::GRPC_STATUS,
+                                (tonic::Code::Unimplemented as i32).into(),
+                            );
+                        headers
+                            .insert(
+                                http::header::CONTENT_TYPE,
+                                tonic::metadata::GRPC_CONTENT_TYPE,
+                            );
+                        Ok(response)
+                    })
+                }
+            }
+        }
+    }
+    impl<T> Clone for ChunkingServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    /// Generated gRPC service name
+    pub const SERVICE_NAME: &str = "text_splitter.v1.ChunkingService";
+    impl<T> tonic::server::NamedService for ChunkingServiceServer<T> {
+        const NAME: &'static str = SERVICE_NAME;
+    }
+}
@@ -1,15 +1,75 @@
 #![doc = include_str!("../README.md")]
 #![cfg_attr(docsrs, feature(doc_auto_cfg, doc_cfg))]
 
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "baseline")]
+pub mod baseline;
+mod budget;
 mod chunk_size;
+mod dedup;
+mod document;
+mod embed;
+#[cfg(feature = "encoding")]
+mod encoding;
+mod error;
+mod fingerprint;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod metrics;
+mod offset_map;
+pub mod output;
+#[cfg(feature = "pipeline")]
+pub mod pipeline;
+mod record;
+mod redaction;
+#[cfg(feature = "report")]
+pub mod report;
+mod router;
+#[cfg(feature = "service")]
+pub mod service;
 mod splitter;
+mod template;
+#[cfg(feature = "test-vectors")]
+pub mod test_vectors;
 mod trim;
 
+pub use budget::{BudgetExhausted, ChunkingBudget};
+#[cfg(feature = "aho-corasick")]
+pub use chunk_size::{AhoCorasickSizer, AhoCorasickSizerError};
 pub use chunk_size::{
-    Characters, ChunkCapacity, ChunkCapacityError, ChunkConfig, ChunkConfigError, ChunkSizer,
+    ApproxTokens, Characters, ChunkCapacity, ChunkCapacityError, ChunkConfig, ChunkConfigError,
+    ChunkSizer, ChunkSizerBatch, ChunkingVersion, DynChunkConfig, DynChunkSizer, PooledSizer,
+    SentenceBoundaries, SyntaxClass, SyntaxSizer, SyntaxSpan, SyntaxWeighted, TieBreaking,
+    WeightedCharacters,
+};
+#[cfg(feature = "tokenizers")]
+pub use chunk_size::{ModelTokenizer, ModelTokenizerError};
+pub use dedup::{ChunkDeduplicator, Duplicate};
+pub use document::{Document, DocumentError, DocumentFormat, DocumentLoader, DocumentSplitter};
+pub use embed::{EmbeddedChunks, WithEmbedder};
+#[cfg(feature = "encoding")]
+pub use encoding::DecodedText;
+pub use error::Error;
+pub use fingerprint::chunking_fingerprint;
+pub use offset_map::OffsetMap;
+pub use record::{FieldPriority, RecordChunk, RecordSplitter};
+pub use redaction::Redactions;
+pub use router::SplitterRouter;
+#[cfg(feature = "typst")]
+pub use splitter::TypstSplitter;
+pub use splitter::{
+    BibtexEntry, BibtexSplitter, ChildChunk, ChunkDiagnostic, ChunkNode, ChunkProgress,
+    ChunkingCounters, ClauseSplitter, ConfigFormat, ConfigSplitter, CustomSplitter, DiffFile,
+    DiffHunk, DiffSplitter, FixedWidthSplitter, HierarchicalChunks, LineSplitter, MappedChunk,
+    RecordLength, SchemaLanguage, SemanticRange, SizeCheckpoint, SplitterExt, SplitterSession,
+    SqlSplitter, TextSplitter, TranscriptChunkMetadata, TranscriptSplitter,
+    TranscriptSplitterError, VObjectRecord, VObjectSplitter, WikitextSplitter,
 };
-#[cfg(feature = "markdown")]
-pub use splitter::MarkdownSplitter;
-pub use splitter::TextSplitter;
 #[cfg(feature = "code")]
-pub use splitter::{CodeSplitter, CodeSplitterError};
+pub use splitter::{CodeChunkMetadata, CodeSplitter, CodeSplitterError, CodeSplitterErrorKind};
+#[cfg(feature = "markdown")]
+pub use splitter::{
+    HeadingLevel, MarkdownBlock, MarkdownChunkMetadata, MarkdownElement, MarkdownSplitter,
+};
+pub use template::{ChunkTemplate, ChunkTemplateError};
@@ -0,0 +1,433 @@
+/*!
+# Chunk metrics
+
+Scores for the chunks of a text, to quantitatively compare chunking
+configurations. Measures how often chunks end at sentence and paragraph
+boundaries, how much the chunk sizes vary, and how much of the chunk
+capacity is used.
+
+[`suggest_capacity`] recommends a chunk capacity for a corpus, based on the
+sizes of the sentences, paragraphs and sections of a sample of it.
+
+```
+use text_splitter::{metrics::ChunkMetrics, ChunkConfig, TextSplitter};
+
+let text = "One sentence. Another sentence.\n\nA new paragraph.";
+let config = ChunkConfig::new(35);
+let chunks = TextSplitter::new(35).chunk_indices(text).collect::<Vec<_>>();
+
+let metrics = ChunkMetrics::new(text, chunks, &config);
+assert_eq!(2, metrics.chunks);
+assert_eq!(1.0, metrics.sentence_boundaries);
+assert_eq!(1.0, metrics.paragraph_boundaries);
+```
+*/
+
+use std::fmt;
+
+use itertools::Itertools;
+
+use crate::{splitter::SENTENCE_SEGMENTER, ChunkCapacity, ChunkConfig, ChunkSizer};
+
+/// Quality metrics for the chunks of a text.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ChunkMetrics {
+    /// Number of chunks
+    pub chunks: usize,
+    /// Size of the smallest chunk, as measured by the chunk sizer
+    pub min_size: usize,
+    /// Size of the largest chunk, as measured by the chunk sizer
+    pub max_size: usize,
+    /// Average size of the chunks
+    pub mean_size: f64,
+    /// Variance of the chunk sizes. Lower means more evenly sized chunks.
+    pub size_variance: f64,
+    /// Average size of the chunks divided by the max chunk capacity, from 0
+    /// to 1 unless chunks are oversized.
+    pub capacity_utilization: f64,
+    /// Number of chunks larger than the max chunk capacity
+    pub oversized: usize,
+    /// Fraction of the boundaries between chunks that are at the end of a
+    /// [Unicode sentence](https://www.unicode.org/reports/tr29/#Sentence_Boundaries).
+    /// 1 if there is only a single chunk.
+    pub sentence_boundaries: f64,
+    /// Fraction of the boundaries between chunks that are at a paragraph
+    /// break (a blank line) or right before a Markdown heading.
+    /// 1 if there is only a single chunk.
+    pub paragraph_boundaries: f64,
+}
+
+impl ChunkMetrics {
+    /// Calculate the metrics for the chunks of a text and their byte offsets,
+    /// such as the output of `chunk_indices`. The size of each chunk is
+    /// measured with the sizer of the `chunk_config`, and compared to its
+    /// capacity.
+    #[must_use]
+    pub fn new<'text, Sizer>(
+        text: &'text str,
+        chunks: impl IntoIterator<Item = (usize, &'text str)>,
+        chunk_config: &ChunkConfig<Sizer>,
+    ) -> Self
+    where
+        Sizer: ChunkSizer,
+    {
+        let chunks = chunks
+            .into_iter()
+            .sorted_by_key(|(offset, _)| *offset)
+            .collect::<Vec<_>>();
+        if chunks.is_empty() {
+            return Self::default();
+        }
+
+        let sizes = chunks
+            .iter()
+            .map(|(_, chunk)| chunk_config.sizer.size(chunk))
+            .collect::<Vec<_>>();
+        let max = chunk_config.capacity().max();
+        #[allow(clippy::cast_precision_loss)]
+        let (mean_size, size_variance) = {
+            let count = sizes.len() as f64;
+            let mean = sizes.iter().sum::<usize>() as f64 / count;
+            let variance = sizes
+                .iter()
+                .map(|&size| (size as f64 - mean).powi(2))
+                .sum::<f64>()
+                / count;
+            (mean, variance)
+        };
+
+        // Only the boundaries between chunks count, not the end of the text
+        let ends = chunks[..chunks.len() - 1]
+            .iter()
+            .map(|(offset, chunk)| offset + chunk.len())
+            .collect::<Vec<_>>();
+        let sentence_ends = SENTENCE_SEGMENTER.segment_str(text).collect::<Vec<_>>();
+
+        #[allow(clippy::cast_precision_loss)]
+        let ratio = |matching: usize| {
+            if ends.is_empty() {
+                1.0
+            } else {
+                matching as f64 / ends.len() as f64
+            }
+        };
+
+        #[allow(clippy::cast_precision_loss)]
+        Self {
+            chunks: chunks.len(),
+            min_size: sizes.iter().copied().min().unwrap_or_default(),
+            max_size: sizes.iter().copied().max().unwrap_or_default(),
+            mean_size,
+            size_variance,
+            capacity_utilization: if max == 0 {
+                0.0
+            } else {
+                mean_size / max as f64
+            },
+            oversized: sizes.iter().filter(|&&size| size > max).count(),
+            sentence_boundaries: ratio(
+                ends.iter()
+                    .filter(|&&end| is_sentence_boundary(text, &sentence_ends, end))
+                    .count(),
+            ),
+            paragraph_boundaries: ratio(
+                ends.iter()
+                    .filter(|&&end| is_paragraph_boundary(text, end))
+                    .count(),
+            ),
+        }
+    }
+}
+
+/// Distribution of the sizes of one kind of semantic unit, such as sentences,
+/// as measured by a chunk sizer.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct UnitSizes {
+    /// Number of units
+    pub count: usize,
+    /// Median size of the units
+    pub median: usize,
+    /// Size that 90% of the units are smaller than or equal to
+    pub p90: usize,
+    /// Size of the largest unit
+    pub max: usize,
+}
+
+impl UnitSizes {
+    fn new(mut sizes: Vec<usize>) -> Self {
+        if sizes.is_empty() {
+            return Self::default();
+        }
+        sizes.sort_unstable();
+        // Nearest rank, so that the percentile is always one of the sizes
+        let percentile = |p: usize| sizes[(sizes.len() * p).div_ceil(100).max(1) - 1];
+        Self {
+            count: sizes.len(),
+            median: percentile(50),
+            p90: percentile(90),
+            max: sizes[sizes.len() - 1],
+        }
+    }
+}
+
+/// A chunk capacity recommended by [`suggest_capacity`], along with the sizes
+/// of the semantic units of the sample it is based on. The `Display`
+/// implementation prints a short summary of the recommendation.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CapacitySuggestion {
+    /// Recommended desired chunk size
+    pub desired: usize,
+    /// Recommended max chunk size, at least `desired`
+    pub max: usize,
+    /// Sizes of the sentences of the sample texts
+    pub sentences: UnitSizes,
+    /// Sizes of the paragraphs of the sample texts, separated by blank lines
+    pub paragraphs: UnitSizes,
+    /// Sizes of the sections of the sample texts, starting at Markdown
+    /// headings
+    pub sections: UnitSizes,
+    /// Median size of the sample texts
+    pub median_text_size: usize,
+}
+
+impl CapacitySuggestion {
+    /// The recommended chunk capacity, from `desired` to `max`, to use with a
+    /// [`ChunkConfig`].
+    #[must_use]
+    pub fn capacity(&self) -> ChunkCapacity {
+        ChunkCapacity::from(self.desired..=self.max)
+    }
+}
+
+impl fmt::Display for CapacitySuggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Suggested capacity: {}..={}", self.desired, self.max)?;
+        writeln!(f, "Median text size: {}", self.median_text_size)?;
+        for (name, sizes) in [
+            ("Sentences", &self.sentences),
+            ("Paragraphs", &self.paragraphs),
+            ("Sections", &self.sections),
+        ] {
+            writeln!(
+                f,
+                "{name}: {} (median {}, p90 {}, max {})",
+                sizes.count, sizes.median, sizes.p90, sizes.max
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Recommend a chunk capacity for a corpus, from a sample of its texts, so
+/// that each text is split into about `target_chunks_per_doc` chunks.
+///
+/// The sizes of the sentences, paragraphs and sections of the texts are
+/// measured with the `sizer`, which should be the same one the corpus is
+/// chunked with. The desired size is the median size of the texts divided by
+/// the target number of chunks, but at least large enough to fit 90% of the
+/// sentences, so that chunks rarely have to split a sentence. The max size
+/// leaves up to 50% more room, so that most paragraphs can stay whole. Both
+/// are limited to the max size of the sizer, if it has one.
+///
+/// ```
+/// use text_splitter::{metrics::suggest_capacity, Characters, ChunkConfig, TextSplitter};
+///
+/// let samples = [
+///     "A first sentence. A second one.\n\nA new paragraph with a few more words in it.",
+///     "Another document.\n\nWith two short paragraphs.",
+/// ];
+/// let suggestion = suggest_capacity(&samples, &Characters, 2);
+/// println!("{suggestion}");
+///
+/// let splitter = TextSplitter::new(ChunkConfig::new(suggestion.capacity()));
+/// ```
+#[must_use]
+pub fn suggest_capacity<Sizer>(
+    sample_texts: impl IntoIterator<Item = impl AsRef<str>>,
+    sizer: &Sizer,
+    target_chunks_per_doc: usize,
+) -> CapacitySuggestion
+where
+    Sizer: ChunkSizer + ?Sized,
+{
+    let mut text_sizes = vec![];
+    let mut sentences = vec![];
+    let mut paragraphs = vec![];
+    let mut sections = vec![];
+    let measure = |units: &mut Vec<usize>, unit: &str| {
+        let unit = unit.trim();
+        if !unit.is_empty() {
+            units.push(sizer.size(unit));
+        }
+    };
+
+    for text in sample_texts {
+        let text = text.as_ref();
+        if text.trim().is_empty() {
+            continue;
+        }
+        text_sizes.push(sizer.size(text));
+        for (start, end) in SENTENCE_SEGMENTER.segment_str(text).tuple_windows() {
+            measure(&mut sentences, &text[start..end]);
+        }
+        for paragraph in split_before(text, |line, previous| {
+            previous.is_some_and(|previous| previous.trim().is_empty()) && !line.trim().is_empty()
+        }) {
+            measure(&mut paragraphs, paragraph);
+        }
+        for section in split_before(text, |line, _| line.starts_with('#')) {
+            measure(&mut sections, section);
+        }
+    }
+
+    let text_sizes = UnitSizes::new(text_sizes);
+    let sentences = UnitSizes::new(sentences);
+    let paragraphs = UnitSizes::new(paragraphs);
+    let sections = UnitSizes::new(sections);
+
+    let limit = sizer.max_size().unwrap_or(usize::MAX);
+    let desired = text_sizes
+        .median
+        .div_ceil(target_chunks_per_doc.max(1))
+        .max(sentences.p90)
+        .max(1)
+        .min(limit);
+    let max = paragraphs
+        .p90
+        .min(desired.saturating_add(desired / 2))
+        .max(desired)
+        .min(limit);
+
+    CapacitySuggestion {
+        desired,
+        max,
+        sentences,
+        paragraphs,
+        sections,
+        median_text_size: text_sizes.median,
+    }
+}
+
+/// Split a text into the parts that start at each line for which `starts` is
+/// true, given the line and the line before it.
+fn split_before(
+    text: &str,
+    starts: impl Fn(&str, Option<&str>) -> bool,
+) -> impl Iterator<Item = &str> {
+    let mut offset = 0;
+    let mut previous = None;
+    let mut boundaries = vec![0];
+    for line in text.split_inclusive('\n') {
+        if offset > 0 && starts(line, previous) {
+            boundaries.push(offset);
+        }
+        previous = Some(line);
+        offset += line.len();
+    }
+    boundaries.push(text.len());
+    boundaries
+        .into_iter()
+        .tuple_windows()
+        .map(move |(start, end)| &text[start..end])
+}
+
+/// Whether the only text between `end` and the next sentence boundary is
+/// whitespace, since chunks are usually trimmed.
+fn is_sentence_boundary(text: &str, sentence_ends: &[usize], end: usize) -> bool {
+    let index = sentence_ends.partition_point(|&boundary| boundary < end);
+    sentence_ends
+        .get(index)
+        .is_some_and(|&boundary| text[end..boundary].trim().is_empty())
+}
+
+/// Whether the text after `end` starts with a blank line or a Markdown heading,
+/// ignoring any other whitespace.
+fn is_paragraph_boundary(text: &str, end: usize) -> bool {
+    let rest = &text[end..];
+    let next = rest.trim_start();
+    let whitespace = &rest[..rest.len() - next.len()];
+    next.is_empty()
+        || whitespace.matches('\n').count() >= 2
+        || (whitespace.contains('\n') && next.starts_with('#'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty() {
+        let metrics = ChunkMetrics::new("", [], &ChunkConfig::new(10));
+
+        assert_eq!(ChunkMetrics::default(), metrics);
+    }
+
+    #[test]
+    fn sizes() {
+        let text = "aaaa bb";
+        let metrics = ChunkMetrics::new(text, [(0, "aaaa"), (5, "bb")], &ChunkConfig::new(4));
+
+        assert_eq!(metrics.chunks, 2);
+        assert_eq!(metrics.min_size, 2);
+        assert_eq!(metrics.max_size, 4);
+        assert!((metrics.mean_size - 3.0).abs() < f64::EPSILON);
+        assert!((metrics.size_variance - 1.0).abs() < f64::EPSILON);
+        assert!((metrics.capacity_utilization - 0.75).abs() < f64::EPSILON);
+        assert_eq!(metrics.oversized, 0);
+    }
+
+    #[test]
+    fn boundaries() {
+        let text = "One two. Three four.\n\n# Five\nSix seven";
+        let chunks = [
+            (0, "One two."),
+            (9, "Three"),
+            (15, "four."),
+            (22, "# Five\nSix"),
+            (33, "seven"),
+        ];
+        let metrics = ChunkMetrics::new(text, chunks, &ChunkConfig::new(10));
+
+        assert!((metrics.sentence_boundaries - 0.5).abs() < f64::EPSILON);
+        assert!((metrics.paragraph_boundaries - 0.25).abs() < f64::EPSILON);
+        assert_eq!(metrics.oversized, 0);
+    }
+
+    #[test]
+    fn suggests_capacity() {
+        let samples = [
+            "# Title\n\nOne sentence here. Another sentence.\n\nA second paragraph.\n\n# Next\n\nMore text.",
+            "Short text.",
+            "",
+        ];
+        let suggestion = suggest_capacity(samples, &crate::Characters, 2);
+
+        assert_eq!(
+            suggestion.sentences,
+            UnitSizes {
+                count: 7,
+                median: 11,
+                p90: 19,
+                max: 19
+            }
+        );
+        assert_eq!(suggestion.paragraphs.count, 6);
+        assert_eq!(suggestion.sections.count, 3);
+        assert_eq!(suggestion.median_text_size, 11);
+        // Large enough for the longest sentence, with room for most paragraphs
+        assert_eq!((suggestion.desired, suggestion.max), (19, 28));
+        assert_eq!(suggestion.capacity(), ChunkCapacity::from(19..=28));
+    }
+
+    #[test]
+    fn heading_boundaries() {
+        assert!(is_paragraph_boundary("a\n# b", 1));
+        assert!(is_paragraph_boundary("a\n\nb", 1));
+        assert!(is_paragraph_boundary("a  ", 1));
+        assert!(!is_paragraph_boundary("a\nb", 1));
+        assert!(!is_paragraph_boundary("a #b", 1));
+    }
+}
@@ -0,0 +1,213 @@
+/*!
+# [`OffsetMap`]
+Map byte offsets between a text that was transformed before chunking, such as
+by removing, masking, or joining parts of it, and the original text.
+*/
+
+use std::ops::Range;
+
+/// Maps byte ranges of a transformed text back to the ranges of the original
+/// text they came from, and the other way around.
+///
+/// The transformed text is made up of pieces, each of which came from a range
+/// of the original text. A piece that wasn't changed maps offsets one to one,
+/// and a piece that was replaced maps as a whole. All of the preprocessing
+/// methods of the splitters, like `chunks_redacting`, use this to return the
+/// original range of each chunk. It can also be used for custom preprocessing,
+/// such as converting HTML to Markdown, by building the map with
+/// [`OffsetMap::push`] while writing the transformed text.
+///
+/// ```
+/// use text_splitter::OffsetMap;
+///
+/// let text = "Some <b>bold</b> text";
+/// let (transformed, offsets) = OffsetMap::replace(text, [(5..8, "**"), (12..16, "**")]);
+///
+/// assert_eq!("Some **bold** text", transformed);
+/// assert_eq!(5..16, offsets.original_range(5..13));
+/// assert_eq!(7..11, offsets.transformed_range(8..12));
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OffsetMap {
+    /// Each piece of the transformed text, as its range in the transformed
+    /// text and the range of the original text it came from, in order.
+    pieces: Vec<(Range<usize>, Range<usize>)>,
+}
+
+impl OffsetMap {
+    /// Create an empty map, to add pieces to with [`OffsetMap::push`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace each of the sorted, non-overlapping byte ranges of the text
+    /// with its replacement, and return the new text along with the map of
+    /// its offsets. The ranges must be on character boundaries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ranges aren't sorted, overlap, or aren't on character
+    /// boundaries of the text.
+    #[must_use]
+    pub fn replace<'replacement>(
+        text: &str,
+        replacements: impl IntoIterator<Item = (Range<usize>, &'replacement str)>,
+    ) -> (String, Self) {
+        let mut replaced = String::with_capacity(text.len());
+        let mut offsets = Self::new();
+        let mut cursor = 0;
+        for (range, replacement) in replacements {
+            let unchanged = &text[cursor..range.start];
+            replaced.push_str(unchanged);
+            offsets.push(unchanged.len(), unchanged.len());
+            replaced.push_str(replacement);
+            offsets.push(replacement.len(), range.len());
+            cursor = range.end;
+        }
+        replaced.push_str(&text[cursor..]);
+        offsets.push(text.len() - cursor, text.len() - cursor);
+        (replaced, offsets)
+    }
+
+    /// Add the next piece of the transformed text, which is `transformed`
+    /// bytes long and came from the next `original` bytes of the original
+    /// text. If both lengths are the same, the piece is treated as unchanged,
+    /// and offsets within it map one to one.
+    ///
+    /// ```
+    /// use text_splitter::OffsetMap;
+    ///
+    /// // "a &amp; b" was decoded to "a & b"
+    /// let mut offsets = OffsetMap::new();
+    /// offsets.push(2, 2);
+    /// offsets.push(1, 5);
+    /// offsets.push(2, 2);
+    ///
+    /// assert_eq!(2..7, offsets.original_range(2..3));
+    /// assert_eq!(3..5, offsets.transformed_range(7..9));
+    /// ```
+    pub fn push(&mut self, transformed: usize, original: usize) {
+        let (transformed_end, original_end) =
+            self.pieces.last().map_or((0, 0), |(t, o)| (t.end, o.end));
+        match self.pieces.last_mut() {
+            _ if transformed == 0 && original == 0 => {}
+            // Merge unchanged pieces, so lookups stay fast
+            Some((t, o)) if t.len() == o.len() && transformed == original => {
+                t.end += transformed;
+                o.end += original;
+            }
+            _ => self.pieces.push((
+                transformed_end..transformed_end + transformed,
+                original_end..original_end + original,
+            )),
+        }
+    }
+
+    /// Whether no pieces were added to the map.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pieces.is_empty()
+    }
+
+    /// The byte range of the original text that the given range of the
+    /// transformed text came from. Includes the whole of any replaced piece
+    /// the range overlaps.
+    #[must_use]
+    pub fn original_range(&self, range: Range<usize>) -> Range<usize> {
+        map_range(&self.pieces, range, |(t, o)| (t, o))
+    }
+
+    /// The byte range of the transformed text that the given range of the
+    /// original text ended up in. Includes the whole of any replaced piece the
+    /// range overlaps.
+    #[must_use]
+    pub fn transformed_range(&self, range: Range<usize>) -> Range<usize> {
+        map_range(&self.pieces, range, |(t, o)| (o, t))
+    }
+}
+
+/// Map a range from one side of the pieces to the other. `sides` returns the
+/// range of a piece on the side being mapped from, then the side mapped to.
+fn map_range(
+    pieces: &[(Range<usize>, Range<usize>)],
+    range: Range<usize>,
+    sides: impl Fn(&(Range<usize>, Range<usize>)) -> (&Range<usize>, &Range<usize>),
+) -> Range<usize> {
+    if pieces.is_empty() {
+        return range;
+    }
+    let last = pieces.len() - 1;
+
+    // A start at the end of one piece is the start of the next one
+    let (from, to) = sides(
+        &pieces[pieces
+            .partition_point(|piece| sides(piece).0.end <= range.start)
+            .min(last)],
+    );
+    let start = if range.start >= from.end {
+        to.end
+    } else if from.len() == to.len() {
+        to.start + range.start.saturating_sub(from.start)
+    } else {
+        to.start
+    };
+    // An end at the start of one piece is the end of the previous one
+    let (from, to) = sides(
+        &pieces[pieces
+            .partition_point(|piece| sides(piece).0.end < range.end)
+            .min(last)],
+    );
+    let end = if range.end >= from.end || (from.len() != to.len() && range.end > from.start) {
+        to.end
+    } else {
+        to.start + range.end.saturating_sub(from.start)
+    };
+    start..end.max(start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remove_and_map_ranges() {
+        let text = "keep drop keep é drop";
+        let (kept, offsets) = OffsetMap::replace(text, [(5..10, ""), (17..22, "")]);
+
+        assert_eq!(kept, "keep keep é");
+        assert_eq!(offsets.original_range(0..4), 0..4);
+        assert_eq!(offsets.original_range(5..9), 10..14);
+        assert_eq!(offsets.original_range(2..7), 2..12);
+        assert_eq!(offsets.original_range(0..5), 0..5);
+        assert_eq!(offsets.transformed_range(10..14), 5..9);
+        assert_eq!(offsets.transformed_range(6..12), 5..7);
+    }
+
+    #[test]
+    fn replace_and_map_ranges() {
+        let text = "mail a@b.c to d@e.f now";
+        let (replaced, offsets) =
+            OffsetMap::replace(text, [(5..10, "[EMAIL]"), (14..19, "[EMAIL]")]);
+
+        assert_eq!(replaced, "mail [EMAIL] to [EMAIL] now");
+        assert_eq!(offsets.original_range(0..4), 0..4);
+        assert_eq!(offsets.original_range(5..12), 5..10);
+        assert_eq!(offsets.original_range(6..8), 5..10);
+        assert_eq!(offsets.original_range(13..15), 11..13);
+        assert_eq!(offsets.original_range(16..23), 14..19);
+        assert_eq!(offsets.original_range(0..27), 0..23);
+        assert_eq!(offsets.transformed_range(6..7), 5..12);
+        assert_eq!(offsets.transformed_range(11..13), 13..15);
+        assert_eq!(offsets.transformed_range(0..23), 0..27);
+    }
+
+    #[test]
+    fn empty_map_is_identity() {
+        let offsets = OffsetMap::new();
+
+        assert!(offsets.is_empty());
+        assert_eq!(offsets.original_range(2..5), 2..5);
+        assert_eq!(offsets.transformed_range(2..5), 2..5);
+    }
+}
@@ -0,0 +1,580 @@
+/*!
+# Chunk output
+
+Owned representations of the chunks of a text, with a stable shape for
+serialization, so that chunks written by a command-line tool, or returned by
+the language bindings, all look the same. With the `serde` feature enabled,
+a [`ChunkingRun`] serializes to JSON matching [`JSON_SCHEMA`].
+
+```
+use text_splitter::{output::ChunkingRun, ChunkConfig, TextSplitter};
+
+let text = "Some text\n\nfrom a\ndocument";
+let config = ChunkConfig::new(10);
+let chunks = TextSplitter::new(10).chunk_indices(text).collect::<Vec<_>>();
+
+let run = ChunkingRun::new(text, chunks, &config);
+assert_eq!(3, run.chunks.len());
+assert_eq!("from a", run.chunks[1].text);
+assert_eq!(11..17, run.chunks[1].metadata.start..run.chunks[1].metadata.end);
+```
+*/
+
+use std::ops::Range;
+
+use itertools::Itertools;
+
+use crate::{splitter::SENTENCE_SEGMENTER, ChunkConfig, ChunkSizer};
+
+/// Version of the shape of [`ChunkingRun`] and the types it contains. Only
+/// increased for changes that aren't backwards compatible, such as removing or
+/// renaming a field.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// [JSON Schema](https://json-schema.org/) of a serialized [`ChunkingRun`].
+pub const JSON_SCHEMA: &str = r##"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "ChunkingRun",
+  "type": "object",
+  "required": ["schema_version", "desired_capacity", "max_capacity", "overlap", "chunks"],
+  "properties": {
+    "schema_version": { "type": "integer", "const": 1 },
+    "desired_capacity": { "type": "integer", "minimum": 0 },
+    "max_capacity": { "type": "integer", "minimum": 0 },
+    "overlap": { "type": "integer", "minimum": 0 },
+    "chunks": { "type": "array", "items": { "$ref": "#/$defs/Chunk" } }
+  },
+  "$defs": {
+    "Chunk": {
+      "type": "object",
+      "required": ["index", "text", "metadata"],
+      "properties": {
+        "index": { "type": "integer", "minimum": 0 },
+        "text": { "type": "string" },
+        "metadata": { "$ref": "#/$defs/ChunkMetadata" }
+      }
+    },
+    "ChunkMetadata": {
+      "type": "object",
+      "required": ["start", "end", "char_start", "char_end", "size"],
+      "properties": {
+        "start": { "type": "integer", "minimum": 0 },
+        "end": { "type": "integer", "minimum": 0 },
+        "char_start": { "type": "integer", "minimum": 0 },
+        "char_end": { "type": "integer", "minimum": 0 },
+        "size": { "type": "integer", "minimum": 0 },
+        "previous": { "type": ["integer", "null"], "minimum": 0 },
+        "next": { "type": ["integer", "null"], "minimum": 0 },
+        "language": { "type": ["string", "null"] },
+        "title": { "type": ["string", "null"] },
+        "untrimmed": {
+          "type": ["object", "null"],
+          "required": ["start", "end"],
+          "properties": {
+            "start": { "type": "integer", "minimum": 0 },
+            "end": { "type": "integer", "minimum": 0 }
+          }
+        }
+      }
+    }
+  }
+}"##;
+
+/// Where a chunk came from within the text, and its size.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChunkMetadata {
+    /// Byte offset of the start of the chunk within the text
+    pub start: usize,
+    /// Byte offset of the end of the chunk within the text
+    pub end: usize,
+    /// Character offset of the start of the chunk within the text, for
+    /// languages that index strings by character
+    pub char_start: usize,
+    /// Character offset of the end of the chunk within the text
+    pub char_end: usize,
+    /// Size of the chunk, as measured by the chunk sizer
+    pub size: usize,
+    /// Index of the previous chunk of the text, if any, such as for
+    /// expanding the context around a chunk
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub previous: Option<usize>,
+    /// Index of the next chunk of the text, if any
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub next: Option<usize>,
+    /// [ISO 639-3](https://en.wikipedia.org/wiki/ISO_639-3) code of the
+    /// language of the chunk, such as `eng`, if it was detected with
+    /// `ChunkingRun::with_languages`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub language: Option<String>,
+    /// Short title of the chunk, if one was generated with
+    /// `ChunkingRun::with_titles`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub title: Option<String>,
+    /// Byte range of the chunk within the text before surrounding whitespace
+    /// was trimmed, if known, such as for highlighting the exact span of the
+    /// chunk in the original document. Set by
+    /// `ChunkingRun::from_untrimmed_ranges`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub untrimmed: Option<Range<usize>>,
+}
+
+/// A single chunk of a text.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Chunk {
+    /// Index of the chunk among all chunks of the text
+    pub index: usize,
+    /// Text of the chunk
+    pub text: String,
+    /// Where the chunk came from, and its size
+    pub metadata: ChunkMetadata,
+}
+
+impl AsRef<str> for Chunk {
+    fn as_ref(&self) -> &str {
+        &self.text
+    }
+}
+
+/// All chunks of a text, along with the configuration they were generated
+/// with.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChunkingRun {
+    /// Version of the shape of this output, see [`SCHEMA_VERSION`]
+    pub schema_version: u32,
+    /// Desired chunk capacity of the configuration
+    pub desired_capacity: usize,
+    /// Max chunk capacity of the configuration
+    pub max_capacity: usize,
+    /// Overlap between chunks of the configuration
+    pub overlap: usize,
+    /// Chunks of the text, in order
+    pub chunks: Vec<Chunk>,
+}
+
+impl ChunkingRun {
+    /// Collect the chunks of a text and their byte offsets, such as the output
+    /// of `chunk_indices`. The size of each chunk is measured with the sizer
+    /// of the `chunk_config`.
+    #[must_use]
+    pub fn new<'text, Sizer>(
+        text: &'text str,
+        chunks: impl IntoIterator<Item = (usize, &'text str)>,
+        chunk_config: &ChunkConfig<Sizer>,
+    ) -> Self
+    where
+        Sizer: ChunkSizer,
+    {
+        Self::collect(
+            text,
+            chunks
+                .into_iter()
+                .map(|(offset, chunk)| (offset, chunk, None)),
+            chunk_config,
+        )
+    }
+
+    /// Collect the chunks of a text, their byte offsets, and their byte ranges
+    /// before trimming, such as the output of `chunks_with_untrimmed_ranges`.
+    /// The size of each chunk is measured with the sizer of the
+    /// `chunk_config`.
+    ///
+    /// ```
+    /// use text_splitter::{output::ChunkingRun, SplitterExt, TextSplitter};
+    ///
+    /// let text = "Some text\n\nfrom a\ndocument";
+    /// let splitter = TextSplitter::new(10);
+    /// let run = ChunkingRun::from_untrimmed_ranges(
+    ///     text,
+    ///     splitter.chunks_with_untrimmed_ranges(text),
+    ///     splitter.chunk_config(),
+    /// );
+    ///
+    /// assert_eq!("from a", run.chunks[1].text);
+    /// assert_eq!(11..17, run.chunks[1].metadata.start..run.chunks[1].metadata.end);
+    /// assert_eq!(Some(11..18), run.chunks[1].metadata.untrimmed);
+    /// ```
+    #[must_use]
+    pub fn from_untrimmed_ranges<'text, Sizer>(
+        text: &'text str,
+        chunks: impl IntoIterator<Item = (usize, &'text str, Range<usize>)>,
+        chunk_config: &ChunkConfig<Sizer>,
+    ) -> Self
+    where
+        Sizer: ChunkSizer,
+    {
+        Self::collect(
+            text,
+            chunks
+                .into_iter()
+                .map(|(offset, chunk, untrimmed)| (offset, chunk, Some(untrimmed))),
+            chunk_config,
+        )
+    }
+
+    fn collect<'text, Sizer>(
+        text: &'text str,
+        chunks: impl IntoIterator<Item = (usize, &'text str, Option<Range<usize>>)>,
+        chunk_config: &ChunkConfig<Sizer>,
+    ) -> Self
+    where
+        Sizer: ChunkSizer,
+    {
+        // Chunks are usually in order, so character offsets are counted from
+        // the previous chunk.
+        let mut byte_cursor = 0;
+        let mut char_cursor = 0;
+        let mut char_offset = |offset: usize| {
+            if offset < byte_cursor {
+                byte_cursor = 0;
+                char_cursor = 0;
+            }
+            char_cursor += text[byte_cursor..offset].chars().count();
+            byte_cursor = offset;
+            char_cursor
+        };
+
+        let mut chunks = chunks
+            .into_iter()
+            .enumerate()
+            .map(|(index, (offset, chunk, untrimmed))| {
+                let end = offset + chunk.len();
+                Chunk {
+                    index,
+                    text: chunk.to_string(),
+                    metadata: ChunkMetadata {
+                        start: offset,
+                        end,
+                        char_start: char_offset(offset),
+                        char_end: char_offset(end),
+                        size: chunk_config.sizer().size(chunk),
+                        previous: index.checked_sub(1),
+                        next: None,
+                        language: None,
+                        title: None,
+                        untrimmed,
+                    },
+                }
+            })
+            .collect::<Vec<_>>();
+        if let Some((_, rest)) = chunks.split_last_mut() {
+            for chunk in rest {
+                chunk.metadata.next = Some(chunk.index + 1);
+            }
+        }
+
+        Self {
+            schema_version: SCHEMA_VERSION,
+            desired_capacity: chunk_config.capacity().desired(),
+            max_capacity: chunk_config.capacity().max(),
+            overlap: chunk_config.overlap(),
+            chunks,
+        }
+    }
+
+    /// Generate a short title for each chunk with the given callback, which
+    /// gets the chunk with its metadata, and store it in the metadata of the
+    /// chunk. [`default_title`] uses the first heading or sentence of the
+    /// chunk.
+    ///
+    /// ```
+    /// use text_splitter::{output::{default_title, ChunkingRun}, ChunkConfig, TextSplitter};
+    ///
+    /// let text = "# Install\n\nRun the installer.\n\nThen restart. It is quick.";
+    /// let splitter = TextSplitter::new(30);
+    /// let run = ChunkingRun::new(text, splitter.chunk_indices(text), splitter.chunk_config())
+    ///     .with_titles(default_title);
+    ///
+    /// assert_eq!(Some("Install"), run.chunks[0].metadata.title.as_deref());
+    /// assert_eq!(Some("Then restart."), run.chunks[1].metadata.title.as_deref());
+    /// ```
+    #[must_use]
+    pub fn with_titles(mut self, mut title: impl FnMut(&Chunk) -> Option<String>) -> Self {
+        for chunk in &mut self.chunks {
+            chunk.metadata.title = title(chunk);
+        }
+        self
+    }
+
+    /// Detect the language of each chunk with
+    /// [whatlang](https://docs.rs/whatlang), such as to route chunks of a
+    /// document with several languages to language-specific embedding models.
+    ///
+    /// The language is only set if the detection is reliable, which usually
+    /// needs more than a few words of text, so short chunks may not have one.
+    ///
+    /// ```
+    /// use text_splitter::{output::ChunkingRun, TextSplitter};
+    ///
+    /// let text = "The quick brown fox jumps over the lazy dog, and then runs away into the forest.\n\n\
+    ///     Der schnelle braune Fuchs springt über den faulen Hund und läuft dann in den Wald.";
+    /// let splitter = TextSplitter::new(100);
+    /// let run = ChunkingRun::new(text, splitter.chunk_indices(text), splitter.chunk_config())
+    ///     .with_languages();
+    ///
+    /// assert_eq!(Some("eng"), run.chunks[0].metadata.language.as_deref());
+    /// assert_eq!(Some("deu"), run.chunks[1].metadata.language.as_deref());
+    /// ```
+    #[cfg(feature = "whatlang")]
+    #[must_use]
+    pub fn with_languages(mut self) -> Self {
+        for chunk in &mut self.chunks {
+            chunk.metadata.language = whatlang::detect(&chunk.text)
+                .filter(whatlang::Info::is_reliable)
+                .map(|info| info.lang().code().to_string());
+        }
+        self
+    }
+}
+
+/// Max number of characters in a title generated by [`default_title`]
+const MAX_TITLE_CHARS: usize = 80;
+
+/// A title for a chunk, from the first Markdown heading in the chunk, or
+/// otherwise its first sentence, with whitespace collapsed. Long titles are
+/// shortened to 80 characters at a word boundary, followed by a `…`.
+/// Returns `None` if the chunk is empty.
+///
+/// Meant to be used with [`ChunkingRun::with_titles`].
+#[must_use]
+pub fn default_title(chunk: &Chunk) -> Option<String> {
+    let text = chunk.text.trim();
+    let heading = text.lines().find_map(|line| {
+        let line = line.trim_start();
+        let title = line.trim_start_matches('#');
+        let hashes = line.len() - title.len();
+        ((1..=6).contains(&hashes) && (title.is_empty() || title.starts_with([' ', '\t'])))
+            .then(|| title.trim().trim_end_matches('#').trim_end())
+            .filter(|title| !title.is_empty())
+    });
+    let title = match heading {
+        Some(heading) => heading,
+        None => SENTENCE_SEGMENTER
+            .segment_str(text)
+            .nth(1)
+            .map_or(text, |end| &text[..end]),
+    };
+    let title = title.split_whitespace().join(" ");
+    if title.is_empty() {
+        return None;
+    }
+    if title.chars().count() <= MAX_TITLE_CHARS {
+        return Some(title);
+    }
+
+    let cut = title
+        .char_indices()
+        .nth(MAX_TITLE_CHARS - 1)
+        .map_or(title.len(), |(index, _)| index);
+    let shortened = if title[cut..].starts_with(' ') {
+        &title[..cut]
+    } else {
+        title[..cut]
+            .rsplit_once(' ')
+            .map_or(&title[..cut], |(shortened, _)| shortened)
+    };
+    Some(format!("{}…", shortened.trim_end()))
+}
+
+/// Expand the chunk at position `k` of `chunks` with up to `window` of its
+/// neighbors on each side, such as to give more context around a chunk found
+/// at query time, and return the text they cover.
+///
+/// The chunks have to be in order, like those of a [`ChunkingRun`]. Text that
+/// is part of more than one chunk because of overlap is only included once,
+/// based on the byte offsets of each chunk. Neighbors that don't touch, such
+/// as because whitespace between them was trimmed, are joined with a space.
+/// Returns `None` if there is no chunk at position `k`.
+///
+/// ```
+/// use text_splitter::{output::{merge_adjacent, ChunkingRun}, ChunkConfig, TextSplitter};
+///
+/// let text = "One two three four five six";
+/// let splitter = TextSplitter::new(ChunkConfig::new(9).with_overlap(4).unwrap());
+/// let run = ChunkingRun::new(text, splitter.chunk_indices(text), splitter.chunk_config());
+///
+/// assert_eq!("two three", run.chunks[1].text);
+/// assert_eq!(
+///     Some("One two three four five".to_owned()),
+///     merge_adjacent(&run.chunks, 1, 1)
+/// );
+/// ```
+#[must_use]
+pub fn merge_adjacent(chunks: &[Chunk], k: usize, window: usize) -> Option<String> {
+    if k >= chunks.len() {
+        return None;
+    }
+    let neighbors =
+        &chunks[k.saturating_sub(window)..=k.saturating_add(window).min(chunks.len() - 1)];
+
+    let mut merged = String::new();
+    let mut end: Option<usize> = None;
+    for chunk in neighbors {
+        let ChunkMetadata { start, .. } = chunk.metadata;
+        match end {
+            // Skip the part that overlaps with the text merged so far
+            Some(end) if start < end => {
+                merged.push_str(chunk.text.get(end - start..).unwrap_or_default());
+            }
+            Some(_) => {
+                merged.push(' ');
+                merged.push_str(&chunk.text);
+            }
+            None => merged.push_str(&chunk.text),
+        }
+        end = Some(end.map_or(chunk.metadata.end, |end| end.max(chunk.metadata.end)));
+    }
+    Some(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn char_offsets() {
+        let text = "é a é b";
+        let run = ChunkingRun::new(
+            text,
+            [(0, "é a"), (3, "a é"), (8, "b")],
+            &ChunkConfig::new(3),
+        );
+
+        let offsets = run
+            .chunks
+            .iter()
+            .map(|c| (c.metadata.char_start, c.metadata.char_end))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![(0, 3), (2, 5), (6, 7)], offsets);
+        assert_eq!(3, run.chunks[1].metadata.size);
+    }
+
+    #[test]
+    fn links_neighbors() {
+        let run = ChunkingRun::new(
+            "a b c",
+            [(0, "a"), (2, "b"), (4, "c")],
+            &ChunkConfig::new(1),
+        );
+
+        let links = run
+            .chunks
+            .iter()
+            .map(|c| (c.metadata.previous, c.metadata.next))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![(None, Some(1)), (Some(0), Some(2)), (Some(1), None)],
+            links
+        );
+    }
+
+    #[test]
+    fn default_titles() {
+        let title = |text: &str| {
+            default_title(&Chunk {
+                index: 0,
+                text: text.to_string(),
+                metadata: ChunkMetadata {
+                    start: 0,
+                    end: text.len(),
+                    char_start: 0,
+                    char_end: text.chars().count(),
+                    size: 0,
+                    previous: None,
+                    next: None,
+                    language: None,
+                    title: None,
+                    untrimmed: None,
+                },
+            })
+        };
+
+        assert_eq!(
+            title("Intro\n\n## Setup ##\n\nText"),
+            Some("Setup".to_string())
+        );
+        assert_eq!(
+            title("#hashtag first.\nSecond sentence."),
+            Some("#hashtag first.".to_string())
+        );
+        assert_eq!(
+            title(&"word ".repeat(30)),
+            Some(format!("{}…", ["word"; 16].join(" ")))
+        );
+        assert_eq!(title(" \n "), None);
+    }
+
+    #[cfg(feature = "whatlang")]
+    #[test]
+    fn short_chunks_have_no_language() {
+        let run = ChunkingRun::new(
+            "Bonjour. Ceci est un texte assez long pour que la langue soit détectée.",
+            [
+                (0, "Bonjour."),
+                (
+                    9,
+                    "Ceci est un texte assez long pour que la langue soit détectée.",
+                ),
+            ],
+            &ChunkConfig::new(100),
+        )
+        .with_languages();
+
+        let languages = run
+            .chunks
+            .iter()
+            .map(|c| c.metadata.language.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![None, Some("fra")], languages);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes_fields_in_schema() {
+        let run = ChunkingRun::new("Some text", [(0, "Some text")], &ChunkConfig::new(10));
+        let value = serde_json::to_value(&run).unwrap();
+        let schema = serde_json::from_str::<serde_json::Value>(JSON_SCHEMA).unwrap();
+
+        let keys = |value: &serde_json::Value| {
+            let mut keys = value
+                .as_object()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>();
+            keys.sort();
+            keys
+        };
+        assert_eq!(keys(&schema["properties"]), keys(&value));
+        assert_eq!(
+            keys(&schema["$defs"]["Chunk"]["properties"]),
+            keys(&value["chunks"][0])
+        );
+        assert_eq!(
+            keys(&schema["$defs"]["ChunkMetadata"]["properties"]),
+            keys(&value["chunks"][0]["metadata"])
+        );
+        assert_eq!(serde_json::from_value::<ChunkingRun>(value).unwrap(), run);
+    }
+
+    #[test]
+    fn merge_adjacent_clamps_window() {
+        let text = "é a é b";
+        let run = ChunkingRun::new(
+            text,
+            [(0, "é a"), (3, "a é"), (8, "b")],
+            &ChunkConfig::new(3),
+        );
+
+        assert_eq!(merge_adjacent(&run.chunks, 0, 0).as_deref(), Some("é a"));
+        assert_eq!(merge_adjacent(&run.chunks, 0, 1).as_deref(), Some("é a é"));
+        assert_eq!(
+            merge_adjacent(&run.chunks, 2, 5).as_deref(),
+            Some("é a é b")
+        );
+        assert_eq!(merge_adjacent(&run.chunks, 3, 1), None);
+        assert_eq!(merge_adjacent(&[], 0, 1), None);
+    }
+}
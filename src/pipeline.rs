@@ -0,0 +1,325 @@
+/*!
+# Chunk pipeline
+
+Chunk a stream of documents, such as messages consumed from Kafka or another
+queue, on a pool of worker threads, and get a stream of chunk batches back.
+
+Documents are chunked with bounded concurrency, so only a limited number of
+documents are pulled from the input stream ahead of the batches that haven't
+been consumed yet. Batches come out in the same order as the documents went
+in, and the chunks of each document stay in order.
+
+```
+use futures_util::{stream, StreamExt};
+use text_splitter::{pipeline::ChunkPipeline, TextSplitter};
+
+# #[tokio::main(flavor = "current_thread")]
+# async fn main() {
+let pipeline = ChunkPipeline::new(TextSplitter::new(10))
+    .with_concurrency(4)
+    .with_batch_size(2);
+let documents = stream::iter([("doc-1", "Some text\n\nfrom a\ndocument".to_owned())]);
+let batches = pipeline.run(documents).collect::<Vec<_>>().await;
+
+assert_eq!(2, batches.len());
+assert_eq!("from a", batches[0].chunks[1].text);
+assert!(batches[1].last);
+# }
+```
+*/
+
+use std::{fmt, num::NonZeroUsize, panic, sync::Arc, thread};
+
+use futures_util::{stream, Stream, StreamExt};
+
+use crate::{
+    output::{Chunk, ChunkingRun},
+    ChunkSizer, DocumentSplitter,
+};
+
+/// A batch of chunks from a single document.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ChunkBatch<Id> {
+    /// Identifier of the document the chunks came from
+    pub document: Id,
+    /// Chunks of the document, in order. The index of each chunk is its index
+    /// among all chunks of the document.
+    pub chunks: Vec<Chunk>,
+    /// Whether this is the last batch of the document, such as for
+    /// committing the offset of the document once all of its chunks were
+    /// handled. A document without any chunks still gets a single, empty
+    /// batch.
+    pub last: bool,
+}
+
+/// Callback that generates the title of a chunk
+type TitleFn = Arc<dyn Fn(&Chunk) -> Option<String> + Send + Sync>;
+
+/// Chunks a stream of documents on a pool of blocking worker threads, with
+/// bounded concurrency, and returns a stream of chunk batches.
+///
+/// Requires a [tokio](https://docs.rs/tokio) runtime, since each document is
+/// chunked with `spawn_blocking`, so that chunking large documents doesn't
+/// block the async tasks of the runtime.
+pub struct ChunkPipeline<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Splitter to chunk each document with
+    splitter: Arc<DocumentSplitter<Sizer>>,
+    /// Max number of documents being chunked at once
+    concurrency: usize,
+    /// Max number of chunks in each batch
+    batch_size: usize,
+    /// Generates the title of each chunk, if set
+    titles: Option<TitleFn>,
+}
+
+impl<Sizer> ChunkPipeline<Sizer>
+where
+    Sizer: ChunkSizer + Send + Sync + 'static,
+{
+    /// Creates a new [`ChunkPipeline`], which chunks documents with the given
+    /// splitter. By default, as many documents are chunked at once as there
+    /// are CPUs available, and all chunks of a document are in one batch.
+    #[must_use]
+    pub fn new(splitter: impl Into<DocumentSplitter<Sizer>>) -> Self {
+        Self {
+            splitter: Arc::new(splitter.into()),
+            concurrency: thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            batch_size: usize::MAX,
+            titles: None,
+        }
+    }
+
+    /// Set the max number of documents that are chunked at once, which is
+    /// also how far ahead of the consumer of the batches the input stream is
+    /// read. At least one document is always chunked at a time.
+    #[must_use]
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Set the max number of chunks in each batch, such as the max batch size
+    /// of an embedding model. Documents with more chunks are split into
+    /// several batches. Each batch has at least one chunk.
+    #[must_use]
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Generate a short title for each chunk with the given callback, such as
+    /// [`default_title`](crate::output::default_title), on the same worker
+    /// thread that chunks the document, and store it in the metadata of the
+    /// chunk.
+    #[must_use]
+    pub fn with_titles(
+        mut self,
+        title: impl Fn(&Chunk) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.titles = Some(Arc::new(title));
+        self
+    }
+
+    /// Chunk a stream of documents, each with an identifier and its text, and
+    /// return a stream of the batches of chunks of each document, in the same
+    /// order as the documents. Clone the pipeline first to run it on several
+    /// streams with the same splitter.
+    ///
+    /// # Panics
+    ///
+    /// If chunking a document panics, the panic is resumed when the stream
+    /// reaches that document.
+    pub fn run<Id, Documents>(self, documents: Documents) -> impl Stream<Item = ChunkBatch<Id>>
+    where
+        Id: Clone + Send + 'static,
+        Documents: Stream<Item = (Id, String)>,
+    {
+        let Self {
+            splitter,
+            concurrency,
+            batch_size,
+            titles,
+        } = self;
+        documents
+            .map(move |(document, text)| {
+                let splitter = Arc::clone(&splitter);
+                let titles = titles.clone();
+                async move {
+                    let chunks = tokio::task::spawn_blocking(move || {
+                        let run = ChunkingRun::from_untrimmed_ranges(
+                            &text,
+                            splitter.chunks_with_untrimmed_ranges(&text),
+                            splitter.chunk_config(),
+                        );
+                        match titles {
+                            Some(title) => run.with_titles(|chunk| title(chunk)).chunks,
+                            None => run.chunks,
+                        }
+                    })
+                    .await
+                    .unwrap_or_else(|error| match error.try_into_panic() {
+                        Ok(payload) => panic::resume_unwind(payload),
+                        Err(error) => panic!("Chunking was cancelled: {error}"),
+                    });
+                    (document, chunks)
+                }
+            })
+            .buffered(concurrency)
+            .flat_map(move |(document, chunks)| stream::iter(batches(document, chunks, batch_size)))
+    }
+}
+
+impl<Sizer> Clone for ChunkPipeline<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    fn clone(&self) -> Self {
+        Self {
+            splitter: Arc::clone(&self.splitter),
+            concurrency: self.concurrency,
+            batch_size: self.batch_size,
+            titles: self.titles.clone(),
+        }
+    }
+}
+
+impl<Sizer> fmt::Debug for ChunkPipeline<Sizer>
+where
+    Sizer: ChunkSizer + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChunkPipeline")
+            .field("splitter", &self.splitter)
+            .field("concurrency", &self.concurrency)
+            .field("batch_size", &self.batch_size)
+            .field("titles", &self.titles.is_some())
+            .finish()
+    }
+}
+
+/// Split the chunks of a document into batches of at most `batch_size`.
+fn batches<Id: Clone>(document: Id, chunks: Vec<Chunk>, batch_size: usize) -> Vec<ChunkBatch<Id>> {
+    if chunks.is_empty() {
+        return vec![ChunkBatch {
+            document,
+            chunks,
+            last: true,
+        }];
+    }
+
+    let count = chunks.len().div_ceil(batch_size);
+    let mut chunks = chunks.into_iter();
+    (1..=count)
+        .map(|n| ChunkBatch {
+            document: document.clone(),
+            chunks: chunks.by_ref().take(batch_size).collect(),
+            last: n == count,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TextSplitter;
+
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn preserves_document_order() {
+        let documents = (0..100)
+            .map(|n| (n, "Some text\n\nfrom a\ndocument ".repeat(n % 7)))
+            .collect::<Vec<_>>();
+        let splitter = TextSplitter::new(10);
+
+        let batches = ChunkPipeline::new(TextSplitter::new(10))
+            .with_concurrency(8)
+            .run(stream::iter(documents.clone()))
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(
+            batches.iter().map(|b| b.document).collect::<Vec<_>>(),
+            (0..100).collect::<Vec<_>>()
+        );
+        for (batch, (_, text)) in batches.iter().zip(&documents) {
+            assert!(batch.last);
+            assert_eq!(
+                batch
+                    .chunks
+                    .iter()
+                    .map(|c| c.text.as_str())
+                    .collect::<Vec<_>>(),
+                splitter.chunks(text).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn generates_titles() {
+        let batches = ChunkPipeline::new(TextSplitter::new(10))
+            .with_titles(crate::output::default_title)
+            .run(stream::iter([(
+                "doc",
+                "Some text\n\nfrom a\ndocument".to_owned(),
+            )]))
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(
+            batches[0]
+                .chunks
+                .iter()
+                .map(|c| c.metadata.title.as_deref())
+                .collect::<Vec<_>>(),
+            vec![Some("Some text"), Some("from a"), Some("document")]
+        );
+    }
+
+    #[test]
+    fn splits_chunks_into_batches() {
+        let chunks = (0..5)
+            .map(|index| Chunk {
+                index,
+                text: index.to_string(),
+                metadata: crate::output::ChunkMetadata {
+                    start: index,
+                    end: index + 1,
+                    char_start: index,
+                    char_end: index + 1,
+                    size: 1,
+                    previous: index.checked_sub(1),
+                    next: Some(index + 1).filter(|&next| next < 5),
+                    language: None,
+                    title: None,
+                    untrimmed: None,
+                },
+            })
+            .collect::<Vec<_>>();
+
+        let batches = batches("doc", chunks, 2);
+
+        assert_eq!(
+            batches
+                .iter()
+                .map(|b| (b.chunks.len(), b.last))
+                .collect::<Vec<_>>(),
+            vec![(2, false), (2, false), (1, true)]
+        );
+        assert_eq!(batches[2].chunks[0].index, 4);
+    }
+
+    #[test]
+    fn empty_documents_get_an_empty_batch() {
+        assert_eq!(
+            batches("doc", vec![], 2),
+            vec![ChunkBatch {
+                document: "doc",
+                chunks: vec![],
+                last: true
+            }]
+        );
+    }
+}
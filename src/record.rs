@@ -0,0 +1,215 @@
+/*!
+# [`RecordSplitter`]
+Split structured records made up of named fields, such as a title, abstract,
+and body, so that each chunk stays within a single field and each field can
+be split as much or as little as it needs.
+*/
+
+use ahash::AHashMap;
+
+use crate::{ChunkConfig, ChunkSizer, TextSplitter};
+
+/// How much a field of a record may be split into chunks.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum FieldPriority {
+    /// The field is always a single chunk, even if it is larger than the
+    /// chunk capacity, such as for a title.
+    Atomic,
+    /// The field is a single chunk if it fits within the max chunk capacity,
+    /// and is split by its semantic levels otherwise, such as for an abstract.
+    PreferAtomic,
+    /// The field is split by its semantic levels like any other text, such as
+    /// for the body of a document.
+    #[default]
+    Split,
+}
+
+/// A chunk of a single field of a record, as returned by
+/// [`RecordSplitter::chunks`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecordChunk<'record> {
+    /// Name of the field the chunk came from
+    pub field: &'record str,
+    /// Byte offset of the chunk within the text of the field
+    pub offset: usize,
+    /// Text of the chunk
+    pub text: &'record str,
+}
+
+/// Splits records made up of named fields. Chunks never cross from one field
+/// into another, and each field is split according to its
+/// [`FieldPriority`]. Fields without a priority are split by their semantic
+/// levels, the same way as with a [`TextSplitter`].
+///
+/// ```
+/// use text_splitter::{FieldPriority, RecordSplitter};
+///
+/// let splitter = RecordSplitter::new(20)
+///     .with_field_priority("title", FieldPriority::Atomic);
+/// let record = [
+///     ("title", "A title that is too long for one chunk"),
+///     ("body", "The first sentence. The second one."),
+/// ];
+/// let chunks = splitter
+///     .chunks(&record)
+///     .map(|chunk| (chunk.field, chunk.text))
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(
+///     vec![
+///         ("title", "A title that is too long for one chunk"),
+///         ("body", "The first sentence."),
+///         ("body", "The second one."),
+///     ],
+///     chunks
+/// );
+/// ```
+#[derive(Debug)]
+pub struct RecordSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Splitter used for the fields that can be split
+    splitter: TextSplitter<Sizer>,
+    /// How much each field may be split, by field name
+    priorities: AHashMap<String, FieldPriority>,
+}
+
+impl<Sizer> RecordSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Creates a new [`RecordSplitter`].
+    ///
+    /// ```
+    /// use text_splitter::RecordSplitter;
+    ///
+    /// // By default, the chunk sizer is based on characters.
+    /// let splitter = RecordSplitter::new(512);
+    /// ```
+    #[must_use]
+    pub fn new(chunk_config: impl Into<ChunkConfig<Sizer>>) -> Self {
+        Self {
+            splitter: TextSplitter::new(chunk_config),
+            priorities: AHashMap::new(),
+        }
+    }
+
+    /// Set how much the field with the given name may be split.
+    #[must_use]
+    pub fn with_field_priority(
+        mut self,
+        field: impl Into<String>,
+        priority: FieldPriority,
+    ) -> Self {
+        self.priorities.insert(field.into(), priority);
+        self
+    }
+
+    /// How much the field with the given name may be split.
+    #[must_use]
+    pub fn field_priority(&self, field: &str) -> FieldPriority {
+        self.priorities.get(field).copied().unwrap_or_default()
+    }
+
+    /// Returns an iterator over the chunks of each field of the record, given
+    /// as pairs of field names and their text, in order. Each chunk is
+    /// annotated with the name of its field and its byte offset within the
+    /// field.
+    /// Whitespace around atomic fields is trimmed, and empty fields don't
+    /// produce any chunks.
+    pub fn chunks<'splitter, 'record: 'splitter>(
+        &'splitter self,
+        record: &'record [(&'record str, &'record str)],
+    ) -> impl Iterator<Item = RecordChunk<'record>> + 'splitter {
+        record.iter().flat_map(move |&(field, text)| {
+            let atomic = match self.field_priority(field) {
+                FieldPriority::Atomic => true,
+                FieldPriority::PreferAtomic => self.fits(text.trim()),
+                FieldPriority::Split => false,
+            };
+            let chunks: Box<dyn Iterator<Item = (usize, &'record str)> + 'splitter> = if atomic {
+                let trimmed = text.trim_start();
+                let offset = text.len() - trimmed.len();
+                Box::new(
+                    Some((offset, trimmed.trim_end()))
+                        .into_iter()
+                        .filter(|(_, chunk)| !chunk.is_empty()),
+                )
+            } else {
+                Box::new(self.splitter.chunk_indices(text))
+            };
+            chunks.map(move |(offset, text)| RecordChunk {
+                field,
+                offset,
+                text,
+            })
+        })
+    }
+
+    /// Whether the text fits within the max chunk capacity as a whole
+    fn fits(&self, text: &str) -> bool {
+        let chunk_config = self.splitter.chunk_config();
+        chunk_config.sizer().size(text) <= chunk_config.capacity().max()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefer_atomic_fields_are_only_split_if_too_large() {
+        let text = "One sentence here. And another.";
+        let record = [("abstract", text), ("body", text)];
+
+        let splitter = RecordSplitter::new(10..=40)
+            .with_field_priority("abstract", FieldPriority::PreferAtomic);
+        assert_eq!(
+            splitter.chunks(&record).collect::<Vec<_>>(),
+            vec![
+                RecordChunk {
+                    field: "abstract",
+                    offset: 0,
+                    text
+                },
+                RecordChunk {
+                    field: "body",
+                    offset: 0,
+                    text: "One sentence here."
+                },
+                RecordChunk {
+                    field: "body",
+                    offset: 19,
+                    text: "And another."
+                },
+            ]
+        );
+
+        let splitter = RecordSplitter::new(10..=20)
+            .with_field_priority("abstract", FieldPriority::PreferAtomic);
+        assert_eq!(splitter.chunks(&record[..1]).count(), 2);
+    }
+
+    #[test]
+    fn atomic_fields_are_trimmed() {
+        let splitter = RecordSplitter::new(5).with_field_priority("title", FieldPriority::Atomic);
+        let record = [("title", "  A long title \n"), ("empty", " ")];
+
+        assert_eq!(
+            splitter.chunks(&record).collect::<Vec<_>>(),
+            vec![RecordChunk {
+                field: "title",
+                offset: 2,
+                text: "A long title"
+            }]
+        );
+    }
+
+    #[test]
+    fn fields_default_to_split() {
+        let splitter = RecordSplitter::new(5);
+
+        assert_eq!(splitter.field_priority("body"), FieldPriority::Split);
+    }
+}
@@ -0,0 +1,89 @@
+/*!
+Spans of a text to mask before chunking, such as email addresses or keys.
+*/
+
+use std::ops::Range;
+
+use regex::Regex;
+
+/// Spans of a text to replace with a placeholder before chunking, found
+/// either by regular expressions or by byte ranges.
+///
+/// Chunk sizes are measured on the masked text, so the chunks fit the
+/// capacity of the text that is actually sent on, and a placeholder is never
+/// split across chunks.
+///
+/// ```
+/// use regex::Regex;
+/// use text_splitter::Redactions;
+///
+/// let redactions = Redactions::new("[REDACTED]")
+///     .with_pattern(Regex::new(r"\S+@\S+").unwrap())
+///     .with_range(0..4);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Redactions {
+    /// Text that replaces each redacted span
+    placeholder: String,
+    /// Patterns whose matches are redacted
+    patterns: Vec<Regex>,
+    /// Byte ranges that are redacted
+    ranges: Vec<Range<usize>>,
+}
+
+impl Redactions {
+    /// Redact spans by replacing them with the given placeholder. Overlapping
+    /// or touching spans are replaced with a single placeholder.
+    #[must_use]
+    pub fn new(placeholder: impl Into<String>) -> Self {
+        Self {
+            placeholder: placeholder.into(),
+            patterns: vec![],
+            ranges: vec![],
+        }
+    }
+
+    /// Redact every match of the pattern.
+    #[must_use]
+    pub fn with_pattern(mut self, pattern: Regex) -> Self {
+        self.patterns.push(pattern);
+        self
+    }
+
+    /// Redact the byte range of the text. The range is widened to the nearest
+    /// character boundaries, and limited to the length of the text.
+    #[must_use]
+    pub fn with_range(mut self, range: Range<usize>) -> Self {
+        self.ranges.push(range);
+        self
+    }
+
+    /// Text that replaces each redacted span
+    pub(crate) fn placeholder(&self) -> &str {
+        &self.placeholder
+    }
+
+    /// Byte ranges of all spans to redact in the text, unsorted and possibly
+    /// overlapping.
+    pub(crate) fn ranges(&self, text: &str) -> Vec<Range<usize>> {
+        self.patterns
+            .iter()
+            .flat_map(|pattern| pattern.find_iter(text).map(|m| m.range()))
+            .chain(self.ranges.iter().cloned())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_patterns_and_ranges() {
+        let redactions = Redactions::new("*")
+            .with_pattern(Regex::new(r"\d+").unwrap())
+            .with_range(0..2);
+
+        assert_eq!(redactions.ranges("ab 12 c 3"), vec![3..5, 8..9, 0..2]);
+    }
+}
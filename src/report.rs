@@ -0,0 +1,276 @@
+/*!
+# Chunk reports
+
+Render a text with the boundaries of its chunks highlighted as a standalone
+HTML file. Neighboring chunks alternate colors, overlap between chunks is
+highlighted, and each chunk is labeled with its index and size, with chunks
+larger than the max capacity marked. Helpful when tuning the capacity and
+overlap of a [`ChunkConfig`], or when reporting a chunking issue.
+
+```
+use text_splitter::{report::ChunkReport, ChunkConfig, TextSplitter};
+
+let text = "Some text from a document";
+let config = ChunkConfig::new(10);
+let chunks = TextSplitter::new(10).chunk_indices(text).collect::<Vec<_>>();
+
+let html = ChunkReport::new(text, chunks, &config)
+    .with_title("My document")
+    .to_html();
+assert!(html.contains("<title>My document</title>"));
+```
+*/
+
+use std::{fmt::Write as _, fs, io, path::Path};
+
+use crate::{ChunkCapacity, ChunkConfig, ChunkSizer};
+
+/// Styles for the generated report
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;}\
+table{border-collapse:collapse;margin-bottom:1em;}\
+td,th{border:1px solid #ccc;padding:.2em .6em;text-align:left;}\
+.text{font-family:monospace;white-space:pre-wrap;line-height:1.8;}\
+.c0{background:#dbeafe;}.c1{background:#dcfce7;}\
+.overlap{background:#fde68a;}.gap{color:#888;}\
+.label{font-size:.7em;background:#374151;color:#fff;border-radius:3px;padding:0 .3em;margin-right:.2em;}\
+.label.oversized{background:#dc2626;}";
+
+/// A chunk within the report
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct ReportChunk {
+    /// Byte offset of the chunk within the text
+    offset: usize,
+    /// Byte length of the chunk
+    len: usize,
+    /// Size of the chunk, as measured by the chunk sizer
+    size: usize,
+}
+
+impl ReportChunk {
+    fn end(&self) -> usize {
+        self.offset + self.len
+    }
+}
+
+/// An HTML report of the chunks of a text, with the chunk boundaries, overlap
+/// regions, and the size of each chunk highlighted.
+#[derive(Clone, Debug)]
+pub struct ChunkReport<'text> {
+    /// The whole text that was chunked
+    text: &'text str,
+    /// Chunks of the text, sorted by offset
+    chunks: Vec<ReportChunk>,
+    /// Capacity the chunks were generated with
+    capacity: ChunkCapacity,
+    /// Title of the report
+    title: String,
+}
+
+impl<'text> ChunkReport<'text> {
+    /// Create a report for the chunks of a text and their byte offsets, such
+    /// as the output of `chunk_indices`. The size of each chunk is measured
+    /// with the sizer of the `chunk_config`, and compared to its capacity.
+    #[must_use]
+    pub fn new<Sizer>(
+        text: &'text str,
+        chunks: impl IntoIterator<Item = (usize, &'text str)>,
+        chunk_config: &ChunkConfig<Sizer>,
+    ) -> Self
+    where
+        Sizer: ChunkSizer,
+    {
+        let mut chunks = chunks
+            .into_iter()
+            .map(|(offset, chunk)| ReportChunk {
+                offset,
+                len: chunk.len(),
+                size: chunk_config.sizer.size(chunk),
+            })
+            .collect::<Vec<_>>();
+        chunks.sort_by_key(|chunk| (chunk.offset, chunk.len));
+        Self {
+            text,
+            chunks,
+            capacity: *chunk_config.capacity(),
+            title: "Chunk report".to_string(),
+        }
+    }
+
+    /// Set the title of the report. Defaults to "Chunk report".
+    #[must_use]
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Render the report as a standalone HTML document.
+    #[must_use]
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        // Writing to a `String` never fails
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n",
+            title = escape(&self.title),
+        );
+        self.write_summary(&mut html);
+        html.push_str("<div class=\"text\">");
+        self.write_text(&mut html);
+        html.push_str("</div>\n</body>\n</html>\n");
+        html
+    }
+
+    /// Write the report as an HTML file to the given path.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the file can't be written.
+    pub fn write_html(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_html())
+    }
+
+    /// Table with statistics about all of the chunks
+    fn write_summary(&self, html: &mut String) {
+        let sizes = self.chunks.iter().map(|chunk| chunk.size);
+        let total = sizes.clone().sum::<usize>();
+        let oversized = sizes
+            .clone()
+            .filter(|&size| size > self.capacity.max())
+            .count();
+        let overlap = self
+            .chunks
+            .windows(2)
+            .map(|pair| pair[0].end().saturating_sub(pair[1].offset))
+            .sum::<usize>();
+        #[allow(clippy::cast_precision_loss)]
+        let average = total as f64 / self.chunks.len().max(1) as f64;
+        let capacity = if self.capacity.desired() == self.capacity.max() {
+            self.capacity.max().to_string()
+        } else {
+            format!("{}..={}", self.capacity.desired(), self.capacity.max())
+        };
+        let _ = write!(
+            html,
+            "<table>\n<tr><th>Chunks</th><td>{chunks}</td></tr>\n<tr><th>Capacity</th><td>{capacity}</td></tr>\n<tr><th>Smallest chunk</th><td>{min}</td></tr>\n<tr><th>Largest chunk</th><td>{max}</td></tr>\n<tr><th>Average chunk</th><td>{average:.1}</td></tr>\n<tr><th>Oversized chunks</th><td>{oversized}</td></tr>\n<tr><th>Overlapping bytes</th><td>{overlap}</td></tr>\n</table>\n",
+            chunks = self.chunks.len(),
+            min = sizes.clone().min().unwrap_or_default(),
+            max = sizes.max().unwrap_or_default(),
+        );
+    }
+
+    /// The text, split into segments by every chunk boundary, and highlighted
+    /// by how many chunks each segment is in.
+    fn write_text(&self, html: &mut String) {
+        let mut boundaries = self
+            .chunks
+            .iter()
+            .flat_map(|chunk| [chunk.offset, chunk.end()])
+            .chain([0, self.text.len()])
+            .collect::<Vec<_>>();
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        let mut next_label = 0;
+        for window in boundaries.windows(2) {
+            let (start, end) = (window[0], window[1]);
+            while let Some(chunk) = self
+                .chunks
+                .get(next_label)
+                .filter(|chunk| chunk.offset <= start)
+            {
+                let oversized = if chunk.size > self.capacity.max() {
+                    " oversized"
+                } else {
+                    ""
+                };
+                let _ = write!(
+                    html,
+                    "<span class=\"label{oversized}\">#{next_label} ({size})</span>",
+                    size = chunk.size,
+                );
+                next_label += 1;
+            }
+
+            let mut containing = self
+                .chunks
+                .iter()
+                .enumerate()
+                .skip_while(|(_, chunk)| chunk.end() < end)
+                .take_while(|(_, chunk)| chunk.offset <= start)
+                .filter(|(_, chunk)| chunk.end() >= end);
+            let class = match (containing.next(), containing.next()) {
+                (None, _) => "gap".to_string(),
+                (Some((index, _)), None) => format!("c{}", index % 2),
+                (Some(_), Some(_)) => "overlap".to_string(),
+            };
+            let _ = write!(
+                html,
+                "<span class=\"{class}\">{}</span>",
+                escape(&self.text[start..end])
+            );
+        }
+    }
+}
+
+/// Escape text for use in HTML
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_html() {
+        assert_eq!(
+            escape("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn highlights_chunks_gaps_and_overlap() {
+        let text = "aaa bbb ccc";
+        let config = ChunkConfig::new(5);
+        let report = ChunkReport::new(text, [(0, "aaa b"), (4, "bbb"), (8, "ccc")], &config);
+        let mut html = String::new();
+        report.write_text(&mut html);
+
+        assert_eq!(
+            html,
+            "<span class=\"label\">#0 (5)</span><span class=\"c0\">aaa </span>\
+             <span class=\"label\">#1 (3)</span><span class=\"overlap\">b</span>\
+             <span class=\"c1\">bb</span><span class=\"gap\"> </span>\
+             <span class=\"label\">#2 (3)</span><span class=\"c0\">ccc</span>"
+        );
+    }
+
+    #[test]
+    fn marks_oversized_chunks() {
+        let text = "aaaaaa";
+        let config = ChunkConfig::new(5);
+        let html = ChunkReport::new(text, [(0, text)], &config).to_html();
+
+        assert!(html.contains("<span class=\"label oversized\">#0 (6)</span>"));
+        assert!(html.contains("<tr><th>Oversized chunks</th><td>1</td></tr>"));
+    }
+
+    #[test]
+    fn empty_text() {
+        let config = ChunkConfig::new(5);
+        let html = ChunkReport::new("", [], &config).to_html();
+
+        assert!(html.contains("<tr><th>Chunks</th><td>0</td></tr>"));
+    }
+}
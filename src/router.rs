@@ -0,0 +1,190 @@
+/*!
+# [`SplitterRouter`]
+Pick a splitter based on the content type (MIME type) of the text, so that
+callers receiving mixed content don't each have to write the same dispatch.
+*/
+
+use ahash::AHashMap;
+
+#[cfg(feature = "code")]
+use crate::CodeSplitter;
+#[cfg(feature = "markdown")]
+use crate::MarkdownSplitter;
+use crate::{ChunkSizer, DocumentSplitter, TextSplitter};
+
+/// Routes text to a splitter based on its content type, such as
+/// `text/markdown` or `text/x-python`. Content types without a splitter of
+/// their own use the fallback splitter.
+///
+/// Content types are matched case-insensitively, and any parameters, like
+/// `; charset=utf-8`, are ignored.
+///
+/// ```
+/// use text_splitter::{DocumentSplitter, MarkdownSplitter, SplitterRouter, TextSplitter};
+///
+/// let router = SplitterRouter::new(TextSplitter::new(20))
+///     .with_splitter("text/markdown", MarkdownSplitter::new(20));
+/// let text = "# Header\n\nfrom a document";
+///
+/// assert_eq!(
+///     vec!["# Header", "from a document"],
+///     router.chunks("text/markdown; charset=utf-8", text).collect::<Vec<_>>()
+/// );
+/// assert!(matches!(router.splitter("text/plain"), DocumentSplitter::Text(_)));
+/// ```
+#[derive(Debug)]
+pub struct SplitterRouter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Splitter to use for each normalized content type
+    splitters: AHashMap<String, DocumentSplitter<Sizer>>,
+    /// Splitter to use for any other content type
+    fallback: DocumentSplitter<Sizer>,
+}
+
+impl<Sizer> SplitterRouter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Creates a new [`SplitterRouter`], which uses the given splitter for
+    /// any content type that doesn't have a splitter of its own.
+    ///
+    /// ```
+    /// use text_splitter::{SplitterRouter, TextSplitter};
+    ///
+    /// let router = SplitterRouter::new(TextSplitter::new(512));
+    /// ```
+    #[must_use]
+    pub fn new(fallback: impl Into<DocumentSplitter<Sizer>>) -> Self {
+        Self {
+            splitters: AHashMap::new(),
+            fallback: fallback.into(),
+        }
+    }
+
+    /// Use the given splitter for text of the given content type. Replaces
+    /// any splitter previously set for the same content type.
+    #[must_use]
+    pub fn with_splitter(
+        mut self,
+        content_type: &str,
+        splitter: impl Into<DocumentSplitter<Sizer>>,
+    ) -> Self {
+        self.splitters
+            .insert(normalize(content_type), splitter.into());
+        self
+    }
+
+    /// The splitter that will be used for text of the given content type.
+    #[must_use]
+    pub fn splitter(&self, content_type: &str) -> &DocumentSplitter<Sizer> {
+        self.splitters
+            .get(&normalize(content_type))
+            .unwrap_or(&self.fallback)
+    }
+
+    /// Generate a list of chunks from a given text, using the splitter for
+    /// its content type. Each chunk will be up to the `chunk_capacity`.
+    pub fn chunks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        content_type: &str,
+        text: &'text str,
+    ) -> Box<dyn Iterator<Item = &'text str> + 'splitter> {
+        Box::new(self.chunk_indices(content_type, text).map(|(_, t)| t))
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets,
+    /// using the splitter for its content type. Each chunk will be up to the
+    /// `chunk_capacity`.
+    pub fn chunk_indices<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        content_type: &str,
+        text: &'text str,
+    ) -> Box<dyn Iterator<Item = (usize, &'text str)> + 'splitter> {
+        self.splitter(content_type).chunk_indices(text)
+    }
+}
+
+/// Lowercase the content type and remove any parameters, so that
+/// `Text/Markdown; charset=utf-8` matches `text/markdown`.
+fn normalize(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+impl<Sizer> From<TextSplitter<Sizer>> for DocumentSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    fn from(splitter: TextSplitter<Sizer>) -> Self {
+        Self::Text(splitter)
+    }
+}
+
+#[cfg(feature = "markdown")]
+impl<Sizer> From<MarkdownSplitter<Sizer>> for DocumentSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    fn from(splitter: MarkdownSplitter<Sizer>) -> Self {
+        Self::Markdown(splitter)
+    }
+}
+
+#[cfg(feature = "code")]
+impl<Sizer> From<CodeSplitter<Sizer>> for DocumentSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    fn from(splitter: CodeSplitter<Sizer>) -> Self {
+        Self::Code(splitter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_content_types() {
+        assert_eq!(normalize("Text/Markdown; charset=UTF-8"), "text/markdown");
+        assert_eq!(normalize(" text/plain "), "text/plain");
+        assert_eq!(normalize(""), "");
+    }
+
+    #[test]
+    fn unknown_content_types_use_fallback() {
+        let router = SplitterRouter::new(TextSplitter::new(10));
+        let text = "Some text\n\nfrom a\ndocument";
+
+        assert_eq!(
+            router.chunk_indices("text/html", text).collect::<Vec<_>>(),
+            TextSplitter::new(10)
+                .chunk_indices(text)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[cfg(feature = "code")]
+    #[test]
+    fn routes_to_code_splitter() {
+        let router = SplitterRouter::new(TextSplitter::new(10)).with_splitter(
+            "text/x-rust",
+            CodeSplitter::new(tree_sitter_rust::LANGUAGE, 10).unwrap(),
+        );
+
+        assert!(matches!(
+            router.splitter("TEXT/X-RUST"),
+            DocumentSplitter::Code(_)
+        ));
+        assert!(matches!(
+            router.splitter("text/x-python"),
+            DocumentSplitter::Text(_)
+        ));
+    }
+}
@@ -0,0 +1,247 @@
+/*!
+# Chunking service
+
+A ready-made [axum](https://docs.rs/axum) router for running chunking as an
+HTTP service. `POST /chunks` accepts a JSON [`ChunkRequest`] with the text
+and its configuration, and returns the chunks as a JSON
+[`ChunkingRun`](crate::output::ChunkingRun).
+
+```
+let app = axum::Router::new().nest("/v1", text_splitter::service::router());
+```
+
+A request looks like:
+
+```json
+{
+  "text": "Some text\n\nfrom a\ndocument",
+  "config": { "capacity": 10, "max_capacity": 20, "overlap": 2, "format": "markdown" }
+}
+```
+
+Only `capacity` is required within the `config`. Chunk sizes are measured in
+characters. The router can be served with `axum::serve`, or merged into a
+larger application and have layers like authentication or a different body
+size limit added to it, like any other axum router. Axum limits request
+bodies to 2 MB by default.
+*/
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    output::ChunkingRun, Characters, ChunkCapacity, ChunkCapacityError, ChunkConfig,
+    ChunkConfigError, DocumentError, DocumentFormat, DocumentSplitter,
+};
+
+/// A request to chunk a text.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChunkRequest {
+    /// The text to chunk
+    pub text: String,
+    /// How to chunk the text
+    pub config: ChunkRequestConfig,
+}
+
+/// Configuration of a [`ChunkRequest`], mirroring the options of a
+/// [`ChunkConfig`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChunkRequestConfig {
+    /// Desired chunk capacity, in characters
+    pub capacity: usize,
+    /// Max chunk capacity, in characters. Defaults to the desired capacity.
+    #[serde(default)]
+    pub max_capacity: Option<usize>,
+    /// Overlap between chunks, in characters. Defaults to 0.
+    #[serde(default)]
+    pub overlap: usize,
+    /// Whether to trim whitespace from each chunk. Defaults to `true`.
+    #[serde(default = "default_trim")]
+    pub trim: bool,
+    /// Format of the text, which determines the splitter. Defaults to plain
+    /// text.
+    #[serde(default)]
+    pub format: ChunkRequestFormat,
+}
+
+fn default_trim() -> bool {
+    true
+}
+
+/// Format of the text of a [`ChunkRequest`].
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ChunkRequestFormat {
+    /// Plain text, split with a [`TextSplitter`](crate::TextSplitter)
+    #[default]
+    Text,
+    /// Markdown, split with a [`MarkdownSplitter`](crate::MarkdownSplitter)
+    #[cfg(feature = "markdown")]
+    Markdown,
+}
+
+impl From<ChunkRequestFormat> for DocumentFormat {
+    fn from(format: ChunkRequestFormat) -> Self {
+        match format {
+            ChunkRequestFormat::Text => Self::Text,
+            #[cfg(feature = "markdown")]
+            ChunkRequestFormat::Markdown => Self::Markdown,
+        }
+    }
+}
+
+/// Indicates the configuration of a [`ChunkRequest`] was invalid. Responds
+/// with a `422 Unprocessable Entity` status, and a JSON body with a
+/// human-readable message in its `error` field.
+#[derive(Error, Debug)]
+#[error(transparent)]
+#[allow(clippy::module_name_repetitions)]
+pub struct ServiceError(#[from] ServiceErrorRepr);
+
+/// Private error and free to change across minor version of the crate.
+#[derive(Error, Debug)]
+enum ServiceErrorRepr {
+    #[error(transparent)]
+    Capacity(#[from] ChunkCapacityError),
+    #[error(transparent)]
+    Config(#[from] ChunkConfigError),
+    #[error(transparent)]
+    Document(#[from] DocumentError),
+}
+
+impl IntoResponse for ServiceError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({ "error": self.to_string() });
+        (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response()
+    }
+}
+
+/// Router for the chunking service, with a `POST /chunks` route handled by
+/// [`chunk`].
+pub fn router() -> Router {
+    Router::new().route("/chunks", post(chunk))
+}
+
+/// Handler that chunks the text of a [`ChunkRequest`] according to its
+/// configuration.
+///
+/// # Errors
+///
+/// Will return an error if the configuration is invalid, such as a max
+/// capacity that is less than the desired capacity, or an overlap that isn't
+/// smaller than the desired capacity.
+#[allow(clippy::unused_async)]
+pub async fn chunk(Json(request): Json<ChunkRequest>) -> Result<Json<ChunkingRun>, ServiceError> {
+    let splitter = DocumentSplitter::new(
+        &request.config.format.into(),
+        request.config.chunk_config()?,
+    )
+    .map_err(ServiceErrorRepr::Document)?;
+
+    let text = &request.text;
+    Ok(Json(ChunkingRun::from_untrimmed_ranges(
+        text,
+        splitter.chunks_with_untrimmed_ranges(text),
+        splitter.chunk_config(),
+    )))
+}
+
+impl ChunkRequestConfig {
+    /// Build the [`ChunkConfig`] for this request.
+    fn chunk_config(&self) -> Result<ChunkConfig<Characters>, ServiceError> {
+        let capacity = ChunkCapacity::new(self.capacity)
+            .with_max(self.max_capacity.unwrap_or(self.capacity))
+            .map_err(ServiceErrorRepr::Capacity)?;
+        Ok(ChunkConfig::new(capacity)
+            .with_overlap(self.overlap)
+            .map_err(ServiceErrorRepr::Config)?
+            .with_trim(self.trim))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::Request};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn post(body: serde_json::Value) -> (StatusCode, serde_json::Value) {
+        let response = router()
+            .oneshot(
+                Request::post("/chunks")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn chunks_text() {
+        let (status, body) = post(serde_json::json!({
+            "text": "Some text\n\nfrom a\ndocument",
+            "config": { "capacity": 10 }
+        }))
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        let run: ChunkingRun = serde_json::from_value(body).unwrap();
+        assert_eq!(
+            run.chunks
+                .iter()
+                .map(|c| c.text.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Some text", "from a", "document"]
+        );
+        assert_eq!(run.max_capacity, 10);
+    }
+
+    #[cfg(feature = "markdown")]
+    #[tokio::test]
+    async fn chunks_markdown() {
+        let (status, body) = post(serde_json::json!({
+            "text": "# Header\n\nfrom a document",
+            "config": { "capacity": 20, "format": "markdown" }
+        }))
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        let run: ChunkingRun = serde_json::from_value(body).unwrap();
+        assert_eq!(
+            run.chunks
+                .iter()
+                .map(|c| c.text.as_str())
+                .collect::<Vec<_>>(),
+            vec!["# Header", "from a document"]
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_config_is_unprocessable() {
+        let (status, body) = post(serde_json::json!({
+            "text": "Some text",
+            "config": { "capacity": 10, "overlap": 10 }
+        }))
+        .await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            body["error"],
+            "The overlap is larger than or equal to the desired chunk capacity"
+        );
+    }
+}
@@ -1,27 +1,81 @@
-use std::{cmp::Ordering, fmt, iter::once, ops::Range};
-
+use std::{
+    cmp::Ordering,
+    fmt,
+    iter::{from_fn, once, Peekable},
+    ops::Range,
+};
+
+use ahash::AHashMap;
 use either::Either;
 use itertools::Itertools;
 use strum::IntoEnumIterator;
 
-use self::fallback::FallbackLevel;
-use crate::{chunk_size::MemoizedChunkSizer, trim::Trim, ChunkCapacity, ChunkConfig, ChunkSizer};
+pub(crate) use self::fallback::SENTENCE_SEGMENTER;
+use self::fallback::{first_grapheme_chars, FallbackLevel};
+use crate::{
+    chunk_size::MemoizedChunkSizer, redaction::Redactions, trim::Trim, BudgetExhausted,
+    ChunkCapacity, ChunkConfig, ChunkSizer, ChunkTemplate, ChunkTemplateError, ChunkingBudget,
+    ChunkingVersion, OffsetMap, SentenceBoundaries, SyntaxSpan, TieBreaking,
+};
 
+mod bibtex;
+mod clause;
 #[cfg(feature = "code")]
 mod code;
+mod config;
+mod custom;
+mod diff;
 mod fallback;
+mod fixed_width;
+mod line;
 #[cfg(feature = "markdown")]
 mod markdown;
+mod schema;
+mod sql;
 mod text;
+mod transcript;
+#[cfg(feature = "typst")]
+mod typst;
+mod vobject;
+mod wikitext;
 
+#[allow(clippy::module_name_repetitions)]
+pub use bibtex::{BibtexEntry, BibtexSplitter};
+#[allow(clippy::module_name_repetitions)]
+pub use clause::ClauseSplitter;
 #[cfg(feature = "code")]
 #[allow(clippy::module_name_repetitions)]
-pub use code::{CodeSplitter, CodeSplitterError};
+pub use code::{CodeChunkMetadata, CodeSplitter, CodeSplitterError, CodeSplitterErrorKind};
+#[allow(clippy::module_name_repetitions)]
+pub use config::{ConfigFormat, ConfigSplitter};
+#[allow(clippy::module_name_repetitions)]
+pub use custom::CustomSplitter;
+#[allow(clippy::module_name_repetitions)]
+pub use diff::{DiffFile, DiffHunk, DiffSplitter};
+#[allow(clippy::module_name_repetitions)]
+pub use fixed_width::{FixedWidthSplitter, RecordLength};
+#[allow(clippy::module_name_repetitions)]
+pub use line::LineSplitter;
 #[cfg(feature = "markdown")]
 #[allow(clippy::module_name_repetitions)]
-pub use markdown::MarkdownSplitter;
+pub use markdown::{
+    Element as MarkdownElement, HeadingLevel, MarkdownBlock, MarkdownChunkMetadata,
+    MarkdownSplitter,
+};
+pub use schema::SchemaLanguage;
+#[allow(clippy::module_name_repetitions)]
+pub use sql::SqlSplitter;
 #[allow(clippy::module_name_repetitions)]
 pub use text::TextSplitter;
+#[allow(clippy::module_name_repetitions)]
+pub use transcript::{TranscriptChunkMetadata, TranscriptSplitter, TranscriptSplitterError};
+#[cfg(feature = "typst")]
+#[allow(clippy::module_name_repetitions)]
+pub use typst::TypstSplitter;
+#[allow(clippy::module_name_repetitions)]
+pub use vobject::{VObjectRecord, VObjectSplitter};
+#[allow(clippy::module_name_repetitions)]
+pub use wikitext::WikitextSplitter;
 
 /// Shared interface for splitters that can generate chunks of text based on the
 /// associated semantic level.
@@ -40,6 +94,223 @@ where
     /// Generate a list of offsets for each semantic level within the text.
     fn parse(&self, text: &str) -> Vec<(Self::Level, Range<usize>)>;
 
+    /// Ranges within the text that should never be split internally, even by
+    /// the fallback levels. Chunks will only start or end outside of these.
+    fn atomic_ranges(&self, _text: &str) -> Vec<Range<usize>> {
+        vec![]
+    }
+
+    /// The same ranges as `parse`, in order and without overlap, if they can
+    /// be found lazily while chunking instead of all up front.
+    fn lazy_ranges<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        _text: &'text str,
+    ) -> Option<RangeIter<'splitter, Self::Level>> {
+        None
+    }
+
+    /// Titles of the headings that the text after each offset is under, for
+    /// the breadcrumbs of a chunk template, if the format has headings.
+    fn heading_trails(&self, _text: &str) -> Vec<(usize, Vec<String>)> {
+        vec![]
+    }
+
+    /// Whether neighboring chunks are merged by `merge_chunks`, in which case
+    /// all chunks of the text need to be generated before any are returned.
+    fn merges_chunks(&self) -> bool {
+        false
+    }
+
+    /// Merge neighboring chunks in a second pass, once all chunks of the text
+    /// have been generated, if the splitter is configured to. `fits` is
+    /// whether the chunk at the given offset is small enough to be merged.
+    fn merge_chunks<'text>(
+        &self,
+        _text: &'text str,
+        chunks: Vec<(usize, &'text str)>,
+        _fits: &dyn Fn(usize, &str) -> bool,
+    ) -> Vec<(usize, &'text str)> {
+        chunks
+    }
+
+    /// Same as `merge_chunks`, along with some data for each chunk, which is
+    /// combined with `combine` for the chunks that were merged into one.
+    fn merge_chunks_with<'text, T>(
+        &self,
+        text: &'text str,
+        chunks: Vec<(usize, &'text str, T)>,
+        fits: &dyn Fn(usize, &str) -> bool,
+        combine: impl Fn(Vec<T>) -> T,
+    ) -> Vec<(usize, &'text str, T)> {
+        let merged = self.merge_chunks(
+            text,
+            chunks
+                .iter()
+                .map(|(offset, chunk, _)| (*offset, *chunk))
+                .collect(),
+            fits,
+        );
+        let mut chunks = chunks.into_iter().peekable();
+        merged
+            .into_iter()
+            .map(|(offset, chunk)| {
+                let end = offset + chunk.len();
+                let data = chunks
+                    .peeking_take_while(|(start, chunk, _)| start + chunk.len() <= end)
+                    .map(|(_, _, data)| data)
+                    .collect();
+                (offset, chunk, combine(data))
+            })
+            .collect()
+    }
+
+    /// Comments and strings in the text, sorted by offset and without
+    /// overlap, for sizers that weigh them differently, if the format has
+    /// them.
+    fn syntax_spans(&self, _text: &str) -> Vec<SyntaxSpan> {
+        vec![]
+    }
+
+    /// The `syntax_spans` of the text, only if the chunk sizer takes them
+    /// into account, since finding them can be expensive.
+    fn sized_syntax_spans(&self, text: &str) -> Vec<SyntaxSpan> {
+        if self.chunk_config().sizer().syntax_sizer().is_some() {
+            self.syntax_spans(text)
+        } else {
+            vec![]
+        }
+    }
+
+    /// Whether to use the `lazy_ranges` for all chunking, if there are any.
+    fn parse_lazily(&self) -> bool {
+        false
+    }
+
+    /// Iterator over the chunks of the text, parsing it lazily if `lazy` is
+    /// set and the splitter supports it. Parsing is skipped if the whole text
+    /// fits in a single chunk.
+    fn text_chunks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+        lazy: bool,
+    ) -> TextChunks<'text, 'splitter, Sizer, Self::Level> {
+        let syntax_spans = self.sized_syntax_spans(text);
+        let mut chunks = TextChunks::new(self.chunk_config(), text, vec![], vec![], Self::TRIM)
+            .with_syntax_spans(syntax_spans.clone());
+        if chunks.fit_whole_text() {
+            chunks
+        } else {
+            self.parsed_text_chunks_with_syntax_spans(text, lazy, syntax_spans)
+        }
+    }
+
+    /// Iterator over the chunks of the text, parsing it lazily if `lazy` is
+    /// set and the splitter supports it, even if the whole text would fit in
+    /// a single chunk.
+    fn parsed_text_chunks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+        lazy: bool,
+    ) -> TextChunks<'text, 'splitter, Sizer, Self::Level> {
+        self.parsed_text_chunks_with_syntax_spans(text, lazy, self.sized_syntax_spans(text))
+    }
+
+    /// Same as `parsed_text_chunks`, with the `sized_syntax_spans` of the
+    /// text already found, so they aren't found again.
+    fn parsed_text_chunks_with_syntax_spans<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+        lazy: bool,
+        syntax_spans: Vec<SyntaxSpan>,
+    ) -> TextChunks<'text, 'splitter, Sizer, Self::Level> {
+        match lazy.then(|| self.lazy_ranges(text)).flatten() {
+            Some(ranges) => TextChunks::new(
+                self.chunk_config(),
+                text,
+                vec![],
+                self.atomic_ranges(text),
+                Self::TRIM,
+            )
+            .with_lazy_ranges(ranges),
+            None => TextChunks::new(
+                self.chunk_config(),
+                text,
+                self.parse(text),
+                self.atomic_ranges(text),
+                Self::TRIM,
+            ),
+        }
+        .with_syntax_spans(syntax_spans)
+    }
+
+    /// Chunks of the text rendered with the template, along with the range of
+    /// the original text each chunk came from. The `{breadcrumbs}` of a chunk
+    /// are the titles of the headings it is under, separated by ` > ` and
+    /// followed by `breadcrumbs_suffix`, or empty if there are no headings.
+    ///
+    /// Breadcrumbs are shortened until they are at most half of the max
+    /// capacity in size. Since the breadcrumbs would otherwise split a
+    /// section that fits in a chunk by itself, they are shortened further,
+    /// or left out, for a chunk that doesn't fit with them.
+    fn templated_chunks(
+        &self,
+        text: &str,
+        template: &ChunkTemplate,
+        breadcrumbs_suffix: &str,
+    ) -> Vec<MappedChunk> {
+        let sizer = self.chunk_config().sizer();
+        let max = self.chunk_config().capacity().max();
+        let budget = max / 2;
+        let trails = once((0, vec![]))
+            .chain(self.heading_trails(text))
+            .collect::<Vec<_>>();
+        let trail_at =
+            |offset: usize| &trails[trails.partition_point(|(start, _)| *start <= offset) - 1].1;
+        let breadcrumbs = |titles: &[String], budget: usize| {
+            breadcrumbs(titles, breadcrumbs_suffix, sizer, budget)
+        };
+        // There can't be more chunks than bytes, so no index is wider than this
+        let widest_index = text.len();
+        let prefix_sizes = trails
+            .iter()
+            .map(|(offset, titles)| {
+                let breadcrumbs = breadcrumbs(titles, budget);
+                let size = sizer.size(&template.render("", widest_index, &breadcrumbs));
+                (*offset, size)
+            })
+            .collect::<Vec<_>>();
+        let min_prefix_size = sizer.size(&template.render("", widest_index, ""));
+        let prefix_size = |offset: usize| {
+            prefix_sizes[prefix_sizes.partition_point(|(start, _)| *start <= offset) - 1].1
+        };
+        // A merged chunk needs to fit along with the rest of the template
+        let fits = |offset: usize, chunk: &str| {
+            prefix_size(offset) + sizer.size(chunk) <= self.chunk_config().capacity().desired()
+        };
+
+        let chunks = self
+            .parsed_text_chunks(text, false)
+            .with_prefix_sizes(prefix_sizes.clone(), min_prefix_size)
+            .collect();
+        self.merge_chunks(text, chunks, &fits)
+            .into_iter()
+            .enumerate()
+            .map(|(index, (offset, chunk))| {
+                let titles = trail_at(offset);
+                let mut rendered = template.render(chunk, index, &breadcrumbs(titles, budget));
+                if sizer.size(&rendered) > max {
+                    let rest = sizer.size(&template.render(chunk, index, ""));
+                    let budget = budget.min(max.saturating_sub(rest));
+                    rendered = template.render(chunk, index, &breadcrumbs(titles, budget));
+                }
+                MappedChunk {
+                    range: offset..offset + chunk.len(),
+                    text: rendered,
+                }
+            })
+            .collect()
+    }
+
     /// Returns an iterator over chunks of the text and their byte offsets.
     /// Each chunk will be up to the max size of the `ChunkConfig`.
     fn chunk_indices<'splitter, 'text: 'splitter>(
@@ -49,12 +320,13 @@ where
     where
         Sizer: 'splitter,
     {
-        TextChunks::<Sizer, Self::Level>::new(
-            self.chunk_config(),
-            text,
-            self.parse(text),
-            Self::TRIM,
-        )
+        let chunks = self.text_chunks(text, self.parse_lazily());
+        if self.merges_chunks() {
+            let fits = fits_desired(self.chunk_config());
+            Either::Left(self.merge_chunks(text, chunks.collect(), &fits).into_iter())
+        } else {
+            Either::Right(chunks)
+        }
     }
 
     /// Generate a list of chunks from a given text.
@@ -70,12 +342,1424 @@ where
     }
 }
 
+/// Methods that all splitters have in common, in addition to their own
+/// `chunks` and `chunk_indices`, such as for chunking only part of a text,
+/// adding metadata to chunks, or inspecting the ranges a splitter finds.
+/// Import the trait to use them with any of the splitters.
+///
+/// ```
+/// use text_splitter::{SplitterExt, TextSplitter};
+///
+/// let splitter = TextSplitter::new(10);
+/// let text = "Some text\n\nfrom a\ndocument";
+/// let chunks = splitter.tail_chunks(text, 1);
+///
+/// assert_eq!(vec![(18, "document")], chunks);
+/// ```
+pub trait SplitterExt<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Returns an iterator over chunks of the text and their byte offsets,
+    /// like `chunk_indices`, but reuses the buffers of a [`SplitterSession`]
+    /// instead of allocating new ones. Useful when chunking many documents in
+    /// a row with the same session.
+    ///
+    /// See the `chunks` method of the splitter for more information.
+    ///
+    /// ```
+    /// use text_splitter::{SplitterExt, SplitterSession, TextSplitter};
+    ///
+    /// let splitter = TextSplitter::new(10);
+    /// let mut session = SplitterSession::new();
+    ///
+    /// for text in ["Some text\n\nfrom a\ndocument", "Another document"] {
+    ///     let chunks = splitter
+    ///         .chunk_indices_with_session(text, &mut session)
+    ///         .collect::<Vec<_>>();
+    ///     assert_eq!(splitter.chunk_indices(text).collect::<Vec<_>>(), chunks);
+    /// }
+    /// ```
+    fn chunk_indices_with_session<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+        session: &'splitter mut SplitterSession,
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter
+    where
+        Sizer: 'splitter;
+
+    /// Returns an iterator over the first `n` chunks of the text and their
+    /// byte offsets, the same as `chunk_indices(text).take(n)`. Useful for
+    /// previews of large texts.
+    ///
+    /// If the splitter can parse the text lazily, only as much of the text is
+    /// parsed as is needed for those chunks. The [`TextSplitter`] finds
+    /// newlines lazily for this even without
+    /// [`TextSplitter::with_lazy_parsing`]. Otherwise the whole text is still
+    /// parsed, but no chunks are generated after the first `n`.
+    ///
+    /// See the `chunks` method of the splitter for more information.
+    ///
+    /// ```
+    /// use text_splitter::{SplitterExt, TextSplitter};
+    ///
+    /// let splitter = TextSplitter::new(10);
+    /// let text = "Some text\n\nfrom a\ndocument";
+    /// let chunks = splitter.take_chunks(text, 2).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec![(0, "Some text"), (11, "from a")], chunks);
+    /// ```
+    fn take_chunks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+        n: usize,
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter
+    where
+        Sizer: 'splitter;
+
+    /// Returns the last `n` chunks of the text and their byte offsets, without
+    /// chunking the whole text. Useful for the most recent part of a long log
+    /// or transcript.
+    ///
+    /// Chunking starts from a semantic boundary far enough from the end of
+    /// the text to produce `n` chunks, preferring the highest semantic level.
+    /// The chunks are the same as chunking the text from that boundary, which
+    /// usually, but not always, match the last chunks of `chunk_indices`.
+    ///
+    /// See the `chunks` method of the splitter for more information.
+    ///
+    /// ```
+    /// use text_splitter::{SplitterExt, TextSplitter};
+    ///
+    /// let splitter = TextSplitter::new(10);
+    /// let text = "Some text\n\nfrom a\ndocument";
+    /// let chunks = splitter.tail_chunks(text, 2);
+    ///
+    /// assert_eq!(vec![(11, "from a"), (18, "document")], chunks);
+    /// ```
+    #[must_use]
+    fn tail_chunks<'text>(&self, text: &'text str, n: usize) -> Vec<(usize, &'text str)>;
+
+    /// Returns an iterator over chunks of the text and their byte offsets,
+    /// along with any diagnostics about the chunk, such as a chunk that is
+    /// larger than the max capacity because it couldn't be split any further.
+    ///
+    /// See the `chunks` method of the splitter for more information.
+    ///
+    /// ```
+    /// use text_splitter::{ChunkConfig, ChunkDiagnostic, ChunkSizer, SplitterExt, TextSplitter};
+    ///
+    /// // Measure chunks in bytes
+    /// struct Bytes;
+    ///
+    /// impl ChunkSizer for Bytes {
+    ///     fn size(&self, chunk: &str) -> usize {
+    ///         chunk.len()
+    ///     }
+    /// }
+    ///
+    /// let splitter = TextSplitter::new(ChunkConfig::new(1).with_sizer(Bytes));
+    /// let text = "aé";
+    /// let chunks = splitter.chunks_with_diagnostics(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         (0, "a", vec![]),
+    ///         (1, "é", vec![ChunkDiagnostic::Oversized { size: 2, max: 1 }]),
+    ///     ],
+    ///     chunks
+    /// );
+    /// ```
+    fn chunks_with_diagnostics<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str, Vec<ChunkDiagnostic>)> + 'splitter
+    where
+        Sizer: 'splitter;
+
+    /// Returns an iterator over chunks of the text and their byte offsets,
+    /// along with the byte range of each chunk before surrounding whitespace
+    /// was trimmed, such as for highlighting the exact span of a chunk in the
+    /// original document.
+    ///
+    /// See the `chunks` method of the splitter for more information.
+    ///
+    /// ```
+    /// use text_splitter::{ChunkConfig, SplitterExt, TextSplitter};
+    ///
+    /// let splitter = TextSplitter::new(10);
+    /// let text = "  Some text\n\nfrom a document ";
+    /// let chunks = splitter.chunks_with_untrimmed_ranges(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(
+    ///     vec![(2, "Some text", 0..13), (13, "from a", 13..20), (20, "document", 20..29)],
+    ///     chunks
+    /// );
+    /// ```
+    fn chunks_with_untrimmed_ranges<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str, Range<usize>)> + 'splitter
+    where
+        Sizer: 'splitter;
+
+    /// Returns all of the chunks of the text and their byte offsets, along
+    /// with [`ChunkingCounters`] of the work done to generate them, such as
+    /// the number of calls to the chunk sizer. Useful for understanding how
+    /// chunking scales with a given chunk sizer, and for tuning the capacity.
+    ///
+    /// See the `chunks` method of the splitter for more information.
+    ///
+    /// ```
+    /// use text_splitter::{SplitterExt, TextSplitter};
+    ///
+    /// let splitter = TextSplitter::new(10);
+    /// let text = "Some text\n\nfrom a\ndocument";
+    /// let (chunks, counters) = splitter.chunk_indices_with_counters(text);
+    ///
+    /// assert_eq!(vec![(0, "Some text"), (11, "from a"), (18, "document")], chunks);
+    /// assert_eq!(3, counters.chunks);
+    /// assert!(counters.sizer_calls <= counters.size_lookups);
+    /// ```
+    #[must_use]
+    fn chunk_indices_with_counters<'text>(
+        &self,
+        text: &'text str,
+    ) -> (Vec<(usize, &'text str)>, ChunkingCounters);
+
+    /// Returns all of the chunks of the text and their byte offsets, unless
+    /// the [`ChunkingBudget`] is exhausted first, such as because its deadline
+    /// passed. The budget is checked before each chunk is generated.
+    ///
+    /// See the `chunks` method of the splitter for more information.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use text_splitter::{ChunkingBudget, SplitterExt, TextSplitter};
+    ///
+    /// let splitter = TextSplitter::new(10);
+    /// let text = "Some text\n\nfrom a\ndocument";
+    /// let budget = ChunkingBudget::new().with_deadline(Duration::from_secs(1));
+    ///
+    /// let chunks = match splitter.chunk_indices_within_budget(text, &budget) {
+    ///     Ok(chunks) => chunks,
+    ///     // Fall back to a cheaper splitter for the rest of the text
+    ///     Err(exhausted) => {
+    ///         let offset = exhausted.offset();
+    ///         let mut chunks = exhausted.into_chunks();
+    ///         chunks.extend(
+    ///             TextSplitter::new(10)
+    ///                 .chunk_indices(&text[offset..])
+    ///                 .map(|(o, chunk)| (offset + o, chunk)),
+    ///         );
+    ///         chunks
+    ///     }
+    /// };
+    ///
+    /// assert_eq!(vec![(0, "Some text"), (11, "from a"), (18, "document")], chunks);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return an error with the chunks generated so far if the budget
+    /// is exhausted before all chunks were generated.
+    fn chunk_indices_within_budget<'text>(
+        &self,
+        text: &'text str,
+        budget: &ChunkingBudget,
+    ) -> Result<Vec<(usize, &'text str)>, BudgetExhausted<'text>>;
+
+    /// Returns an iterator over chunks of the text and their byte offsets,
+    /// which calls `on_progress` with the [`ChunkProgress`] after each chunk,
+    /// such as for showing a progress bar. It is called once more at the end
+    /// if trailing whitespace was trimmed from the last chunk, so the bytes
+    /// processed always reach the length of the text.
+    ///
+    /// See the `chunks` method of the splitter for more information.
+    ///
+    /// ```
+    /// use text_splitter::{SplitterExt, TextSplitter};
+    ///
+    /// let splitter = TextSplitter::new(10);
+    /// let text = "Some text\n\nfrom a\ndocument";
+    /// let mut progress = vec![];
+    /// let chunks = splitter
+    ///     .chunk_indices_with_progress(text, |p| progress.push(p.bytes_processed))
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec![(0, "Some text"), (11, "from a"), (18, "document")], chunks);
+    /// assert_eq!(vec![9, 17, 26], progress);
+    /// ```
+    fn chunk_indices_with_progress<'splitter, 'text: 'splitter, F>(
+        &'splitter self,
+        text: &'text str,
+        on_progress: F,
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter
+    where
+        Sizer: 'splitter,
+        F: FnMut(ChunkProgress) + 'splitter;
+
+    /// Split a text containing several documents, separated by `delimiter`,
+    /// and return an iterator over the chunks of each document. Each item is
+    /// the index of the document, the byte offset of the chunk within the
+    /// whole text, and the chunk itself.
+    ///
+    /// Each document is chunked independently, so a chunk never contains text
+    /// from more than one document.
+    ///
+    /// ```
+    /// use text_splitter::{SplitterExt, TextSplitter};
+    ///
+    /// let splitter = TextSplitter::new(20);
+    /// let text = "First document\n===DOC===\nSecond document";
+    /// let chunks = splitter
+    ///     .chunk_indices_by_document(text, "\n===DOC===\n")
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec![(0, 0, "First document"), (1, 25, "Second document")], chunks);
+    /// ```
+    fn chunk_indices_by_document<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+        delimiter: &'splitter str,
+    ) -> impl Iterator<Item = (usize, usize, &'text str)> + 'splitter
+    where
+        Sizer: 'splitter;
+
+    /// Returns an iterator over chunks of the text and their byte offsets,
+    /// where chunks always break at the given byte offsets, such as page
+    /// boundaries from a PDF extractor. No chunk crosses a break, which takes
+    /// precedence over every semantic level.
+    ///
+    /// Breaks don't need to be sorted. Breaks past the end of the text are
+    /// ignored, and breaks within a character are moved to its start.
+    ///
+    /// See the `chunks` method of the splitter for more information.
+    ///
+    /// ```
+    /// use text_splitter::{SplitterExt, TextSplitter};
+    ///
+    /// let splitter = TextSplitter::new(20);
+    /// // Page break after "Some text"
+    /// let text = "Some text from a document";
+    /// let chunks = splitter.chunk_indices_with_breaks(text, &[10]).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec![(0, "Some text"), (10, "from a document")], chunks);
+    /// ```
+    fn chunk_indices_with_breaks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+        breaks: &'splitter [usize],
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter
+    where
+        Sizer: 'splitter;
+
+    /// Returns an iterator over chunks of the text and their byte offsets,
+    /// where chunks never start or end within any of the given byte ranges,
+    /// such as named entities or citations detected upstream.
+    ///
+    /// A protected range is kept in the same chunk as the text around it if
+    /// it fits within the capacity. Otherwise it is put in its own chunk,
+    /// which may be larger than the max capacity.
+    /// Ranges are widened to the nearest char boundaries, and cut off at the
+    /// end of the text, so ranges past the end of the text are ignored.
+    ///
+    /// See the `chunks` method of the splitter for more information.
+    ///
+    /// ```
+    /// use text_splitter::{SplitterExt, TextSplitter};
+    ///
+    /// let splitter = TextSplitter::new(15);
+    /// // Keep the name together
+    /// let text = "Talk to Jane Doe today";
+    /// let chunks = splitter
+    ///     .chunk_indices_with_protected_ranges(text, &[8..16])
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec![(0, "Talk to"), (8, "Jane Doe today")], chunks);
+    /// ```
+    fn chunk_indices_with_protected_ranges<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+        protected: &'splitter [Range<usize>],
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter
+    where
+        Sizer: 'splitter;
+
+    /// Chunks the text with the given byte ranges left out, such as headers,
+    /// footers, or navigation that was detected upstream. Each chunk only
+    /// contains the text that wasn't ignored, and its size is measured without
+    /// the ignored text, but its range is still the range of the original
+    /// text it came from, including any ignored text within it.
+    ///
+    /// Ranges are widened to the nearest character boundaries.
+    ///
+    /// See the `chunks` method of the splitter for more information.
+    ///
+    /// ```
+    /// use text_splitter::{MappedChunk, SplitterExt, TextSplitter};
+    ///
+    /// let splitter = TextSplitter::new(20);
+    /// let text = "Page 1\nSome text\nPage 2\nfrom a document";
+    /// let chunks = splitter.chunks_ignoring_ranges(text, &[0..7, 17..24]);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         MappedChunk { range: 7..16, text: "Some text".to_string() },
+    ///         MappedChunk { range: 24..39, text: "from a document".to_string() },
+    ///     ],
+    ///     chunks
+    /// );
+    /// ```
+    #[must_use]
+    fn chunks_ignoring_ranges(&self, text: &str, ignore: &[Range<usize>]) -> Vec<MappedChunk>;
+
+    /// Returns chunks of the text with each span of the [`Redactions`] replaced
+    /// by its placeholder, along with the byte range of the original text that
+    /// each chunk came from. Chunk sizes are measured on the masked text, so
+    /// chunks stay within the capacity of what is actually sent on, and a
+    /// placeholder is never split across chunks.
+    ///
+    /// See the `chunks` method of the splitter for more information.
+    ///
+    /// ```
+    /// use regex::Regex;
+    /// use text_splitter::{MappedChunk, Redactions, SplitterExt, TextSplitter};
+    ///
+    /// let splitter = TextSplitter::new(20);
+    /// let redactions = Redactions::new("[EMAIL]").with_pattern(Regex::new(r"\S+@\S+").unwrap());
+    /// let text = "Mail jane@example.com\nabout the document";
+    /// let chunks = splitter.chunks_redacting(text, &redactions);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         MappedChunk { range: 0..21, text: "Mail [EMAIL]".to_string() },
+    ///         MappedChunk { range: 22..40, text: "about the document".to_string() },
+    ///     ],
+    ///     chunks
+    /// );
+    /// ```
+    #[must_use]
+    fn chunks_redacting(&self, text: &str, redactions: &Redactions) -> Vec<MappedChunk>;
+
+    /// Returns chunks of the text rendered with the [`ChunkTemplate`], along
+    /// with the byte range of the original text that each chunk came from.
+    /// The size of the rendered template around each chunk counts towards
+    /// the chunk capacity, so the rendered text fits within it.
+    ///
+    /// For formats with headings, such as Markdown, `{breadcrumbs}` are the
+    /// titles of the headings each chunk is under, separated by ` > `. A
+    /// chunk that starts with a heading only has the headings it is nested
+    /// under in its breadcrumbs. Breadcrumbs that would take up more than
+    /// half of the max capacity are shortened the same way as those of
+    /// `MarkdownSplitter::chunks_with_breadcrumbs`, and a sentence or section
+    /// that fits in a chunk by itself isn't split to make room for them.
+    ///
+    /// See the `chunks` method of the splitter for more information.
+    ///
+    /// ```
+    /// use text_splitter::{ChunkTemplate, MappedChunk, SplitterExt, TextSplitter};
+    ///
+    /// let splitter = TextSplitter::new(30);
+    /// let template = ChunkTemplate::new("{chunk} [{doc_id}#{index}]")
+    ///     .unwrap()
+    ///     .with_field("doc_id", "notes.txt");
+    /// let chunks = splitter
+    ///     .chunks_with_template("Some text\n\nfrom a document", &template)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         MappedChunk { range: 0..9, text: "Some text [notes.txt#0]".to_string() },
+    ///         MappedChunk { range: 11..26, text: "from a document [notes.txt#1]".to_string() },
+    ///     ],
+    ///     chunks,
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if a custom field of the template has no value.
+    fn chunks_with_template(
+        &self,
+        text: &str,
+        template: &ChunkTemplate,
+    ) -> Result<Vec<MappedChunk>, ChunkTemplateError>;
+
+    /// Split the text into coarse parent chunks, and each parent into finer
+    /// child chunks, such as for parent-document retrieval. Each child chunk
+    /// is annotated with the index of its parent and its byte offsets.
+    ///
+    /// The text is only parsed once for both levels. Only the given
+    /// configurations are used, not the one of this splitter.
+    ///
+    /// ```
+    /// use text_splitter::{ChildChunk, ChunkConfig, SplitterExt, TextSplitter};
+    ///
+    /// let splitter = TextSplitter::new(512);
+    /// let text = "One. Two.\n\nThree.";
+    /// let chunks = splitter.hierarchical_chunks(text, &ChunkConfig::new(10), &ChunkConfig::new(5));
+    ///
+    /// assert_eq!(vec![(0, "One. Two."), (11, "Three.")], chunks.parents);
+    /// assert_eq!(
+    ///     ChildChunk { parent: 0, offset: 5, parent_offset: 5, text: "Two." },
+    ///     chunks.children[1]
+    /// );
+    /// ```
+    fn hierarchical_chunks<'text, ParentSizer, ChildSizer>(
+        &self,
+        text: &'text str,
+        parent_config: &ChunkConfig<ParentSizer>,
+        child_config: &ChunkConfig<ChildSizer>,
+    ) -> HierarchicalChunks<'text>
+    where
+        ParentSizer: ChunkSizer,
+        ChildSizer: ChunkSizer;
+
+    /// Split the text into a tree of chunks, with one level of chunks for each
+    /// of the given configurations, ordered from the largest capacity to the
+    /// smallest. For example, sections, then chunks, then sentences. The root
+    /// node is the whole text, and each chunk is contained within its parent.
+    ///
+    /// The text is only parsed once for all levels. Only the given
+    /// configurations are used, not the one of this splitter.
+    ///
+    /// ```
+    /// use text_splitter::{ChunkConfig, SplitterExt, TextSplitter};
+    ///
+    /// let splitter = TextSplitter::new(512);
+    /// let text = "One. Two.\n\nThree.";
+    /// let tree = splitter.chunk_tree(text, &[ChunkConfig::new(10), ChunkConfig::new(5)]);
+    ///
+    /// assert_eq!(2, tree.children.len());
+    /// assert_eq!("One. Two.", tree.children[0].text);
+    /// assert_eq!("Two.", tree.children[0].children[1].text);
+    /// ```
+    fn chunk_tree<'text, TreeSizer>(
+        &self,
+        text: &'text str,
+        configs: &[ChunkConfig<TreeSizer>],
+    ) -> ChunkNode<'text>
+    where
+        TreeSizer: ChunkSizer;
+
+    /// Returns the semantic ranges that the splitter found in the text, in
+    /// order, such as for inspecting or visualizing the possible chunk
+    /// boundaries. Ranges of higher levels can contain ranges of lower ones.
+    ///
+    /// See [`SemanticRange`] for more information.
+    ///
+    /// ```
+    /// use text_splitter::{SemanticRange, SplitterExt, TextSplitter};
+    ///
+    /// let splitter = TextSplitter::new(512);
+    /// let ranges = splitter.parse_ranges("One\nTwo\n\nThree");
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         SemanticRange { level: 0, name: "LineBreaks(1)".to_string(), range: 3..4 },
+    ///         SemanticRange { level: 1, name: "LineBreaks(2)".to_string(), range: 7..9 },
+    ///     ],
+    ///     ranges
+    /// );
+    /// ```
+    #[must_use]
+    fn parse_ranges(&self, text: &str) -> Vec<SemanticRange>;
+
+    /// Returns the cumulative size of the text at the start of each semantic
+    /// range and at the end of the text, as measured by the chunk sizer,
+    /// without chunking the text. Useful for dashboards, or for planning the
+    /// chunk capacity for a document. Checkpoints can be filtered by level,
+    /// such as to only keep paragraph boundaries.
+    ///
+    /// The size at each checkpoint is the sum of the sizes of the text between
+    /// checkpoints, which can differ slightly from the size of the whole text
+    /// before it for sizers such as tokenizers.
+    ///
+    /// See [`SizeCheckpoint`] for more information.
+    ///
+    /// ```
+    /// use text_splitter::{SizeCheckpoint, SplitterExt, TextSplitter};
+    ///
+    /// let splitter = TextSplitter::new(512);
+    /// let checkpoints = splitter.size_checkpoints("One\nTwo\n\nThree");
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         SizeCheckpoint { offset: 3, level: 0, size: 3 },
+    ///         SizeCheckpoint { offset: 7, level: 1, size: 7 },
+    ///         SizeCheckpoint { offset: 14, level: 2, size: 14 },
+    ///     ],
+    ///     checkpoints
+    /// );
+    /// ```
+    #[must_use]
+    fn size_checkpoints(&self, text: &str) -> Vec<SizeCheckpoint>;
+}
+
+impl<Sizer, T> SplitterExt<Sizer> for T
+where
+    Sizer: ChunkSizer,
+    T: Splitter<Sizer>,
+{
+    fn take_chunks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+        n: usize,
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter
+    where
+        Sizer: 'splitter,
+    {
+        if self.merges_chunks() {
+            Either::Left(self.chunk_indices(text).take(n))
+        } else {
+            Either::Right(self.text_chunks(text, true).take(n))
+        }
+    }
+
+    fn chunk_indices_with_counters<'text>(
+        &self,
+        text: &'text str,
+    ) -> (Vec<(usize, &'text str)>, ChunkingCounters) {
+        let mut chunks = self.text_chunks(text, self.parse_lazily());
+        let indices = chunks.by_ref().collect();
+        let mut counters = chunks.counters();
+        let indices = self.merge_chunks(text, indices, &fits_desired(self.chunk_config()));
+        counters.chunks = indices.len();
+        (indices, counters)
+    }
+
+    fn chunk_indices_within_budget<'text>(
+        &self,
+        text: &'text str,
+        budget: &ChunkingBudget,
+    ) -> Result<Vec<(usize, &'text str)>, BudgetExhausted<'text>> {
+        let mut chunks = self
+            .text_chunks(text, self.parse_lazily())
+            .with_budget(budget);
+        let indices = chunks.by_ref().collect();
+        let indices = self.merge_chunks(text, indices, &fits_desired(self.chunk_config()));
+        if chunks.interrupted {
+            Err(BudgetExhausted::new(indices))
+        } else {
+            Ok(indices)
+        }
+    }
+
+    fn chunk_indices_with_progress<'splitter, 'text: 'splitter, F>(
+        &'splitter self,
+        text: &'text str,
+        mut on_progress: F,
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter
+    where
+        Sizer: 'splitter,
+        F: FnMut(ChunkProgress) + 'splitter,
+    {
+        let mut chunks = self.chunk_indices(text);
+        let mut progress = ChunkProgress {
+            bytes_processed: 0,
+            total_bytes: text.len(),
+            chunks: 0,
+        };
+        let mut finished = false;
+        from_fn(move || {
+            if finished {
+                return None;
+            }
+            let chunk = chunks.next();
+            if let Some((offset, chunk)) = chunk {
+                progress.chunks += 1;
+                progress.bytes_processed = progress.bytes_processed.max(offset + chunk.len());
+            } else {
+                finished = true;
+                // Trimmed whitespace at the end of the text still counts
+                if progress.bytes_processed == progress.total_bytes {
+                    return None;
+                }
+                progress.bytes_processed = progress.total_bytes;
+            }
+            on_progress(progress);
+            chunk
+        })
+    }
+
+    fn tail_chunks<'text>(&self, text: &'text str, n: usize) -> Vec<(usize, &'text str)> {
+        if n == 0 {
+            return vec![];
+        }
+        if self.merges_chunks() {
+            // Which chunks are merged depends on all of the chunks before them
+            let mut chunks = self.chunk_indices(text).collect::<Vec<_>>();
+            return chunks.split_off(chunks.len().saturating_sub(n));
+        }
+
+        let offsets = SemanticSplitRanges::new(self.parse(text)).ranges;
+        let atomic_ranges = self.atomic_ranges(text);
+        let mut window = self.chunk_config().capacity().max().saturating_mul(n);
+        loop {
+            let start = tail_start(text, &offsets, &atomic_ranges, window);
+            let mut chunks = chunks_within(
+                self.chunk_config(),
+                text,
+                start..text.len(),
+                &offsets,
+                &atomic_ranges,
+                Self::TRIM,
+            );
+            if chunks.len() >= n || start == 0 {
+                return chunks.split_off(chunks.len().saturating_sub(n));
+            }
+            // Not enough text for `n` chunks, so look further back
+            window = window.saturating_mul(2).max(1);
+        }
+    }
+
+    fn chunks_with_diagnostics<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str, Vec<ChunkDiagnostic>)> + 'splitter
+    where
+        Sizer: 'splitter,
+    {
+        let mut chunks = self.text_chunks(text, self.parse_lazily());
+        let diagnostics = from_fn(move || {
+            let (offset, chunk) = chunks.next()?;
+            Some((offset, chunk, chunks.diagnostics()))
+        });
+        if !self.merges_chunks() {
+            return Either::Right(diagnostics);
+        }
+
+        let merged = self.merge_chunks_with(
+            text,
+            diagnostics.collect(),
+            &fits_desired(self.chunk_config()),
+            |diagnostics| diagnostics.into_iter().flatten().collect(),
+        );
+        Either::Left(merged.into_iter())
+    }
+
+    fn chunks_with_untrimmed_ranges<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str, Range<usize>)> + 'splitter
+    where
+        Sizer: 'splitter,
+    {
+        let mut chunks = self.text_chunks(text, self.parse_lazily());
+        let spans = from_fn(move || {
+            let (offset, chunk) = chunks.next()?;
+            Some((offset, chunk, chunks.untrimmed_range()))
+        });
+        if !self.merges_chunks() {
+            return Either::Right(spans);
+        }
+
+        // A merged chunk spans from the first to the last chunk merged into it
+        let merged = self.merge_chunks_with(
+            text,
+            spans.collect(),
+            &fits_desired(self.chunk_config()),
+            |ranges| ranges[0].start..ranges[ranges.len() - 1].end,
+        );
+        Either::Left(merged.into_iter())
+    }
+
+    fn chunk_indices_with_session<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+        session: &'splitter mut SplitterSession,
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter
+    where
+        Sizer: 'splitter,
+    {
+        let chunks = self
+            .text_chunks(text, self.parse_lazily())
+            .with_session(session);
+        if self.merges_chunks() {
+            let fits = fits_desired(self.chunk_config());
+            Either::Left(self.merge_chunks(text, chunks.collect(), &fits).into_iter())
+        } else {
+            Either::Right(chunks)
+        }
+    }
+
+    fn chunk_indices_by_document<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+        delimiter: &'splitter str,
+    ) -> impl Iterator<Item = (usize, usize, &'text str)> + 'splitter
+    where
+        Sizer: 'splitter,
+    {
+        split_documents(text, delimiter)
+            .enumerate()
+            .flat_map(move |(index, (offset, document))| {
+                self.chunk_indices(document)
+                    .map(move |(chunk_offset, chunk)| (index, offset + chunk_offset, chunk))
+            })
+    }
+
+    fn chunk_indices_with_breaks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+        breaks: &'splitter [usize],
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter
+    where
+        Sizer: 'splitter,
+    {
+        let offsets = self.parse(text);
+        let atomic_ranges = self.atomic_ranges(text);
+        break_segments(text, breaks)
+            .into_iter()
+            .flat_map(move |within| {
+                let chunks = chunks_within(
+                    self.chunk_config(),
+                    text,
+                    within,
+                    &offsets,
+                    &atomic_ranges,
+                    Self::TRIM,
+                );
+                // Chunks are only merged within each segment
+                self.merge_chunks(text, chunks, &fits_desired(self.chunk_config()))
+            })
+    }
+
+    fn chunk_indices_with_protected_ranges<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+        protected: &'splitter [Range<usize>],
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter
+    where
+        Sizer: 'splitter,
+    {
+        let mut atomic_ranges = self.atomic_ranges(text);
+        atomic_ranges.extend(normalize_ranges(text, protected));
+        let chunks = TextChunks::<Sizer, T::Level>::new(
+            self.chunk_config(),
+            text,
+            self.parse(text),
+            atomic_ranges,
+            Self::TRIM,
+        );
+        if self.merges_chunks() {
+            let fits = fits_desired(self.chunk_config());
+            Either::Left(self.merge_chunks(text, chunks.collect(), &fits).into_iter())
+        } else {
+            Either::Right(chunks)
+        }
+    }
+
+    fn chunks_ignoring_ranges(&self, text: &str, ignore: &[Range<usize>]) -> Vec<MappedChunk> {
+        let (kept, offsets) = OffsetMap::replace(
+            text,
+            normalize_ranges(text, ignore)
+                .into_iter()
+                .map(|range| (range, "")),
+        );
+        self.chunk_indices(&kept)
+            .map(|(offset, chunk)| MappedChunk {
+                range: offsets.original_range(offset..offset + chunk.len()),
+                text: chunk.to_string(),
+            })
+            .collect()
+    }
+
+    fn chunks_redacting(&self, text: &str, redactions: &Redactions) -> Vec<MappedChunk> {
+        let ranges = normalize_ranges(text, &redactions.ranges(text));
+        let (masked, offsets) = OffsetMap::replace(
+            text,
+            ranges
+                .iter()
+                .map(|range| (range.clone(), redactions.placeholder())),
+        );
+        let placeholders = ranges
+            .into_iter()
+            .map(|range| offsets.transformed_range(range))
+            .collect::<Vec<_>>();
+        self.chunk_indices_with_protected_ranges(&masked, &placeholders)
+            .map(|(offset, chunk)| MappedChunk {
+                range: offsets.original_range(offset..offset + chunk.len()),
+                text: chunk.to_string(),
+            })
+            .collect()
+    }
+
+    fn chunks_with_template(
+        &self,
+        text: &str,
+        template: &ChunkTemplate,
+    ) -> Result<Vec<MappedChunk>, ChunkTemplateError> {
+        template.check_fields()?;
+        Ok(self.templated_chunks(text, template, ""))
+    }
+
+    fn parse_ranges(&self, text: &str) -> Vec<SemanticRange> {
+        let ranges = SemanticSplitRanges::new(self.parse(text)).ranges;
+        let levels = ranges
+            .iter()
+            .map(|(level, _)| *level)
+            .sorted()
+            .dedup()
+            .collect::<Vec<_>>();
+        ranges
+            .into_iter()
+            .map(|(level, range)| SemanticRange {
+                level: levels.binary_search(&level).unwrap_or_default(),
+                name: format!("{level:?}"),
+                range,
+            })
+            .collect()
+    }
+
+    fn size_checkpoints(&self, text: &str) -> Vec<SizeCheckpoint> {
+        let ranges = self.parse_ranges(text);
+        let end_level = ranges.iter().map(|r| r.level + 1).max().unwrap_or_default();
+        let sizer = &self.chunk_config().sizer;
+        let mut cursor = 0;
+        let mut size = 0;
+        ranges
+            .into_iter()
+            .map(|r| (r.range.start, r.level))
+            .filter(|(offset, _)| *offset > 0)
+            .chain(once((text.len(), end_level)))
+            .sorted_by_key(|(offset, _)| *offset)
+            .coalesce(|a, b| {
+                if a.0 == b.0 {
+                    Ok((a.0, a.1.max(b.1)))
+                } else {
+                    Err((a, b))
+                }
+            })
+            .map(|(offset, level)| {
+                size += sizer.size(&text[cursor..offset]);
+                cursor = offset;
+                SizeCheckpoint {
+                    offset,
+                    level,
+                    size,
+                }
+            })
+            .collect()
+    }
+
+    fn hierarchical_chunks<'text, ParentSizer, ChildSizer>(
+        &self,
+        text: &'text str,
+        parent_config: &ChunkConfig<ParentSizer>,
+        child_config: &ChunkConfig<ChildSizer>,
+    ) -> HierarchicalChunks<'text>
+    where
+        ParentSizer: ChunkSizer,
+        ChildSizer: ChunkSizer,
+    {
+        let offsets = self.parse(text);
+        let atomic_ranges = self.atomic_ranges(text);
+        let parents = self.merge_chunks(
+            text,
+            chunks_within(
+                parent_config,
+                text,
+                0..text.len(),
+                &offsets,
+                &atomic_ranges,
+                Self::TRIM,
+            ),
+            &fits_desired(parent_config),
+        );
+
+        let children = parents
+            .iter()
+            .enumerate()
+            .flat_map(|(index, &(parent_offset, parent))| {
+                let children = chunks_within(
+                    child_config,
+                    text,
+                    parent_offset..parent_offset + parent.len(),
+                    &offsets,
+                    &atomic_ranges,
+                    Self::TRIM,
+                );
+                self.merge_chunks(text, children, &fits_desired(child_config))
+                    .into_iter()
+                    .map(move |(offset, chunk)| ChildChunk {
+                        parent: index,
+                        offset,
+                        parent_offset: offset - parent_offset,
+                        text: chunk,
+                    })
+            })
+            .collect();
+
+        HierarchicalChunks { parents, children }
+    }
+
+    fn chunk_tree<'text, TreeSizer>(
+        &self,
+        text: &'text str,
+        configs: &[ChunkConfig<TreeSizer>],
+    ) -> ChunkNode<'text>
+    where
+        TreeSizer: ChunkSizer,
+    {
+        let offsets = self.parse(text);
+        let atomic_ranges = self.atomic_ranges(text);
+        ChunkNode {
+            offset: 0,
+            text,
+            children: chunk_nodes(
+                text,
+                0..text.len(),
+                configs,
+                &offsets,
+                &atomic_ranges,
+                Self::TRIM,
+                &|chunks, fits| self.merge_chunks(text, chunks, fits),
+            ),
+        }
+    }
+}
+
+/// The titles joined into breadcrumbs, such as `"Guide > Install"`, followed
+/// by the `suffix`. The longest titles are shortened until it is at most
+/// `budget` in size, and it is empty if there are no titles, or if it doesn't
+/// fit even then.
+fn breadcrumbs(titles: &[String], suffix: &str, sizer: &impl ChunkSizer, budget: usize) -> String {
+    if titles.is_empty() {
+        return String::new();
+    }
+    let mut lengths = titles
+        .iter()
+        .map(|title| title.chars().count())
+        .collect::<Vec<_>>();
+    loop {
+        let breadcrumbs = titles
+            .iter()
+            .zip(&lengths)
+            .map(|(title, &len)| {
+                let shortened = title.chars().take(len).collect::<String>();
+                if shortened.len() < title.len() {
+                    shortened + "…"
+                } else {
+                    shortened
+                }
+            })
+            .join(" > ")
+            + suffix;
+        if sizer.size(&breadcrumbs) <= budget {
+            return breadcrumbs;
+        }
+        match lengths.iter_mut().filter(|len| **len > 0).max() {
+            Some(longest) => *longest /= 2,
+            None => return String::new(),
+        }
+    }
+}
+
+/// Split text into the documents between each `delimiter`, along with the
+/// byte offset of each document. An empty delimiter returns the whole text.
+fn split_documents<'delimiter, 'text: 'delimiter>(
+    text: &'text str,
+    delimiter: &'delimiter str,
+) -> impl Iterator<Item = (usize, &'text str)> + 'delimiter {
+    let mut start = 0;
+    let mut matches = (!delimiter.is_empty())
+        .then(|| text.match_indices(delimiter))
+        .into_iter()
+        .flatten();
+    let mut finished = false;
+    from_fn(move || {
+        if finished {
+            return None;
+        }
+        if let Some((index, delimiter)) = matches.next() {
+            let document = (start, &text[start..index]);
+            start = index + delimiter.len();
+            Some(document)
+        } else {
+            finished = true;
+            Some((start, &text[start..]))
+        }
+    })
+}
+
+/// Generate chunks of only the part of the text that is `within` the given
+/// range, reusing the semantic ranges of the whole text. Offsets are relative
+/// to the whole text.
+fn chunks_within<'text, Sizer, Level>(
+    chunk_config: &ChunkConfig<Sizer>,
+    text: &'text str,
+    within: Range<usize>,
+    offsets: &[(Level, Range<usize>)],
+    atomic_ranges: &[Range<usize>],
+    trim: Trim,
+) -> Vec<(usize, &'text str)>
+where
+    Sizer: ChunkSizer,
+    Level: SemanticLevel,
+{
+    let start = within.start;
+    TextChunks::<Sizer, Level>::new(
+        chunk_config,
+        &text[within.clone()],
+        offsets
+            .iter()
+            .filter_map(|(level, range)| clip_range(range, &within).map(|range| (*level, range)))
+            .collect(),
+        atomic_ranges
+            .iter()
+            .filter_map(|range| clip_range(range, &within))
+            .collect(),
+        trim,
+    )
+    .map(|(offset, chunk)| (start + offset, chunk))
+    .collect()
+}
+
+/// Where to start chunking the text to get chunks for at least the last
+/// `window` bytes. Prefers the start of a range of the highest level within
+/// another `window` bytes before that, so that chunking starts at a strong
+/// semantic boundary, such as a heading or paragraph.
+fn tail_start<Level>(
+    text: &str,
+    offsets: &[(Level, Range<usize>)],
+    atomic_ranges: &[Range<usize>],
+    window: usize,
+) -> usize
+where
+    Level: SemanticLevel,
+{
+    let target = text.len().saturating_sub(window);
+    if target == 0 {
+        return 0;
+    }
+
+    let candidates = offsets
+        .iter()
+        .filter(|(_, range)| range.start > 0 && range.start <= target)
+        .filter(|(_, range)| {
+            !atomic_ranges
+                .iter()
+                .any(|atomic| atomic.start < range.start && range.start < atomic.end)
+        });
+    candidates
+        .clone()
+        .filter(|(_, range)| range.start >= target.saturating_sub(window))
+        .max_by_key(|(level, range)| (*level, range.start))
+        .or_else(|| candidates.max_by_key(|(_, range)| range.start))
+        .map_or(0, |(_, range)| range.start)
+}
+
+/// Ranges of the text between each of the breaks. Breaks are sorted, those
+/// outside of the text are ignored, and those within a character are moved
+/// back to the start of that character.
+fn break_segments(text: &str, breaks: &[usize]) -> Vec<Range<usize>> {
+    let mut breaks = breaks
+        .iter()
+        .filter(|&&offset| offset > 0 && offset < text.len())
+        .map(|&offset| {
+            (0..=offset)
+                .rev()
+                .find(|&offset| text.is_char_boundary(offset))
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    breaks.sort_unstable();
+    breaks.dedup();
+
+    once(0)
+        .chain(breaks)
+        .chain(once(text.len()))
+        .tuple_windows()
+        .map(|(start, end)| start..end)
+        .filter(|range| !range.is_empty())
+        .collect()
+}
+
+/// Sort and merge overlapping or touching ranges, widened to the nearest
+/// character boundaries.
+fn normalize_ranges(text: &str, ranges: &[Range<usize>]) -> Vec<Range<usize>> {
+    ranges
+        .iter()
+        .map(|range| {
+            let start = (0..=range.start.min(text.len()))
+                .rev()
+                .find(|&offset| text.is_char_boundary(offset))
+                .unwrap_or_default();
+            let end = (range.end.min(text.len())..=text.len())
+                .find(|&offset| text.is_char_boundary(offset))
+                .unwrap_or(text.len());
+            start..end
+        })
+        .filter(|range| !range.is_empty())
+        .sorted_unstable_by_key(|range| range.start)
+        .coalesce(|a, b| {
+            if b.start <= a.end {
+                Ok(a.start..a.end.max(b.end))
+            } else {
+                Err((a, b))
+            }
+        })
+        .collect()
+}
+
+/// Recursively generate the nodes of a chunk tree for the text `within` the
+/// given range, with one level for each config. The chunks of each level are
+/// merged with `merge`.
+fn chunk_nodes<'text, Sizer, Level>(
+    text: &'text str,
+    within: Range<usize>,
+    configs: &[ChunkConfig<Sizer>],
+    offsets: &[(Level, Range<usize>)],
+    atomic_ranges: &[Range<usize>],
+    trim: Trim,
+    merge: &MergeChunks<'_, 'text>,
+) -> Vec<ChunkNode<'text>>
+where
+    Sizer: ChunkSizer,
+    Level: SemanticLevel,
+{
+    let Some((config, rest)) = configs.split_first() else {
+        return vec![];
+    };
+    let chunks = chunks_within(config, text, within, offsets, atomic_ranges, trim);
+    merge(chunks, &fits_desired(config))
+        .into_iter()
+        .map(|(offset, chunk)| ChunkNode {
+            offset,
+            text: chunk,
+            children: chunk_nodes(
+                text,
+                offset..offset + chunk.len(),
+                rest,
+                offsets,
+                atomic_ranges,
+                trim,
+                merge,
+            ),
+        })
+        .collect()
+}
+
+/// Merges the chunks of a level of a chunk tree, given whether a chunk at an
+/// offset fits the capacity of that level.
+type MergeChunks<'merge, 'text> = dyn Fn(Vec<(usize, &'text str)>, &dyn Fn(usize, &str) -> bool) -> Vec<(usize, &'text str)>
+    + 'merge;
+
+/// Whether a chunk is within the desired capacity of the config, such as for
+/// merging chunks.
+fn fits_desired<Sizer>(chunk_config: &ChunkConfig<Sizer>) -> impl Fn(usize, &str) -> bool + '_
+where
+    Sizer: ChunkSizer,
+{
+    |_, chunk| chunk_config.sizer().size(chunk) <= chunk_config.capacity().desired()
+}
+
+/// Clip a range to the parts of it that are `within` another range, relative
+/// to the start of `within`. Returns `None` if they don't overlap.
+fn clip_range(range: &Range<usize>, within: &Range<usize>) -> Option<Range<usize>> {
+    let start = range.start.max(within.start);
+    let end = range.end.min(within.end);
+    // Empty ranges are only kept if they were already empty
+    if start > end || (start == end && !range.is_empty()) {
+        return None;
+    }
+    Some(start - within.start..end - within.start)
+}
+
+/// Buffers that are reused across calls to `chunk_indices_with_session`, so
+/// that chunking many documents in a row doesn't allocate them again for each
+/// document. Helpful for high-throughput services.
+///
+/// A session can be used with any splitter, but only by one chunk iterator at
+/// a time. Buffers are returned to the session when the iterator is dropped.
+#[derive(Debug, Default)]
+pub struct SplitterSession {
+    /// Allocation for the sections considered for the next chunk
+    next_sections: Vec<(usize, &'static str)>,
+    /// Allocation for the cache of chunk sizes
+    size_cache: AHashMap<Range<usize>, usize>,
+}
+
+impl SplitterSession {
+    /// Create a new session with empty buffers.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Reuse the allocation of a list of sections for text with another lifetime.
+fn recycle_sections<'to>(mut sections: Vec<(usize, &str)>) -> Vec<(usize, &'to str)> {
+    sections.clear();
+    // Collecting from the same vector with items of the same layout reuses the allocation
+    sections
+        .into_iter()
+        .map(|(offset, _)| (offset, ""))
+        .collect()
+}
+
+/// A range of text at a particular semantic level, as found when parsing the
+/// text, returned by `parse_ranges`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SemanticRange {
+    /// Rank of the semantic level among all of the levels found in the text,
+    /// starting at 0 for the lowest level. A higher level is always split
+    /// before a lower one.
+    pub level: usize,
+    /// Name of the semantic level, such as `LineBreaks(2)` or
+    /// `Heading(H1)`, for display purposes.
+    pub name: String,
+    /// Byte range of the text at this level
+    pub range: Range<usize>,
+}
+
+/// A node in a tree of chunks generated by `chunk_tree`. The root node is the
+/// whole text, and the children of each node are the chunks of the next level
+/// within it.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ChunkNode<'text> {
+    /// Byte offset of the chunk within the whole text
+    pub offset: usize,
+    /// Text of the chunk
+    pub text: &'text str,
+    /// Smaller chunks within this chunk, in order
+    pub children: Vec<ChunkNode<'text>>,
+}
+
+/// Parent and child chunks of a text, generated in a single pass by
+/// `hierarchical_chunks`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HierarchicalChunks<'text> {
+    /// Coarse chunks, with their byte offset within the text
+    pub parents: Vec<(usize, &'text str)>,
+    /// Fine chunks, in order. Each one is contained within a single parent.
+    pub children: Vec<ChildChunk<'text>>,
+}
+
+/// A chunk that is contained within a larger parent chunk.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ChildChunk<'text> {
+    /// Index of the parent chunk within [`HierarchicalChunks::parents`]
+    pub parent: usize,
+    /// Byte offset of the chunk within the whole text
+    pub offset: usize,
+    /// Byte offset of the chunk within its parent chunk
+    pub parent_offset: usize,
+    /// Text of the chunk
+    pub text: &'text str,
+}
+
+/// The cumulative size of a text up to a semantic boundary, returned by
+/// `size_checkpoints`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SizeCheckpoint {
+    /// Byte offset of the boundary within the text
+    pub offset: usize,
+    /// Rank of the highest semantic level starting at this offset, the same
+    /// as in `parse_ranges`. The end of the text is ranked above all levels.
+    pub level: usize,
+    /// Size of the text before the offset, as the sum of the sizes of the text
+    /// between each checkpoint
+    pub size: usize,
+}
+
+/// A chunk of a text that was changed before chunking, such as by removing
+/// ignored ranges, along with the range of the original text it came from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MappedChunk {
+    /// Byte range of the original text that the chunk came from
+    pub range: Range<usize>,
+    /// Text of the chunk, which can differ from the original text in `range`
+    pub text: String,
+}
+
+/// Information about how a chunk was generated that may need special handling,
+/// returned alongside each chunk by `chunks_with_diagnostics`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ChunkDiagnostic {
+    /// The chunk is larger than the max chunk capacity, because it contains a
+    /// single character (or token sequence) that couldn't be split any further.
+    Oversized {
+        /// Size of the chunk, as measured by the chunk sizer
+        size: usize,
+        /// Max capacity of a chunk
+        max: usize,
+    },
+}
+
+/// Counts of the work done to chunk a text, returned by
+/// `chunk_indices_with_counters`. Useful for understanding how chunking scales
+/// with a particular chunk sizer and text, and for tuning the capacity.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ChunkingCounters {
+    /// Number of chunks generated
+    pub chunks: usize,
+    /// Number of times the size of a possible chunk was needed
+    pub size_lookups: usize,
+    /// Number of times the chunk sizer was called. Lower than the number of
+    /// lookups if sizes could be reused from the cache.
+    pub sizer_calls: usize,
+    /// Number of sections of text that were considered for a chunk
+    pub sections_examined: usize,
+    /// Number of steps of the binary searches for the end of each chunk,
+    /// and the start of the overlap with the next one
+    pub search_iterations: usize,
+}
+
+/// How far chunking a text has gotten, passed to the callback of
+/// `chunk_indices_with_progress`, such as for showing a progress bar.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ChunkProgress {
+    /// Number of bytes of the text up to the end of the last chunk
+    pub bytes_processed: usize,
+    /// Length of the whole text in bytes
+    pub total_bytes: usize,
+    /// Number of chunks generated so far
+    pub chunks: usize,
+}
+
+/// How a particular semantic level relates to surrounding text elements.
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum SemanticSplitPosition {
+    /// The semantic level should be treated as its own chunk.
+    Own,
+    /// The semantic level should be included in the next chunk.
+    #[cfg_attr(not(any(feature = "markdown", feature = "typst")), allow(dead_code))]
+    Next,
+}
+
 /// Custom-defined levels of semantic splitting for custom document types.
 trait SemanticLevel: Copy + fmt::Debug + Ord + PartialOrd + 'static {
+    /// Whether text that doesn't fit within the lowest level can be split by
+    /// sentences before falling back to words.
+    const SENTENCE_FALLBACK: bool = true;
+
+    /// Where the text of a range of this level should go when splitting.
+    /// Default is to treat it as its own item.
+    fn split_position(self) -> SemanticSplitPosition {
+        SemanticSplitPosition::Own
+    }
+
+    /// Whether whitespace preceding a range of this level should be included
+    /// in the same section, rather than being its own section.
+    fn treat_whitespace_as_previous(self) -> bool {
+        false
+    }
+
     /// Given a level, split the text into sections based on the level.
     /// Level ranges are also provided of items that are equal to or greater than the current level.
-    /// Default implementation assumes that all level ranges should be treated
-    /// as their own item.
     fn sections(
         text: &str,
         level_ranges: impl Iterator<Item = (Self, Range<usize>)>,
@@ -94,7 +1778,7 @@ trait SemanticLevel: Copy + fmt::Debug + Ord + PartialOrd + 'static {
                             return text.get(cursor..).map(|t| Either::Left(once((cursor, t))));
                         }
                         // Return text preceding match + the match
-                        Some((_, range)) => {
+                        Some((level, range)) => {
                             if range.start < cursor {
                                 continue;
                             }
@@ -102,13 +1786,32 @@ trait SemanticLevel: Copy + fmt::Debug + Ord + PartialOrd + 'static {
                             let prev_section = text
                                 .get(offset..range.start)
                                 .expect("invalid character sequence");
-                            let separator = text
-                                .get(range.start..range.end)
-                                .expect("invalid character sequence");
-                            cursor = range.end;
-                            return Some(Either::Right(
-                                [(offset, prev_section), (range.start, separator)].into_iter(),
-                            ));
+                            match level.split_position() {
+                                SemanticSplitPosition::Own => {
+                                    if level.treat_whitespace_as_previous()
+                                        && prev_section.chars().all(char::is_whitespace)
+                                    {
+                                        let section = text
+                                            .get(offset..range.end)
+                                            .expect("invalid character sequence");
+                                        cursor = range.end;
+                                        return Some(Either::Left(once((offset, section))));
+                                    }
+                                    let separator = text
+                                        .get(range.start..range.end)
+                                        .expect("invalid character sequence");
+                                    cursor = range.end;
+                                    return Some(Either::Right(
+                                        [(offset, prev_section), (range.start, separator)]
+                                            .into_iter(),
+                                    ));
+                                }
+                                SemanticSplitPosition::Next => {
+                                    // Separator will be part of the next chunk
+                                    cursor = range.start;
+                                    return Some(Either::Left(once((offset, prev_section))));
+                                }
+                            }
                         }
                     }
                 }
@@ -130,6 +1833,11 @@ where
     cursor: usize,
     /// Range of each semantic item and its precalculated semantic level
     ranges: Vec<(Level, Range<usize>)>,
+    /// Sorted start offsets of the ranges of each level, ordered by level.
+    /// Lets lookups jump to the next range of a level with a binary search,
+    /// instead of scanning all of the ranges in between, which is quadratic
+    /// for texts with many ranges of one level and few of another.
+    level_starts: Vec<(Level, Vec<usize>)>,
 }
 
 impl<Level> SemanticSplitRanges<Level>
@@ -141,19 +1849,91 @@ where
         ranges.sort_unstable_by(|(_, a), (_, b)| {
             a.start.cmp(&b.start).then_with(|| b.end.cmp(&a.end))
         });
-        Self { cursor: 0, ranges }
+        let mut split = Self {
+            cursor: 0,
+            ranges: Vec::new(),
+            level_starts: Vec::new(),
+        };
+        for (level, range) in &ranges {
+            split.index_range(*level, range.start);
+        }
+        split.ranges = ranges;
+        split
+    }
+
+    /// Add the start of a range that comes after all of the indexed ones
+    fn index_range(&mut self, level: Level, start: usize) {
+        match self
+            .level_starts
+            .binary_search_by_key(&level, |(level, _)| *level)
+        {
+            Ok(index) => self.level_starts[index].1.push(start),
+            Err(index) => self.level_starts.insert(index, (level, vec![start])),
+        }
+    }
+
+    /// Index of the first range that hasn't been moved past and starts at or
+    /// after the offset
+    fn first_index(&self, offset: usize) -> usize {
+        self.cursor + self.ranges[self.cursor..].partition_point(|(_, range)| range.start < offset)
+    }
+
+    /// Index of the first range at or after `from` that starts at or after
+    /// the offset
+    fn index_of_start(&self, from: usize, offset: usize) -> usize {
+        from + self.ranges[from..].partition_point(|(_, range)| range.start < offset)
     }
 
     /// Retrieve ranges for all sections of a given level after an offset
+    #[cfg(test)]
     fn ranges_after_offset(
         &self,
         offset: usize,
     ) -> impl Iterator<Item = (Level, Range<usize>)> + '_ {
-        self.ranges[self.cursor..]
+        self.ranges[self.first_index(offset)..]
             .iter()
-            .filter(move |(_, sep)| sep.start >= offset)
             .map(|(l, r)| (*l, r.start..r.end))
     }
+
+    /// Ranges of the given level or higher after an offset. Skips over the
+    /// ranges of lower levels in between with a binary search per level.
+    fn ranges_of_level_or_higher(
+        &self,
+        offset: usize,
+        level: Level,
+    ) -> impl Iterator<Item = (Level, Range<usize>)> + '_ {
+        let levels = &self.level_starts[self.level_starts.partition_point(|(l, _)| *l < level)..];
+        let mut index = self.first_index(offset);
+        std::iter::from_fn(move || loop {
+            let (l, range) = self.ranges.get(index)?;
+            if *l >= level {
+                index += 1;
+                return Some((*l, range.clone()));
+            }
+            // Jump to the next range that could be of a high enough level
+            let next_start = levels
+                .iter()
+                .filter_map(|(_, starts)| {
+                    starts
+                        .get(starts.partition_point(|start| *start <= range.start))
+                        .copied()
+                })
+                .min();
+            index = match next_start {
+                // There could still be one with the same start
+                _ if self
+                    .ranges
+                    .get(index + 1)
+                    .is_some_and(|(_, next)| next.start == range.start) =>
+                {
+                    index + 1
+                }
+                Some(start) => self.index_of_start(index + 1, start),
+                None => self.ranges.len(),
+            };
+        })
+    }
+
     /// Retrieve ranges for all sections of a given level after an offset
     fn level_ranges_after_offset(
         &self,
@@ -162,26 +1942,24 @@ where
     ) -> impl Iterator<Item = (Level, Range<usize>)> + '_ {
         // Find the first item of this level. Allows us to skip larger items of a higher level that surround this one.
         // Otherwise all lower levels would only return the first item of the higher level that wraps it.
-        let first_item = self
-            .ranges_after_offset(offset)
-            .position(|(l, _)| l == level)
-            .and_then(|i| {
-                self.ranges_after_offset(offset)
-                    .skip(i)
-                    .coalesce(|(a_level, a_range), (b_level, b_range)| {
-                        // If we are at the first item, if two neighboring elements have the same level and start, take the shorter one
-                        if a_level == b_level && a_range.start == b_range.start && i == 0 {
-                            Ok((b_level, b_range))
-                        } else {
-                            Err(((a_level, a_range), (b_level, b_range)))
-                        }
-                    })
-                    // Just take the first of these items
-                    .next()
-            });
-        // let first_item = self.ranges_after_offset(offset).find(|(l, _)| l == &level);
-        self.ranges_after_offset(offset)
-            .filter(move |(l, _)| l >= &level)
+        let first = self.first_index(offset);
+        let first_item = self.first_index_of_level(first, level).and_then(|index| {
+            let i = index - first;
+            self.ranges[index..]
+                .iter()
+                .map(|(l, r)| (*l, r.clone()))
+                .coalesce(|(a_level, a_range), (b_level, b_range)| {
+                    // If we are at the first item, if two neighboring elements have the same level and start, take the shorter one
+                    if a_level == b_level && a_range.start == b_range.start && i == 0 {
+                        Ok((b_level, b_range))
+                    } else {
+                        Err(((a_level, a_range), (b_level, b_range)))
+                    }
+                })
+                // Just take the first of these items
+                .next()
+        });
+        self.ranges_of_level_or_higher(offset, level)
             .skip_while(move |(l, r)| {
                 first_item.as_ref().is_some_and(|(_, fir)| {
                     (l > &level && r.contains(&fir.start))
@@ -190,13 +1968,36 @@ where
             })
     }
 
+    /// Index of the first range of exactly this level, at or after the
+    /// index `from`
+    fn first_index_of_level(&self, from: usize, level: Level) -> Option<usize> {
+        let (_, starts) = &self.level_starts[self
+            .level_starts
+            .binary_search_by_key(&level, |(l, _)| *l)
+            .ok()?];
+        let (_, first) = self.ranges.get(from)?;
+        let start = *starts.get(starts.partition_point(|start| *start < first.start))?;
+        let index = self.index_of_start(from, start);
+        self.ranges[index..]
+            .iter()
+            .position(|(l, _)| *l == level)
+            .map(|i| index + i)
+    }
+
     /// Return a unique, sorted list of all line break levels present before the next max level, added
     /// to all of the base semantic levels, in order from smallest to largest
     fn levels_in_remaining_text(&self, offset: usize) -> impl Iterator<Item = Level> + '_ {
-        self.ranges_after_offset(offset)
-            .map(|(l, _)| l)
-            .sorted()
-            .dedup()
+        // Starts are sorted, so only the last one of each level needs checking
+        let first = self
+            .ranges
+            .get(self.first_index(offset))
+            .map(|(_, range)| range.start);
+        self.level_starts
+            .iter()
+            .filter(move |(_, starts)| {
+                first.is_some_and(|first| starts.last().is_some_and(|last| *last >= first))
+            })
+            .map(|(level, _)| *level)
     }
 
     /// Split a given text into iterator over each semantic chunk
@@ -214,42 +2015,151 @@ where
         .map(move |(i, str)| (offset + i, str))
     }
 
-    /// Clear out ranges we have moved past so future iterations are faster
-    fn update_cursor(&mut self, cursor: usize) {
-        self.cursor += self.ranges[self.cursor..]
+    /// The highest semantic level of any range that starts or ends at the
+    /// given offset, if there are any.
+    fn boundary_level(&self, offset: usize) -> Option<Level> {
+        self.ranges[self.cursor..]
             .iter()
-            .position(|(_, range)| range.start >= cursor)
-            .unwrap_or_else(|| self.ranges.len() - self.cursor);
+            .take_while(|(_, range)| range.start <= offset)
+            .filter(|(_, range)| range.start == offset || range.end == offset)
+            .map(|(level, _)| *level)
+            .max()
+    }
+
+    /// Add a range that comes after all of the existing ranges, dropping the
+    /// ranges that have already been moved past.
+    fn push(&mut self, level: Level, range: Range<usize>) {
+        if self.cursor > 0 {
+            self.compact();
+        }
+        self.index_range(level, range.start);
+        self.ranges.push((level, range));
+    }
+
+    /// Drop the ranges that have already been moved past, and free their
+    /// memory once most of it is unused.
+    fn compact(&mut self) {
+        self.ranges.drain(..self.cursor);
+        self.cursor = 0;
+        let first = self
+            .ranges
+            .first()
+            .map_or(usize::MAX, |(_, range)| range.start);
+        for (_, starts) in &mut self.level_starts {
+            starts.drain(..starts.partition_point(|start| *start < first));
+            if starts.capacity() > starts.len() * 2 {
+                starts.shrink_to_fit();
+            }
+        }
+        if self.ranges.capacity() > self.ranges.len() * 2 {
+            self.ranges.shrink_to_fit();
+        }
+    }
+
+    /// The highest level of the ranges that haven't been moved past yet
+    fn max_remaining_level(&self) -> Option<Level> {
+        self.levels_in_remaining_text(0).last()
+    }
+
+    /// Clear out ranges we have moved past so future iterations are faster.
+    /// Once they are more than half of the ranges, they are dropped, so that
+    /// memory is bounded by the rest of the document, such as for a large
+    /// Markdown or code file that is parsed up front.
+    fn update_cursor(&mut self, cursor: usize) {
+        self.cursor = self.first_index(cursor);
+        if self.cursor > self.ranges.len() / 2 {
+            self.compact();
+        }
+    }
+}
+
+/// Number of steps of the binary search for the next chunk whose candidates
+/// are measured in a single batch, if the chunk sizer supports batches.
+const BATCH_SEARCH_DEPTH: usize = 3;
+
+/// Indices that a binary search between `low` and `high` could check in its
+/// next `depth` steps, starting with the first one.
+fn binary_search_probes(low: usize, high: usize, depth: usize) -> Vec<usize> {
+    let mut probes = vec![];
+    let mut ranges = vec![(low, high)];
+    for _ in 0..depth {
+        ranges = ranges
+            .into_iter()
+            .filter(|(low, high)| low <= high)
+            .flat_map(|(low, high)| {
+                let mid = low + (high - low) / 2;
+                probes.push(mid);
+                let left = (mid > low).then(|| (low, mid - 1));
+                left.into_iter().chain(once((mid + 1, high)))
+            })
+            .collect();
     }
+    probes
 }
 
 /// Returns chunks of text with their byte offsets as an iterator.
 #[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)]
 struct TextChunks<'text, 'sizer, Sizer, Level>
 where
     Sizer: ChunkSizer,
     Level: SemanticLevel,
 {
+    /// Version of the chunking algorithm to use
+    algorithm_version: ChunkingVersion,
+    /// Sorted, non-overlapping ranges that chunks can't start or end within
+    atomic_ranges: Vec<Range<usize>>,
+    /// Budget that stops chunking once exhausted, if any
+    budget: Option<&'sizer ChunkingBudget>,
     /// Overal capacity of the chunk
     capacity: ChunkCapacity,
     /// How to validate chunk sizes
     chunk_sizer: MemoizedChunkSizer<'sizer, Sizer>,
     /// Average number of sections in a chunk for each level
     chunk_stats: ChunkStats,
+    /// Number of chunks generated so far
+    chunks: usize,
     /// Current byte offset in the `text`
     cursor: usize,
+    /// Whether chunking stopped because the budget was exhausted
+    interrupted: bool,
+    /// Size of the most recently generated chunk
+    last_chunk_size: usize,
+    /// Byte range of the most recently generated chunk, before trimming
+    last_untrimmed: Range<usize>,
+    /// Whether chunks end right after a newline, if the chunk contains one
+    line_aligned: bool,
+    /// Ranges that haven't been added to `semantic_split` yet, if parsing lazily
+    lazy_ranges: Option<LazyRanges<'sizer, Level>>,
     /// Reusable container for next sections to avoid extra allocations
     next_sections: Vec<(usize, &'text str)>,
     /// Overlap capacity
     overlap: ChunkCapacity,
     /// Previous item's end byte offset
     prev_item_end: usize,
+    /// Number of steps of the binary searches so far
+    search_iterations: usize,
+    /// Number of sections that have been considered for a chunk so far
+    sections_examined: usize,
     /// Splitter used for determining semantic levels.
     semantic_split: SemanticSplitRanges<Level>,
+    /// Session to return the reusable buffers to once finished
+    session: Option<&'sizer mut SplitterSession>,
+    /// Whether closing punctuation right after a chunk is pulled into it
+    sticky_punctuation: bool,
+    /// How to find sentence boundaries for the sentence fallback level
+    sentence_boundaries: SentenceBoundaries,
     /// Original text to iterate over and generate chunks from
     text: &'text str,
+    /// How to choose between chunks that are all within the capacity
+    tie_breaking: TieBreaking,
     /// The trimming method to apply
     trim: Trim,
+    /// Size of the whole text, if it fits in a single chunk and was not
+    /// parsed
+    whole_text_size: Option<usize>,
+    /// Whether words are only split at whitespace
+    whole_words: bool,
 }
 
 impl<'sizer, 'text: 'sizer, Sizer, Level> TextChunks<'text, 'sizer, Sizer, Level>
@@ -263,37 +2173,221 @@ where
         chunk_config: &'sizer ChunkConfig<Sizer>,
         text: &'text str,
         offsets: Vec<(Level, Range<usize>)>,
+        mut atomic_ranges: Vec<Range<usize>>,
         trim: Trim,
     ) -> Self {
         let ChunkConfig {
             capacity,
             overlap,
             sizer,
+            tie_breaking,
+            sentence_boundaries,
+            algorithm_version,
+            sticky_punctuation,
+            whole_words,
+            line_aligned,
             trim: trim_enabled,
         } = chunk_config;
+        atomic_ranges.sort_unstable_by_key(|range| range.start);
+        let atomic_ranges = atomic_ranges
+            .into_iter()
+            .coalesce(|a, b| {
+                if b.start < a.end {
+                    Ok(a.start..a.end.max(b.end))
+                } else {
+                    Err((a, b))
+                }
+            })
+            .collect();
+        let mut chunk_sizer = MemoizedChunkSizer::new(sizer);
+        chunk_sizer.set_algorithm_version(*algorithm_version);
         Self {
+            algorithm_version: *algorithm_version,
+            atomic_ranges,
+            budget: None,
             capacity: *capacity,
-            chunk_sizer: MemoizedChunkSizer::new(sizer),
+            chunk_sizer,
             chunk_stats: ChunkStats::new(),
+            chunks: 0,
             cursor: 0,
+            interrupted: false,
+            last_chunk_size: 0,
+            last_untrimmed: 0..0,
+            line_aligned: *line_aligned,
+            lazy_ranges: None,
             next_sections: Vec::new(),
             overlap: (*overlap).into(),
             prev_item_end: 0,
+            search_iterations: 0,
+            sections_examined: 0,
             semantic_split: SemanticSplitRanges::new(offsets),
+            session: None,
+            sentence_boundaries: *sentence_boundaries,
+            sticky_punctuation: *sticky_punctuation,
             text,
+            tie_breaking: *tie_breaking,
             trim: if *trim_enabled { trim } else { Trim::None },
+            whole_text_size: None,
+            whole_words: *whole_words,
+        }
+    }
+
+    /// Generate the whole text as a single chunk, without parsing it, if it
+    /// is smaller than the desired capacity. Returns whether it is.
+    ///
+    /// Texts with more than [`MAX_BYTES_PER_UNIT`] bytes for each unit of
+    /// the capacity aren't measured, so large texts don't pay for an extra
+    /// call to the chunk sizer on the whole text.
+    fn fit_whole_text(&mut self) -> bool {
+        if self.text.len() > self.capacity.desired.saturating_mul(MAX_BYTES_PER_UNIT) {
+            return false;
+        }
+        let size = self.chunk_sizer.chunk_size(0, self.text, self.trim);
+        if self.capacity.fits(size) == Ordering::Less {
+            self.whole_text_size = Some(size);
+        }
+        self.whole_text_size.is_some()
+    }
+
+    /// Find the semantic ranges lazily from the given iterator, rather than
+    /// all up front.
+    fn with_lazy_ranges(mut self, ranges: RangeIter<'sizer, Level>) -> Self {
+        self.lazy_ranges = Some(LazyRanges(ranges.peekable()));
+        self
+    }
+
+    /// Stop generating chunks once the budget is exhausted.
+    fn with_budget(mut self, budget: &'sizer ChunkingBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Whether the budget is exhausted, in which case no more chunks are
+    /// generated.
+    fn budget_exhausted(&mut self) -> bool {
+        if !self.interrupted && self.budget.is_some_and(ChunkingBudget::is_exhausted) {
+            self.interrupted = true;
+        }
+        self.interrupted
+    }
+
+    /// Add ranges from the lazy iterator until there are enough to generate
+    /// the next chunk the same way as if all of the ranges were known.
+    ///
+    /// Ranges are added until a chunk from the cursor to the start of the
+    /// next range is too large, since no chunk can reach past that. Then, up
+    /// to a range of the highest level found so far, so that every level's
+    /// sections past that point end where they would with all of the ranges,
+    /// instead of at the end of the text.
+    fn fill_lazy_ranges(&mut self) {
+        let Some(LazyRanges(ranges)) = self.lazy_ranges.as_mut() else {
+            return;
+        };
+
+        let mut end = self.cursor;
+        let mut step = self.capacity.max.max(1);
+        while end < self.text.len() {
+            end = end.saturating_add(step).min(self.text.len());
+            while !self.text.is_char_boundary(end) {
+                end += 1;
+            }
+            while let Some((level, range)) = ranges.next_if(|(_, range)| range.start < end) {
+                self.semantic_split.push(level, range);
+            }
+            let chunk = self.text.get(self.cursor..end).expect("Invalid range");
+            let chunk_size = self.chunk_sizer.chunk_size(self.cursor, chunk, self.trim);
+            if self.capacity.fits(chunk_size).is_gt() {
+                break;
+            }
+            // Look further ahead each time to limit how often chunks are measured
+            step = step.saturating_mul(2);
+        }
+
+        let max_level = self.semantic_split.max_remaining_level();
+        for (level, range) in ranges.by_ref() {
+            self.semantic_split.push(level, range);
+            if max_level.map_or(true, |max| level >= max) {
+                break;
+            }
+        }
+    }
+
+    /// Counts of the work done to generate the chunks so far
+    fn counters(&self) -> ChunkingCounters {
+        let (size_lookups, sizer_calls) = self.chunk_sizer.counts();
+        ChunkingCounters {
+            chunks: self.chunks,
+            size_lookups,
+            sizer_calls,
+            sections_examined: self.sections_examined,
+            search_iterations: self.search_iterations,
         }
     }
 
+    /// Reuse the buffers of the session, until this iterator is dropped.
+    fn with_session(mut self, session: &'sizer mut SplitterSession) -> Self {
+        self.chunk_sizer
+            .set_cache(std::mem::take(&mut session.size_cache));
+        self.next_sections = recycle_sections(std::mem::take(&mut session.next_sections));
+        self.session = Some(session);
+        self
+    }
+
+    /// Measure chunks with the comments and strings within them, if the
+    /// chunk sizer takes them into account.
+    fn with_syntax_spans(mut self, syntax_spans: Vec<SyntaxSpan>) -> Self {
+        self.chunk_sizer.set_syntax_spans(syntax_spans);
+        self
+    }
+
+    /// Count the size of a prefix that will be added to each chunk as part
+    /// of its size, as the offset each prefix starts applying at and its size.
+    /// Levels are chosen with `min_prefix_size`, the size the prefixes can be
+    /// shortened to.
+    fn with_prefix_sizes(
+        mut self,
+        prefix_sizes: Vec<(usize, usize)>,
+        min_prefix_size: usize,
+    ) -> Self {
+        self.chunk_sizer
+            .set_prefix_sizes(prefix_sizes, min_prefix_size);
+        self
+    }
+
     /// Generate the next chunk, applying trimming settings.
     /// Returns final byte offset and str.
     /// Will return `None` if given an invalid range.
     fn next_chunk(&mut self) -> Option<(usize, &'text str)> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("next_chunk", cursor = self.cursor).entered();
+
+        if let Some(size) = self.whole_text_size {
+            self.cursor = self.text.len();
+            self.chunk_stats.update_max_chunk_size(self.text.len());
+            self.last_chunk_size = size;
+            self.last_untrimmed = 0..self.text.len();
+            return Some(self.trim.trim(0, self.text));
+        }
+
         self.semantic_split.update_cursor(self.cursor);
+        self.fill_lazy_ranges();
         let low = self.update_next_sections();
-        let (start, end) = self.binary_search_next_chunk(low)?;
+        let (start, mut end) = self.binary_search_next_chunk(low)?;
+        if self.sticky_punctuation {
+            end = self.sticky_punctuation_end(start, end);
+        }
+        if self.line_aligned {
+            end = self.line_aligned_end(start, end);
+        }
+        if self.algorithm_version.includes(ChunkingVersion::V2) {
+            end = self.bidi_control_end(start, end);
+        }
         let chunk = self.text.get(start..end)?;
         self.chunk_stats.update_max_chunk_size(end - start);
+        self.last_chunk_size = self.chunk_sizer.chunk_size(start, chunk, self.trim);
+        self.last_untrimmed = start..end;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(start, end, size = self.last_chunk_size, "generated chunk");
 
         // Reset caches so we can reuse the memory allocation
         self.chunk_sizer.clear_cache();
@@ -304,6 +2398,37 @@ where
         Some(self.trim.trim(start, chunk))
     }
 
+    /// Diagnostics for the most recently generated chunk
+    fn diagnostics(&self) -> Vec<ChunkDiagnostic> {
+        if self.last_chunk_size > self.capacity.max {
+            vec![ChunkDiagnostic::Oversized {
+                size: self.last_chunk_size,
+                max: self.capacity.max,
+            }]
+        } else {
+            vec![]
+        }
+    }
+
+    /// Byte range of the most recently generated chunk, before trimming
+    fn untrimmed_range(&self) -> Range<usize> {
+        self.last_untrimmed.clone()
+    }
+
+    /// Measure the chunks the next steps of the binary search between `low`
+    /// and `high` could check in a single batch.
+    fn prefetch_candidates(&mut self, start: usize, low: usize, high: usize) {
+        let ends = binary_search_probes(low, high, BATCH_SEARCH_DEPTH)
+            .into_iter()
+            .map(|index| {
+                let (offset, str) = self.next_sections[index];
+                offset + str.len()
+            })
+            .collect::<Vec<_>>();
+        self.chunk_sizer
+            .prefetch(start, self.text, &ends, self.trim);
+    }
+
     /// Use binary search to find the next chunk that fits within the chunk size
     fn binary_search_next_chunk(&mut self, mut low: usize) -> Option<(usize, usize)> {
         let start = self.cursor;
@@ -312,14 +2437,32 @@ where
         let mut high = self.next_sections.len().saturating_sub(1);
         let mut successful_index = None;
         let mut successful_chunk_size = None;
+        let prefer_smaller = self.tie_breaking == TieBreaking::PreferSmaller;
 
         while low <= high {
+            if self.budget_exhausted() {
+                return None;
+            }
+            self.search_iterations += 1;
             let mid = low + (high - low) / 2;
             let (offset, str) = self.next_sections[mid];
             let text_end = offset + str.len();
             let chunk = self.text.get(start..text_end)?;
+            if self.chunk_sizer.needs_batch(start, chunk, self.trim) {
+                self.prefetch_candidates(start, low, high);
+            }
             let chunk_size = self.chunk_sizer.chunk_size(start, chunk, self.trim);
             let fits = self.capacity.fits(chunk_size);
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                low,
+                mid,
+                high,
+                text_end,
+                chunk_size,
+                ?fits,
+                "binary search step"
+            );
 
             match fits {
                 Ordering::Less => {
@@ -330,7 +2473,7 @@ where
                         successful_chunk_size = Some(chunk_size);
                     }
                 }
-                Ordering::Equal => {
+                Ordering::Equal if prefer_smaller => {
                     // If we found a smaller equals use it. Or if this is the first equals we found
                     if text_end < end || !equals_found {
                         end = text_end;
@@ -339,6 +2482,15 @@ where
                     }
                     equals_found = true;
                 }
+                Ordering::Equal => {
+                    // Keep searching for the largest equals
+                    if text_end > end {
+                        end = text_end;
+                        successful_index = Some(mid);
+                        successful_chunk_size = Some(chunk_size);
+                    }
+                    equals_found = true;
+                }
                 Ordering::Greater => {
                     // If we're too big on our smallest run, we must return at least one section
                     if mid == 0 && start == end {
@@ -347,10 +2499,10 @@ where
                         successful_chunk_size = Some(chunk_size);
                     }
                 }
-            };
+            }
 
             // Adjust search area
-            if fits.is_lt() {
+            if fits.is_lt() || (fits.is_eq() && !prefer_smaller) {
                 low = mid + 1;
             } else if mid > 0 {
                 high = mid - 1;
@@ -360,6 +2512,12 @@ where
             }
         }
 
+        if let (TieBreaking::PreferHigherLevel, true, Some(successful_index)) =
+            (self.tie_breaking, equals_found, successful_index)
+        {
+            return Some((start, self.highest_level_end(start, successful_index)?));
+        }
+
         if let (Some(successful_index), Some(chunk_size)) =
             (successful_index, successful_chunk_size)
         {
@@ -385,10 +2543,125 @@ where
         Some((start, end))
     }
 
+    /// Extend the end of the chunk past any closing punctuation that directly
+    /// follows it, if the chunk still fits within the capacity and doesn't end
+    /// within an atomic range.
+    fn sticky_punctuation_end(&mut self, start: usize, end: usize) -> usize {
+        let rest = &self.text[end..];
+        let len = rest.len() - rest.trim_start_matches(CLOSING_PUNCTUATION).len();
+        if len == 0 || start == end || self.text[..end].ends_with(char::is_whitespace) {
+            return end;
+        }
+
+        let sticky_end = end + len;
+        let index = self
+            .atomic_ranges
+            .partition_point(|range| range.start < sticky_end);
+        if index > 0 && sticky_end < self.atomic_ranges[index - 1].end {
+            return end;
+        }
+        let chunk = &self.text[start..sticky_end];
+        let chunk_size = self.chunk_sizer.chunk_size(start, chunk, self.trim);
+        if self.capacity.fits(chunk_size).is_gt() {
+            return end;
+        }
+        sticky_end
+    }
+
+    /// Move the end of the chunk to before any bidi control characters it
+    /// ends with, so that they stay with the text they apply to in the next
+    /// chunk. The end stays if the chunk would be empty, or the new end would
+    /// fall within an atomic range.
+    fn bidi_control_end(&self, start: usize, end: usize) -> usize {
+        if end == self.text.len() {
+            return end;
+        }
+        let bidi_end = start + self.text[start..end].trim_end_matches(BIDI_CONTROLS).len();
+        if bidi_end == end || bidi_end == start {
+            return end;
+        }
+
+        let index = self
+            .atomic_ranges
+            .partition_point(|range| range.start < bidi_end);
+        if index > 0 && bidi_end < self.atomic_ranges[index - 1].end {
+            return end;
+        }
+        bidi_end
+    }
+
+    /// Move the end of the chunk to right after a newline, unless it already
+    /// ends at a line boundary. A newline directly after the chunk is pulled
+    /// into it if it still fits within the capacity, otherwise the chunk ends
+    /// after the last newline within it that isn't inside an atomic range.
+    fn line_aligned_end(&mut self, start: usize, end: usize) -> usize {
+        if start == end || end == self.text.len() || self.text[..end].ends_with('\n') {
+            return end;
+        }
+
+        let rest = &self.text[end..];
+        if let Some(len) = ["\n", "\r\n"]
+            .into_iter()
+            .find(|newline| rest.starts_with(newline))
+            .map(str::len)
+        {
+            let chunk = &self.text[start..end + len];
+            let chunk_size = self.chunk_sizer.chunk_size(start, chunk, self.trim);
+            if self.capacity.fits(chunk_size).is_le() {
+                return end + len;
+            }
+        }
+
+        self.text[start..end]
+            .match_indices('\n')
+            .map(|(index, _)| start + index + 1)
+            .rev()
+            .find(|&line_end| {
+                let index = self
+                    .atomic_ranges
+                    .partition_point(|range| range.start < line_end);
+                index == 0 || line_end >= self.atomic_ranges[index - 1].end
+            })
+            .unwrap_or(end)
+    }
+
+    /// Out of all of the next sections up to the largest one that fits, find
+    /// the end offset on the highest semantic level boundary whose chunk is
+    /// still within the capacity.
+    fn highest_level_end(&mut self, start: usize, largest_index: usize) -> Option<usize> {
+        // Find the smallest section that is within the capacity
+        let mut low = 0;
+        let mut high = largest_index;
+        while low < high {
+            self.search_iterations += 1;
+            let mid = low + (high - low) / 2;
+            let (offset, str) = self.next_sections[mid];
+            let chunk = self.text.get(start..offset + str.len())?;
+            let chunk_size = self.chunk_sizer.chunk_size(start, chunk, self.trim);
+            if self.capacity.fits(chunk_size).is_lt() {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        // The end of the text is always the highest level boundary. Later
+        // items win ties, so the largest chunk on the highest level is used.
+        self.next_sections[low..=largest_index]
+            .iter()
+            .map(|(offset, str)| offset + str.len())
+            .max_by_key(|&end| {
+                (
+                    end == self.text.len(),
+                    self.semantic_split.boundary_level(end),
+                )
+            })
+    }
+
     /// Use binary search to find the sections that fit within the overlap size.
     /// If no overlap deisired, return end.
     fn update_cursor(&mut self, end: usize) {
-        if self.overlap.max == 0 {
+        if self.overlap.max == 0 || self.next_sections.is_empty() {
             self.cursor = end;
             return;
         }
@@ -396,15 +2669,18 @@ where
         // Binary search for overlap
         let mut start = end;
         let mut low = 0;
-        // Find closest index that would work
+        // Find closest index that would work. Sticky punctuation and line
+        // alignment can move the end past the last section.
         let mut high = match self
             .next_sections
             .binary_search_by_key(&end, |(offset, str)| offset + str.len())
         {
             Ok(i) | Err(i) => i,
-        };
+        }
+        .min(self.next_sections.len() - 1);
 
         while low <= high {
+            self.search_iterations += 1;
             let mid = low + (high - low) / 2;
             let (offset, _) = self.next_sections[mid];
             let chunk_size = self.chunk_sizer.chunk_size(
@@ -464,12 +2740,14 @@ where
             let (semantic_level, fallback_max_offset) = self.chunk_sizer.find_correct_level(
                 self.cursor,
                 &self.capacity,
-                FallbackLevel::iter().filter_map(|level| {
-                    level
-                        .sections(remaining_text)
-                        .next()
-                        .map(|(_, str)| (level, str))
-                }),
+                FallbackLevel::iter()
+                    .filter(|level| Level::SENTENCE_FALLBACK || *level != FallbackLevel::Sentence)
+                    .filter_map(|level| {
+                        level
+                            .sections(remaining_text, self.sentence_boundaries, self.whole_words)
+                            .next()
+                            .map(|(_, str)| (level, str))
+                    }),
                 self.trim,
             );
 
@@ -479,17 +2757,56 @@ where
             };
 
             let fallback_level = semantic_level.unwrap_or(FallbackLevel::Char);
+            let cursor = self.cursor;
+            let text = self.text;
+            let atomic_ranges = &self.atomic_ranges;
+            let fallback_sections = if fallback_level == FallbackLevel::Char
+                && self.algorithm_version.includes(ChunkingVersion::V3)
+            {
+                Either::Left(first_grapheme_chars(remaining_text))
+            } else {
+                Either::Right(fallback_level.sections(
+                    remaining_text,
+                    self.sentence_boundaries,
+                    self.whole_words,
+                ))
+            };
 
             Either::Right(
-                fallback_level
-                    .sections(remaining_text)
-                    .map(|(offset, text)| (self.cursor + offset, text)),
+                fallback_sections
+                    // Fallback sections can always end where an atomic range
+                    // ends, even if a section, such as a word, crosses it
+                    .flat_map(move |(offset, str)| {
+                        let start = cursor + offset;
+                        let end = start + str.len();
+                        let first = atomic_ranges.partition_point(|range| range.end <= start);
+                        let ends = atomic_ranges[first..]
+                            .iter()
+                            .map(|range| range.end)
+                            .take_while(move |&range_end| range_end < end);
+                        once(start)
+                            .chain(ends.clone())
+                            .zip(ends.chain(once(end)))
+                            .map(move |(start, end)| (start, &text[start..end]))
+                    }),
             )
         };
 
+        let text = self.text;
+        let atomic_ranges = &self.atomic_ranges;
         let mut sections = sections
-            .take_while(move |(offset, _)| max_offset.map_or(true, |max| *offset <= max))
-            .filter(|(_, str)| !str.is_empty());
+            .filter(|(_, str)| !str.is_empty())
+            // Merge sections that would end within an atomic range
+            .coalesce(|(a_offset, a_str), (b_offset, b_str)| {
+                let end = a_offset + a_str.len();
+                let index = atomic_ranges.partition_point(|range| range.start < end);
+                if index > 0 && end < atomic_ranges[index - 1].end {
+                    Ok((a_offset, &text[a_offset..b_offset + b_str.len()]))
+                } else {
+                    Err(((a_offset, a_str), (b_offset, b_str)))
+                }
+            })
+            .take_while(move |(offset, _)| max_offset.map_or(true, |max| *offset <= max));
 
         // Start filling up the next sections. Since calculating the size of the chunk gets more expensive
         // the farther we go, we conservatively check for a smaller range to do the later binary search in.
@@ -502,6 +2819,7 @@ where
             let prev_num = self.next_sections.len();
             for (offset, str) in sections.by_ref() {
                 self.next_sections.push((offset, str));
+                self.sections_examined += 1;
                 if offset + str.len() > (self.cursor.saturating_add(target_offset)) {
                     break;
                 }
@@ -519,7 +2837,7 @@ where
                     break;
                 }
                 let chunk_size = self.chunk_sizer.chunk_size(
-                    offset,
+                    self.cursor,
                     self.text.get(self.cursor..text_end).expect("Invalid range"),
                     self.trim,
                 );
@@ -540,7 +2858,6 @@ where
                     Ordering::Less => {
                         // We know we can go higher
                         low = new_num.saturating_sub(1);
-                        continue;
                     }
                     Ordering::Equal => {
                         // Don't update low because it could be a range
@@ -551,12 +2868,11 @@ where
                             }
                         }
                         prev_equals = Some(chunk_size);
-                        continue;
                     }
                     Ordering::Greater => {
                         break;
                     }
-                };
+                }
             }
         }
 
@@ -574,14 +2890,14 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             // Make sure we haven't reached the end
-            if self.cursor >= self.text.len() {
+            if self.cursor >= self.text.len() || self.budget_exhausted() {
                 return None;
             }
 
             match self.next_chunk()? {
                 // Make sure we didn't get an empty chunk. Should only happen in
                 // cases where we trim.
-                (_, "") => continue,
+                (_, "") => {}
                 c => {
                     let item_end = c.0 + c.1.len();
                     // Skip because we've emitted a chunk whose content we've already emitted
@@ -589,6 +2905,7 @@ where
                         continue;
                     }
                     self.prev_item_end = item_end;
+                    self.chunks += 1;
                     return Some(c);
                 }
             }
@@ -596,6 +2913,53 @@ where
     }
 }
 
+impl<Sizer, Level> Drop for TextChunks<'_, '_, Sizer, Level>
+where
+    Sizer: ChunkSizer,
+    Level: SemanticLevel,
+{
+    fn drop(&mut self) {
+        // Give the buffers back to the session for the next document
+        if let Some(session) = self.session.take() {
+            session.size_cache = self.chunk_sizer.take_cache();
+            session.next_sections = recycle_sections(std::mem::take(&mut self.next_sections));
+        }
+    }
+}
+
+/// Average number of bytes per unit of chunk size, above which a text is
+/// assumed not to fit in a single chunk without measuring it. Chars are at
+/// most 4 bytes, and tokens are rarely longer than this on average.
+const MAX_BYTES_PER_UNIT: usize = 4;
+
+/// Punctuation that closes a sentence, clause, quote, or bracket, and so
+/// belongs with the text before it.
+const CLOSING_PUNCTUATION: &[char] = &[
+    '.', ',', ';', ':', '!', '?', '…', '"', '\'', ')', ']', '}', '»', '›', '”', '’', '」', '』',
+    '）', '］', '】', '。', '、', '，',
+];
+
+/// Bidi control characters that apply to the text after them: the
+/// left-to-right, right-to-left, and Arabic letter marks, and the characters
+/// that start an embedding, override, or isolate. The characters that end an
+/// embedding or isolate belong with the text before them.
+const BIDI_CONTROLS: &[char] = &[
+    '\u{200E}', '\u{200F}', '\u{061C}', '\u{202A}', '\u{202B}', '\u{202D}', '\u{202E}', '\u{2066}',
+    '\u{2067}', '\u{2068}',
+];
+
+/// Iterator over semantic ranges of a text, in order
+type RangeIter<'splitter, Level> = Box<dyn Iterator<Item = (Level, Range<usize>)> + 'splitter>;
+
+/// Semantic ranges that are found lazily while chunking
+struct LazyRanges<'splitter, Level>(Peekable<RangeIter<'splitter, Level>>);
+
+impl<Level> fmt::Debug for LazyRanges<'_, Level> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyRanges").finish_non_exhaustive()
+    }
+}
+
 /// Keeps track of the average size of chunks as we go
 #[derive(Debug, Default)]
 struct ChunkStats {
@@ -652,6 +3016,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn split_documents_by_delimiter() {
+        let text = "one\n===\ntwo\n===\n\n===\nthree";
+
+        assert_eq!(
+            split_documents(text, "\n===\n").collect::<Vec<_>>(),
+            vec![(0, "one"), (8, "two"), (16, ""), (21, "three")]
+        );
+    }
+
+    #[test]
+    fn split_documents_empty_delimiter() {
+        assert_eq!(
+            split_documents("one two", "").collect::<Vec<_>>(),
+            vec![(0, "one two")]
+        );
+    }
+
+    #[test]
+    fn semantic_ranges_boundary_level() {
+        let ranges = SemanticSplitRanges::new(vec![(0, 2..3), (1, 3..9), (0, 8..9), (2, 12..14)]);
+
+        assert_eq!(ranges.boundary_level(0), None);
+        assert_eq!(ranges.boundary_level(2), Some(0));
+        assert_eq!(ranges.boundary_level(3), Some(1));
+        assert_eq!(ranges.boundary_level(9), Some(1));
+        assert_eq!(ranges.boundary_level(14), Some(2));
+    }
+
     #[test]
     fn semantic_ranges_skip_previous_ranges() {
         let mut ranges = SemanticSplitRanges::new(vec![(0, 0..1), (1, 0..2), (0, 1..2), (2, 0..4)]);
@@ -663,4 +3056,217 @@ mod tests {
             vec![(0, 1..2)]
         );
     }
+
+    #[test]
+    fn semantic_ranges_drop_ranges_behind_cursor() {
+        let mut ranges = SemanticSplitRanges::new((0..100).map(|i| (0, i..i + 1)).collect());
+
+        ranges.update_cursor(40);
+        assert_eq!(ranges.ranges.len(), 100);
+
+        ranges.update_cursor(60);
+        assert_eq!(ranges.ranges.len(), 40);
+        assert_eq!(
+            ranges.levels_in_remaining_text(0).collect::<Vec<_>>(),
+            vec![0]
+        );
+
+        ranges.update_cursor(100);
+        assert!(ranges.ranges.is_empty());
+        assert_eq!(ranges.levels_in_remaining_text(0).count(), 0);
+    }
+
+    /// Sizer that doesn't skip the size cache
+    struct Bytes;
+
+    impl ChunkSizer for Bytes {
+        fn size(&self, chunk: &str) -> usize {
+            chunk.len()
+        }
+    }
+
+    #[test]
+    fn session_keeps_buffers() {
+        let splitter = TextSplitter::new(ChunkConfig::new(10).with_sizer(Bytes));
+        let mut session = SplitterSession::new();
+        splitter
+            .chunk_indices_with_session("Some text\n\nfrom a\ndocument", &mut session)
+            .for_each(drop);
+
+        assert!(session.next_sections.capacity() > 0);
+        assert!(session.size_cache.capacity() > 0);
+    }
+
+    #[test]
+    fn lazy_ranges_only_parse_ahead_of_the_next_chunk() {
+        let text = "line\n".repeat(1000);
+        let splitter = TextSplitter::new(20);
+        let mut chunks = splitter.text_chunks(&text, true);
+
+        assert_eq!(chunks.next(), Some((0, "line\nline\nline\nline")));
+        assert!(chunks.semantic_split.ranges.len() < 20);
+    }
+
+    #[test]
+    fn tail_start_prefers_higher_levels() {
+        let text = "a".repeat(20);
+        let offsets = vec![(0, 2..3), (1, 8..10), (0, 12..13), (0, 14..15)];
+
+        assert_eq!(tail_start(&text, &offsets, &[], 6), 8);
+        assert_eq!(tail_start(&text, &offsets, &[1..3, 7..9], 6), 14);
+        assert_eq!(tail_start(&text, &offsets, &[], 17), 2);
+        assert_eq!(tail_start(&text, &offsets, &[], 20), 0);
+    }
+
+    #[test]
+    fn break_segments_between_breaks() {
+        let text = "aé bc";
+
+        assert_eq!(break_segments(text, &[]), vec![0..6]);
+        assert_eq!(
+            break_segments(text, &[4, 2, 0, 6, 4]),
+            vec![0..1, 1..4, 4..6]
+        );
+    }
+
+    #[test]
+    fn normalize_ranges_widens_to_char_boundaries() {
+        assert_eq!(normalize_ranges("aéb", &[2..3, 3..3]), vec![1..3]);
+        assert_eq!(
+            normalize_ranges("abcdef", &[3..5, 0..1, 1..2, 4..6]),
+            vec![0..2, 3..6]
+        );
+    }
+
+    #[test]
+    #[allow(clippy::single_range_in_vec_init)]
+    fn protected_ranges_within_chars() {
+        let text = "1.1 {{//\u{200f}";
+        let splitter = TextSplitter::new(2);
+
+        assert_eq!(
+            splitter
+                .chunk_indices_with_protected_ranges(text, &[6..10])
+                .map(|(_, chunk)| chunk)
+                .collect::<Vec<_>>(),
+            vec!["1.", "1", "{{", "//\u{200f}"]
+        );
+    }
+
+    #[test]
+    fn chunking_stops_once_budget_is_exhausted() {
+        use std::sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        };
+
+        /// Cancels chunking once it measures a chunk containing `c`.
+        struct CancelAtC(Arc<AtomicBool>);
+
+        impl ChunkSizer for CancelAtC {
+            fn size(&self, chunk: &str) -> usize {
+                if chunk.contains('c') {
+                    self.0.store(true, Ordering::Relaxed);
+                }
+                chunk.len()
+            }
+        }
+
+        let text = "a b c d";
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let budget = ChunkingBudget::new().with_cancellation(Arc::clone(&cancelled));
+        let splitter =
+            TextSplitter::new(ChunkConfig::new(1).with_sizer(CancelAtC(Arc::clone(&cancelled))));
+
+        let exhausted = splitter
+            .chunk_indices_within_budget(text, &budget)
+            .unwrap_err();
+        let all = TextSplitter::new(1).chunk_indices(text).collect::<Vec<_>>();
+        assert!(exhausted.chunks().len() < all.len());
+        assert!(all.starts_with(exhausted.chunks()));
+
+        cancelled.store(false, Ordering::Relaxed);
+        assert_eq!(
+            TextSplitter::new(1).chunk_indices_within_budget("a b", &budget),
+            Ok(vec![(0, "a"), (2, "b")])
+        );
+    }
+
+    #[test]
+    fn cancellation_is_checked_within_binary_search() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        /// Counts how often it measures a chunk.
+        struct CountingSizer(Arc<AtomicUsize>);
+
+        impl ChunkSizer for CountingSizer {
+            fn size(&self, chunk: &str) -> usize {
+                self.0.fetch_add(1, Ordering::Relaxed);
+                chunk.len()
+            }
+        }
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let check_calls = Arc::clone(&calls);
+        let budget = ChunkingBudget::new()
+            .with_cancellation_check(move || check_calls.load(Ordering::Relaxed) > 0);
+        let splitter =
+            TextSplitter::new(ChunkConfig::new(100).with_sizer(CountingSizer(Arc::clone(&calls))));
+
+        let text = "word ".repeat(100);
+        let exhausted = splitter
+            .chunk_indices_within_budget(&text, &budget)
+            .unwrap_err();
+
+        assert!(exhausted.chunks().is_empty());
+        let cancelled_calls = calls.swap(0, Ordering::Relaxed);
+        assert!(splitter.chunk_indices(&text).next().is_some());
+        assert!(cancelled_calls < calls.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn progress_reaches_end_of_text() {
+        let splitter = TextSplitter::new(4);
+        let mut progress = vec![];
+        let chunks = splitter
+            .chunk_indices_with_progress("123\n123 ", |p| progress.push(p))
+            .collect::<Vec<_>>();
+
+        assert_eq!(chunks, vec![(0, "123"), (4, "123")]);
+        assert_eq!(
+            progress
+                .iter()
+                .map(|p| (p.bytes_processed, p.chunks))
+                .collect::<Vec<_>>(),
+            vec![(3, 1), (7, 2), (8, 2)]
+        );
+        assert!(progress.iter().all(|p| p.total_bytes == 8));
+    }
+
+    #[test]
+    fn small_text_is_one_chunk_without_parsing() {
+        let text = " Some text\n\nfrom a document ";
+        let splitter = TextSplitter::new(100);
+        let mut chunks = splitter.text_chunks(text, false);
+
+        assert_eq!(chunks.next(), Some((1, "Some text\n\nfrom a document")));
+        assert_eq!(chunks.next(), None);
+        assert!(chunks.semantic_split.ranges.is_empty());
+        assert_eq!(chunks.counters().sizer_calls, 1);
+    }
+
+    #[test]
+    fn text_at_capacity_is_parsed() {
+        let text = "Some\ntext";
+        let splitter = TextSplitter::new(text.len());
+
+        assert!(!splitter
+            .text_chunks(text, false)
+            .semantic_split
+            .ranges
+            .is_empty());
+    }
 }
@@ -0,0 +1,425 @@
+/*!
+# [`BibtexSplitter`]
+Semantic splitting of BibTeX and BibLaTeX bibliographies. Each `@entry{...}` is
+never split, and groups of entries separated by comments are a higher level.
+*/
+
+use std::ops::Range;
+
+use crate::{
+    splitter::{SemanticLevel, Splitter},
+    ChunkConfig, ChunkSizer,
+};
+
+/// Splitter for BibTeX and BibLaTeX bibliographies (`.bib` files).
+///
+/// Each entry (`@article{...}`, `@string{...}`, etc.) is kept whole, even if
+/// it is larger than the chunk capacity, so a chunk never contains a partial
+/// citation. Comment lines (starting with `%`) and `@comment` entries start a
+/// new group of entries, which is a higher level than single entries, so
+/// bibliographies organized into sections are split by section first.
+///
+/// Use [`BibtexSplitter::chunks_with_metadata`] to get the citation keys of
+/// the entries in each chunk.
+///
+/// ```
+/// use text_splitter::BibtexSplitter;
+///
+/// let splitter = BibtexSplitter::new(40);
+/// let text = "@book{knuth, title={TAOCP}}\n@article{turing, title={Computing}}";
+/// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+///
+/// assert_eq!(
+///     vec!["@book{knuth, title={TAOCP}}", "@article{turing, title={Computing}}"],
+///     chunks
+/// );
+/// ```
+#[derive(Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub struct BibtexSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Method of determining chunk sizes.
+    chunk_config: ChunkConfig<Sizer>,
+}
+
+/// An entry of a bibliography, as returned by
+/// [`BibtexSplitter::chunks_with_metadata`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BibtexEntry<'text> {
+    /// Type of the entry, such as `article` or `book`, as written in the text.
+    pub entry_type: &'text str,
+    /// Citation key of the entry.
+    pub key: &'text str,
+}
+
+impl<Sizer> BibtexSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Creates a new [`BibtexSplitter`].
+    ///
+    /// ```
+    /// use text_splitter::BibtexSplitter;
+    ///
+    /// // By default, the chunk sizer is based on characters.
+    /// let splitter = BibtexSplitter::new(512);
+    /// ```
+    #[must_use]
+    pub fn new(chunk_config: impl Into<ChunkConfig<Sizer>>) -> Self {
+        Self {
+            chunk_config: chunk_config.into(),
+        }
+    }
+
+    /// Generate a list of chunks from a given text. Each chunk will be up to
+    /// the `max_chunk_size`.
+    ///
+    /// ## Method
+    ///
+    /// To preserve as much semantic meaning within a chunk as possible, each chunk is composed of the largest semantic units that can fit in the next given chunk. For each splitter type, there is a defined set of semantic levels. Here is an example of the steps used:
+    ///
+    /// 1. Entries
+    /// 2. Groups of entries, started by a comment
+    ///
+    /// Entries are never split, even if they are larger than the chunk
+    /// capacity.
+    ///
+    /// ```
+    /// use text_splitter::BibtexSplitter;
+    ///
+    /// let splitter = BibtexSplitter::new(40);
+    /// let text = "% Books\n@book{a, title={A}}\n@book{b, title={B}}\n% Articles\n@article{c}";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(
+    ///     vec!["% Books\n@book{a, title={A}}", "@book{b, title={B}}", "% Articles\n@article{c}"],
+    ///     chunks
+    /// );
+    /// ```
+    pub fn chunks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = &'text str> + 'splitter {
+        Splitter::<_>::chunks(self, text)
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets.
+    /// Each chunk will be up to the `max_chunk_size`.
+    ///
+    /// See [`BibtexSplitter::chunks`] for more information.
+    pub fn chunk_indices<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter {
+        Splitter::<_>::chunk_indices(self, text)
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets,
+    /// along with the entries that are part of each chunk. Entries without a
+    /// citation key, such as `@string` or `@preamble`, are not included.
+    ///
+    /// See [`BibtexSplitter::chunks`] for more information.
+    ///
+    /// ```
+    /// use text_splitter::{BibtexEntry, BibtexSplitter};
+    ///
+    /// let splitter = BibtexSplitter::new(512);
+    /// let text = "@string{acm = \"ACM\"}\n@inproceedings{lamport78, publisher = acm}";
+    /// let (_, _, entries) = splitter.chunks_with_metadata(text).next().unwrap();
+    ///
+    /// assert_eq!(
+    ///     vec![BibtexEntry { entry_type: "inproceedings", key: "lamport78" }],
+    ///     entries
+    /// );
+    /// ```
+    pub fn chunks_with_metadata<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str, Vec<BibtexEntry<'text>>)> + 'splitter {
+        let entries = parse_bibtex(text).entries;
+        self.chunk_indices(text).map(move |(offset, chunk)| {
+            let range = offset..offset + chunk.len();
+            let metadata = entries
+                .iter()
+                .filter(|entry| entry.range.start < range.end && range.start < entry.range.end)
+                .filter_map(|entry| entry.metadata)
+                .collect();
+            (offset, chunk, metadata)
+        })
+    }
+}
+
+impl<Sizer> Splitter<Sizer> for BibtexSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    type Level = Element;
+
+    fn chunk_config(&self) -> &ChunkConfig<Sizer> {
+        &self.chunk_config
+    }
+
+    fn parse(&self, text: &str) -> Vec<(Self::Level, Range<usize>)> {
+        let bibliography = parse_bibtex(text);
+        bibliography
+            .groups
+            .into_iter()
+            .map(|range| (Element::Group, range))
+            .chain(
+                bibliography
+                    .entries
+                    .into_iter()
+                    .map(|entry| (Element::Entry, entry.range)),
+            )
+            .collect()
+    }
+
+    fn atomic_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        parse_bibtex(text)
+            .entries
+            .into_iter()
+            .map(|entry| entry.range)
+            .collect()
+    }
+}
+
+/// Different semantic levels that text can be split by.
+/// Each level provides a method of splitting text into chunks of a given level
+/// as well as a fallback in case a given fallback is too large.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Element {
+    /// A single entry, including any comment lines directly above it
+    Entry,
+    /// Entries following a comment, up until the next comment
+    Group,
+}
+
+impl SemanticLevel for Element {}
+
+/// An entry of the bibliography, with its byte range in the text
+#[derive(Debug)]
+struct ParsedEntry<'text> {
+    range: Range<usize>,
+    /// Type and key, if the entry has a citation key
+    metadata: Option<BibtexEntry<'text>>,
+}
+
+/// Entries and groups of a bibliography
+#[derive(Debug)]
+struct Bibliography<'text> {
+    entries: Vec<ParsedEntry<'text>>,
+    groups: Vec<Range<usize>>,
+}
+
+/// Find the entries and groups of a bibliography. Any text outside of an entry
+/// is a comment in BibTeX, but only lines starting with `%` and `@comment`
+/// entries start a new group.
+fn parse_bibtex(text: &str) -> Bibliography<'_> {
+    let mut entries = vec![];
+    let mut groups = vec![];
+    // Start of the current group, and whether it has any entries yet
+    let mut group: Option<(usize, bool)> = None;
+    let mut last_end = 0;
+    let mut i = 0;
+
+    while let Some(at) = next_marker(&text[i..]) {
+        let start = i + at;
+        if text[start..].starts_with('%') {
+            // Comment lines start a new group, unless they directly follow
+            // another comment.
+            if let Some((group_start, true)) = group {
+                groups.push(group_start..last_end);
+                group = None;
+            }
+            group.get_or_insert((start, false));
+            i = start + text[start..].find('\n').unwrap_or(text.len() - start);
+            continue;
+        }
+
+        let Some((end, metadata, is_comment)) = parse_entry(text, start) else {
+            i = start + 1;
+            continue;
+        };
+        if is_comment {
+            if let Some((group_start, true)) = group {
+                groups.push(group_start..last_end);
+                group = None;
+            }
+            group.get_or_insert((start, false));
+        } else {
+            // Comment lines directly above the entry stay with it
+            let entry_start = group
+                .filter(|(_, has_entries)| !has_entries)
+                .map_or(start, |(group_start, _)| group_start);
+            entries.push(ParsedEntry {
+                range: entry_start..end,
+                metadata,
+            });
+            if let Some((_, has_entries)) = group.as_mut() {
+                *has_entries = true;
+            }
+        }
+        last_end = end;
+        i = end;
+    }
+
+    if let Some((group_start, true)) = group {
+        groups.push(group_start..last_end);
+    }
+
+    Bibliography { entries, groups }
+}
+
+/// Offset of the next `@` or `%` comment that starts a line (ignoring
+/// indentation), since `@` can also appear in comments between entries.
+fn next_marker(text: &str) -> Option<usize> {
+    text.match_indices(['@', '%']).map(|(i, _)| i).find(|&i| {
+        text[..i]
+            .rsplit('\n')
+            .next()
+            .is_some_and(|l| l.trim().is_empty())
+    })
+}
+
+/// Parse an entry starting with `@` at `start`. Returns the end of the entry,
+/// its type and key if it has one, and whether it is an `@comment`.
+fn parse_entry(text: &str, start: usize) -> Option<(usize, Option<BibtexEntry<'_>>, bool)> {
+    let rest = &text[start + 1..];
+    let type_len = rest
+        .bytes()
+        .take_while(|b| b.is_ascii_alphanumeric() || *b == b'_')
+        .count();
+    let entry_type = &rest[..type_len];
+    let after_type = rest[type_len..].trim_start();
+    let open = after_type.chars().next()?;
+    let close = match open {
+        '{' => '}',
+        '(' => ')',
+        _ => return None,
+    };
+    if entry_type.is_empty() {
+        return None;
+    }
+
+    let body_start = text.len() - after_type.len() + 1;
+    let mut depth = 0usize;
+    let mut in_quotes = false;
+    let mut end = text.len();
+    for (i, c) in text[body_start..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth > 0 => depth -= 1,
+            '"' if depth == 0 => in_quotes = !in_quotes,
+            _ if c == close && depth == 0 && !in_quotes => {
+                end = body_start + i + 1;
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    let kind = entry_type.to_ascii_lowercase();
+    let is_comment = kind == "comment";
+    let metadata = (!matches!(kind.as_str(), "comment" | "string" | "preamble"))
+        .then(|| {
+            let body = &text[body_start..end];
+            let key = body.split(',').next()?.trim();
+            (!key.is_empty() && !key.contains(['{', '}', '=']))
+                .then_some(BibtexEntry { entry_type, key })
+        })
+        .flatten();
+
+    Some((end, metadata, is_comment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BIB: &str = r#"% Generated by a reference manager
+@string{jacm = "Journal of the ACM"}
+
+% Books
+@book{knuth1997,
+  author = {Donald E. Knuth},
+  title = {The Art of Computer Programming},
+  note = {Contains {nested} braces and an @ sign}
+}
+@book{sicp,
+  title = "Structure and {I}nterpretation of Computer Programs"
+}
+
+@comment{Articles below}
+@article(lamport1978, title = {Time, Clocks}, journal = jacm)
+"#;
+
+    #[test]
+    fn empty_string() {
+        let chunks = BibtexSplitter::new(ChunkConfig::new(100).with_trim(false))
+            .chunks("")
+            .collect::<Vec<_>>();
+
+        assert!(chunks.is_empty());
+    }
+
+    #[test]
+    fn parses_entries() {
+        let bibliography = parse_bibtex(BIB);
+
+        assert_eq!(
+            bibliography
+                .entries
+                .iter()
+                .map(|e| e.metadata.map(|m| (m.entry_type, m.key)))
+                .collect::<Vec<_>>(),
+            vec![
+                None,
+                Some(("book", "knuth1997")),
+                Some(("book", "sicp")),
+                Some(("article", "lamport1978")),
+            ]
+        );
+        assert!(BIB[bibliography.entries[1].range.clone()].starts_with("% Books\n@book{knuth1997"));
+        assert!(BIB[bibliography.entries[1].range.clone()].ends_with("@ sign}\n}"));
+        assert_eq!(
+            &BIB[bibliography.entries[3].range.clone()],
+            "@comment{Articles below}\n@article(lamport1978, title = {Time, Clocks}, journal = jacm)"
+        );
+    }
+
+    #[test]
+    fn parses_groups() {
+        let bibliography = parse_bibtex(BIB);
+        let groups = bibliography
+            .groups
+            .iter()
+            .map(|r| &BIB[r.clone()])
+            .collect::<Vec<_>>();
+
+        assert_eq!(groups.len(), 3);
+        assert!(groups[0].starts_with("% Generated"));
+        assert!(groups[0].ends_with("ACM\"}"));
+        assert!(groups[1].starts_with("% Books"));
+        assert!(groups[1].ends_with("Programs\"\n}"));
+        assert!(groups[2].starts_with("@comment{Articles below}"));
+    }
+
+    #[test]
+    fn entries_are_atomic() {
+        let splitter = BibtexSplitter::new(20);
+        let chunks = splitter.chunks(BIB).collect::<Vec<_>>();
+
+        assert!(chunks
+            .iter()
+            .any(|c| c.starts_with("% Books\n@book{knuth1997") && c.ends_with("@ sign}\n}")));
+    }
+
+    #[test]
+    fn unterminated_entry() {
+        let bibliography = parse_bibtex("@book{a, title = {b}\n");
+
+        assert_eq!(bibliography.entries[0].range, 0..21);
+    }
+}
@@ -0,0 +1,438 @@
+/*!
+# [`ClauseSplitter`]
+Semantic splitting of plain-text legal documents, such as contracts, based on
+their numbered clauses.
+*/
+
+use std::{cmp::Reverse, ops::Range};
+
+use itertools::Itertools;
+
+use crate::{
+    splitter::{SemanticLevel, Splitter},
+    ChunkConfig, ChunkSizer,
+};
+
+use super::{fallback::GRAPHEME_SEGMENTER, text::CAPTURE_LINEBREAKS};
+
+/// Legal document splitter. Recursively splits chunks into the largest
+/// semantic units that fit within the chunk size. Also will
+/// attempt to merge neighboring chunks if they can fit within the
+/// given chunk size.
+///
+/// Clauses are detected by the numbering at the start of a line, like `1.`,
+/// `1.1`, `1.1.1`, `(a)`, or `(i)`, and nested based on the order in which
+/// the numbering styles appear.
+#[derive(Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub struct ClauseSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Method of determining chunk sizes.
+    chunk_config: ChunkConfig<Sizer>,
+}
+
+impl<Sizer> ClauseSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Creates a new [`ClauseSplitter`].
+    ///
+    /// ```
+    /// use text_splitter::ClauseSplitter;
+    ///
+    /// // By default, the chunk sizer is based on characters.
+    /// let splitter = ClauseSplitter::new(512);
+    /// ```
+    #[must_use]
+    pub fn new(chunk_config: impl Into<ChunkConfig<Sizer>>) -> Self {
+        Self {
+            chunk_config: chunk_config.into(),
+        }
+    }
+
+    /// Generate a list of chunks from a given text. Each chunk will be up to
+    /// the `max_chunk_size`.
+    ///
+    /// ## Method
+    ///
+    /// To preserve as much semantic meaning within a chunk as possible, each chunk is composed of the largest semantic units that can fit in the next given chunk. For each splitter type, there is a defined set of semantic levels. Here is an example of the steps used:
+    ///
+    /// 1. Characters
+    /// 2. [Unicode Grapheme Cluster Boundaries](https://www.unicode.org/reports/tr29/#Grapheme_Cluster_Boundaries)
+    /// 3. [Unicode Word Boundaries](https://www.unicode.org/reports/tr29/#Word_Boundaries)
+    /// 4. [Unicode Sentence Boundaries](https://www.unicode.org/reports/tr29/#Sentence_Boundaries)
+    /// 5. Ascending sequence length of newlines. (Newline is `\r\n`, `\n`, or `\r`)
+    /// 6. Numbered clauses by descending depth. So `1.` is a higher level than `1.1`, which is a higher level than `(a)` within it, and so on.
+    ///
+    /// Splitting doesn't occur below the character level, otherwise you could get partial bytes of a char, which may not be a valid unicode str.
+    ///
+    /// ```
+    /// use text_splitter::ClauseSplitter;
+    ///
+    /// let splitter = ClauseSplitter::new(30);
+    /// let text = "1. Term\n1.1 Starts today.\n1.2 Ends in a year.\n2. Fees\n2.1 None.";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(
+    ///     vec!["1. Term\n1.1 Starts today.", "1.2 Ends in a year.", "2. Fees\n2.1 None."],
+    ///     chunks
+    /// );
+    /// ```
+    pub fn chunks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = &'text str> + 'splitter {
+        Splitter::<_>::chunks(self, text)
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets.
+    /// Each chunk will be up to the `max_chunk_size`.
+    ///
+    /// See [`ClauseSplitter::chunks`] for more information.
+    pub fn chunk_indices<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter {
+        Splitter::<_>::chunk_indices(self, text)
+    }
+}
+
+impl<Sizer> Splitter<Sizer> for ClauseSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    type Level = Element;
+
+    fn chunk_config(&self) -> &ChunkConfig<Sizer> {
+        &self.chunk_config
+    }
+
+    fn parse(&self, text: &str) -> Vec<(Self::Level, Range<usize>)> {
+        CAPTURE_LINEBREAKS
+            .find_iter(text)
+            .map(|m| {
+                let count = GRAPHEME_SEGMENTER
+                    .segment_str(m.as_str())
+                    .tuple_windows::<(usize, usize)>()
+                    .count();
+                (Element::LineBreak(count), m.range())
+            })
+            .chain(
+                clauses(text)
+                    .into_iter()
+                    .map(|(depth, range)| (Element::Clause(Reverse(depth)), range)),
+            )
+            .collect()
+    }
+}
+
+/// Different semantic levels that text can be split by.
+/// Each level provides a method of splitting text into chunks of a given level
+/// as well as a fallback in case a given fallback is too large.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Element {
+    /// Sequence of a given number of linebreaks
+    LineBreak(usize),
+    /// A numbered clause, including any nested clauses, by depth. Top-level
+    /// clauses have a depth of 0.
+    Clause(Reverse<usize>),
+}
+
+impl SemanticLevel for Element {}
+
+/// Numbering styles of a clause
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Style {
+    /// `1.`, `1.1`, `1.1.1` and so on, by number of segments
+    Numbered(usize),
+    /// `(1)` or `1)`
+    ParenNumber,
+    /// `(a)` or `a)`
+    LowerAlpha,
+    /// `(A)` or `A)`
+    UpperAlpha,
+    /// `(i)` or `i)`
+    LowerRoman,
+    /// `(I)` or `I)`
+    UpperRoman,
+}
+
+/// Numbering found at the start of a line
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Marker {
+    Style(Style),
+    /// A single letter that is also a roman numeral, like `(i)`, which could
+    /// come after `(h)` or be the first item of a roman numbered list.
+    Ambiguous(char),
+}
+
+/// Parse the clause numbering at the start of a line, if there is any.
+fn marker(line: &str) -> Option<Marker> {
+    let line = line.trim_start();
+    let token = line.split_whitespace().next()?;
+    // Need some text after the number for it to be a clause
+    if token.len() == line.trim_end().len() {
+        return None;
+    }
+    let (inner, paren) = if let Some(inner) = token.strip_prefix('(') {
+        (inner.strip_suffix(')')?, true)
+    } else if let Some(inner) = token.strip_suffix(')') {
+        (inner, true)
+    } else {
+        (token, false)
+    };
+    if inner.is_empty() || inner.len() > 6 {
+        return None;
+    }
+
+    if !paren {
+        let number = inner.strip_suffix('.').unwrap_or(inner);
+        let segments = number.split('.').collect::<Vec<_>>();
+        let numeric = segments
+            .iter()
+            .all(|s| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()));
+        // A single number needs a trailing `.` so that plain numbers don't count
+        return (numeric && (segments.len() > 1 || number.len() < inner.len()))
+            .then_some(Marker::Style(Style::Numbered(segments.len())));
+    }
+
+    if inner.bytes().all(|b| b.is_ascii_digit()) {
+        return Some(Marker::Style(Style::ParenNumber));
+    }
+    let is_roman = |c: char| "ivxlc".contains(c.to_ascii_lowercase());
+    let mut chars = inner.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if matches!(c.to_ascii_lowercase(), 'i' | 'v' | 'x') => {
+            Some(Marker::Ambiguous(c))
+        }
+        (Some(c), None) if c.is_ascii_lowercase() => Some(Marker::Style(Style::LowerAlpha)),
+        (Some(c), None) if c.is_ascii_uppercase() => Some(Marker::Style(Style::UpperAlpha)),
+        _ if inner.chars().all(|c| c.is_ascii_lowercase() && is_roman(c)) => {
+            Some(Marker::Style(Style::LowerRoman))
+        }
+        _ if inner.chars().all(|c| c.is_ascii_uppercase() && is_roman(c)) => {
+            Some(Marker::Style(Style::UpperRoman))
+        }
+        _ => None,
+    }
+}
+
+/// Number at the start of a line, like the `2` in `2.1`.
+fn leading_number(line: &str) -> usize {
+    let line = line.trim_start();
+    let digits = line.bytes().take_while(u8::is_ascii_digit).count();
+    line[..digits].parse().unwrap_or(usize::MAX)
+}
+
+/// Letter of a lettered marker, like the `b` in `(b)`.
+fn marker_letter(line: &str) -> Option<char> {
+    line.trim_start()
+        .trim_start_matches('(')
+        .chars()
+        .next()
+        .filter(char::is_ascii_alphabetic)
+}
+
+/// Find all clauses in the text, with their depth. A clause runs from its
+/// numbering until the next clause of the same or a higher level, without any
+/// trailing whitespace.
+fn clauses(text: &str) -> Vec<(usize, Range<usize>)> {
+    // Styles of the clauses that are currently open, with the last letter
+    // used in that style.
+    let mut stack: Vec<(Style, Option<char>)> = vec![];
+    let mut starts: Vec<(usize, usize)> = vec![];
+    // Previous line, if it wasn't blank
+    let mut previous: Option<&str> = None;
+    // First number of the last numbered clause
+    let mut last_number = 0;
+    let mut offset = 0;
+
+    for line in text.split_inclusive(['\n', '\r']) {
+        let start = offset;
+        offset += line.len();
+        let content = line.trim_end_matches(['\n', '\r']);
+        if content.trim().is_empty() {
+            previous = None;
+            continue;
+        }
+
+        let number = leading_number(content);
+        let style = marker(content).and_then(|marker| match marker {
+            // A number at the start of a wrapped line, like a year, isn't the
+            // start of a clause unless it continues the numbering.
+            Marker::Style(Style::Numbered(_))
+                if number > last_number + 1
+                    && previous.is_some_and(|line| {
+                        line.trim_end()
+                            .ends_with(|c: char| c.is_alphanumeric() || c == ',')
+                    }) =>
+            {
+                None
+            }
+            Marker::Style(style) => Some(style),
+            Marker::Ambiguous(letter) => Some(resolve(&stack, letter)),
+        });
+        previous = Some(content);
+        let Some(style) = style else {
+            continue;
+        };
+        if let Style::Numbered(_) = style {
+            last_number = number;
+        }
+
+        let letter = marker_letter(content);
+        if let Some(index) = stack.iter().position(|(s, _)| *s == style) {
+            stack.truncate(index + 1);
+            stack[index].1 = letter;
+        } else {
+            stack.push((style, letter));
+        }
+        starts.push((
+            stack.len() - 1,
+            start + content.len() - content.trim_start().len(),
+        ));
+    }
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(index, &(depth, start))| {
+            let end = starts[index + 1..]
+                .iter()
+                .find(|(next_depth, _)| *next_depth <= depth)
+                .map_or(text.len(), |&(_, next_start)| next_start);
+            (depth, start..start + text[start..end].trim_end().len())
+        })
+        .collect()
+}
+
+/// Decide whether a single letter like `i` is the next letter of an alphabetic
+/// list or a roman numeral.
+fn resolve(stack: &[(Style, Option<char>)], letter: char) -> Style {
+    let (alpha, roman) = if letter.is_ascii_lowercase() {
+        (Style::LowerAlpha, Style::LowerRoman)
+    } else {
+        (Style::UpperAlpha, Style::UpperRoman)
+    };
+    let follows_letter = stack.iter().any(|&(style, last)| {
+        style == alpha && last.is_some_and(|last| u32::from(last) + 1 == u32::from(letter))
+    });
+    if follows_letter {
+        alpha
+    } else if letter.eq_ignore_ascii_case(&'i') || stack.iter().any(|(style, _)| *style == roman) {
+        roman
+    } else {
+        alpha
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::splitter::SemanticSplitRanges;
+
+    use super::*;
+
+    #[test]
+    fn empty_string() {
+        let chunks = ClauseSplitter::new(ChunkConfig::new(100).with_trim(false))
+            .chunks("")
+            .collect::<Vec<_>>();
+
+        assert!(chunks.is_empty());
+    }
+
+    #[test]
+    fn markers() {
+        assert_eq!(marker("1. Term"), Some(Marker::Style(Style::Numbered(1))));
+        assert_eq!(
+            marker("  1.2.3 Term"),
+            Some(Marker::Style(Style::Numbered(3)))
+        );
+        assert_eq!(marker("1.2. Term"), Some(Marker::Style(Style::Numbered(2))));
+        assert_eq!(marker("(1) Term"), Some(Marker::Style(Style::ParenNumber)));
+        assert_eq!(marker("(a) Term"), Some(Marker::Style(Style::LowerAlpha)));
+        assert_eq!(marker("B) Term"), Some(Marker::Style(Style::UpperAlpha)));
+        assert_eq!(marker("(ii) Term"), Some(Marker::Style(Style::LowerRoman)));
+        assert_eq!(marker("(IV) Term"), Some(Marker::Style(Style::UpperRoman)));
+        assert_eq!(marker("(i) Term"), Some(Marker::Ambiguous('i')));
+        assert_eq!(marker("1 Term"), None);
+        assert_eq!(marker("1."), None);
+        assert_eq!(marker("(see below) Term"), None);
+        assert_eq!(marker("Term"), None);
+    }
+
+    #[test]
+    fn nested_clauses() {
+        let text = "1. Term\n1.1 Start\n(a) one\n(b) two\n1.2 End\n2. Fees";
+
+        assert_eq!(
+            clauses(text)
+                .into_iter()
+                .map(|(depth, range)| (depth, &text[range]))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, "1. Term\n1.1 Start\n(a) one\n(b) two\n1.2 End"),
+                (1, "1.1 Start\n(a) one\n(b) two"),
+                (2, "(a) one"),
+                (2, "(b) two"),
+                (1, "1.2 End"),
+                (0, "2. Fees"),
+            ]
+        );
+    }
+
+    #[test]
+    fn roman_and_alpha_letters() {
+        let text = "(a) a\n(i) one\n(ii) two\n(b) b\n(h) h\n(i) i";
+
+        assert_eq!(
+            clauses(text)
+                .into_iter()
+                .map(|(depth, range)| (depth, &text[range]))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, "(a) a\n(i) one\n(ii) two"),
+                (1, "(i) one"),
+                (1, "(ii) two"),
+                (0, "(b) b"),
+                (0, "(h) h"),
+                (0, "(i) i"),
+            ]
+        );
+    }
+
+    #[test]
+    fn wrapped_numbers_are_not_clauses() {
+        let text = "1. This Agreement ends in\n2030. After that, it renews.\n2. Fees";
+
+        assert_eq!(
+            clauses(text)
+                .into_iter()
+                .map(|(depth, range)| (depth, &text[range]))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, "1. This Agreement ends in\n2030. After that, it renews."),
+                (0, "2. Fees"),
+            ]
+        );
+    }
+
+    #[test]
+    fn clauses_are_above_line_breaks() {
+        let splitter = ClauseSplitter::new(10);
+        let text = "1. Term\n\n2. Fees";
+        let ranges = SemanticSplitRanges::new(splitter.parse(text));
+
+        assert_eq!(
+            ranges.ranges,
+            vec![
+                (Element::Clause(Reverse(0)), 0..7),
+                (Element::LineBreak(2), 7..9),
+                (Element::Clause(Reverse(0)), 9..16),
+            ]
+        );
+        assert!(Element::Clause(Reverse(2)) > Element::LineBreak(10));
+    }
+}
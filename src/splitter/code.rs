@@ -1,12 +1,19 @@
-use std::{cmp::Ordering, ops::Range};
+use std::{
+    cmp::Ordering,
+    ops::{Range, RangeInclusive},
+};
 
+use ahash::AHashSet;
 use thiserror::Error;
-use tree_sitter::{Language, LanguageError, Parser, TreeCursor, MIN_COMPATIBLE_LANGUAGE_VERSION};
+use tree_sitter::{
+    Language, LanguageError, Node, Parser, Tree, TreeCursor, LANGUAGE_VERSION,
+    MIN_COMPATIBLE_LANGUAGE_VERSION,
+};
 
 use crate::{
     splitter::{SemanticLevel, Splitter},
     trim::Trim,
-    ChunkConfig, ChunkSizer,
+    ChunkConfig, ChunkSizer, SyntaxClass, SyntaxSpan,
 };
 
 /// Indicates there was an error with creating a `CodeSplitter`.
@@ -17,19 +24,79 @@ use crate::{
 #[allow(clippy::module_name_repetitions)]
 pub struct CodeSplitterError(#[from] CodeSplitterErrorRepr);
 
+impl CodeSplitterError {
+    /// The cause of the error, such as for showing the language bindings'
+    /// users how to fix it.
+    #[must_use]
+    pub fn kind(&self) -> CodeSplitterErrorKind {
+        match &self.0 {
+            CodeSplitterErrorRepr::LanguageVersionMismatch { expected, got } => {
+                CodeSplitterErrorKind::LanguageVersionMismatch {
+                    expected: expected.clone(),
+                    got: *got,
+                }
+            }
+            CodeSplitterErrorRepr::ParserInitFailed(_) => CodeSplitterErrorKind::ParserInitFailed,
+        }
+    }
+}
+
+/// The cause of a [`CodeSplitterError`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+#[allow(clippy::module_name_repetitions)]
+pub enum CodeSplitterErrorKind {
+    /// The tree-sitter ABI version of the language isn't supported by the
+    /// version of tree-sitter this crate uses. Usually fixed by upgrading or
+    /// downgrading the grammar crate or package of the language.
+    LanguageVersionMismatch {
+        /// Range of ABI versions that are supported
+        expected: RangeInclusive<usize>,
+        /// ABI version of the language
+        got: usize,
+    },
+    /// The parser could not be initialized with the language.
+    ParserInitFailed,
+}
+
 /// Private error and free to change across minor version of the crate.
 #[derive(Error, Debug)]
 enum CodeSplitterErrorRepr {
     #[error(
-        "Language version {0:?} is too old. Expected at least version {min_version}",
-        min_version=MIN_COMPATIBLE_LANGUAGE_VERSION,
+        "Language version {got} is not supported. Expected a version from {} to {}. Try a version of the language's grammar built for a compatible version of tree-sitter",
+        expected.start(),
+        expected.end(),
     )]
-    LanguageError(LanguageError),
+    LanguageVersionMismatch {
+        expected: RangeInclusive<usize>,
+        got: usize,
+    },
+    #[error("Parser could not be initialized with the language")]
+    ParserInitFailed(#[source] LanguageError),
 }
 
 /// Source code splitter. Recursively splits chunks into the largest
 /// semantic units that fit within the chunk size. Also will attempt to merge
 /// neighboring chunks if they can fit within the given chunk size.
+///
+/// If the chunk sizer is a [`SyntaxSizer`](crate::SyntaxSizer), such as
+/// [`SyntaxWeighted`](crate::SyntaxWeighted), chunks are measured along with
+/// the comments and string literals within them, so that they can count
+/// differently than the rest of the code. Nodes whose kind contains `comment`
+/// or `string` are passed to the sizer.
+///
+/// ```
+/// use text_splitter::{Characters, ChunkConfig, CodeSplitter, SyntaxClass, SyntaxWeighted};
+///
+/// // Comments don't count towards the chunk capacity
+/// let sizer = SyntaxWeighted::new(Characters).with_weight(SyntaxClass::Comment, 0.0);
+/// let splitter =
+///     CodeSplitter::new(tree_sitter_rust::LANGUAGE, ChunkConfig::new(32).with_sizer(sizer))
+///         .expect("Invalid tree-sitter language");
+/// let text = "// Adds one to a number\nfn inc(x: u8) -> u8 { x + 1 }";
+///
+/// assert_eq!(vec![text], splitter.chunks(text).collect::<Vec<_>>());
+/// ```
 #[derive(Debug)]
 #[allow(clippy::module_name_repetitions)]
 pub struct CodeSplitter<Sizer>
@@ -40,6 +107,26 @@ where
     chunk_config: ChunkConfig<Sizer>,
     /// Language to use for parsing the code.
     language: Language,
+    /// Kinds of syntax nodes that shouldn't be used as split points.
+    disabled_kinds: AHashSet<String>,
+    /// Deepest level of the syntax tree used as split points, if limited.
+    max_depth: Option<usize>,
+    /// Kinds of syntax nodes whose docstring is kept with their header.
+    docstring_kinds: AHashSet<String>,
+}
+
+/// Whether a chunk of code is syntactically complete, as returned by
+/// [`CodeSplitter::chunks_with_metadata`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CodeChunkMetadata {
+    /// Whether the chunk only contains whole top-level items, such as
+    /// functions or classes, without any syntax errors, so it parses on its
+    /// own.
+    pub complete: bool,
+    /// Kinds of the top-level syntax nodes that are only partially in the
+    /// chunk, such as `"function_item"`. Node kinds are specific to each
+    /// tree-sitter grammar.
+    pub partial_node_kinds: Vec<&'static str>,
 }
 
 impl<Sizer> CodeSplitter<Sizer>
@@ -57,24 +144,122 @@ where
     ///
     /// # Errors
     ///
-    /// Will return an error if the language version is too old to be compatible
-    /// with the current version of the tree-sitter crate.
+    /// Will return an error if the language version is too old or too new to
+    /// be compatible with the current version of the tree-sitter crate.
     pub fn new(
         language: impl Into<Language>,
         chunk_config: impl Into<ChunkConfig<Sizer>>,
     ) -> Result<Self, CodeSplitterError> {
+        let language = language.into();
+        let expected = MIN_COMPATIBLE_LANGUAGE_VERSION..=LANGUAGE_VERSION;
+        if !expected.contains(&language.version()) {
+            return Err(CodeSplitterErrorRepr::LanguageVersionMismatch {
+                expected,
+                got: language.version(),
+            }
+            .into());
+        }
         // Verify that this is a valid language so we can rely on that later.
         let mut parser = Parser::new();
-        let language = language.into();
         parser
             .set_language(&language)
-            .map_err(CodeSplitterErrorRepr::LanguageError)?;
+            .map_err(CodeSplitterErrorRepr::ParserInitFailed)?;
         Ok(Self {
             chunk_config: chunk_config.into(),
             language,
+            disabled_kinds: AHashSet::new(),
+            max_depth: None,
+            docstring_kinds: AHashSet::new(),
         })
     }
 
+    /// The tree-sitter ABI version of the splitter's language.
+    ///
+    /// ```
+    /// use text_splitter::CodeSplitter;
+    ///
+    /// let splitter = CodeSplitter::new(tree_sitter_rust::LANGUAGE, 512).expect("Invalid language");
+    /// assert_eq!(14, splitter.language_abi_version());
+    /// ```
+    #[must_use]
+    pub fn language_abi_version(&self) -> usize {
+        self.language.version()
+    }
+
+    /// Ignore syntax nodes of the given kinds when splitting, such as
+    /// `"line_comment"`. The text of these nodes is still included in the
+    /// chunks, and their children are still used as split points, but the
+    /// nodes themselves are no longer treated as semantic boundaries.
+    ///
+    /// Node kinds are specific to each tree-sitter grammar.
+    ///
+    /// ```
+    /// use text_splitter::CodeSplitter;
+    ///
+    /// let splitter = CodeSplitter::new(tree_sitter_rust::LANGUAGE, 512)
+    ///     .expect("Invalid language")
+    ///     .with_disabled_node_kinds(&["line_comment", "block_comment"]);
+    /// ```
+    #[must_use]
+    pub fn with_disabled_node_kinds(mut self, kinds: &[&str]) -> Self {
+        self.disabled_kinds = kinds.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    /// Only use syntax nodes up to the given depth of the tree as split
+    /// points, where top-level items are at depth 1. Deeper nodes are treated
+    /// as plain text, and split by the text levels like sentences and words.
+    ///
+    /// Useful for deeply nested code, such as large data literals or
+    /// generated code, where every level of nesting would otherwise be its
+    /// own semantic level, which slows down choosing the level to split at.
+    ///
+    /// ```
+    /// use text_splitter::CodeSplitter;
+    ///
+    /// let splitter = CodeSplitter::new(tree_sitter_rust::LANGUAGE, 512)
+    ///     .expect("Invalid language")
+    ///     .with_max_depth(8);
+    /// ```
+    #[must_use]
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Keep the docstring of syntax nodes of the given kinds, such as
+    /// `"function_definition"` and `"class_definition"` in Python, together
+    /// with the header of the node, even if the rest of the body has to be
+    /// split into other chunks.
+    ///
+    /// A docstring is the first statement in the `body` of the node, if that
+    /// statement is only a string. Node kinds are specific to each
+    /// tree-sitter grammar.
+    ///
+    /// ```
+    /// use text_splitter::CodeSplitter;
+    ///
+    /// let splitter = CodeSplitter::new(tree_sitter_python::LANGUAGE, 60)
+    ///     .expect("Invalid language")
+    ///     .with_docstring_node_kinds(&["function_definition", "class_definition"]);
+    /// let text = "def greet(name):\n    \"\"\"Greet someone by name.\"\"\"\n    greeting = f\"Hello, {name}!\"\n    print(greeting)";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!("def greet(name):\n    \"\"\"Greet someone by name.\"\"\"", chunks[0]);
+    /// ```
+    #[must_use]
+    pub fn with_docstring_node_kinds(mut self, kinds: &[&str]) -> Self {
+        self.docstring_kinds = kinds.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    /// The chunk configuration the splitter was created with, such as for
+    /// measuring text with the same sizer.
+    #[must_use]
+    pub fn chunk_config(&self) -> &ChunkConfig<Sizer> {
+        &self.chunk_config
+    }
+
     /// Generate a list of chunks from a given text. Each chunk will be up to the `chunk_capacity`.
     ///
     /// ## Method
@@ -131,6 +316,79 @@ where
     ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter {
         Splitter::<_>::chunk_indices(self, text)
     }
+
+    /// Returns an iterator over chunks of the text and their byte offsets,
+    /// along with whether each chunk spans complete top-level items or cuts
+    /// through a syntax node. Useful for only embedding complete chunks, or
+    /// for adding ellipsis markers to partial ones.
+    ///
+    /// See [`CodeSplitter::chunks`] for more information.
+    ///
+    /// ```
+    /// use text_splitter::CodeSplitter;
+    ///
+    /// let splitter = CodeSplitter::new(tree_sitter_rust::LANGUAGE, 16).expect("Invalid language");
+    /// let text = "fn a() {}\nfn main() {\n    let x = 5;\n}";
+    /// let chunks = splitter.chunks_with_metadata(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!("fn a() {}", chunks[0].1);
+    /// assert!(chunks[0].2.complete);
+    /// assert_eq!("fn main()", chunks[1].1);
+    /// assert!(!chunks[1].2.complete);
+    /// assert_eq!(vec!["function_item"], chunks[1].2.partial_node_kinds);
+    /// ```
+    pub fn chunks_with_metadata<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str, CodeChunkMetadata)> + 'splitter {
+        let tree = self.parse_tree(text);
+        let root = tree.root_node();
+        let items = root
+            .children(&mut root.walk())
+            .filter(|node| !node.byte_range().is_empty())
+            .map(|node| (node.kind(), node.byte_range(), node.has_error()))
+            .collect::<Vec<_>>();
+        // Items are sorted and don't overlap, and chunk starts only increase,
+        // so items that end before a chunk never overlap a later one.
+        let mut first = 0;
+        self.chunk_indices(text).map(move |(offset, chunk)| {
+            let range = offset..offset + chunk.len();
+            let mut metadata = CodeChunkMetadata {
+                complete: true,
+                partial_node_kinds: vec![],
+            };
+            first += items[first..].partition_point(|(_, item, _)| item.end <= range.start);
+            let overlapping = items[first..].partition_point(|(_, item, _)| item.start < range.end);
+            for (kind, item, has_error) in &items[first..first + overlapping] {
+                if item.start < range.start || range.end < item.end {
+                    metadata.complete = false;
+                    metadata.partial_node_kinds.push(kind);
+                } else if *has_error {
+                    metadata.complete = false;
+                }
+            }
+            (offset, chunk, metadata)
+        })
+    }
+}
+
+impl<Sizer> CodeSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Parse the text into a syntax tree with the splitter's language.
+    fn parse_tree(&self, text: &str) -> Tree {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&self.language)
+            // We verify at initialization that the language is valid, so this should be safe.
+            .expect("Error loading language");
+        // The only reason the tree would be None is:
+        // - No language was set (we do that)
+        // - There was a timeout or cancellation option set (we don't)
+        // - So it should be safe to unwrap here
+        parser.parse(text, None).expect("Error parsing source code")
+    }
 }
 
 impl<Sizer> Splitter<Sizer> for CodeSplitter<Sizer>
@@ -146,21 +404,65 @@ where
     }
 
     fn parse(&self, text: &str) -> Vec<(Self::Level, Range<usize>)> {
-        let mut parser = Parser::new();
-        parser
-            .set_language(&self.language)
-            // We verify at initialization that the language is valid, so this should be safe.
-            .expect("Error loading language");
-        // The only reason the tree would be None is:
-        // - No language was set (we do that)
-        // - There was a timeout or cancellation option set (we don't)
-        // - So it should be safe to unwrap here
-        let tree = parser.parse(text, None).expect("Error parsing source code");
+        let tree = self.parse_tree(text);
 
-        CursorOffsets::new(tree.walk()).collect()
+        let mut offsets = CursorOffsets::new(tree.walk()).with_max_depth(self.max_depth);
+        if self.disabled_kinds.is_empty() && self.docstring_kinds.is_empty() {
+            return offsets.collect();
+        }
+
+        let mut ranges = vec![];
+        let mut docstrings = vec![];
+        while let Some(item) = offsets.next() {
+            let node = offsets.cursor.node();
+            let has_children = self.max_depth.map_or(true, |max| item.0 .0 < max);
+            if has_children && self.docstring_kinds.contains(node.kind()) {
+                if let Some((body, docstring)) = docstring(node) {
+                    docstrings.push((item.0, node.start_byte()..docstring.end_byte(), body));
+                }
+            }
+            if !self.disabled_kinds.contains(node.kind()) {
+                ranges.push(item);
+            }
+        }
+
+        // Replace the header and docstring with a single range at the level
+        // of the header, and start the body after the docstring.
+        for (depth, header, body) in docstrings {
+            let children = Depth(depth.0 + 1);
+            ranges.retain(|(level, range)| {
+                level.0 <= depth.0 || range.start < header.start || range.end > header.end
+            });
+            for (level, range) in &mut ranges {
+                if *level == children && *range == body {
+                    range.start = header.end;
+                }
+            }
+            ranges.push((children, header));
+        }
+        ranges
+    }
+
+    fn syntax_spans(&self, text: &str) -> Vec<SyntaxSpan> {
+        syntax_spans(self.parse_tree(text).walk())
     }
 }
 
+/// The body of a node and the first statement in it, if that statement is
+/// only a string, such as a Python docstring.
+fn docstring(node: Node<'_>) -> Option<(Range<usize>, Node<'_>)> {
+    let body = node.child_by_field_name("body")?;
+    let statement = body.named_child(0)?;
+    let mut inner = statement;
+    while inner.named_child_count() == 1 {
+        inner = inner.named_child(0)?;
+    }
+    inner
+        .kind()
+        .contains("string")
+        .then_some((body.byte_range(), statement))
+}
+
 /// New type around a usize to capture the depth of a given code node.
 /// Custom type so that we can implement custom ordering, since we want to
 /// sort items of lower depth as higher priority.
@@ -184,11 +486,22 @@ impl Ord for Depth {
 /// order.
 struct CursorOffsets<'cursor> {
     cursor: TreeCursor<'cursor>,
+    /// Children of nodes at this depth are skipped, if set.
+    max_depth: Option<usize>,
 }
 
 impl<'cursor> CursorOffsets<'cursor> {
     fn new(cursor: TreeCursor<'cursor>) -> Self {
-        Self { cursor }
+        Self {
+            cursor,
+            max_depth: None,
+        }
+    }
+
+    /// Don't descend into nodes deeper than the given depth
+    fn with_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
     }
 }
 
@@ -197,7 +510,10 @@ impl Iterator for CursorOffsets<'_> {
 
     fn next(&mut self) -> Option<Self::Item> {
         // There are children (can call this initially because we don't want the root node)
-        if self.cursor.goto_first_child() {
+        let below_max_depth = self
+            .max_depth
+            .map_or(true, |max| (self.cursor.depth() as usize) < max);
+        if below_max_depth && self.cursor.goto_first_child() {
             return Some((
                 Depth(self.cursor.depth() as usize),
                 self.cursor.node().byte_range(),
@@ -224,9 +540,44 @@ impl Iterator for CursorOffsets<'_> {
 
 impl SemanticLevel for Depth {}
 
+/// Class of syntax of a node, by its kind, if it isn't regular code.
+fn syntax_class(kind: &str) -> Option<SyntaxClass> {
+    if kind.contains("comment") {
+        Some(SyntaxClass::Comment)
+    } else if kind.contains("string") {
+        Some(SyntaxClass::String)
+    } else {
+        None
+    }
+}
+
+/// The outermost comments and strings in the tree, in order. Nodes within a
+/// comment or string aren't included.
+fn syntax_spans(mut cursor: TreeCursor<'_>) -> Vec<SyntaxSpan> {
+    let mut spans = vec![];
+    'walk: loop {
+        let node = cursor.node();
+        match syntax_class(node.kind()).filter(|_| cursor.depth() > 0) {
+            Some(class) if !node.byte_range().is_empty() => spans.push(SyntaxSpan {
+                range: node.byte_range(),
+                kind: node.kind(),
+                class,
+            }),
+            None if cursor.goto_first_child() => continue,
+            Some(_) | None => {}
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                break 'walk;
+            }
+        }
+    }
+    spans
+}
+
 #[cfg(test)]
 mod tests {
-    use tree_sitter::{Node, Tree};
+    use tree_sitter::Node;
 
     use super::*;
 
@@ -239,6 +590,44 @@ mod tests {
         assert_eq!(chunks, vec!["fn main()", "{\n    let x = 5;", "}"]);
     }
 
+    #[test]
+    fn syntax_spans_are_outermost() {
+        let splitter = CodeSplitter::new(tree_sitter_rust::LANGUAGE, 16).unwrap();
+        let text = "// a\nlet s = \"b\\n\"; /* c */";
+        let spans = syntax_spans(splitter.parse_tree(text).walk());
+
+        assert_eq!(
+            spans
+                .iter()
+                .map(|span| (&text[span.range.clone()], span.kind, span.class))
+                .collect::<Vec<_>>(),
+            [
+                ("// a", "line_comment", SyntaxClass::Comment),
+                ("\"b\\n\"", "string_literal", SyntaxClass::String),
+                ("/* c */", "block_comment", SyntaxClass::Comment),
+            ]
+        );
+    }
+
+    #[test]
+    fn language_version_mismatch_is_structured() {
+        let error = CodeSplitterError::from(CodeSplitterErrorRepr::LanguageVersionMismatch {
+            expected: 13..=14,
+            got: 15,
+        });
+
+        assert_eq!(
+            error.kind(),
+            CodeSplitterErrorKind::LanguageVersionMismatch {
+                expected: 13..=14,
+                got: 15
+            }
+        );
+        assert!(error.to_string().starts_with(
+            "Language version 15 is not supported. Expected a version from 13 to 14."
+        ));
+    }
+
     #[test]
     fn rust_splitter_indices() {
         let splitter = CodeSplitter::new(tree_sitter_rust::LANGUAGE, 16).unwrap();
@@ -251,6 +640,71 @@ mod tests {
         );
     }
 
+    #[test]
+    fn disabled_node_kinds() {
+        let text = "fn main() {\n    let x = 5;\n}";
+        let splitter = CodeSplitter::new(tree_sitter_rust::LANGUAGE, 16).unwrap();
+        let all = splitter.parse(text);
+        let splitter = splitter.with_disabled_node_kinds(&["block"]);
+        let filtered = splitter.parse(text);
+
+        assert_eq!(all.len(), filtered.len() + 1);
+        assert!(!filtered.contains(&(Depth(2), 10..28)));
+        assert!(all.contains(&(Depth(2), 10..28)));
+    }
+
+    #[test]
+    fn docstrings_stay_with_header() {
+        let text = "class Greeter:\n    \"\"\"Greets people.\"\"\"\n\n    def greet(self, name):\n        \"\"\"Return a greeting for a name.\"\"\"\n        greeting = \"Hello, \" + name\n        return greeting\n";
+        let splitter = CodeSplitter::new(tree_sitter_python::LANGUAGE, 80).unwrap();
+        let chunks = splitter.chunks(text).collect::<Vec<_>>();
+        assert!(!chunks.contains(
+            &"def greet(self, name):\n        \"\"\"Return a greeting for a name.\"\"\""
+        ));
+
+        let chunks = splitter
+            .with_docstring_node_kinds(&["class_definition", "function_definition"])
+            .chunks(text)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            chunks,
+            vec![
+                "class Greeter:\n    \"\"\"Greets people.\"\"\"",
+                "def greet(self, name):\n        \"\"\"Return a greeting for a name.\"\"\"",
+                "        greeting = \"Hello, \" + name\n        return greeting"
+            ]
+        );
+    }
+
+    #[test]
+    fn metadata_for_complete_items() {
+        let splitter = CodeSplitter::new(tree_sitter_rust::LANGUAGE, 30).unwrap();
+        let text = "fn a() {}\nfn b() {}\n\nfn main() {\n    let x = 5;\n    let y = 6;\n}";
+        let chunks = splitter.chunks_with_metadata(text).collect::<Vec<_>>();
+
+        assert_eq!(chunks[0].1, "fn a() {}\nfn b() {}");
+        assert_eq!(
+            chunks[0].2,
+            CodeChunkMetadata {
+                complete: true,
+                partial_node_kinds: vec![]
+            }
+        );
+        assert!(chunks[1..].iter().all(|(_, _, metadata)| !metadata.complete
+            && metadata.partial_node_kinds == vec!["function_item"]));
+    }
+
+    #[test]
+    fn metadata_for_syntax_errors() {
+        let splitter = CodeSplitter::new(tree_sitter_rust::LANGUAGE, 100).unwrap();
+        let text = "fn main() {\n    let x = ;\n}";
+        let chunks = splitter.chunks_with_metadata(text).collect::<Vec<_>>();
+
+        assert_eq!(chunks.len(), 1);
+        assert!(!chunks[0].2.complete);
+        assert!(chunks[0].2.partial_node_kinds.is_empty());
+    }
+
     #[test]
     fn depth_partialord() {
         assert_eq!(Depth(0).partial_cmp(&Depth(1)), Some(Ordering::Greater));
@@ -313,6 +767,25 @@ fn fn4() {}";
         assert_eq!(offsets, naive_offsets(&tree));
     }
 
+    #[test]
+    fn max_depth_code_offsets() {
+        let splitter = CodeSplitter::new(tree_sitter_rust::LANGUAGE, 10).unwrap();
+        let source_code = "fn test() {
+    let x = [[[1, 2], [3]], [[4]]];
+}";
+        let tree = splitter.parse_tree(source_code);
+
+        let offsets = splitter.with_max_depth(3).parse(source_code);
+
+        assert_eq!(
+            offsets,
+            naive_offsets(&tree)
+                .into_iter()
+                .filter(|(Depth(depth), _)| *depth <= 3)
+                .collect::<Vec<_>>()
+        );
+    }
+
     fn naive_offsets(tree: &Tree) -> Vec<(Depth, Range<usize>)> {
         let root_node = tree.root_node();
         let mut offsets = vec![];
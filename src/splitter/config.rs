@@ -0,0 +1,661 @@
+/*!
+# [`ConfigSplitter`]
+Semantic splitting of configuration files, such as YAML or TOML. Top-level
+keys and tables are the highest semantic level, with nested mappings and
+arrays below them.
+*/
+
+use std::{cmp::Ordering, ops::Range};
+
+use crate::{
+    splitter::{SemanticLevel, Splitter},
+    trim::Trim,
+    ChunkConfig, ChunkSizer,
+};
+
+/// Configuration file formats supported by the [`ConfigSplitter`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ConfigFormat {
+    /// YAML, using block style for nested mappings and sequences.
+    Yaml,
+    /// TOML
+    Toml,
+}
+
+/// Splitter for configuration files, such as YAML or TOML.
+///
+/// Keys and tables are split by their nesting depth, so the splitter will
+/// first try to fit whole top-level keys or tables into a chunk, then nested
+/// mappings or array items, and so on. Comments directly above a key are kept
+/// with it.
+///
+/// A key with a scalar value (including multi-line strings) is never split,
+/// even if it is larger than the chunk capacity, so each chunk remains
+/// syntactically meaningful on its own.
+///
+/// This isn't a validating parser, and only looks at the structure of each
+/// line, so it works with documents that contain templating or small errors.
+///
+/// ```
+/// use text_splitter::{ConfigFormat, ConfigSplitter};
+///
+/// let splitter = ConfigSplitter::new(ConfigFormat::Yaml, 30);
+/// let text = "server:\n  host: localhost\n  port: 8080\nlogging:\n  level: info";
+/// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+///
+/// assert_eq!(
+///     vec!["server:\n  host: localhost", "port: 8080", "logging:\n  level: info"],
+///     chunks
+/// );
+/// ```
+#[derive(Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub struct ConfigSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Method of determining chunk sizes.
+    chunk_config: ChunkConfig<Sizer>,
+    /// Format of the configuration file
+    format: ConfigFormat,
+}
+
+impl<Sizer> ConfigSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Creates a new [`ConfigSplitter`] for the given format.
+    ///
+    /// ```
+    /// use text_splitter::{ConfigFormat, ConfigSplitter};
+    ///
+    /// // By default, the chunk sizer is based on characters.
+    /// let splitter = ConfigSplitter::new(ConfigFormat::Toml, 512);
+    /// ```
+    #[must_use]
+    pub fn new(format: ConfigFormat, chunk_config: impl Into<ChunkConfig<Sizer>>) -> Self {
+        Self {
+            chunk_config: chunk_config.into(),
+            format,
+        }
+    }
+
+    /// Generate a list of chunks from a given text. Each chunk will be up to
+    /// the `max_chunk_size`.
+    ///
+    /// ## Method
+    ///
+    /// To preserve as much semantic meaning within a chunk as possible, each chunk is composed of the largest semantic units that can fit in the next given chunk. For each splitter type, there is a defined set of semantic levels. Here is an example of the steps used:
+    ///
+    /// 1. Characters
+    /// 2. [Unicode Grapheme Cluster Boundaries](https://www.unicode.org/reports/tr29/#Grapheme_Cluster_Boundaries)
+    /// 3. [Unicode Word Boundaries](https://www.unicode.org/reports/tr29/#Word_Boundaries)
+    /// 4. [Unicode Sentence Boundaries](https://www.unicode.org/reports/tr29/#Sentence_Boundaries)
+    /// 5. Ascending depth of keys, tables, and array items. So a top-level key would have a higher level than a key nested inside of it, and so on.
+    ///
+    /// Keys with scalar values are never split internally, so the lower
+    /// levels are only used for values such as multi-line arrays.
+    ///
+    /// ```
+    /// use text_splitter::{ConfigFormat, ConfigSplitter};
+    ///
+    /// let splitter = ConfigSplitter::new(ConfigFormat::Toml, 30);
+    /// let text = "[package]\nname = \"demo\"\n\n[dependencies]\nserde = \"1\"";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec!["[package]\nname = \"demo\"", "[dependencies]\nserde = \"1\""], chunks);
+    /// ```
+    pub fn chunks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = &'text str> + 'splitter {
+        Splitter::<_>::chunks(self, text)
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets.
+    /// Each chunk will be up to the `max_chunk_size`.
+    ///
+    /// See [`ConfigSplitter::chunks`] for more information.
+    pub fn chunk_indices<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter {
+        Splitter::<_>::chunk_indices(self, text)
+    }
+
+    /// Parse the entries of the document
+    fn entries(&self, text: &str) -> Vec<Entry> {
+        match self.format {
+            ConfigFormat::Yaml => yaml_entries(text),
+            ConfigFormat::Toml => toml_entries(text),
+        }
+    }
+}
+
+impl<Sizer> Splitter<Sizer> for ConfigSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    type Level = Depth;
+
+    const TRIM: Trim = Trim::PreserveIndentation;
+
+    fn chunk_config(&self) -> &ChunkConfig<Sizer> {
+        &self.chunk_config
+    }
+
+    fn parse(&self, text: &str) -> Vec<(Self::Level, Range<usize>)> {
+        self.entries(text)
+            .into_iter()
+            .map(|entry| (Depth(entry.depth), entry.range))
+            .collect()
+    }
+
+    fn atomic_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        self.entries(text)
+            .into_iter()
+            .filter(|entry| entry.scalar)
+            .map(|entry| entry.range)
+            .collect()
+    }
+}
+
+/// New type around a usize to capture the nesting depth of a given entry.
+/// Custom type so that we can implement custom ordering, since we want to
+/// sort items of lower depth as higher priority.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Depth(usize);
+
+impl PartialOrd for Depth {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Depth {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.cmp(&self.0)
+    }
+}
+
+impl SemanticLevel for Depth {}
+
+/// A key, table, or array item in the document.
+#[derive(Debug, Eq, PartialEq)]
+struct Entry {
+    /// Nesting depth, with top-level entries at 0
+    depth: usize,
+    /// Byte range of the entry, including comments directly above it
+    range: Range<usize>,
+    /// Whether the entry only contains a scalar value, and shouldn't be split
+    scalar: bool,
+}
+
+/// Each line of the text along with its byte offset, without line endings.
+fn lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split_inclusive('\n').scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        Some((start, line.trim_end()))
+    })
+}
+
+/// An entry that hasn't been closed yet
+#[derive(Debug)]
+struct OpenEntry {
+    /// Indentation of the entry, in bytes
+    indent: usize,
+    /// Start of the entry
+    start: usize,
+    /// Whether the entry is a sequence item (`- `) rather than a key
+    sequence_item: bool,
+    /// Whether the value is a block scalar (`|` or `>`)
+    block_scalar: bool,
+    /// Whether any nested entries were found
+    has_children: bool,
+}
+
+/// Find the entries of a YAML document, based on the indentation of each
+/// line.
+#[allow(clippy::too_many_lines)]
+fn yaml_entries(text: &str) -> Vec<Entry> {
+    let mut entries = vec![];
+    let mut stack: Vec<OpenEntry> = vec![];
+    let mut pending_comment = None;
+    let mut last_end = 0;
+
+    let close = |entries: &mut Vec<Entry>, stack: &mut Vec<OpenEntry>, end: usize| {
+        let entry = stack.pop().expect("entry to close");
+        entries.push(Entry {
+            depth: stack.len(),
+            range: entry.start..end.max(entry.start),
+            scalar: !entry.has_children,
+        });
+    };
+
+    for (offset, line) in lines(text) {
+        let content = line.trim_start();
+        let indent = line.len() - content.len();
+
+        if content.is_empty() {
+            pending_comment = None;
+            continue;
+        }
+        // Block scalars can contain anything as long as it is indented
+        if stack
+            .last()
+            .is_some_and(|top| top.block_scalar && indent > top.indent)
+        {
+            last_end = offset + line.len();
+            continue;
+        }
+        if content.starts_with('#') {
+            pending_comment.get_or_insert(offset + indent);
+            continue;
+        }
+        if content == "---" || content == "..." || content.starts_with("--- ") {
+            while !stack.is_empty() {
+                close(&mut entries, &mut stack, last_end);
+            }
+            pending_comment = None;
+            last_end = offset + line.len();
+            continue;
+        }
+
+        let sequence_item = content == "-" || content.starts_with("- ");
+        if !sequence_item && yaml_value(content).is_none() {
+            // Continuation of a multi-line scalar or flow collection
+            last_end = offset + line.len();
+            continue;
+        }
+
+        // Close any entries that this line isn't nested in
+        while let Some(top) = stack.last() {
+            let nested = indent > top.indent
+                || (indent == top.indent && sequence_item && !top.sequence_item);
+            if nested {
+                break;
+            }
+            close(&mut entries, &mut stack, last_end);
+        }
+
+        let start = pending_comment.take().unwrap_or(offset + indent);
+        let push = |stack: &mut Vec<OpenEntry>, entry: OpenEntry| {
+            if let Some(parent) = stack.last_mut() {
+                parent.has_children = true;
+            }
+            stack.push(entry);
+        };
+        let mut key = Some((indent, content));
+        if sequence_item {
+            push(
+                &mut stack,
+                OpenEntry {
+                    indent,
+                    start,
+                    sequence_item: true,
+                    block_scalar: false,
+                    has_children: false,
+                },
+            );
+            let item = content[1..].trim_start();
+            let item_indent = line.len() - item.len();
+            key = (!item.is_empty()).then_some((item_indent, item));
+        }
+        if let Some((key_indent, key)) = key {
+            let value = yaml_value(key);
+            if sequence_item && value.is_none() {
+                // Scalar sequence item
+                if let Some(top) = stack.last_mut() {
+                    top.block_scalar = key.starts_with(['|', '>']);
+                }
+            } else {
+                push(
+                    &mut stack,
+                    OpenEntry {
+                        indent: key_indent,
+                        start: if sequence_item {
+                            offset + key_indent
+                        } else {
+                            start
+                        },
+                        sequence_item: false,
+                        block_scalar: value.is_some_and(|v| v.starts_with(['|', '>'])),
+                        has_children: false,
+                    },
+                );
+            }
+        }
+        last_end = offset + line.len();
+    }
+
+    while !stack.is_empty() {
+        close(&mut entries, &mut stack, last_end);
+    }
+    entries
+}
+
+/// If the line is a YAML mapping key, returns the value after the `:`.
+fn yaml_value(line: &str) -> Option<&str> {
+    let key_end = match line.chars().next()? {
+        '[' | '{' | '#' => return None,
+        '-' if line == "-" || line.starts_with("- ") => return None,
+        quote @ ('"' | '\'') => line[1..].find(quote)? + 2,
+        _ => 0,
+    };
+    let colon = key_end
+        + line[key_end..]
+            .match_indices(':')
+            .map(|(i, _)| i)
+            .find(|&i| {
+                line[key_end + i + 1..].is_empty() || line[key_end + i + 1..].starts_with(' ')
+            })?;
+    // A comment before the colon means this isn't a key
+    if line[..colon].contains(" #") {
+        return None;
+    }
+    Some(line[colon + 1..].trim_start())
+}
+
+/// Find the entries of a TOML document. Tables and top-level keys are at
+/// the top level, with keys within a table and items of multi-line arrays
+/// nested below them.
+fn toml_entries(text: &str) -> Vec<Entry> {
+    let mut entries = vec![];
+    let mut table: Option<(usize, usize)> = None;
+    let mut pending_comment = None;
+    let mut last_end = 0;
+    // Key with a value that spans multiple lines, and the state of its value
+    let mut multiline: Option<(usize, usize, TomlValue)> = None;
+
+    for (offset, line) in lines(text) {
+        let content = line.trim_start();
+        let indent = line.len() - content.len();
+
+        if let Some((depth, start, mut value)) = multiline.take() {
+            value.scan(content);
+            let is_item = value.brackets > 0 && !value.in_string();
+            if is_item && !content.is_empty() && !content.starts_with('#') {
+                entries.push(Entry {
+                    depth: depth + 1,
+                    range: offset + indent..offset + line.len(),
+                    scalar: true,
+                });
+            }
+            last_end = offset + line.len();
+            if value.is_complete() {
+                entries.push(Entry {
+                    depth,
+                    range: start..last_end,
+                    scalar: !value.is_array,
+                });
+            } else {
+                multiline = Some((depth, start, value));
+            }
+            continue;
+        }
+
+        if content.is_empty() {
+            pending_comment = None;
+            continue;
+        }
+        if content.starts_with('#') {
+            pending_comment.get_or_insert(offset + indent);
+            continue;
+        }
+
+        let start = pending_comment.take().unwrap_or(offset + indent);
+        if content.starts_with('[') {
+            if let Some((depth, table_start)) = table.take() {
+                entries.push(Entry {
+                    depth,
+                    range: table_start..last_end,
+                    scalar: false,
+                });
+            }
+            let name = content.trim_start_matches('[');
+            let depth = toml_key_segments(name) - 1;
+            table = Some((depth, start));
+            last_end = offset + line.len();
+            continue;
+        }
+
+        let depth = table.map_or(0, |(depth, _)| depth + 1);
+        let mut value = TomlValue::default();
+        if let Some(eq) = content.find('=') {
+            value.is_array = content[eq + 1..].trim_start().starts_with(['[', '{']);
+            value.scan(&content[eq + 1..]);
+        }
+        last_end = offset + line.len();
+        if value.is_complete() {
+            entries.push(Entry {
+                depth,
+                range: start..last_end,
+                scalar: true,
+            });
+        } else {
+            multiline = Some((depth, start, value));
+        }
+    }
+
+    if let Some((depth, start, value)) = multiline {
+        entries.push(Entry {
+            depth,
+            range: start..last_end,
+            scalar: !value.is_array,
+        });
+    }
+    if let Some((depth, start)) = table {
+        entries.push(Entry {
+            depth,
+            range: start..last_end,
+            scalar: false,
+        });
+    }
+    entries
+}
+
+/// Number of dotted segments in a TOML key, ignoring dots within quotes.
+fn toml_key_segments(key: &str) -> usize {
+    let mut quote = None;
+    let mut segments = 1;
+    for c in key.chars() {
+        match (quote, c) {
+            (None, ']') => break,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '.') => segments += 1,
+            (Some(q), c) if q == c => quote = None,
+            _ => {}
+        }
+    }
+    segments
+}
+
+/// State of a TOML value that may span multiple lines.
+#[derive(Debug, Default)]
+struct TomlValue {
+    /// Nesting depth of arrays and inline tables
+    brackets: usize,
+    /// Inside of a multi-line basic string (`"""`)
+    basic_multiline: bool,
+    /// Inside of a multi-line literal string (`'''`)
+    literal_multiline: bool,
+    /// Whether the value is an array or inline table
+    is_array: bool,
+}
+
+impl TomlValue {
+    /// Update the state with the next line of the value
+    fn scan(&mut self, line: &str) {
+        let mut chars = line.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            let rest = &line[i..];
+            if self.basic_multiline {
+                if c == '\\' {
+                    chars.next();
+                } else if rest.starts_with(r#"""""#) {
+                    self.basic_multiline = false;
+                    chars.nth(1);
+                }
+                continue;
+            }
+            if self.literal_multiline {
+                if rest.starts_with("'''") {
+                    self.literal_multiline = false;
+                    chars.nth(1);
+                }
+                continue;
+            }
+            match c {
+                '#' => return,
+                '[' | '{' => self.brackets += 1,
+                ']' | '}' => self.brackets = self.brackets.saturating_sub(1),
+                '"' if rest.starts_with(r#"""""#) => {
+                    self.basic_multiline = true;
+                    chars.nth(1);
+                }
+                '\'' if rest.starts_with("'''") => {
+                    self.literal_multiline = true;
+                    chars.nth(1);
+                }
+                '"' => {
+                    // Single-line basic string, with escapes
+                    while let Some((_, c)) = chars.next() {
+                        match c {
+                            '\\' => {
+                                chars.next();
+                            }
+                            '"' => break,
+                            _ => {}
+                        }
+                    }
+                }
+                '\'' => {
+                    for (_, c) in chars.by_ref() {
+                        if c == '\'' {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Inside of a multi-line string
+    fn in_string(&self) -> bool {
+        self.basic_multiline || self.literal_multiline
+    }
+
+    /// The value doesn't continue on the next line
+    fn is_complete(&self) -> bool {
+        self.brackets == 0 && !self.in_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(entries: Vec<Entry>) -> Vec<(usize, Range<usize>, bool)> {
+        let mut entries = entries
+            .into_iter()
+            .map(|e| (e.depth, e.range, e.scalar))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(_, range, _)| (range.start, range.end));
+        entries
+    }
+
+    #[test]
+    fn yaml_nested_mappings() {
+        let text = "a:\n  b: 1\n  c:\n    d: 2\ne: 3";
+
+        assert_eq!(
+            entries(yaml_entries(text)),
+            vec![
+                (0, 0..23, false),
+                (1, 5..9, true),
+                (1, 12..23, false),
+                (2, 19..23, true),
+                (0, 24..28, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn yaml_sequences() {
+        let text = "items:\n- name: a\n  value: b\n- plain\nnext: 1";
+
+        assert_eq!(
+            entries(yaml_entries(text)),
+            vec![
+                (0, 0..35, false),
+                (1, 7..27, false),
+                (2, 9..16, true),
+                (2, 19..27, true),
+                (1, 28..35, true),
+                (0, 36..43, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn yaml_block_scalars_and_comments() {
+        let text = "# The script\nscript: |\n  echo a\n\n  # not a comment\nafter: 1";
+
+        assert_eq!(
+            entries(yaml_entries(text)),
+            vec![(0, 0..50, true), (0, 51..59, true)]
+        );
+    }
+
+    #[test]
+    fn yaml_key_detection() {
+        assert_eq!(yaml_value("key: value"), Some("value"));
+        assert_eq!(yaml_value("key:"), Some(""));
+        assert_eq!(yaml_value("\"a: b\": c"), Some("c"));
+        assert_eq!(
+            yaml_value("url: http://example.com"),
+            Some("http://example.com")
+        );
+        assert_eq!(yaml_value("just text"), None);
+        assert_eq!(yaml_value("[a, b]"), None);
+        assert_eq!(yaml_value("# a: b"), None);
+    }
+
+    #[test]
+    fn toml_tables_and_arrays() {
+        let text = "title = \"x\"\n\n# Owner\n[owner]\nname = \"y\"\nlist = [\n  1,\n  2,\n]\n\n[owner.extra]\ntext = \"\"\"\na\n[b]\n\"\"\"";
+
+        assert_eq!(
+            entries(toml_entries(text)),
+            vec![
+                (0, 0..11, true),
+                (0, 13..60, false),
+                (1, 29..39, true),
+                (1, 40..60, false),
+                (2, 51..53, true),
+                (2, 56..58, true),
+                (1, 62..96, false),
+                (2, 76..96, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn toml_key_segments_ignore_quoted_dots() {
+        assert_eq!(toml_key_segments("a]"), 1);
+        assert_eq!(toml_key_segments("a.b]"), 2);
+        assert_eq!(toml_key_segments("a.\"b.c\"]]"), 2);
+    }
+
+    #[test]
+    fn never_splits_scalars() {
+        let splitter = ConfigSplitter::new(ConfigFormat::Yaml, 10);
+        let text = "key: a long scalar value\nother: 1";
+        let chunks = splitter.chunks(text).collect::<Vec<_>>();
+
+        assert_eq!(vec!["key: a long scalar value", "other: 1"], chunks);
+    }
+}
@@ -0,0 +1,225 @@
+/*!
+# [`CustomSplitter`]
+Semantic splitting of any document format, based on semantic levels provided
+by a user-defined parser.
+*/
+
+use std::{fmt, ops::Range};
+
+use crate::{
+    splitter::{SemanticLevel, Splitter},
+    ChunkConfig, ChunkSizer,
+};
+
+/// Splitter for document formats that aren't supported out of the box.
+///
+/// The document structure is provided by a `parse` function, which returns the
+/// byte range of each semantic item in the text along with its level. Higher
+/// levels are larger semantic units, so for a custom markup language a section
+/// could be level `2`, a paragraph level `1`, and a line break level `0`.
+/// Ranges can either cover a whole element (like a section) or just the
+/// separator between elements (like a line break).
+///
+/// Below all custom levels, the same Unicode sentence, word, grapheme and
+/// character fallbacks as the other splitters are used. The chunking algorithm
+/// is otherwise identical to [`crate::TextSplitter`].
+///
+/// ```
+/// use text_splitter::CustomSplitter;
+///
+/// // Sections start with `§`, and are made up of lines.
+/// let splitter = CustomSplitter::new(16, |text: &str| {
+///     text.match_indices('§')
+///         .map(|(i, s)| (1, i..i + s.len()))
+///         .chain(text.match_indices('\n').map(|(i, s)| (0, i..i + s.len())))
+///         .collect()
+/// });
+/// let text = "§ One\nalpha beta\n§ Two\ngamma";
+/// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+///
+/// assert_eq!(vec!["§ One\nalpha beta", "§ Two\ngamma"], chunks);
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub struct CustomSplitter<Sizer, Parse>
+where
+    Sizer: ChunkSizer,
+    Parse: Fn(&str) -> Vec<(usize, Range<usize>)>,
+{
+    /// Method of determining chunk sizes.
+    chunk_config: ChunkConfig<Sizer>,
+    /// User-provided parser for the semantic levels of the document.
+    parse: Parse,
+}
+
+impl<Sizer, Parse> CustomSplitter<Sizer, Parse>
+where
+    Sizer: ChunkSizer,
+    Parse: Fn(&str) -> Vec<(usize, Range<usize>)>,
+{
+    /// Creates a new [`CustomSplitter`].
+    ///
+    /// `parse` returns the semantic items found in a given text, as pairs of
+    /// `(level, byte range)`. Ranges don't need to be sorted, but ranges that
+    /// are out of bounds or not on a char boundary are ignored.
+    ///
+    /// ```
+    /// use text_splitter::CustomSplitter;
+    ///
+    /// // By default, the chunk sizer is based on characters.
+    /// let splitter = CustomSplitter::new(512, |text: &str| {
+    ///     text.match_indices('\n').map(|(i, _)| (0, i..i + 1)).collect()
+    /// });
+    /// ```
+    #[must_use]
+    pub fn new(chunk_config: impl Into<ChunkConfig<Sizer>>, parse: Parse) -> Self {
+        Self {
+            chunk_config: chunk_config.into(),
+            parse,
+        }
+    }
+
+    /// Generate a list of chunks from a given text. Each chunk will be up to the `chunk_capacity`.
+    ///
+    /// Each chunk is composed of the largest semantic units that can fit in
+    /// the next given chunk, using the levels returned by the `parse`
+    /// function. If an item of the lowest custom level is still too large, it
+    /// is split by Unicode sentences, words, grapheme clusters, and finally
+    /// characters.
+    pub fn chunks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = &'text str> + 'splitter {
+        Splitter::<_>::chunks(self, text)
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets.
+    /// Each chunk will be up to the `chunk_capacity`.
+    ///
+    /// See [`CustomSplitter::chunks`] for more information.
+    pub fn chunk_indices<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter {
+        Splitter::<_>::chunk_indices(self, text)
+    }
+}
+
+impl<Sizer, Parse> fmt::Debug for CustomSplitter<Sizer, Parse>
+where
+    Sizer: ChunkSizer + fmt::Debug,
+    Parse: Fn(&str) -> Vec<(usize, Range<usize>)>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomSplitter")
+            .field("chunk_config", &self.chunk_config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Sizer, Parse> Splitter<Sizer> for CustomSplitter<Sizer, Parse>
+where
+    Sizer: ChunkSizer,
+    Parse: Fn(&str) -> Vec<(usize, Range<usize>)>,
+{
+    type Level = CustomLevel;
+
+    fn chunk_config(&self) -> &ChunkConfig<Sizer> {
+        &self.chunk_config
+    }
+
+    fn parse(&self, text: &str) -> Vec<(Self::Level, Range<usize>)> {
+        (self.parse)(text)
+            .into_iter()
+            .filter(|(_, range)| text.get(range.clone()).is_some())
+            .map(|(level, range)| (CustomLevel(level), range))
+            .collect()
+    }
+}
+
+/// Semantic level returned by the user-provided parser. Higher levels are
+/// larger semantic units.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct CustomLevel(usize);
+
+impl SemanticLevel for CustomLevel {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{splitter::SemanticSplitRanges, TieBreaking};
+
+    use super::*;
+
+    fn lines(text: &str) -> Vec<(usize, Range<usize>)> {
+        text.match_indices('\n')
+            .map(|(i, _)| (0, i..i + 1))
+            .collect()
+    }
+
+    #[test]
+    fn splits_by_custom_level() {
+        let splitter = CustomSplitter::new(10, lines);
+        let chunks = splitter
+            .chunk_indices("Some text\nfrom a\ndocument")
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![(0, "Some text"), (10, "from a"), (17, "document")],
+            chunks
+        );
+    }
+
+    #[test]
+    fn falls_back_to_unicode_levels() {
+        let splitter = CustomSplitter::new(10, lines);
+        let chunks = splitter
+            .chunks("Some text from a document")
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec!["Some text", "from a", "document"], chunks);
+    }
+
+    #[test]
+    fn splits_at_higher_levels_first() {
+        let splitter = CustomSplitter::new(ChunkConfig::new(9).with_trim(false), |_: &str| {
+            vec![(1, 3..4), (0, 1..2), (0, 5..6)]
+        });
+        let chunks = splitter.chunks("a b|c d e").collect::<Vec<_>>();
+
+        assert_eq!(vec!["a b|c d e"], chunks);
+
+        let chunks = splitter.chunks("a b|c d e f g").collect::<Vec<_>>();
+
+        assert_eq!(vec!["a b|", "c d e f g"], chunks);
+    }
+
+    #[test]
+    fn tie_breaking() {
+        let text = "aa bb cc dd ee";
+        let parse = |_: &str| vec![(0, 2..3), (1, 3..9), (0, 5..6), (0, 8..9), (0, 11..12)];
+
+        let chunks = |tie_breaking| {
+            let config = ChunkConfig::new(2..=12).with_tie_breaking(tie_breaking);
+            CustomSplitter::new(config, parse)
+                .chunks(text)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            chunks(TieBreaking::PreferSmaller),
+            vec!["aa", "bb cc", "dd", "ee"]
+        );
+        assert_eq!(chunks(TieBreaking::PreferLarger), vec!["aa bb cc", "dd ee"]);
+        assert_eq!(
+            chunks(TieBreaking::PreferHigherLevel),
+            vec!["aa bb cc", "dd ee"]
+        );
+    }
+
+    #[test]
+    fn ignores_invalid_ranges() {
+        let splitter = CustomSplitter::new(10, |_: &str| vec![(0, 1..2), (0, 2..100)]);
+        let ranges = SemanticSplitRanges::new(splitter.parse("éé"));
+
+        assert_eq!(Vec::<(CustomLevel, Range<usize>)>::new(), ranges.ranges);
+    }
+}
@@ -0,0 +1,508 @@
+/*!
+# [`DiffSplitter`]
+Semantic splitting of unified diffs and patch files. Files are the highest
+semantic level, followed by hunks and then individual lines, and hunk headers
+are never separated from their body.
+*/
+
+use std::ops::Range;
+
+use crate::{
+    splitter::{SemanticLevel, Splitter},
+    trim::Trim,
+    ChunkConfig, ChunkSizer,
+};
+
+/// Splitter for unified diffs, such as the output of `git diff` or `diff -u`,
+/// and patch files.
+///
+/// The splitter will first try to fit whole files into a chunk, then whole
+/// hunks, and finally individual lines. A file header (`diff --git`, `---`,
+/// `+++`, etc.) and a hunk header (`@@ ... @@`) are always kept together with
+/// at least the first line of the hunk that follows them, so every chunk that
+/// contains changes also says where they are.
+///
+/// Use [`DiffSplitter::chunks_with_metadata`] to get the paths and hunk line
+/// ranges each chunk belongs to, which is useful for chunks that don't
+/// contain the headers themselves.
+///
+/// ```
+/// use text_splitter::DiffSplitter;
+///
+/// let splitter = DiffSplitter::new(60);
+/// let text = "--- a/lib.rs\n+++ b/lib.rs\n@@ -1,2 +1,2 @@\n-old\n+new\n context";
+/// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+///
+/// assert_eq!(vec!["--- a/lib.rs\n+++ b/lib.rs\n@@ -1,2 +1,2 @@\n-old\n+new\n context"], chunks);
+/// ```
+#[derive(Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub struct DiffSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Method of determining chunk sizes.
+    chunk_config: ChunkConfig<Sizer>,
+}
+
+/// A file within a diff, as returned by [`DiffSplitter::chunks_with_metadata`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DiffFile<'text> {
+    /// Path of the file before the change, or `None` if the file was added.
+    pub old_path: Option<&'text str>,
+    /// Path of the file after the change, or `None` if the file was deleted.
+    pub new_path: Option<&'text str>,
+    /// Hunks of the file that are part of the chunk.
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// Line ranges of a hunk, from its `@@ -old_start,old_lines +new_start,new_lines @@` header.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DiffHunk {
+    /// First line of the hunk in the old file
+    pub old_start: usize,
+    /// Number of lines of the hunk in the old file
+    pub old_lines: usize,
+    /// First line of the hunk in the new file
+    pub new_start: usize,
+    /// Number of lines of the hunk in the new file
+    pub new_lines: usize,
+}
+
+impl<Sizer> DiffSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Creates a new [`DiffSplitter`].
+    ///
+    /// ```
+    /// use text_splitter::DiffSplitter;
+    ///
+    /// // By default, the chunk sizer is based on characters.
+    /// let splitter = DiffSplitter::new(512);
+    /// ```
+    #[must_use]
+    pub fn new(chunk_config: impl Into<ChunkConfig<Sizer>>) -> Self {
+        Self {
+            chunk_config: chunk_config.into(),
+        }
+    }
+
+    /// Generate a list of chunks from a given text. Each chunk will be up to
+    /// the `max_chunk_size`.
+    ///
+    /// ## Method
+    ///
+    /// To preserve as much semantic meaning within a chunk as possible, each chunk is composed of the largest semantic units that can fit in the next given chunk. For each splitter type, there is a defined set of semantic levels. Here is an example of the steps used:
+    ///
+    /// 1. Characters
+    /// 2. [Unicode Grapheme Cluster Boundaries](https://www.unicode.org/reports/tr29/#Grapheme_Cluster_Boundaries)
+    /// 3. [Unicode Word Boundaries](https://www.unicode.org/reports/tr29/#Word_Boundaries)
+    /// 4. [Unicode Sentence Boundaries](https://www.unicode.org/reports/tr29/#Sentence_Boundaries)
+    /// 5. Lines of a hunk
+    /// 6. Hunks, including their `@@` header
+    /// 7. Files, including their header
+    ///
+    /// Headers are never split from the first line that follows them.
+    ///
+    /// ```
+    /// use text_splitter::DiffSplitter;
+    ///
+    /// let splitter = DiffSplitter::new(40);
+    /// let text = "--- a/lib.rs\n+++ b/lib.rs\n@@ -1 +1 @@\n-a\n+b\n@@ -9 +9 @@\n-c\n+d";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(
+    ///     vec!["--- a/lib.rs\n+++ b/lib.rs\n@@ -1 +1 @@\n-a\n+b", "@@ -9 +9 @@\n-c\n+d"],
+    ///     chunks
+    /// );
+    /// ```
+    pub fn chunks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = &'text str> + 'splitter {
+        Splitter::<_>::chunks(self, text)
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets.
+    /// Each chunk will be up to the `max_chunk_size`.
+    ///
+    /// See [`DiffSplitter::chunks`] for more information.
+    pub fn chunk_indices<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter {
+        Splitter::<_>::chunk_indices(self, text)
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets,
+    /// along with the files and hunks that each chunk is a part of.
+    ///
+    /// Each file only contains the hunks that overlap with the chunk, so a
+    /// chunk in the middle of a large hunk still knows which file and lines
+    /// it belongs to.
+    ///
+    /// See [`DiffSplitter::chunks`] for more information.
+    ///
+    /// ```
+    /// use text_splitter::{DiffHunk, DiffSplitter};
+    ///
+    /// let splitter = DiffSplitter::new(40);
+    /// let text = "--- a/lib.rs\n+++ b/lib.rs\n@@ -1 +1 @@\n-a\n+b\n@@ -9 +9 @@\n-c\n+d";
+    /// let (_, chunk, files) = splitter.chunks_with_metadata(text).nth(1).unwrap();
+    ///
+    /// assert_eq!("@@ -9 +9 @@\n-c\n+d", chunk);
+    /// assert_eq!(Some("lib.rs"), files[0].new_path);
+    /// assert_eq!(
+    ///     vec![DiffHunk { old_start: 9, old_lines: 1, new_start: 9, new_lines: 1 }],
+    ///     files[0].hunks
+    /// );
+    /// ```
+    pub fn chunks_with_metadata<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str, Vec<DiffFile<'text>>)> + 'splitter {
+        let files = parse_diff(text);
+        self.chunk_indices(text).map(move |(offset, chunk)| {
+            let range = offset..offset + chunk.len();
+            let metadata = files
+                .iter()
+                .filter(|file| overlaps(&file.range, &range))
+                .map(|file| DiffFile {
+                    old_path: file.old_path,
+                    new_path: file.new_path,
+                    hunks: file
+                        .hunks
+                        .iter()
+                        .filter(|hunk| overlaps(&hunk.range, &range))
+                        .map(|hunk| hunk.hunk)
+                        .collect(),
+                })
+                .collect();
+            (offset, chunk, metadata)
+        })
+    }
+}
+
+impl<Sizer> Splitter<Sizer> for DiffSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    type Level = Element;
+
+    const TRIM: Trim = Trim::PreserveIndentation;
+
+    fn chunk_config(&self) -> &ChunkConfig<Sizer> {
+        &self.chunk_config
+    }
+
+    fn parse(&self, text: &str) -> Vec<(Self::Level, Range<usize>)> {
+        let mut ranges = vec![];
+        for file in parse_diff(text) {
+            ranges.push((Element::File, file.range));
+            for hunk in file.hunks {
+                ranges.push((Element::Hunk, hunk.range));
+                ranges.extend(hunk.lines.into_iter().map(|line| (Element::Line, line)));
+            }
+        }
+        ranges
+    }
+
+    fn atomic_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        parse_diff(text)
+            .into_iter()
+            .flat_map(|file| {
+                // Keep each header with the first line that follows it
+                let file_header = file.hunks.first().map_or(file.range.clone(), |hunk| {
+                    file.range.start..hunk.lines.first().map_or(hunk.range.end, |l| l.end)
+                });
+                let hunk_headers = file.hunks.into_iter().map(|hunk| {
+                    hunk.range.start..hunk.lines.first().map_or(hunk.range.end, |l| l.end)
+                });
+                std::iter::once(file_header).chain(hunk_headers)
+            })
+            .collect()
+    }
+}
+
+/// Different semantic levels that text can be split by.
+/// Each level provides a method of splitting text into chunks of a given level
+/// as well as a fallback in case a given fallback is too large.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Element {
+    /// A single line of a hunk
+    Line,
+    /// A hunk, including its `@@` header
+    Hunk,
+    /// All changes to a file, including its header
+    File,
+}
+
+impl SemanticLevel for Element {}
+
+/// A file of the diff, with byte ranges within the text.
+#[derive(Debug)]
+struct ParsedFile<'text> {
+    /// Byte range of the file, from the start of its header to its last hunk
+    range: Range<usize>,
+    old_path: Option<&'text str>,
+    new_path: Option<&'text str>,
+    hunks: Vec<ParsedHunk>,
+}
+
+/// A hunk of the diff, with byte ranges within the text.
+#[derive(Debug)]
+struct ParsedHunk {
+    /// Byte range of the hunk, including its header
+    range: Range<usize>,
+    /// Byte range of each line after the header
+    lines: Vec<Range<usize>>,
+    hunk: DiffHunk,
+}
+
+fn overlaps(a: &Range<usize>, b: &Range<usize>) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+/// Each line of the text along with its byte offset, without line endings.
+fn lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split_inclusive('\n').scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        Some((start, line.trim_end_matches(['\n', '\r'])))
+    })
+}
+
+/// Find the files and hunks of a unified diff. Any text before the first
+/// file, such as the headers of an email patch, is ignored.
+fn parse_diff(text: &str) -> Vec<ParsedFile<'_>> {
+    let mut files: Vec<ParsedFile<'_>> = vec![];
+    let mut lines = lines(text).peekable();
+    // Remaining old and new lines of the current hunk
+    let mut remaining = None;
+
+    while let Some((offset, line)) = lines.next() {
+        let end = offset + line.len();
+
+        if let Some((old, new)) = remaining {
+            let hunk_line = match line.as_bytes().first() {
+                Some(b'-') if old > 0 => Some((old - 1, new)),
+                Some(b'+') if new > 0 => Some((old, new - 1)),
+                Some(b' ') | None if old > 0 && new > 0 => Some((old - 1, new - 1)),
+                Some(b'\\') => Some((old, new)),
+                _ => None,
+            };
+            if let Some(counts) = hunk_line {
+                let file = files.last_mut().expect("hunks are always in a file");
+                let hunk = file.hunks.last_mut().expect("hunk was started");
+                hunk.lines.push(offset..end);
+                hunk.range.end = end;
+                file.range.end = end;
+                // Keep going for any `\ No newline at end of file` markers
+                remaining = (counts != (0, 0)
+                    || lines.peek().is_some_and(|(_, l)| l.starts_with('\\')))
+                .then_some(counts);
+                continue;
+            }
+            remaining = None;
+        }
+
+        if let Some(hunk) = line.strip_prefix("@@ ").and_then(parse_hunk_header) {
+            if let Some(file) = files.last_mut() {
+                file.hunks.push(ParsedHunk {
+                    range: offset..end,
+                    lines: vec![],
+                    hunk,
+                });
+                file.range.end = end;
+                remaining = Some((hunk.old_lines, hunk.new_lines));
+            }
+            continue;
+        }
+
+        let starts_file = line.starts_with("diff ")
+            || (line.starts_with("--- ")
+                && lines.peek().is_some_and(|(_, l)| l.starts_with("+++ "))
+                // `---` directly after a `diff` line is part of the same header
+                && files.last().map_or(true, |file| !file.hunks.is_empty()));
+        if starts_file {
+            files.push(ParsedFile {
+                range: offset..end,
+                old_path: None,
+                new_path: None,
+                hunks: vec![],
+            });
+            if let Some(paths) = line.strip_prefix("diff --git ") {
+                // Best guess for file headers without `---`/`+++` lines, like renames
+                if let Some((old, new)) = paths.split_once(" b/") {
+                    let file = files.last_mut().expect("file was just added");
+                    file.old_path = Some(old.strip_prefix("a/").unwrap_or(old));
+                    file.new_path = Some(new);
+                }
+            }
+        }
+
+        // Other header lines belong to the current file, if it has no hunks yet
+        let Some(file) = files.last_mut().filter(|file| file.hunks.is_empty()) else {
+            continue;
+        };
+        file.range.end = end;
+        if let Some(path) = line.strip_prefix("--- ") {
+            file.old_path = header_path(path, "a/");
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            file.new_path = header_path(path, "b/");
+        } else if let Some(path) = line.strip_prefix("rename from ") {
+            file.old_path = Some(path);
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            file.new_path = Some(path);
+        } else if line.starts_with("new file mode") {
+            file.old_path = None;
+        } else if line.starts_with("deleted file mode") {
+            file.new_path = None;
+        }
+    }
+
+    files
+}
+
+/// Path from a `---` or `+++` line, without any timestamp or `a/`/`b/`
+/// prefix. `/dev/null` means the file doesn't exist on that side.
+fn header_path<'text>(path: &'text str, prefix: &str) -> Option<&'text str> {
+    let path = path.split('\t').next().unwrap_or(path).trim_end();
+    (path != "/dev/null").then(|| path.strip_prefix(prefix).unwrap_or(path))
+}
+
+/// Parse the line ranges of a hunk header, after the leading `@@ `.
+fn parse_hunk_header(header: &str) -> Option<DiffHunk> {
+    let mut parts = header.split_whitespace();
+    let (old_start, old_lines) = parse_hunk_range(parts.next()?.strip_prefix('-')?)?;
+    let (new_start, new_lines) = parse_hunk_range(parts.next()?.strip_prefix('+')?)?;
+    (parts.next()? == "@@").then_some(DiffHunk {
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+    })
+}
+
+/// Parse a `start,lines` hunk range, where the number of lines defaults to 1.
+fn parse_hunk_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, lines)) => Some((start.parse().ok()?, lines.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "From 123 Mon Sep 17 00:00:00 2001
+Subject: [PATCH] Example
+
+diff --git a/src/lib.rs b/src/lib.rs
+index 83db48f..bf269f4 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@ fn main() {
+ fn main() {
+-    old();
++    new();
+ }
+diff --git a/README.md b/README.md
+deleted file mode 100644
+--- a/README.md
++++ /dev/null
+@@ -1 +0,0 @@
+-# Readme
+\\ No newline at end of file
+diff --git a/old.txt b/new.txt
+similarity index 100%
+rename from old.txt
+rename to new.txt
+";
+
+    #[test]
+    fn empty_string() {
+        let chunks = DiffSplitter::new(ChunkConfig::new(100).with_trim(false))
+            .chunks("")
+            .collect::<Vec<_>>();
+
+        assert!(chunks.is_empty());
+    }
+
+    #[test]
+    fn parses_files_and_hunks() {
+        let files = parse_diff(DIFF);
+
+        assert_eq!(
+            files
+                .iter()
+                .map(|f| (f.old_path, f.new_path, f.hunks.len()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some("src/lib.rs"), Some("src/lib.rs"), 1),
+                (Some("README.md"), None, 1),
+                (Some("old.txt"), Some("new.txt"), 0),
+            ]
+        );
+        assert!(DIFF[files[0].range.clone()].starts_with("diff --git a/src/lib.rs"));
+        assert!(DIFF[files[0].range.clone()].ends_with(" }"));
+        assert_eq!(
+            &DIFF[files[1].hunks[0].range.clone()],
+            "@@ -1 +0,0 @@\n-# Readme\n\\ No newline at end of file"
+        );
+        assert_eq!(
+            files[0].hunks[0].hunk,
+            DiffHunk {
+                old_start: 1,
+                old_lines: 3,
+                new_start: 1,
+                new_lines: 3
+            }
+        );
+    }
+
+    #[test]
+    fn plain_unified_diff() {
+        let text = "--- a.txt\t2024-01-01\n+++ b.txt\t2024-01-02\n@@ -1,2 +1,2 @@\n-a\n+b\n \n--- c.txt\n+++ c.txt\n@@ -5 +5 @@\n-c\n+d\n";
+        let files = parse_diff(text);
+
+        assert_eq!(
+            files
+                .iter()
+                .map(|f| (f.old_path, f.new_path, f.hunks[0].lines.len()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some("a.txt"), Some("b.txt"), 3),
+                (Some("c.txt"), Some("c.txt"), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn headers_stay_with_first_line() {
+        let splitter = DiffSplitter::new(ChunkConfig::new(20).with_trim(false));
+        let text = "--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n-aaaa\n+bbbb";
+
+        for chunk in splitter.chunks(text) {
+            assert!(!chunk.trim_end().ends_with("@@"), "{chunk:?}");
+            assert!(!chunk.trim_end().ends_with("b/x"), "{chunk:?}");
+        }
+    }
+
+    #[test]
+    fn metadata_for_chunks_within_a_hunk() {
+        let splitter = DiffSplitter::new(30);
+        let chunks = splitter.chunks_with_metadata(DIFF).collect::<Vec<_>>();
+
+        let (_, chunk, files) = chunks
+            .iter()
+            .find(|(_, chunk, _)| chunk.contains("new();"))
+            .unwrap();
+        assert!(!chunk.contains("@@"));
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].old_path, Some("src/lib.rs"));
+        assert_eq!(files[0].hunks.len(), 1);
+    }
+}
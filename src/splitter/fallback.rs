@@ -3,12 +3,22 @@ use std::sync::LazyLock;
 use auto_enums::auto_enum;
 use icu_segmenter::{GraphemeClusterSegmenter, SentenceSegmenter, WordSegmenter};
 use itertools::Itertools;
+use regex::Regex;
 use strum::EnumIter;
 
+use crate::SentenceBoundaries;
+
 pub static GRAPHEME_SEGMENTER: LazyLock<GraphemeClusterSegmenter> =
     LazyLock::new(GraphemeClusterSegmenter::new);
 static WORD_SEGMENTER: LazyLock<WordSegmenter> = LazyLock::new(WordSegmenter::new_dictionary);
-static SENTENCE_SEGMENTER: LazyLock<SentenceSegmenter> = LazyLock::new(SentenceSegmenter::new);
+pub static SENTENCE_SEGMENTER: LazyLock<SentenceSegmenter> = LazyLock::new(SentenceSegmenter::new);
+/// Abbreviations common in scientific text that don't end a sentence
+static SCIENTIFIC_ABBREVIATION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(?:^|[\s(\[])(?:et al|e\.g|i\.e|cf|vs|viz|approx|ca|resp|fig|figs|eq|eqs|ref|refs|tab|sec|ch|no|nos|vol|pp|suppl)\.\s*$").unwrap()
+});
+/// Numbered citation, like `[12]` or `[3, 5-7]`
+static CITATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\[\d+(?:\s*[,\-\x{2013}]\s*\d+)*\]\s*").unwrap());
 
 /// When using a custom semantic level, it is possible that none of them will
 /// be small enough to fit into the chunk size. In order to make sure we can
@@ -28,8 +38,35 @@ pub enum FallbackLevel {
 }
 
 impl FallbackLevel {
+    /// Sections of the text at this level. With `whole_words`, the word and
+    /// sentence levels are only split at whitespace.
+    pub fn sections(
+        self,
+        text: &str,
+        sentence_boundaries: SentenceBoundaries,
+        whole_words: bool,
+    ) -> impl Iterator<Item = (usize, &str)> {
+        let keep_words = whole_words && self >= Self::Word;
+        self.segments(text, sentence_boundaries).coalesce(
+            move |(a_offset, a_str), (b_offset, b_str)| {
+                if keep_words
+                    && !a_str.ends_with(char::is_whitespace)
+                    && !b_str.starts_with(char::is_whitespace)
+                {
+                    Ok((a_offset, &text[a_offset..b_offset + b_str.len()]))
+                } else {
+                    Err(((a_offset, a_str), (b_offset, b_str)))
+                }
+            },
+        )
+    }
+
     #[auto_enum(Iterator)]
-    pub fn sections(self, text: &str) -> impl Iterator<Item = (usize, &str)> {
+    fn segments(
+        self,
+        text: &str,
+        sentence_boundaries: SentenceBoundaries,
+    ) -> impl Iterator<Item = (usize, &str)> {
         match self {
             Self::Char => text.char_indices().map(move |(i, c)| {
                 (
@@ -45,6 +82,12 @@ impl FallbackLevel {
                 .segment_str(text)
                 .tuple_windows()
                 .map(|(i, j)| (i, &text[i..j])),
+            Self::Sentence if sentence_boundaries == SentenceBoundaries::Scientific => {
+                scientific_sentences(text)
+                    .into_iter()
+                    .tuple_windows()
+                    .map(|(i, j)| (i, &text[i..j]))
+            }
             Self::Sentence => SENTENCE_SEGMENTER
                 .segment_str(text)
                 .tuple_windows()
@@ -52,3 +95,128 @@ impl FallbackLevel {
         }
     }
 }
+
+/// Sections for the char level, which is only used if the first grapheme
+/// cluster of the text is too large for a chunk on its own. Only that cluster
+/// is split into chars, and the rest of the text into grapheme clusters, so
+/// that sequences like ZWJ emoji and flags are never cut into pieces unless
+/// they alone exceed the capacity.
+pub fn first_grapheme_chars(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut graphemes = GRAPHEME_SEGMENTER
+        .segment_str(text)
+        .tuple_windows()
+        .map(|(i, j)| (i, &text[i..j]));
+    let first = graphemes.next();
+    first
+        .into_iter()
+        .flat_map(|(_, grapheme)| {
+            grapheme
+                .char_indices()
+                .map(|(i, c)| (i, &grapheme[i..i + c.len_utf8()]))
+        })
+        .chain(graphemes)
+}
+
+/// Unicode sentence boundaries, adjusted so that abbreviations common in
+/// scientific text don't end a sentence, and numbered citations right after the
+/// end of a sentence stay with it.
+fn scientific_sentences(text: &str) -> Vec<usize> {
+    let mut boundaries = vec![0];
+    for mut boundary in SENTENCE_SEGMENTER.segment_str(text).skip(1) {
+        let last = boundaries.last().copied().unwrap_or_default();
+        // A citation can move the previous boundary past this one
+        if boundary <= last {
+            continue;
+        }
+        if boundary < text.len() {
+            if SCIENTIFIC_ABBREVIATION.is_match(&text[last..boundary]) {
+                continue;
+            }
+            // The opening bracket of a citation can end up in the previous
+            // sentence, since it is allowed to close a sentence.
+            let start = last + text[last..boundary].trim_end_matches('[').len();
+            if let Some(citation) = CITATION.find(&text[start..]) {
+                boundary = start + citation.end();
+            }
+        }
+        boundaries.push(boundary);
+    }
+    boundaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sentences(text: &str) -> Vec<&str> {
+        FallbackLevel::Sentence
+            .sections(text, SentenceBoundaries::Scientific, false)
+            .map(|(_, sentence)| sentence)
+            .collect()
+    }
+
+    #[test]
+    fn scientific_abbreviations() {
+        assert_eq!(
+            sentences("See Fig. 3 and Eq. 2. Smith et al. Found it, e.g. Here. Done."),
+            vec![
+                "See Fig. 3 and Eq. 2. ",
+                "Smith et al. Found it, e.g. Here. ",
+                "Done."
+            ]
+        );
+    }
+
+    #[test]
+    fn citations_stay_with_sentence() {
+        assert_eq!(
+            sentences("It was shown before.[12, 13] Next one. [4] Last.[5]"),
+            vec![
+                "It was shown before.[12, 13] ",
+                "Next one. [4] ",
+                "Last.[5]"
+            ]
+        );
+    }
+
+    #[test]
+    fn citation_followed_by_short_sentence() {
+        let text = "Shown.[12] \n\nGO\n?a.b";
+
+        assert_eq!(sentences(text).concat(), text);
+        assert_eq!(sentences(text)[0], "Shown.[12] \n\n");
+    }
+
+    #[test]
+    fn whole_words_only_split_at_whitespace() {
+        assert_eq!(
+            FallbackLevel::Word
+                .sections(
+                    "A state-of-the-art  result",
+                    SentenceBoundaries::Unicode,
+                    true
+                )
+                .map(|(_, word)| word)
+                .collect::<Vec<_>>(),
+            vec!["A", " ", "state-of-the-art", "  ", "result"]
+        );
+    }
+
+    #[test]
+    fn only_first_grapheme_split_into_chars() {
+        assert_eq!(
+            first_grapheme_chars("e\u{301}\u{302}\u{1F1EF}\u{1F1F5}x")
+                .map(|(_, section)| section)
+                .collect::<Vec<_>>(),
+            vec!["e", "\u{301}", "\u{302}", "\u{1F1EF}\u{1F1F5}", "x"]
+        );
+    }
+
+    #[test]
+    fn decimals() {
+        assert_eq!(
+            sentences("The value was 3.14 and 2.5. Then 0.5 more."),
+            vec!["The value was 3.14 and 2.5. ", "Then 0.5 more."]
+        );
+    }
+}
@@ -0,0 +1,211 @@
+/*!
+# [`FixedWidthSplitter`]
+Splitting of fixed-width records without delimiters, such as mainframe data
+feeds, where every record has the same length.
+*/
+
+use std::{iter::successors, ops::Range};
+
+use crate::{
+    splitter::{SemanticLevel, Splitter},
+    ChunkConfig, ChunkSizer,
+};
+
+/// Length of each record for the [`FixedWidthSplitter`]. A length of 0 is
+/// treated as 1.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RecordLength {
+    /// Each record is the given number of bytes long. If a record would end
+    /// within a multi-byte character, it ends after that character instead,
+    /// and the next record starts from there.
+    Bytes(usize),
+    /// Each record is the given number of characters long.
+    Chars(usize),
+}
+
+/// Splitter for fixed-width records without delimiters, such as mainframe
+/// data feeds. Packs as many whole records as fit into each chunk.
+///
+/// The last record may be shorter than the others, if the length of the text
+/// isn't a multiple of the record length. Records are never split, even if
+/// they are larger than the chunk capacity.
+///
+/// Since records are often padded with whitespace, chunks should usually not
+/// be trimmed, so that each chunk is made up of whole records.
+#[derive(Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub struct FixedWidthSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Method of determining chunk sizes.
+    chunk_config: ChunkConfig<Sizer>,
+    /// Length of each record
+    record_length: RecordLength,
+}
+
+impl<Sizer> FixedWidthSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Creates a new [`FixedWidthSplitter`] for records of the given length.
+    ///
+    /// ```
+    /// use text_splitter::{FixedWidthSplitter, RecordLength};
+    ///
+    /// // By default, the chunk sizer is based on characters.
+    /// let splitter = FixedWidthSplitter::new(RecordLength::Bytes(80), 512);
+    /// ```
+    #[must_use]
+    pub fn new(record_length: RecordLength, chunk_config: impl Into<ChunkConfig<Sizer>>) -> Self {
+        Self {
+            chunk_config: chunk_config.into(),
+            record_length,
+        }
+    }
+
+    /// Generate a list of chunks from a given text. Each chunk will be up to
+    /// the `max_chunk_size`.
+    ///
+    /// ## Method
+    ///
+    /// To preserve as much semantic meaning within a chunk as possible, each chunk is composed of the largest semantic units that can fit in the next given chunk. For each splitter type, there is a defined set of semantic levels. Here is an example of the steps used:
+    ///
+    /// 1. Records of the given length
+    ///
+    /// Records are never split, even if they are larger than the chunk capacity.
+    ///
+    /// ```
+    /// use text_splitter::{ChunkConfig, FixedWidthSplitter, RecordLength};
+    ///
+    /// let splitter = FixedWidthSplitter::new(
+    ///     RecordLength::Chars(10),
+    ///     ChunkConfig::new(25).with_trim(false),
+    /// );
+    /// let text = "0001Alice 0002Bob   0003Carol 0004Dan   ";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec!["0001Alice 0002Bob   ", "0003Carol 0004Dan   "], chunks);
+    /// ```
+    pub fn chunks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = &'text str> + 'splitter {
+        Splitter::<_>::chunks(self, text)
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets.
+    /// Each chunk will be up to the `max_chunk_size`.
+    ///
+    /// See [`FixedWidthSplitter::chunks`] for more information.
+    pub fn chunk_indices<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter {
+        Splitter::<_>::chunk_indices(self, text)
+    }
+}
+
+impl<Sizer> Splitter<Sizer> for FixedWidthSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    type Level = Element;
+
+    fn chunk_config(&self) -> &ChunkConfig<Sizer> {
+        &self.chunk_config
+    }
+
+    fn parse(&self, text: &str) -> Vec<(Self::Level, Range<usize>)> {
+        records(text, self.record_length)
+            .into_iter()
+            .map(|range| (Element::Record, range))
+            .collect()
+    }
+
+    fn atomic_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        records(text, self.record_length)
+    }
+}
+
+/// Different semantic levels that text can be split by.
+/// Each level provides a method of splitting text into chunks of a given level
+/// as well as a fallback in case a given fallback is too large.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Element {
+    /// A single record
+    Record,
+}
+
+impl SemanticLevel for Element {}
+
+/// Byte ranges of the records in the text.
+fn records(text: &str, record_length: RecordLength) -> Vec<Range<usize>> {
+    let next_end = |start: usize| -> usize {
+        match record_length {
+            RecordLength::Bytes(length) => {
+                let mut end = (start + length.max(1)).min(text.len());
+                while !text.is_char_boundary(end) {
+                    end += 1;
+                }
+                end
+            }
+            RecordLength::Chars(length) => text[start..]
+                .char_indices()
+                .nth(length.max(1))
+                .map_or(text.len(), |(offset, _)| start + offset),
+        }
+    };
+
+    successors(Some(0), |&start| {
+        (start < text.len()).then(|| next_end(start))
+    })
+    .collect::<Vec<_>>()
+    .windows(2)
+    .map(|window| window[0]..window[1])
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_string() {
+        let chunks = FixedWidthSplitter::new(
+            RecordLength::Bytes(10),
+            ChunkConfig::new(100).with_trim(false),
+        )
+        .chunks("")
+        .collect::<Vec<_>>();
+
+        assert!(chunks.is_empty());
+    }
+
+    #[test]
+    fn records_by_bytes() {
+        assert_eq!(
+            records("abcdefg", RecordLength::Bytes(3)),
+            [0..3, 3..6, 6..7]
+        );
+        // Records never end within a character
+        assert_eq!(records("aébc", RecordLength::Bytes(2)), [0..3, 3..5]);
+        assert_eq!(records("abc", RecordLength::Bytes(0)), [0..1, 1..2, 2..3]);
+    }
+
+    #[test]
+    fn records_by_chars() {
+        assert_eq!(records("aébcd", RecordLength::Chars(2)), [0..3, 3..5, 5..6]);
+    }
+
+    #[test]
+    fn oversized_records_are_not_split() {
+        let splitter =
+            FixedWidthSplitter::new(RecordLength::Chars(6), ChunkConfig::new(4).with_trim(false));
+
+        assert_eq!(
+            splitter.chunks("aaaaaabbbbbbcc").collect::<Vec<_>>(),
+            ["aaaaaa", "bbbbbb", "cc"]
+        );
+    }
+}
@@ -0,0 +1,179 @@
+/*!
+# [`LineSplitter`]
+Semantic splitting of line-based text, such as logs or CSV-like data, where
+each line is a unit of its own and sentences are meaningless.
+*/
+
+use std::{ops::Range, sync::LazyLock};
+
+use regex::Regex;
+
+use crate::{
+    splitter::{SemanticLevel, Splitter},
+    trim::Trim,
+    ChunkConfig, ChunkSizer,
+};
+
+/// A newline, followed by any blank lines, which may contain other whitespace
+static CAPTURE_LINE_SEPARATORS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:\r\n|\r|\n)(?:[^\S\r\n]*(?:\r\n|\r|\n))*").unwrap());
+
+/// Line-based text splitter, such as for logs or CSV-like data. Recursively
+/// splits chunks into the largest semantic units that fit within the chunk
+/// size. Also will attempt to merge neighboring chunks if they can fit within
+/// the given chunk size.
+///
+/// Lines are kept whole whenever they fit, and groups of lines separated by
+/// blank lines are kept together before that. Unlike the [`TextSplitter`](crate::TextSplitter),
+/// a line that doesn't fit is split by words, never by sentences.
+#[derive(Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub struct LineSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Method of determining chunk sizes.
+    chunk_config: ChunkConfig<Sizer>,
+}
+
+impl<Sizer> LineSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Creates a new [`LineSplitter`].
+    ///
+    /// ```
+    /// use text_splitter::LineSplitter;
+    ///
+    /// // By default, the chunk sizer is based on characters.
+    /// let splitter = LineSplitter::new(512);
+    /// ```
+    #[must_use]
+    pub fn new(chunk_config: impl Into<ChunkConfig<Sizer>>) -> Self {
+        Self {
+            chunk_config: chunk_config.into(),
+        }
+    }
+
+    /// Generate a list of chunks from a given text. Each chunk will be up to
+    /// the `max_chunk_size`.
+    ///
+    /// ## Method
+    ///
+    /// To preserve as much semantic meaning within a chunk as possible, each chunk is composed of the largest semantic units that can fit in the next given chunk. For each splitter type, there is a defined set of semantic levels. Here is an example of the steps used:
+    ///
+    /// 1. Characters
+    /// 2. [Unicode Grapheme Cluster Boundaries](https://www.unicode.org/reports/tr29/#Grapheme_Cluster_Boundaries)
+    /// 3. [Unicode Word Boundaries](https://www.unicode.org/reports/tr29/#Word_Boundaries)
+    /// 4. Lines
+    /// 5. Groups of lines separated by blank lines, by ascending number of blank lines between them. (Newline is `\r\n`, `\n`, or `\r`)
+    ///
+    /// Lines are never split by sentences, since log lines or rows of a CSV
+    /// file aren't prose. A line is only split by words if it doesn't fit in a
+    /// chunk by itself.
+    ///
+    /// Splitting doesn't occur below the character level, otherwise you could get partial bytes of a char, which may not be a valid unicode str.
+    ///
+    /// ```
+    /// use text_splitter::LineSplitter;
+    ///
+    /// let splitter = LineSplitter::new(32);
+    /// let text = "INFO started\nINFO listening\n\nWARN slow request\nERROR timeout";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(
+    ///     vec!["INFO started\nINFO listening", "WARN slow request\nERROR timeout"],
+    ///     chunks
+    /// );
+    /// ```
+    pub fn chunks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = &'text str> + 'splitter {
+        Splitter::<_>::chunks(self, text)
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets.
+    /// Each chunk will be up to the `max_chunk_size`.
+    ///
+    /// See [`LineSplitter::chunks`] for more information.
+    pub fn chunk_indices<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter {
+        Splitter::<_>::chunk_indices(self, text)
+    }
+}
+
+impl<Sizer> Splitter<Sizer> for LineSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    type Level = LineBreaks;
+
+    const TRIM: Trim = Trim::PreserveIndentation;
+
+    fn chunk_config(&self) -> &ChunkConfig<Sizer> {
+        &self.chunk_config
+    }
+
+    fn parse(&self, text: &str) -> Vec<(Self::Level, Range<usize>)> {
+        CAPTURE_LINE_SEPARATORS
+            .find_iter(text)
+            .map(|m| {
+                let newlines = m.as_str().matches('\n').count() + m.as_str().matches('\r').count()
+                    - m.as_str().matches("\r\n").count();
+                (LineBreaks(newlines), m.range())
+            })
+            .collect()
+    }
+}
+
+/// Sequence of a given number of newlines, with only whitespace between them.
+/// A single newline separates two lines, and each additional newline is a
+/// blank line between them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct LineBreaks(usize);
+
+impl SemanticLevel for LineBreaks {
+    const SENTENCE_FALLBACK: bool = false;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::splitter::SemanticSplitRanges;
+
+    use super::*;
+
+    #[test]
+    fn empty_string() {
+        let chunks = LineSplitter::new(ChunkConfig::new(100).with_trim(false))
+            .chunks("")
+            .collect::<Vec<_>>();
+
+        assert!(chunks.is_empty());
+    }
+
+    #[test]
+    fn blank_lines_with_whitespace() {
+        let splitter = LineSplitter::new(10);
+        let text = "a\r\nb\n  \n\nc";
+        let ranges = SemanticSplitRanges::new(splitter.parse(text));
+
+        assert_eq!(
+            ranges.ranges,
+            vec![(LineBreaks(1), 1..3), (LineBreaks(3), 4..9)]
+        );
+    }
+
+    #[test]
+    fn long_lines_are_not_split_by_sentences() {
+        let splitter = LineSplitter::new(ChunkConfig::new(20).with_trim(false));
+        let text = "Done. Retrying request now\nok";
+
+        assert_eq!(
+            splitter.chunks(text).collect::<Vec<_>>(),
+            ["Done. Retrying ", "request now\nok"]
+        );
+    }
+}
@@ -4,16 +4,15 @@ Semantic splitting of Markdown documents. Tries to use as many semantic units fr
 as possible, according to the Common Mark specification.
 */
 
-use std::{iter::once, ops::Range};
+use std::ops::Range;
 
-use either::Either;
 use itertools::Itertools;
-use pulldown_cmark::{Event, Options, Parser, Tag};
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
 
 use crate::{
-    splitter::{SemanticLevel, Splitter},
+    splitter::{MappedChunk, SemanticLevel, SemanticSplitPosition, Splitter},
     trim::Trim,
-    ChunkConfig, ChunkSizer,
+    ChunkConfig, ChunkSizer, ChunkTemplate,
 };
 
 /// Markdown splitter. Recursively splits chunks into the largest
@@ -21,13 +20,76 @@ use crate::{
 /// attempt to merge neighboring chunks if they can fit within the
 /// given chunk size.
 #[derive(Debug)]
-#[allow(clippy::module_name_repetitions)]
+#[allow(clippy::module_name_repetitions, clippy::struct_excessive_bools)]
 pub struct MarkdownSplitter<Sizer>
 where
     Sizer: ChunkSizer,
 {
     /// Method of determining chunk sizes.
     chunk_config: ChunkConfig<Sizer>,
+    /// Semantic levels that shouldn't be used as split points.
+    disabled_levels: Vec<Element>,
+    /// Whether each term in a definition list is kept together with its
+    /// definitions.
+    atomic_definition_lists: bool,
+    /// Whether MkDocs-style admonitions are treated as a single block.
+    admonitions: bool,
+    /// Whether math expressions should never be split internally.
+    atomic_math: bool,
+    /// Whether an image is kept together with the caption after it.
+    image_captions: bool,
+    /// Whether adjacent chunks of whole sections are merged in a second pass.
+    merged_sections: bool,
+}
+
+/// Whether a chunk starts or ends inside a block element, as returned by
+/// [`MarkdownSplitter::chunks_with_metadata`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MarkdownChunkMetadata {
+    /// Whether the chunk starts inside a block element, such as in the middle
+    /// of a list or table.
+    pub starts_mid_block: bool,
+    /// Whether the chunk ends inside a block element, and the rest of it is
+    /// in the next chunk.
+    pub ends_mid_block: bool,
+    /// Kinds of the blocks the chunk starts inside of, from the outermost to
+    /// the innermost.
+    pub start_blocks: Vec<MarkdownBlock>,
+    /// Kinds of the blocks the chunk ends inside of, from the outermost to
+    /// the innermost.
+    pub end_blocks: Vec<MarkdownBlock>,
+}
+
+/// Kinds of block elements that a chunk can start or end inside of.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum MarkdownBlock {
+    /// Paragraph
+    Paragraph,
+    /// Heading of any level
+    Heading,
+    /// Fenced or indented code block
+    CodeBlock,
+    /// Block quote
+    BlockQuote,
+    /// Ordered or unordered list
+    List,
+    /// Item of a list
+    ListItem,
+    /// Table
+    Table,
+    /// Header or row of a table
+    TableRow,
+    /// HTML block
+    Html,
+    /// Footnote definition
+    FootnoteDefinition,
+    /// Definition list
+    DefinitionList,
+    /// Front matter metadata block
+    Metadata,
+    /// Display math (`$$...$$`)
+    DisplayMath,
 }
 
 impl<Sizer> MarkdownSplitter<Sizer>
@@ -46,9 +108,176 @@ where
     pub fn new(chunk_config: impl Into<ChunkConfig<Sizer>>) -> Self {
         Self {
             chunk_config: chunk_config.into(),
+            disabled_levels: vec![],
+            atomic_definition_lists: false,
+            admonitions: false,
+            atomic_math: false,
+            image_captions: false,
+            merged_sections: false,
         }
     }
 
+    /// Ignore the given semantic levels when splitting. The text of these
+    /// elements is still included in the chunks, but the splitter no longer
+    /// treats them as semantic boundaries. This is useful if, for example,
+    /// `---` is only used decoratively in your documents.
+    ///
+    /// ```
+    /// use text_splitter::{MarkdownElement, MarkdownSplitter};
+    ///
+    /// let splitter = MarkdownSplitter::new(20).with_disabled_levels(&[MarkdownElement::Rule]);
+    /// let text = "Some text\n\n---\n\nfrom a document";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec!["Some text\n\n---", "from a document"], chunks);
+    /// ```
+    #[must_use]
+    pub fn with_disabled_levels(mut self, levels: &[Element]) -> Self {
+        self.disabled_levels = levels.to_vec();
+        self
+    }
+
+    /// Keep each term of a definition list together with its definitions, as
+    /// a single block. Otherwise the term and each definition are separate
+    /// blocks, and a chunk can end between a term and its definition.
+    ///
+    /// If a term and its definitions don't fit in a chunk, they are split by
+    /// the inline semantic levels instead.
+    ///
+    /// ```
+    /// use text_splitter::MarkdownSplitter;
+    ///
+    /// let splitter = MarkdownSplitter::new(25).with_atomic_definition_lists(true);
+    /// let text = "Apple\n: A red fruit\n\nBanana\n: A yellow fruit";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec!["Apple\n: A red fruit", "Banana\n: A yellow fruit"], chunks);
+    /// ```
+    #[must_use]
+    pub fn with_atomic_definition_lists(mut self, atomic_definition_lists: bool) -> Self {
+        self.atomic_definition_lists = atomic_definition_lists;
+        self
+    }
+
+    /// Treat [MkDocs-style admonitions](https://squidfunk.github.io/mkdocs-material/reference/admonitions/)
+    /// as a single block. Admonitions aren't part of the Commonmark spec, so
+    /// otherwise the `!!! note` line is parsed as its own paragraph, and the
+    /// indented content as a code block.
+    ///
+    /// An admonition starts with a line beginning with `!!!`, `???`, or
+    /// `???+`, and includes all of the indented lines that follow it. If it
+    /// doesn't fit in a chunk, it is split by the inline semantic levels
+    /// instead.
+    ///
+    /// ```
+    /// use text_splitter::MarkdownSplitter;
+    ///
+    /// let splitter = MarkdownSplitter::new(30).with_admonitions(true);
+    /// let text = "Some text\n\n!!! note\n\n    Read this first\n\nMore text";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec!["Some text", "!!! note\n\n    Read this first", "More text"], chunks);
+    /// ```
+    #[must_use]
+    pub fn with_admonitions(mut self, admonitions: bool) -> Self {
+        self.admonitions = admonitions;
+        self
+    }
+
+    /// Never split inline (`$...$`) or display (`$$...$$`) math expressions
+    /// internally, even if a math expression is larger than the chunk
+    /// capacity. Otherwise a long expression can be split by words or
+    /// characters like any other text.
+    ///
+    /// Chunks are already split around math expressions before splitting
+    /// within them, so this only changes which chunks are generated once a
+    /// single expression doesn't fit.
+    ///
+    /// ```
+    /// use text_splitter::MarkdownSplitter;
+    ///
+    /// let splitter = MarkdownSplitter::new(10).with_atomic_math(true);
+    /// let text = "Euler: $e^{i \\pi} + 1 = 0$";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec!["Euler:", "$e^{i \\pi} + 1 = 0$"], chunks);
+    /// ```
+    #[must_use]
+    pub fn with_atomic_math(mut self, atomic_math: bool) -> Self {
+        self.atomic_math = atomic_math;
+        self
+    }
+
+    /// Keep an image together with the caption after it, as a single block.
+    /// A caption is a paragraph that is entirely italic, right after a
+    /// paragraph that only contains an image. Otherwise a chunk can end
+    /// between the image and its caption.
+    ///
+    /// If an image and its caption don't fit in a chunk, they are split by
+    /// the inline semantic levels instead.
+    ///
+    /// ```
+    /// use text_splitter::MarkdownSplitter;
+    ///
+    /// let splitter = MarkdownSplitter::new(40).with_image_captions(true);
+    /// let text = "Some text\n\n![A cat](cat.png)\n\n_Figure 1: A cat_\n\nMore text";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(
+    ///     vec!["Some text", "![A cat](cat.png)\n\n_Figure 1: A cat_", "More text"],
+    ///     chunks
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_image_captions(mut self, image_captions: bool) -> Self {
+        self.image_captions = image_captions;
+        self
+    }
+
+    /// Merge adjacent chunks that each consist of whole sections, from a
+    /// heading up to the next one, in a second pass, if their combined size is at most the
+    /// desired capacity. Documents with many small sections otherwise often
+    /// end up with many small chunks, since a chunk ends before a section
+    /// that doesn't fit in it whole.
+    ///
+    /// Chunks are merged across the most deeply nested headings first, so
+    /// sibling sections under the same parent are merged before sections
+    /// under different parents. Chunks that overlap aren't merged.
+    ///
+    /// Which chunks are merged depends on all of the chunks before them, so
+    /// the whole text is chunked even by methods such as `take_chunks` that
+    /// otherwise only chunk as much of it as they need.
+    ///
+    /// ```
+    /// use text_splitter::MarkdownSplitter;
+    ///
+    /// let text = "# Intro\n\nHello.\n\n## Setup\n\nRun it.\n\n### Linux\n\nUse apt to install the tool, then restart.";
+    /// let chunks = MarkdownSplitter::new(60).chunks(text).collect::<Vec<_>>();
+    /// assert_eq!(
+    ///     vec!["# Intro\n\nHello.", "## Setup\n\nRun it.", "### Linux\n\nUse apt to install the tool, then restart."],
+    ///     chunks
+    /// );
+    ///
+    /// let splitter = MarkdownSplitter::new(60).with_merged_sections(true);
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    /// assert_eq!(
+    ///     vec!["# Intro\n\nHello.\n\n## Setup\n\nRun it.", "### Linux\n\nUse apt to install the tool, then restart."],
+    ///     chunks
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_merged_sections(mut self, merged_sections: bool) -> Self {
+        self.merged_sections = merged_sections;
+        self
+    }
+
+    /// The chunk configuration the splitter was created with, such as for
+    /// measuring text with the same sizer.
+    #[must_use]
+    pub fn chunk_config(&self) -> &ChunkConfig<Sizer> {
+        &self.chunk_config
+    }
+
     /// Generate a list of chunks from a given text. Each chunk will be up to
     /// the `max_chunk_size`.
     ///
@@ -105,6 +334,91 @@ where
     ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter {
         Splitter::<_>::chunk_indices(self, text)
     }
+
+    /// Returns an iterator over chunks of the text and their byte offsets,
+    /// along with whether each chunk starts or ends inside a block element,
+    /// and the kinds of those blocks. Useful for adding continuation markers
+    /// when rendering a chunk that starts in the middle of a list or table.
+    ///
+    /// Whitespace around a block doesn't count as being inside of it, so a
+    /// chunk that ends right after a paragraph doesn't end mid-block.
+    ///
+    /// See [`MarkdownSplitter::chunks`] for more information.
+    ///
+    /// ```
+    /// use text_splitter::{MarkdownBlock, MarkdownSplitter};
+    ///
+    /// let splitter = MarkdownSplitter::new(10);
+    /// let text = "- one\n- two\n- three";
+    /// let (_, chunk, metadata) = splitter.chunks_with_metadata(text).nth(2).unwrap();
+    ///
+    /// assert_eq!("- three", chunk);
+    /// assert!(metadata.starts_mid_block);
+    /// assert_eq!(vec![MarkdownBlock::List], metadata.start_blocks);
+    /// assert!(!metadata.ends_mid_block);
+    /// ```
+    pub fn chunks_with_metadata<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str, MarkdownChunkMetadata)> + 'splitter {
+        let mut blocks = block_ranges(text);
+        blocks.sort_by_key(|(_, range)| range.start);
+        // Chunk starts and ends each only increase, so each has its own cursor
+        let mut starts = OpenBlocks::new(blocks.clone());
+        let mut ends = OpenBlocks::new(blocks);
+        self.chunk_indices(text).map(move |(offset, chunk)| {
+            let end = offset + chunk.len();
+            let start_blocks = starts.inside(offset);
+            let end_blocks = ends.inside(end);
+            let metadata = MarkdownChunkMetadata {
+                starts_mid_block: !start_blocks.is_empty(),
+                ends_mid_block: !end_blocks.is_empty(),
+                start_blocks,
+                end_blocks,
+            };
+            (offset, chunk, metadata)
+        })
+    }
+
+    /// Returns an iterator over chunks of the text with the titles of the
+    /// headings they are under prepended as a breadcrumb, such as
+    /// `"Guide > Install\n\n"`, along with the range of the original text
+    /// each chunk came from. A chunk that starts with a heading only has the
+    /// headings it is nested under in its breadcrumb.
+    ///
+    /// The size of the breadcrumb counts towards the chunk capacity, so the
+    /// breadcrumb and the chunk together fit within the max capacity. If a
+    /// breadcrumb would take up more than half of the max capacity, the
+    /// longest heading titles are shortened with a `…` until it fits, or
+    /// left out if even that isn't enough. A sentence or section that fits
+    /// in a chunk by itself isn't split to make room for the breadcrumb.
+    /// Instead, the breadcrumb of that chunk is shortened further, or left
+    /// out.
+    ///
+    /// The breadcrumbs are the same as the `{breadcrumbs}` of
+    /// `chunks_with_template`, followed by a blank line if there are any.
+    ///
+    /// See [`MarkdownSplitter::chunks`] for more information.
+    ///
+    /// ```
+    /// use text_splitter::{MappedChunk, MarkdownSplitter};
+    ///
+    /// let splitter = MarkdownSplitter::new(40);
+    /// let text = "# Guide\n\n## Install\n\nRun the installer, then restart.";
+    /// let chunks = splitter.chunks_with_breadcrumbs(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(
+    ///     MappedChunk { range: 21..53, text: "G… > …\n\nRun the installer, then restart.".to_string() },
+    ///     chunks[2],
+    /// );
+    /// ```
+    pub fn chunks_with_breadcrumbs<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = MappedChunk> + 'splitter {
+        self.templated_chunks(text, &ChunkTemplate::breadcrumbs_and_chunk(), "\n\n")
+            .into_iter()
+    }
 }
 
 impl<Sizer> Splitter<Sizer> for MarkdownSplitter<Sizer>
@@ -119,9 +433,45 @@ where
         &self.chunk_config
     }
 
+    fn heading_trails(&self, text: &str) -> Vec<(usize, Vec<String>)> {
+        heading_trails(text)
+    }
+
+    fn merges_chunks(&self) -> bool {
+        self.merged_sections
+    }
+
+    fn merge_chunks<'text>(
+        &self,
+        text: &'text str,
+        chunks: Vec<(usize, &'text str)>,
+        fits: &dyn Fn(usize, &str) -> bool,
+    ) -> Vec<(usize, &'text str)> {
+        if self.merged_sections {
+            merge_sections(text, chunks, fits)
+        } else {
+            chunks
+        }
+    }
+
     fn parse(&self, text: &str) -> Vec<(Self::Level, Range<usize>)> {
-        Parser::new_ext(text, Options::all())
+        let events = Parser::new_ext(text, Options::all())
             .into_offset_iter()
+            .collect::<Vec<_>>();
+
+        let mut atomic_blocks = vec![];
+        if self.atomic_definition_lists {
+            atomic_blocks.extend(definition_list_items(&events));
+        }
+        if self.admonitions {
+            atomic_blocks.extend(admonitions(text, &events));
+        }
+        if self.image_captions {
+            atomic_blocks.extend(image_captions(text, &events));
+        }
+
+        let mut ranges = events
+            .into_iter()
             .filter_map(|(event, range)| match event {
                 Event::Start(
                     Tag::Emphasis
@@ -164,19 +514,322 @@ where
                 // End events are identical to start, so no need to grab them.
                 Event::End(_) => None,
             })
+            .collect::<Vec<_>>();
+
+        if !atomic_blocks.is_empty() {
+            // Remove any block-level items within an atomic block, so it can
+            // only be split by lower levels.
+            atomic_blocks.sort_unstable_by_key(|range| range.start);
+            ranges.retain(|(level, range)| {
+                let index = atomic_blocks.partition_point(|block| block.start <= range.start);
+                *level < Element::Block
+                    || index == 0
+                    || !(range.end <= atomic_blocks[index - 1].end
+                        && range != &atomic_blocks[index - 1])
+            });
+            ranges.extend(
+                atomic_blocks
+                    .into_iter()
+                    .map(|range| (Element::Block, range)),
+            );
+        }
+
+        ranges.retain(|(level, _)| !self.disabled_levels.contains(level));
+        ranges
+    }
+
+    fn atomic_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        if !self.atomic_math {
+            return vec![];
+        }
+        Parser::new_ext(text, Options::all())
+            .into_offset_iter()
+            .filter(|(event, _)| matches!(event, Event::InlineMath(_) | Event::DisplayMath(_)))
+            .map(|(_, range)| range)
+            .collect()
+    }
+}
+
+/// Ranges of each term in a definition list along with its definitions.
+fn definition_list_items(events: &[(Event<'_>, Range<usize>)]) -> Vec<Range<usize>> {
+    let mut items: Vec<Range<usize>> = vec![];
+    for (event, range) in events {
+        let is_title = match event {
+            Event::Start(Tag::DefinitionListTitle) => true,
+            Event::Start(Tag::DefinitionListDefinition) => false,
+            _ => continue,
+        };
+        match items.last_mut() {
+            // Nested within the current item
+            Some(item) if range.start < item.end => {}
+            Some(item) if !is_title => item.end = range.end,
+            _ => items.push(range.clone()),
+        }
+    }
+    items
+}
+
+/// Ranges of MkDocs-style admonitions: a `!!!`, `???`, or `???+` line at the
+/// start of a paragraph, and all of the indented or blank lines after it.
+fn admonitions(text: &str, events: &[(Event<'_>, Range<usize>)]) -> Vec<Range<usize>> {
+    let paragraph_starts = events
+        .iter()
+        .filter(|(event, _)| matches!(event, Event::Start(Tag::Paragraph)))
+        .map(|(_, range)| range.start)
+        .collect::<Vec<_>>();
+    let is_header = |line: &str| {
+        ["!!!", "???+", "???"].iter().any(|marker| {
+            line.strip_prefix(marker)
+                .is_some_and(|rest| rest.starts_with(' '))
+        })
+    };
+    let is_body =
+        |line: &str| line.trim().is_empty() || line.starts_with("    ") || line.starts_with('\t');
+
+    let mut lines = text
+        .split_inclusive('\n')
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some((start, line))
+        })
+        .peekable();
+    let mut admonitions = vec![];
+    while let Some((start, line)) = lines.next() {
+        if !is_header(line) || paragraph_starts.binary_search(&start).is_err() {
+            continue;
+        }
+        let mut end = start + line.trim_end().len();
+        while let Some((offset, line)) = lines.next_if(|(_, line)| is_body(line)) {
+            if !line.trim().is_empty() {
+                end = offset + line.trim_end().len();
+            }
+        }
+        admonitions.push(start..end);
+    }
+    admonitions
+}
+
+/// Ranges of a paragraph that only contains an image, together with the
+/// paragraph right after it, if that is entirely italic, as a caption.
+fn image_captions(text: &str, events: &[(Event<'_>, Range<usize>)]) -> Vec<Range<usize>> {
+    // Paragraphs can't be nested, so each one ends at the next paragraph end
+    let paragraphs = events
+        .iter()
+        .enumerate()
+        .filter(|(_, (event, _))| matches!(event, Event::Start(Tag::Paragraph)))
+        .filter_map(|(start, (_, range))| {
+            let end = start
+                + events[start..]
+                    .iter()
+                    .position(|(event, _)| matches!(event, Event::End(TagEnd::Paragraph)))?;
+            Some((start, end, range.clone()))
+        })
+        .collect::<Vec<_>>();
+    // Whether the paragraph consists of a single element of the given kind
+    let is_only = |(start, _, range): &(usize, usize, Range<usize>), tag: fn(&Tag<'_>) -> bool| {
+        events.get(start + 1).is_some_and(|(event, inner)| {
+            matches!(event, Event::Start(t) if tag(t))
+                && inner.start == range.start
+                && inner.end == range.start + text[range.clone()].trim_end().len()
+        })
+    };
+
+    paragraphs
+        .iter()
+        .tuple_windows()
+        .filter(|(image, caption)| {
+            caption.0 == image.1 + 1
+                && is_only(image, |tag| matches!(tag, Tag::Image { .. }))
+                && is_only(caption, |tag| matches!(tag, Tag::Emphasis))
+        })
+        .map(|(image, caption)| image.2.start..caption.2.end)
+        .collect()
+}
+
+/// Merge adjacent chunks that each consist of whole sections, if their
+/// combined chunk `fits`. Chunks are merged across
+/// the most deeply nested headings first, so that sibling sections are merged
+/// before sections under different parents.
+fn merge_sections<'text>(
+    text: &'text str,
+    mut chunks: Vec<(usize, &'text str)>,
+    fits: &dyn Fn(usize, &str) -> bool,
+) -> Vec<(usize, &'text str)> {
+    let headings = Parser::new_ext(text, Options::all())
+        .into_offset_iter()
+        .filter_map(|(event, range)| match event {
+            Event::Start(Tag::Heading { level, .. }) => Some((range.start, level.into())),
+            _ => None,
+        })
+        .collect::<Vec<(usize, HeadingLevel)>>();
+    let heading_at = |offset: usize| {
+        headings
+            .binary_search_by_key(&offset, |(start, _)| *start)
+            .ok()
+            .map(|index| headings[index].1)
+    };
+    // Starts at a heading, or is the text before the first heading, and
+    // ends right before the next heading or at the end of the text.
+    let is_whole = |start: usize, end: usize| {
+        let next = headings.partition_point(|(heading, _)| *heading < end);
+        let next = headings
+            .get(next)
+            .map_or(text.len(), |(heading, _)| *heading);
+        (heading_at(start).is_some() || text[..start].trim().is_empty())
+            && text[end..next].trim().is_empty()
+    };
+
+    for level in [
+        HeadingLevel::H6,
+        HeadingLevel::H5,
+        HeadingLevel::H4,
+        HeadingLevel::H3,
+        HeadingLevel::H2,
+        HeadingLevel::H1,
+    ] {
+        let mut merged: Vec<(usize, &str)> = Vec::with_capacity(chunks.len());
+        for (offset, chunk) in chunks {
+            let end = offset + chunk.len();
+            if let Some((last_offset, last_chunk)) = merged.last_mut() {
+                let last_end = *last_offset + last_chunk.len();
+                if last_end <= offset
+                    && heading_at(offset).is_some_and(|heading| heading <= level)
+                    && is_whole(*last_offset, last_end)
+                    && is_whole(offset, end)
+                {
+                    let combined = &text[*last_offset..end];
+                    if fits(*last_offset, combined) {
+                        *last_chunk = combined;
+                        continue;
+                    }
+                }
+            }
+            merged.push((offset, chunk));
+        }
+        chunks = merged;
+    }
+    chunks
+}
+
+/// Byte ranges of each block element, without surrounding whitespace, in
+/// document order, so outer blocks come before the blocks nested in them.
+fn block_ranges(text: &str) -> Vec<(MarkdownBlock, Range<usize>)> {
+    Parser::new_ext(text, Options::all())
+        .into_offset_iter()
+        .filter_map(|(event, range)| {
+            let block = match event {
+                Event::Start(Tag::Paragraph) => MarkdownBlock::Paragraph,
+                Event::Start(Tag::Heading { .. }) => MarkdownBlock::Heading,
+                Event::Start(Tag::CodeBlock(_)) => MarkdownBlock::CodeBlock,
+                Event::Start(Tag::BlockQuote(_)) => MarkdownBlock::BlockQuote,
+                Event::Start(Tag::List(_)) => MarkdownBlock::List,
+                Event::Start(Tag::Item) => MarkdownBlock::ListItem,
+                Event::Start(Tag::Table(_)) => MarkdownBlock::Table,
+                Event::Start(Tag::TableHead | Tag::TableRow) => MarkdownBlock::TableRow,
+                Event::Start(Tag::HtmlBlock) => MarkdownBlock::Html,
+                Event::Start(Tag::FootnoteDefinition(_)) => MarkdownBlock::FootnoteDefinition,
+                Event::Start(Tag::DefinitionList) => MarkdownBlock::DefinitionList,
+                Event::Start(Tag::MetadataBlock(_)) => MarkdownBlock::Metadata,
+                Event::DisplayMath(_) => MarkdownBlock::DisplayMath,
+                _ => return None,
+            };
+            let content = &text[range.clone()];
+            let start = range.start + (content.len() - content.trim_start().len());
+            let end = range.start + content.trim_end().len();
+            Some((block, start..end))
+        })
+        .collect()
+}
+
+/// The blocks that contain each of a sequence of increasing positions. Each
+/// block is only looked at when the positions reach its start and end, rather
+/// than for every position.
+struct OpenBlocks {
+    /// Blocks sorted by their start, in document order for equal starts
+    blocks: Vec<(MarkdownBlock, Range<usize>)>,
+    /// Index of the first block that doesn't start before the last position
+    next: usize,
+    /// Blocks that start before the last position, and haven't ended yet
+    open: Vec<(MarkdownBlock, Range<usize>)>,
+}
+
+impl OpenBlocks {
+    fn new(blocks: Vec<(MarkdownBlock, Range<usize>)>) -> Self {
+        Self {
+            blocks,
+            next: 0,
+            open: vec![],
+        }
+    }
+
+    /// The blocks that the position is inside of, outermost first. Positions
+    /// must not decrease between calls.
+    fn inside(&mut self, position: usize) -> Vec<MarkdownBlock> {
+        let started = self.blocks[self.next..].partition_point(|(_, range)| range.start < position);
+        self.open
+            .extend_from_slice(&self.blocks[self.next..self.next + started]);
+        self.next += started;
+        self.open.retain(|(_, range)| position < range.end);
+        self.open.iter().map(|(block, _)| *block).collect()
+    }
+}
+
+/// Titles of the headings that are in effect from each offset on, outermost
+/// first. A heading adds its own title from the end of it on, and from the
+/// start of it on, only the headings it is nested under are in effect.
+fn heading_trails(text: &str) -> Vec<(usize, Vec<String>)> {
+    let mut trails = vec![];
+    let mut stack: Vec<(HeadingLevel, String)> = vec![];
+    let mut in_heading = false;
+    let titles = |stack: &[(HeadingLevel, String)]| {
+        stack
+            .iter()
+            .map(|(_, title)| title.trim().to_string())
             .collect()
+    };
+    for (event, range) in Parser::new_ext(text, Options::all()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                let level = level.into();
+                // Headings of the same or a lower level end the previous sections
+                while stack.last().is_some_and(|(last, _)| *last <= level) {
+                    stack.pop();
+                }
+                trails.push((range.start, titles(&stack)));
+                stack.push((level, String::new()));
+                in_heading = true;
+            }
+            Event::Text(content) | Event::Code(content) if in_heading => {
+                if let Some((_, title)) = stack.last_mut() {
+                    title.push_str(&content);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                trails.push((range.end, titles(&stack)));
+                in_heading = false;
+            }
+            _ => {}
+        }
     }
+    trails
 }
 
 /// Heading levels in markdown.
 /// Sorted in reverse order for sorting purposes.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub enum HeadingLevel {
+    /// `######`
     H6,
+    /// `#####`
     H5,
+    /// `####`
     H4,
+    /// `###`
     H3,
+    /// `##`
     H2,
+    /// `#`
     H1,
 }
 
@@ -193,19 +846,11 @@ impl From<pulldown_cmark::HeadingLevel> for HeadingLevel {
     }
 }
 
-/// How a particular semantic level relates to surrounding text elements.
-#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
-enum SemanticSplitPosition {
-    /// The semantic level should be treated as its own chunk.
-    Own,
-    /// The semantic level should be included in the next chunk.
-    Next,
-}
-
 /// Different semantic levels that text can be split by.
 /// Each level provides a method of splitting text into chunks of a given level
 /// as well as a fallback in case a given fallback is too large.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[non_exhaustive]
 pub enum Element {
     /// Single line break, which isn't necessarily a new element in Markdown
     SoftBreak,
@@ -220,7 +865,7 @@ pub enum Element {
     Heading(HeadingLevel),
 }
 
-impl Element {
+impl SemanticLevel for Element {
     fn split_position(self) -> SemanticSplitPosition {
         match self {
             Self::SoftBreak | Self::Block | Self::Rule | Self::Inline => SemanticSplitPosition::Own,
@@ -237,81 +882,13 @@ impl Element {
     }
 }
 
-impl SemanticLevel for Element {
-    fn sections(
-        text: &str,
-        level_ranges: impl Iterator<Item = (Self, Range<usize>)>,
-    ) -> impl Iterator<Item = (usize, &str)> {
-        let mut cursor = 0;
-        let mut final_match = false;
-        level_ranges
-            .batching(move |it| {
-                loop {
-                    match it.next() {
-                        // If we've hit the end, actually return None
-                        None if final_match => return None,
-                        // First time we hit None, return the final section of the text
-                        None => {
-                            final_match = true;
-                            return text.get(cursor..).map(|t| Either::Left(once((cursor, t))));
-                        }
-                        // Return text preceding match + the match
-                        Some((level, range)) => {
-                            let offset = cursor;
-                            match level.split_position() {
-                                SemanticSplitPosition::Own => {
-                                    if range.start < cursor {
-                                        continue;
-                                    }
-                                    let prev_section = text
-                                        .get(cursor..range.start)
-                                        .expect("invalid character sequence");
-                                    if level.treat_whitespace_as_previous()
-                                        && prev_section.chars().all(char::is_whitespace)
-                                    {
-                                        let section = text
-                                            .get(cursor..range.end)
-                                            .expect("invalid character sequence");
-                                        cursor = range.end;
-                                        return Some(Either::Left(once((offset, section))));
-                                    }
-                                    let separator = text
-                                        .get(range.start..range.end)
-                                        .expect("invalid character sequence");
-                                    cursor = range.end;
-                                    return Some(Either::Right(
-                                        [(offset, prev_section), (range.start, separator)]
-                                            .into_iter(),
-                                    ));
-                                }
-                                SemanticSplitPosition::Next => {
-                                    if range.start < cursor {
-                                        continue;
-                                    }
-                                    let prev_section = text
-                                        .get(cursor..range.start)
-                                        .expect("invalid character sequence");
-                                    // Separator will be part of the next chunk
-                                    cursor = range.start;
-                                    return Some(Either::Left(once((offset, prev_section))));
-                                }
-                            }
-                        }
-                    }
-                }
-            })
-            .flatten()
-            .filter(|(_, s)| !s.is_empty())
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use std::cmp::min;
 
-    use fake::{Fake, Faker};
-
     use crate::splitter::SemanticSplitRanges;
+    use crate::{ChunkingBudget, SplitterExt, SplitterSession};
+    use fake::{Fake, Faker};
 
     use super::*;
 
@@ -768,6 +1345,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_with_disabled_rule() {
+        let splitter = MarkdownSplitter::new(10).with_disabled_levels(&[Element::Rule]);
+        let markdown = SemanticSplitRanges::new(splitter.parse("Some text\n\n---\n\nwith a rule"));
+
+        assert_eq!(
+            vec![
+                (Element::Block, 0..10),
+                (Element::Inline, 0..9),
+                (Element::Block, 16..27),
+                (Element::Inline, 16..27)
+            ],
+            markdown.ranges_after_offset(0).collect::<Vec<_>>()
+        );
+    }
+
     #[test]
     fn test_heading() {
         for (index, (heading, level)) in [
@@ -807,4 +1400,313 @@ mod tests {
                 .collect::<Vec<_>>()
         );
     }
+
+    #[test]
+    fn definition_list_items_are_atomic() {
+        let text = "Apple\n: A red fruit\n: Grows on trees\n\nBanana\n: A yellow fruit";
+        let splitter = MarkdownSplitter::new(10).with_atomic_definition_lists(true);
+        let markdown = SemanticSplitRanges::new(splitter.parse(text));
+
+        assert_eq!(
+            vec![(Element::Block, 0..38), (Element::Block, 38..61)],
+            markdown
+                .level_ranges_after_offset(0, Element::Block)
+                .collect::<Vec<_>>()
+        );
+
+        let chunks = MarkdownSplitter::new(40)
+            .with_atomic_definition_lists(true)
+            .chunks(text)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "Apple\n: A red fruit\n: Grows on trees",
+                "Banana\n: A yellow fruit"
+            ],
+            chunks
+        );
+    }
+
+    #[test]
+    fn admonitions_are_atomic() {
+        let text = "Intro\n\n!!! note \"Title\"\n\n    First paragraph.\n\n    Second paragraph.\n\n```\n!!! not an admonition\n```\n\nOutro";
+        let splitter = MarkdownSplitter::new(10).with_admonitions(true);
+        let markdown = SemanticSplitRanges::new(splitter.parse(text));
+
+        assert_eq!(
+            vec![(Element::Block, 0..6), (Element::Block, 7..68)],
+            markdown
+                .level_ranges_after_offset(0, Element::Block)
+                .take(2)
+                .collect::<Vec<_>>()
+        );
+
+        let chunks = MarkdownSplitter::new(65)
+            .with_admonitions(true)
+            .chunks(text)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "Intro",
+                "!!! note \"Title\"\n\n    First paragraph.\n\n    Second paragraph.",
+                "```\n!!! not an admonition\n```\n\nOutro"
+            ],
+            chunks
+        );
+    }
+
+    #[test]
+    fn images_are_kept_with_captions() {
+        let text = "Intro\n\n![Chart](chart.png)\n\n*Sales per year*\n\n![Logo](logo.png)\n\nNot *a* caption\n\nOutro";
+        let splitter = MarkdownSplitter::new(10).with_image_captions(true);
+        let markdown = SemanticSplitRanges::new(splitter.parse(text));
+
+        assert_eq!(
+            vec![
+                (Element::Block, 0..6),
+                (Element::Block, 7..45),
+                (Element::Block, 46..64)
+            ],
+            markdown
+                .level_ranges_after_offset(0, Element::Block)
+                .take(3)
+                .collect::<Vec<_>>()
+        );
+
+        let chunks = MarkdownSplitter::new(40)
+            .with_image_captions(true)
+            .chunks(text)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "Intro",
+                "![Chart](chart.png)\n\n*Sales per year*",
+                "![Logo](logo.png)\n\nNot *a* caption",
+                "Outro"
+            ],
+            chunks
+        );
+    }
+
+    #[test]
+    fn merged_sections_on_every_path() {
+        let text = "# Intro\n\nHello.\n\n## Setup\n\nRun it.\n\n### Linux\n\nUse apt to install the tool, then restart.";
+        let splitter = MarkdownSplitter::new(60).with_merged_sections(true);
+        let chunks = splitter.chunk_indices(text).collect::<Vec<_>>();
+        assert_eq!(chunks.len(), 2);
+
+        assert_eq!(chunks, splitter.take_chunks(text, 3).collect::<Vec<_>>());
+        assert_eq!(
+            chunks[..1],
+            splitter.take_chunks(text, 1).collect::<Vec<_>>()
+        );
+        assert_eq!(chunks[1..], splitter.tail_chunks(text, 1));
+        assert_eq!(chunks, splitter.chunk_indices_with_counters(text).0);
+        assert_eq!(
+            Ok(chunks.clone()),
+            splitter
+                .chunk_indices_within_budget(text, &ChunkingBudget::new())
+                .map_err(|_| ())
+        );
+        assert_eq!(
+            chunks,
+            splitter
+                .chunk_indices_with_session(text, &mut SplitterSession::new())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            chunks,
+            splitter
+                .chunks_with_diagnostics(text)
+                .map(|(offset, chunk, _)| (offset, chunk))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            chunks,
+            splitter
+                .chunk_indices_with_protected_ranges(text, &[])
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            chunks,
+            splitter
+                .chunk_indices_with_breaks(text, &[])
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            chunks,
+            splitter
+                .chunk_tree(text, &[ChunkConfig::new(60)])
+                .children
+                .into_iter()
+                .map(|node| (node.offset, node.text))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn merged_sections_prefer_siblings() {
+        let text = "## p2\n\nText.\n\n# Q\n\nMore.\n\n## q1\n\nLast.\n\n## q2\n\nLong";
+        let chunks = [
+            "## p2\n\nText.",
+            "# Q\n\nMore.",
+            "## q1\n\nLast.",
+            "## q2",
+            "Long",
+        ]
+        .into_iter()
+        .map(|chunk| (text.find(chunk).unwrap(), chunk))
+        .collect::<Vec<_>>();
+
+        let merged = merge_sections(text, chunks, &|_, chunk| chunk.chars().count() <= 30);
+
+        assert_eq!(
+            vec![
+                (0, "## p2\n\nText."),
+                (14, "# Q\n\nMore.\n\n## q1\n\nLast."),
+                (40, "## q2"),
+                (47, "Long")
+            ],
+            merged
+        );
+    }
+
+    #[test]
+    fn math_is_atomic() {
+        let text = "Before $a + b + c$ and $$\\sum_{i=0}^n i$$ after";
+
+        let chunks = MarkdownSplitter::new(ChunkConfig::new(6).with_trim(false))
+            .chunks(text)
+            .collect::<Vec<_>>();
+        assert!(chunks.contains(&"$a + b"));
+
+        let chunks = MarkdownSplitter::new(ChunkConfig::new(6).with_trim(false))
+            .with_atomic_math(true)
+            .chunks(text)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "Before",
+                " ",
+                "$a + b + c$",
+                " and ",
+                "$$\\sum_{i=0}^n i$$",
+                " after"
+            ],
+            chunks
+        );
+        assert_eq!(chunks.join(""), text);
+    }
+
+    #[test]
+    fn metadata_for_chunks_within_a_table() {
+        let text = "Intro\n\n| a | b |\n|---|---|\n| 1 | 2 |\n| 3 | 4 |\n\nOutro";
+        let splitter = MarkdownSplitter::new(20);
+        let chunks = splitter.chunks_with_metadata(text).collect::<Vec<_>>();
+
+        assert_eq!(chunks[0].1, "Intro");
+        assert_eq!(chunks[0].2, MarkdownChunkMetadata::default());
+
+        let (_, chunk, metadata) = chunks.iter().find(|(_, c, _)| c.contains("| 3")).unwrap();
+        assert!(!chunk.contains("| a"));
+        assert!(metadata.starts_mid_block);
+        assert_eq!(metadata.start_blocks, vec![MarkdownBlock::Table]);
+
+        assert_eq!(chunks.last().unwrap().2, MarkdownChunkMetadata::default());
+    }
+
+    #[test]
+    fn metadata_for_chunks_within_a_paragraph() {
+        let text = "First sentence. Second sentence.";
+        let splitter = MarkdownSplitter::new(20);
+        let chunks = splitter.chunks_with_metadata(text).collect::<Vec<_>>();
+
+        assert_eq!(chunks.len(), 2);
+        assert!(!chunks[0].2.starts_mid_block);
+        assert!(chunks[0].2.ends_mid_block);
+        assert_eq!(chunks[0].2.end_blocks, vec![MarkdownBlock::Paragraph]);
+        assert!(chunks[1].2.starts_mid_block);
+        assert!(!chunks[1].2.ends_mid_block);
+    }
+
+    #[test]
+    fn breadcrumbs_fit_within_capacity() {
+        let text = "# Guide\n\nIntro text.\n\n## Install\n\nRun the installer. Then restart the machine.\n\n### Linux\n\nUse the package manager.\n\n## Usage\n\nOpen the app.";
+        let splitter = MarkdownSplitter::new(60);
+        let chunks = splitter.chunks_with_breadcrumbs(text).collect::<Vec<_>>();
+
+        for chunk in &chunks {
+            assert!(chunk.text.chars().count() <= 60, "{chunk:?}");
+            assert!(chunk.text.ends_with(&text[chunk.range.clone()]));
+        }
+        let linux = chunks.iter().find(|c| c.text.contains("package")).unwrap();
+        assert_eq!(
+            linux.text,
+            "Guide > Install\n\n### Linux\n\nUse the package manager."
+        );
+        let usage = chunks.iter().find(|c| c.text.contains("Open")).unwrap();
+        assert_eq!(usage.text, "Guide\n\n## Usage\n\nOpen the app.");
+    }
+
+    #[test]
+    fn templates_fit_within_capacity() {
+        let text = "# Guide\n\nIntro text.\n\n## Install\n\nRun the installer. Then restart the machine.\n\n### Linux\n\nUse the package manager.\n\n## Usage\n\nOpen the app.";
+        let splitter = MarkdownSplitter::new(100);
+        let template = ChunkTemplate::new("{breadcrumbs}\n\n{chunk}\n\n[source: {doc_id}#{index}]")
+            .unwrap()
+            .with_field("doc_id", "guide.md");
+        let chunks = splitter.chunks_with_template(text, &template).unwrap();
+
+        for chunk in &chunks {
+            assert!(chunk.text.chars().count() <= 100, "{chunk:?}");
+            assert!(chunk.text.contains(&text[chunk.range.clone()]));
+        }
+        let linux = chunks.iter().find(|c| c.text.contains("package")).unwrap();
+        assert!(linux
+            .text
+            .starts_with("Guide > Install\n\n### Linux\n\nUse the package manager."));
+        assert!(linux.text.ends_with("[source: guide.md#2]"));
+    }
+
+    #[test]
+    fn long_heading_titles_are_shortened() {
+        let text = "# A very long title for the whole document\n\n## Short\n\nSome text here.";
+        let splitter = MarkdownSplitter::new(40);
+        let chunk = splitter
+            .chunks_with_breadcrumbs(text)
+            .find(|c| c.text.ends_with("Some text here."))
+            .unwrap();
+
+        assert_eq!(chunk.text, "A very lon…\n\n## Short\n\nSome text here.");
+    }
+
+    #[test]
+    fn breadcrumbs_shortening_within_a_chunk() {
+        // Chunks under `### Linux` have a longer breadcrumb than the text
+        // after `## Usage`, so the size of a chunk has to include the
+        // breadcrumb of where it starts, not of where it ends. A sentence or
+        // section that fits by itself is kept whole, with a shorter
+        // breadcrumb, or none, instead of splitting it.
+        let text = "# Guide\n\nIntro text.\n\n## Install\n\nRun the installer. Then restart the machine.\n\n### Linux\n\nUse the package manager.\n\n## Usage\n\nOpen the app.";
+        let splitter = MarkdownSplitter::new(41);
+        let chunks = splitter.chunks_with_breadcrumbs(text).collect::<Vec<_>>();
+
+        for chunk in &chunks {
+            assert!(chunk.text.chars().count() <= 41, "{chunk:?}");
+        }
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|c| (c.range.clone(), c.text.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (0..20, "# Guide\n\nIntro text."),
+                (22..32, "Guide\n\n## Install"),
+                (34..52, "Guide > Install\n\nRun the installer."),
+                (53..78, "Guide > Ins…\n\nThen restart the machine."),
+                (80..115, "### Linux\n\nUse the package manager."),
+                (117..140, "Guide\n\n## Usage\n\nOpen the app."),
+            ]
+        );
+    }
 }
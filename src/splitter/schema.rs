@@ -0,0 +1,393 @@
+/*!
+# Schema presets for [`CustomSplitter`]
+Semantic splitting of API schema and IDL files, such as Protocol Buffers,
+GraphQL SDL, and Thrift, where type definitions are the primary semantic unit.
+*/
+
+use std::ops::Range;
+
+use crate::{ChunkConfig, ChunkSizer, CustomSplitter};
+
+/// Schema languages with a ready-made preset for the [`CustomSplitter`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SchemaLanguage {
+    /// Protocol Buffers (`.proto`)
+    Protobuf,
+    /// GraphQL schema definition language
+    GraphQl,
+    /// Apache Thrift IDL
+    Thrift,
+}
+
+/// Parse function used by the schema presets
+type SchemaParse = fn(&str) -> Vec<(usize, Range<usize>)>;
+
+impl<Sizer> CustomSplitter<Sizer, SchemaParse>
+where
+    Sizer: ChunkSizer,
+{
+    /// Creates a new [`CustomSplitter`] for schema and IDL files, such as
+    /// Protocol Buffers, GraphQL SDL, or Thrift.
+    ///
+    /// Top-level definitions (messages, types, services, enums, etc.) are the
+    /// highest semantic level, followed by nested definitions (like nested
+    /// messages, `oneof`s, or RPCs with options), and then individual fields.
+    /// Comments and descriptions directly above an item are kept with it.
+    ///
+    /// The schema is not validated, only the structure of braces, comments,
+    /// and field separators is used.
+    ///
+    /// ```
+    /// use text_splitter::{CustomSplitter, SchemaLanguage};
+    ///
+    /// let splitter = CustomSplitter::schema(SchemaLanguage::Protobuf, 45);
+    /// let text = "// A user\nmessage User {\n  string name = 1;\n}\n\nmessage Empty {}";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(
+    ///     vec!["// A user\nmessage User {\n  string name = 1;\n}", "message Empty {}"],
+    ///     chunks
+    /// );
+    /// ```
+    #[must_use]
+    pub fn schema(language: SchemaLanguage, chunk_config: impl Into<ChunkConfig<Sizer>>) -> Self {
+        let parse: SchemaParse = match language {
+            SchemaLanguage::Protobuf => |text| parse_schema(text, &PROTOBUF),
+            SchemaLanguage::GraphQl => |text| parse_schema(text, &GRAPHQL),
+            SchemaLanguage::Thrift => |text| parse_schema(text, &THRIFT),
+        };
+        Self::new(chunk_config, parse)
+    }
+}
+
+/// Level of top-level definitions
+const DEFINITION: usize = 2;
+/// Level of definitions nested within another one
+const NESTED_DEFINITION: usize = 1;
+/// Level of fields and other items without a body
+const FIELD: usize = 0;
+
+/// Syntax differences between schema languages
+#[derive(Debug)]
+struct Syntax {
+    /// `#` starts a line comment
+    hash_comments: bool,
+    /// A newline ends a field, rather than requiring `;` or `,`
+    newline_ends_field: bool,
+    /// Strings can be single quoted
+    single_quotes: bool,
+}
+
+const PROTOBUF: Syntax = Syntax {
+    hash_comments: false,
+    newline_ends_field: false,
+    single_quotes: true,
+};
+
+const GRAPHQL: Syntax = Syntax {
+    hash_comments: true,
+    newline_ends_field: true,
+    single_quotes: false,
+};
+
+const THRIFT: Syntax = Syntax {
+    hash_comments: true,
+    newline_ends_field: true,
+    single_quotes: true,
+};
+
+/// An item that is still being parsed
+#[derive(Debug)]
+struct OpenItem {
+    /// Start of the item, including any comments before it
+    start: usize,
+    /// Whether the item only contains comments so far
+    comment_only: bool,
+    /// Whether the item has a body in braces
+    has_body: bool,
+}
+
+/// Find the definitions and fields in a schema, along with their levels.
+#[allow(clippy::too_many_lines)]
+fn parse_schema(text: &str, syntax: &Syntax) -> Vec<(usize, Range<usize>)> {
+    let bytes = text.as_bytes();
+    let mut ranges = vec![];
+    // Item currently being parsed at each brace depth
+    let mut items: Vec<Option<OpenItem>> = vec![None];
+    // Nesting of parentheses, brackets, and angle brackets in the current item
+    let mut nesting = 0usize;
+    // End of the last non-whitespace character
+    let mut last_end = 0;
+    let mut i = 0;
+
+    let close =
+        |ranges: &mut Vec<(usize, Range<usize>)>, items: &mut Vec<Option<OpenItem>>, end: usize| {
+            let depth = items.len() - 1;
+            if let Some(item) = items.last_mut().and_then(Option::take) {
+                let level = match (depth, item.has_body) {
+                    (0, _) => DEFINITION,
+                    (_, true) => NESTED_DEFINITION,
+                    (_, false) => FIELD,
+                };
+                ranges.push((level, item.start..end));
+            }
+        };
+    let open = |items: &mut Vec<Option<OpenItem>>, start: usize, comment: bool| {
+        let item = items
+            .last_mut()
+            .expect("always at least one depth")
+            .get_or_insert(OpenItem {
+                start,
+                comment_only: comment,
+                has_body: false,
+            });
+        item.comment_only &= comment;
+    };
+    // End of any comment on the rest of the line, so it stays with the item
+    let trailing_comment = |i: usize| {
+        let rest = &text[i..];
+        let comment = rest.trim_start_matches([' ', '\t']);
+        let is_comment =
+            comment.starts_with("//") || (syntax.hash_comments && comment.starts_with('#'));
+        is_comment.then(|| i + rest.find('\n').unwrap_or(rest.len()))
+    };
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        let rest = &text[i..];
+        match c {
+            b'\n' => {
+                let blank_line = text[last_end..i].contains('\n');
+                let item = items.last().and_then(Option::as_ref);
+                let depth = items.len() - 1;
+                if let Some(item) = item {
+                    let ends = if depth == 0 {
+                        blank_line
+                    } else {
+                        (syntax.newline_ends_field && nesting == 0 && !item.comment_only)
+                            || (item.comment_only && blank_line)
+                    };
+                    if ends {
+                        close(&mut ranges, &mut items, last_end);
+                        nesting = 0;
+                    }
+                }
+                i += 1;
+                continue;
+            }
+            _ if c.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            _ if rest.starts_with("//") || (syntax.hash_comments && c == b'#') => {
+                open(&mut items, i, true);
+                i += rest.find('\n').unwrap_or(rest.len());
+            }
+            _ if rest.starts_with("/*") => {
+                open(&mut items, i, true);
+                i += rest[2..].find("*/").map_or(rest.len(), |end| end + 4);
+            }
+            b'"' | b'\'' if c == b'"' || syntax.single_quotes => {
+                open(&mut items, i, false);
+                i += string_len(rest);
+            }
+            b'{' => {
+                open(&mut items, i, false);
+                if let Some(item) = items.last_mut().and_then(Option::as_mut) {
+                    item.has_body = true;
+                }
+                items.push(None);
+                nesting = 0;
+                i += 1;
+            }
+            b'}' => {
+                close(&mut ranges, &mut items, last_end);
+                if items.len() > 1 {
+                    items.pop();
+                }
+                i += 1;
+                let end = trailing_comment(i).unwrap_or(i);
+                close(&mut ranges, &mut items, end);
+                nesting = 0;
+                last_end = end;
+                i = end;
+                continue;
+            }
+            b'(' | b'[' | b'<' => {
+                open(&mut items, i, false);
+                nesting += 1;
+                i += 1;
+            }
+            b')' | b']' | b'>' => {
+                nesting = nesting.saturating_sub(1);
+                i += 1;
+            }
+            b';' | b',' if nesting == 0 => {
+                i += 1;
+                let end = trailing_comment(i).unwrap_or(i);
+                if items.last().is_some_and(Option::is_some) {
+                    close(&mut ranges, &mut items, end);
+                }
+                last_end = end;
+                i = end;
+                continue;
+            }
+            _ => {
+                open(&mut items, i, false);
+                i += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+        last_end = i;
+    }
+
+    while !items.is_empty() {
+        close(&mut ranges, &mut items, last_end);
+        items.pop();
+    }
+    ranges
+}
+
+/// Length of the string literal at the start of `text`, including quotes.
+/// Unterminated strings end at the end of the line.
+fn string_len(text: &str) -> usize {
+    if let Some(rest) = text.strip_prefix(r#"""""#) {
+        return rest.find(r#"""""#).map_or(text.len(), |end| end + 6);
+    }
+    let quote = text.as_bytes()[0];
+    let mut escaped = false;
+    for (i, c) in text.bytes().enumerate().skip(1) {
+        match c {
+            b'\n' => return i,
+            _ if escaped => escaped = false,
+            b'\\' => escaped = true,
+            _ if c == quote => return i + 1,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items<'text>(text: &'text str, syntax: &Syntax) -> Vec<(usize, &'text str)> {
+        let mut ranges = parse_schema(text, syntax);
+        ranges.sort_by_key(|(_, range)| (range.start, range.end));
+        ranges
+            .into_iter()
+            .map(|(level, range)| (level, &text[range]))
+            .collect()
+    }
+
+    #[test]
+    fn protobuf_definitions() {
+        let text = r#"syntax = "proto3";
+
+// A user
+message User {
+  string name = 1; // Full name
+  map<string, int32> scores = 2 [deprecated = true];
+  message Address {
+    string city = 1;
+  }
+  oneof contact { string email = 3; }
+}
+
+service Users {
+  rpc Get(User)
+    returns (User);
+}"#;
+
+        assert_eq!(
+            items(text, &PROTOBUF),
+            vec![
+                (DEFINITION, "syntax = \"proto3\";"),
+                (DEFINITION, &text[20..text.find("\n\nservice").unwrap()]),
+                (FIELD, "string name = 1; // Full name"),
+                (FIELD, "map<string, int32> scores = 2 [deprecated = true];"),
+                (
+                    NESTED_DEFINITION,
+                    "message Address {\n    string city = 1;\n  }"
+                ),
+                (FIELD, "string city = 1;"),
+                (NESTED_DEFINITION, "oneof contact { string email = 3; }"),
+                (FIELD, "string email = 3;"),
+                (DEFINITION, &text[text.find("service").unwrap()..]),
+                (FIELD, "rpc Get(User)\n    returns (User);"),
+            ]
+        );
+    }
+
+    #[test]
+    fn graphql_definitions() {
+        let text = r#"scalar Date
+
+"""
+A user
+"""
+type User @key(fields: "id") {
+  # The id
+  id: ID!
+  posts(first: Int,
+        after: String): [Post]
+}"#;
+
+        assert_eq!(
+            items(text, &GRAPHQL),
+            vec![
+                (DEFINITION, "scalar Date"),
+                (DEFINITION, &text[13..]),
+                (FIELD, "# The id\n  id: ID!"),
+                (FIELD, "posts(first: Int,\n        after: String): [Post]"),
+            ]
+        );
+    }
+
+    #[test]
+    fn thrift_definitions() {
+        let text = "namespace py example\n\nenum Status {\n  ACTIVE = 1,\n  INACTIVE = 2\n}\n\nstruct User {\n  1: required string name\n  2: map<string, i32> scores;\n}";
+
+        assert_eq!(
+            items(text, &THRIFT),
+            vec![
+                (DEFINITION, "namespace py example"),
+                (
+                    DEFINITION,
+                    "enum Status {\n  ACTIVE = 1,\n  INACTIVE = 2\n}"
+                ),
+                (FIELD, "ACTIVE = 1,"),
+                (FIELD, "INACTIVE = 2"),
+                (
+                    DEFINITION,
+                    "struct User {\n  1: required string name\n  2: map<string, i32> scores;\n}"
+                ),
+                (FIELD, "1: required string name"),
+                (FIELD, "2: map<string, i32> scores;"),
+            ]
+        );
+    }
+
+    #[test]
+    fn strings_and_comments_with_braces() {
+        let text = "message A {\n  /* } */\n  string b = 1 [default = \"}\"];\n}\nmessage C {}";
+
+        assert_eq!(
+            items(text, &PROTOBUF),
+            vec![
+                (DEFINITION, &text[..text.find("\nmessage C").unwrap()]),
+                (FIELD, "/* } */\n  string b = 1 [default = \"}\"];"),
+                (DEFINITION, "message C {}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn string_lengths() {
+        assert_eq!(string_len(r#""a\"b" c"#), 6);
+        assert_eq!(string_len("'a' b"), 3);
+        assert_eq!(string_len("\"\"\"a\n\"b\"\n\"\"\" c"), 12);
+        assert_eq!(string_len("\"open\nnext"), 5);
+    }
+}
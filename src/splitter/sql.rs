@@ -0,0 +1,619 @@
+/*!
+# [`SqlSplitter`]
+Semantic splitting of SQL scripts and dumps. Statements are never split
+internally, except for large `INSERT` statements, which can be split between
+their rows.
+*/
+
+use std::ops::Range;
+
+use crate::{
+    splitter::{SemanticLevel, Splitter},
+    ChunkConfig, ChunkSizer,
+};
+
+/// Splitter for SQL scripts and dumps.
+///
+/// Each statement is kept whole, along with the comments directly above it,
+/// so chunks are always made up of complete statements. Batches, either
+/// separated by `GO` lines or wrapped in a transaction (`BEGIN` ... `COMMIT`),
+/// are a higher level than statements, so the splitter will first try to fit
+/// whole batches into a chunk.
+///
+/// The only statements that can be split are `INSERT` statements with
+/// multiple rows in their `VALUES` list, which are split between rows instead
+/// of at arbitrary characters.
+///
+/// Statements end with `;`, unless the terminator is changed with a
+/// `DELIMITER` line, like in `MySQL`. Strings, quoted identifiers, comments,
+/// and `PostgreSQL` dollar-quoted bodies are skipped, so function bodies stay in a
+/// single statement. This isn't a validating parser, so it works with any SQL
+/// dialect.
+///
+/// ```
+/// use text_splitter::SqlSplitter;
+///
+/// let splitter = SqlSplitter::new(30);
+/// let text = "CREATE TABLE t (id INT);\nINSERT INTO t VALUES (1), (2);";
+/// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+///
+/// assert_eq!(
+///     vec!["CREATE TABLE t (id INT);", "INSERT INTO t VALUES (1), (2);"],
+///     chunks
+/// );
+/// ```
+#[derive(Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub struct SqlSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Method of determining chunk sizes.
+    chunk_config: ChunkConfig<Sizer>,
+}
+
+impl<Sizer> SqlSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Creates a new [`SqlSplitter`].
+    ///
+    /// ```
+    /// use text_splitter::SqlSplitter;
+    ///
+    /// // By default, the chunk sizer is based on characters.
+    /// let splitter = SqlSplitter::new(512);
+    /// ```
+    #[must_use]
+    pub fn new(chunk_config: impl Into<ChunkConfig<Sizer>>) -> Self {
+        Self {
+            chunk_config: chunk_config.into(),
+        }
+    }
+
+    /// Generate a list of chunks from a given text. Each chunk will be up to
+    /// the `max_chunk_size`.
+    ///
+    /// ## Method
+    ///
+    /// To preserve as much semantic meaning within a chunk as possible, each chunk is composed of the largest semantic units that can fit in the next given chunk. For each splitter type, there is a defined set of semantic levels. Here is an example of the steps used:
+    ///
+    /// 1. Rows of an `INSERT` statement's `VALUES` list
+    /// 2. Statements, including any comments directly above them
+    /// 3. Batches, separated by `GO` lines or wrapped in a transaction
+    ///
+    /// Statements are never split internally other than between rows, even
+    /// if they are larger than the chunk capacity.
+    ///
+    /// ```
+    /// use text_splitter::SqlSplitter;
+    ///
+    /// let splitter = SqlSplitter::new(35);
+    /// let text = "INSERT INTO t VALUES\n  (1, 'one'),\n  (2, 'two');";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec!["INSERT INTO t VALUES\n  (1, 'one'),", "(2, 'two');"], chunks);
+    /// ```
+    pub fn chunks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = &'text str> + 'splitter {
+        Splitter::<_>::chunks(self, text)
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets.
+    /// Each chunk will be up to the `max_chunk_size`.
+    ///
+    /// See [`SqlSplitter::chunks`] for more information.
+    pub fn chunk_indices<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter {
+        Splitter::<_>::chunk_indices(self, text)
+    }
+}
+
+impl<Sizer> Splitter<Sizer> for SqlSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    type Level = Element;
+
+    fn chunk_config(&self) -> &ChunkConfig<Sizer> {
+        &self.chunk_config
+    }
+
+    fn parse(&self, text: &str) -> Vec<(Self::Level, Range<usize>)> {
+        let script = Script::parse(text);
+        script
+            .batches
+            .into_iter()
+            .map(|range| (Element::Batch, range))
+            .chain(script.statements.into_iter().flat_map(|statement| {
+                let rows = statement.rows.into_iter().map(|row| (Element::Row, row));
+                std::iter::once((Element::Statement, statement.range)).chain(rows)
+            }))
+            .collect()
+    }
+
+    fn atomic_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        Script::parse(text)
+            .statements
+            .into_iter()
+            .flat_map(
+                |statement| match (statement.rows.first(), statement.rows.last()) {
+                    // Only allow splits between rows
+                    (Some(first), Some(last)) if statement.rows.len() > 1 => {
+                        let rows = statement.rows.clone();
+                        std::iter::once(statement.range.start..first.start)
+                            .chain(rows)
+                            .chain(Some(last.end..statement.range.end))
+                            .collect::<Vec<_>>()
+                    }
+                    _ => vec![statement.range],
+                },
+            )
+            .collect()
+    }
+}
+
+/// Different semantic levels that text can be split by.
+/// Each level provides a method of splitting text into chunks of a given level
+/// as well as a fallback in case a given fallback is too large.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Element {
+    /// A row of an `INSERT` statement's `VALUES` list
+    Row,
+    /// A single statement, including any comments directly above it
+    Statement,
+    /// Statements separated by `GO` lines, or wrapped in a transaction
+    Batch,
+}
+
+impl SemanticLevel for Element {}
+
+/// Role of a statement within a transaction
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Transaction {
+    /// `BEGIN` or `START TRANSACTION`
+    Begin,
+    /// `COMMIT`, `ROLLBACK`, or `END`
+    End,
+    /// Any other statement
+    None,
+}
+
+/// A statement in the script.
+#[derive(Debug)]
+struct Statement {
+    /// Byte range of the statement, including comments directly above it
+    range: Range<usize>,
+    /// Whether the statement starts or ends a transaction
+    transaction: Transaction,
+    /// Rows of the `VALUES` list, if this is an `INSERT` statement
+    rows: Vec<Range<usize>>,
+}
+
+/// A statement that is still being parsed
+#[derive(Debug)]
+struct OpenStatement {
+    /// Start of the statement, including any comments before it
+    start: usize,
+    /// The first few keywords of the statement, uppercased
+    keywords: Vec<String>,
+    /// Number of words in the statement
+    words: usize,
+    /// Nesting of parentheses
+    depth: usize,
+    /// Whether the `VALUES` keyword of an `INSERT` statement was found
+    in_values: bool,
+    /// Rows of the `VALUES` list, and the start of the current one
+    rows: Vec<Range<usize>>,
+    /// Start of the row currently being parsed
+    row_start: Option<usize>,
+}
+
+impl OpenStatement {
+    fn new(start: usize) -> Self {
+        Self {
+            start,
+            keywords: vec![],
+            words: 0,
+            depth: 0,
+            in_values: false,
+            rows: vec![],
+            row_start: None,
+        }
+    }
+
+    /// Record the next word of the statement
+    fn word(&mut self, word: &str) {
+        self.words += 1;
+        if self.keywords.len() < 2 {
+            self.keywords.push(word.to_ascii_uppercase());
+        }
+        if self.depth == 0
+            && word.eq_ignore_ascii_case("values")
+            && matches!(
+                self.keywords.first().map(String::as_str),
+                Some("INSERT" | "REPLACE")
+            )
+        {
+            self.in_values = true;
+        }
+    }
+
+    fn close(self, end: usize) -> Statement {
+        let keywords = self.keywords.iter().map(String::as_str).collect::<Vec<_>>();
+        let transaction = match (keywords.as_slice(), self.words) {
+            (["BEGIN"], 1)
+            | (["BEGIN", "WORK" | "TRANSACTION" | "TRAN"] | ["START", "TRANSACTION"], 2) => {
+                Transaction::Begin
+            }
+            (["COMMIT" | "ROLLBACK" | "END"], 1)
+            | (["COMMIT" | "ROLLBACK" | "END", "WORK" | "TRANSACTION" | "TRAN"], 2) => {
+                Transaction::End
+            }
+            _ => Transaction::None,
+        };
+        Statement {
+            range: self.start..end,
+            transaction,
+            rows: self.rows,
+        }
+    }
+}
+
+/// Statements and batches of a SQL script
+#[derive(Debug)]
+struct Script {
+    statements: Vec<Statement>,
+    batches: Vec<Range<usize>>,
+}
+
+impl Script {
+    /// Find the statements and batches of a script.
+    #[allow(clippy::too_many_lines)]
+    fn parse(text: &str) -> Self {
+        let bytes = text.as_bytes();
+        let mut statements = vec![];
+        let mut go_batches = vec![];
+        let mut batch_start = 0;
+        let mut current: Option<OpenStatement> = None;
+        let mut delimiter = String::from(";");
+        // End of the last non-whitespace character
+        let mut last_end = 0;
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let rest = &text[i..];
+            let line_end = i + rest.find('\n').unwrap_or(rest.len());
+
+            // Client commands that are only recognized on their own line
+            if i == 0 || bytes[i - 1] == b'\n' {
+                let line = text[i..line_end].trim();
+                if is_go(line) {
+                    if let Some(statement) = current.take() {
+                        statements.push(statement.close(last_end));
+                    }
+                    go_batches.push(batch_start..line_end);
+                    batch_start = line_end;
+                    last_end = line_end;
+                    i = line_end;
+                    continue;
+                }
+                if let Some(new_delimiter) = line
+                    .get(..10)
+                    .filter(|d| d.eq_ignore_ascii_case("delimiter "))
+                    .map(|_| line[10..].trim())
+                    .filter(|d| !d.is_empty())
+                {
+                    if let Some(statement) = current.take() {
+                        statements.push(statement.close(last_end));
+                    }
+                    let start = i + (text[i..].len() - text[i..].trim_start().len());
+                    statements.push(
+                        OpenStatement::new(start).close(i + text[i..line_end].trim_end().len()),
+                    );
+                    delimiter = new_delimiter.to_string();
+                    last_end = line_end;
+                    i = line_end;
+                    continue;
+                }
+            }
+
+            let c = bytes[i];
+            if c.is_ascii_whitespace() {
+                i += 1;
+                continue;
+            }
+            if rest.starts_with(&delimiter) {
+                i += delimiter.len();
+                let after = &text[i..];
+                let comment = after.trim_start_matches([' ', '\t']);
+                if comment.starts_with("--") || comment.starts_with("/*") {
+                    let comment_start = i + after.len() - comment.len();
+                    i = comment_start + comment_len(comment);
+                }
+                let statement = current.take().unwrap_or_else(|| OpenStatement::new(i));
+                statements.push(statement.close(i));
+                last_end = i;
+                continue;
+            }
+
+            let statement = current.get_or_insert_with(|| OpenStatement::new(i));
+            if rest.starts_with("--") || rest.starts_with("/*") {
+                i += comment_len(rest);
+            } else if matches!(c, b'\'' | b'"' | b'`') {
+                i += quoted_len(rest);
+            } else if c == b'$'
+                && (i == 0 || !is_word_byte(bytes[i - 1]))
+                && dollar_tag(rest).is_some()
+            {
+                i += dollar_quoted_len(rest);
+            } else if c == b'(' {
+                if statement.in_values && statement.depth == 0 {
+                    statement.row_start = Some(i);
+                }
+                statement.depth += 1;
+                i += 1;
+            } else if c == b')' {
+                statement.depth = statement.depth.saturating_sub(1);
+                i += 1;
+                if statement.depth == 0 {
+                    if let Some(start) = statement.row_start.take() {
+                        statement.rows.push(start..i);
+                    }
+                }
+            } else if is_word_byte(c) {
+                let len = rest.bytes().take_while(|&b| is_word_byte(b)).count();
+                statement.word(&rest[..len]);
+                i += len;
+            } else {
+                i += rest.chars().next().map_or(1, char::len_utf8);
+            }
+            last_end = i;
+        }
+
+        if let Some(statement) = current.take() {
+            statements.push(statement.close(last_end));
+        }
+        if !go_batches.is_empty() && batch_start < last_end {
+            go_batches.push(batch_start..last_end);
+        }
+
+        // Transaction blocks are batches as well
+        let mut batches = go_batches;
+        let mut begin = None;
+        for statement in &statements {
+            match statement.transaction {
+                Transaction::Begin => begin = Some(statement.range.start),
+                Transaction::End => {
+                    if let Some(start) = begin.take() {
+                        batches.push(start..statement.range.end);
+                    }
+                }
+                Transaction::None => {}
+            }
+        }
+
+        Self {
+            statements,
+            batches,
+        }
+    }
+}
+
+/// Whether the line is a `GO` batch separator, optionally with a count.
+fn is_go(line: &str) -> bool {
+    let mut words = line.split_whitespace();
+    words.next().is_some_and(|go| go.eq_ignore_ascii_case("go"))
+        && words
+            .next()
+            .map_or(true, |count| count.parse::<usize>().is_ok())
+        && words.next().is_none()
+}
+
+fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// Length of the comment at the start of `text`. Line comments don't include
+/// the newline.
+fn comment_len(text: &str) -> usize {
+    if let Some(rest) = text.strip_prefix("/*") {
+        rest.find("*/").map_or(text.len(), |end| end + 4)
+    } else {
+        text.find('\n').unwrap_or(text.len())
+    }
+}
+
+/// Length of the quoted string or identifier at the start of `text`,
+/// including quotes. Quotes are escaped by doubling them.
+fn quoted_len(text: &str) -> usize {
+    let quote = text.as_bytes()[0];
+    let bytes = text.as_bytes();
+    let mut i = 1;
+    while i < bytes.len() {
+        if bytes[i] == quote {
+            if bytes.get(i + 1) == Some(&quote) {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    text.len()
+}
+
+/// The tag of a dollar quote (`$$` or `$tag$`) at the start of `text`
+fn dollar_tag(text: &str) -> Option<&str> {
+    let len = text[1..].bytes().take_while(|&b| is_word_byte(b)).count();
+    let tag = text.get(..len + 2)?;
+    (tag.ends_with('$') && !tag.as_bytes().get(1).is_some_and(u8::is_ascii_digit)).then_some(tag)
+}
+
+/// Length of the dollar-quoted string at the start of `text`, including the
+/// tags.
+fn dollar_quoted_len(text: &str) -> usize {
+    let Some(tag) = dollar_tag(text) else {
+        return 1;
+    };
+    text[tag.len()..]
+        .find(tag)
+        .map_or(text.len(), |end| end + 2 * tag.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::splitter::SemanticSplitRanges;
+
+    use super::*;
+
+    fn statements(text: &str) -> Vec<&str> {
+        Script::parse(text)
+            .statements
+            .into_iter()
+            .map(|s| &text[s.range])
+            .collect()
+    }
+
+    #[test]
+    fn empty_string() {
+        let chunks = SqlSplitter::new(ChunkConfig::new(100).with_trim(false))
+            .chunks("")
+            .collect::<Vec<_>>();
+
+        assert!(chunks.is_empty());
+    }
+
+    #[test]
+    fn statements_with_comments_and_strings() {
+        let text = "-- Users\nCREATE TABLE users (name TEXT DEFAULT ';'); -- done\n/* ; */ SELECT \"a;b\" FROM users\n";
+
+        assert_eq!(
+            statements(text),
+            vec![
+                "-- Users\nCREATE TABLE users (name TEXT DEFAULT ';'); -- done",
+                "/* ; */ SELECT \"a;b\" FROM users",
+            ]
+        );
+    }
+
+    #[test]
+    fn dollar_quoted_bodies() {
+        let text = "CREATE FUNCTION f() RETURNS int AS $body$\nBEGIN\n  RETURN 1;\nEND;\n$body$ LANGUAGE plpgsql;\nSELECT $1;";
+
+        assert_eq!(
+            statements(text),
+            vec![
+                "CREATE FUNCTION f() RETURNS int AS $body$\nBEGIN\n  RETURN 1;\nEND;\n$body$ LANGUAGE plpgsql;",
+                "SELECT $1;",
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_delimiter() {
+        let text =
+            "DELIMITER //\nCREATE PROCEDURE p() BEGIN SELECT 1; END //\nDELIMITER ;\nSELECT 2;";
+
+        assert_eq!(
+            statements(text),
+            vec![
+                "DELIMITER //",
+                "CREATE PROCEDURE p() BEGIN SELECT 1; END //",
+                "DELIMITER ;",
+                "SELECT 2;",
+            ]
+        );
+    }
+
+    #[test]
+    fn insert_rows() {
+        let text = "INSERT INTO t (a, b) VALUES (1, f(2)), ('x)', 3);";
+        let script = Script::parse(text);
+
+        assert_eq!(
+            script.statements[0]
+                .rows
+                .iter()
+                .map(|r| &text[r.clone()])
+                .collect::<Vec<_>>(),
+            vec!["(1, f(2))", "('x)', 3)"]
+        );
+    }
+
+    #[test]
+    fn go_batches() {
+        let text = "SELECT 1\nSELECT 2\nGO\nSELECT 3;\ngo 2\nSELECT 4";
+        let splitter = SqlSplitter::new(10);
+        let ranges = SemanticSplitRanges::new(splitter.parse(text));
+
+        assert_eq!(
+            ranges
+                .ranges
+                .iter()
+                .filter(|(level, _)| *level == Element::Batch)
+                .map(|(_, r)| &text[r.clone()])
+                .collect::<Vec<_>>(),
+            vec!["SELECT 1\nSELECT 2\nGO", "\nSELECT 3;\ngo 2", "\nSELECT 4"]
+        );
+        assert_eq!(
+            statements(text),
+            vec!["SELECT 1\nSELECT 2", "SELECT 3;", "SELECT 4"]
+        );
+    }
+
+    #[test]
+    fn transaction_batches() {
+        let text = "BEGIN;\nUPDATE a SET b = 1;\nCOMMIT;\nSELECT 1;";
+        let script = Script::parse(text);
+
+        assert_eq!(
+            script
+                .batches
+                .iter()
+                .map(|r| &text[r.clone()])
+                .collect::<Vec<_>>(),
+            vec!["BEGIN;\nUPDATE a SET b = 1;\nCOMMIT;"]
+        );
+    }
+
+    #[test]
+    fn statements_are_atomic() {
+        let splitter = SqlSplitter::new(10);
+        let text = "SELECT a, b, c FROM t;\nSELECT 1;";
+        let chunks = splitter.chunks(text).collect::<Vec<_>>();
+
+        assert_eq!(vec!["SELECT a, b, c FROM t;", "SELECT 1;"], chunks);
+    }
+
+    #[test]
+    fn go_detection() {
+        assert!(is_go("GO"));
+        assert!(is_go("go 10"));
+        assert!(!is_go("GOTO x"));
+        assert!(!is_go("go away"));
+    }
+
+    #[test]
+    fn trailing_dollar() {
+        let splitter = SqlSplitter::new(1);
+        let text = "SELECT $";
+
+        assert_eq!(
+            splitter.chunk_indices(text).collect::<Vec<_>>(),
+            vec![(0, text)]
+        );
+    }
+
+    #[test]
+    fn dollar_before_non_ascii() {
+        let splitter = SqlSplitter::new(1);
+        let text = "$\u{e9}";
+
+        assert_eq!(splitter.chunks(text).collect::<Vec<_>>(), vec![text]);
+    }
+}
@@ -5,14 +5,16 @@ Semantic splitting of text documents.
 
 use std::{ops::Range, sync::LazyLock};
 
+use auto_enums::auto_enum;
 use itertools::Itertools;
 use regex::Regex;
 
 use crate::{
-    splitter::{SemanticLevel, Splitter},
-    ChunkConfig, ChunkSizer,
+    splitter::{MappedChunk, RangeIter, SemanticLevel, Splitter},
+    ChunkConfig, ChunkSizer, OffsetMap,
 };
 
+#[cfg(not(feature = "simd"))]
 use super::fallback::GRAPHEME_SEGMENTER;
 
 /// Default plain-text splitter. Recursively splits chunks into the largest
@@ -26,6 +28,10 @@ where
 {
     /// Method of determining chunk sizes.
     chunk_config: ChunkConfig<Sizer>,
+    /// Whether to parse the separators lazily, as chunks are generated.
+    lazy_parsing: bool,
+    /// Which separators are used as semantic levels above sentences.
+    separators: Separators,
 }
 
 impl<Sizer> TextSplitter<Sizer>
@@ -44,9 +50,92 @@ where
     pub fn new(chunk_config: impl Into<ChunkConfig<Sizer>>) -> Self {
         Self {
             chunk_config: chunk_config.into(),
+            lazy_parsing: false,
+            separators: Separators::LineBreaks,
         }
     }
 
+    /// Whether to split by sequences of newlines before falling back to
+    /// sentences, words, graphemes, and characters. Defaults to `true`.
+    ///
+    /// When disabled, newlines are treated like any other whitespace and
+    /// chunks are packed with as many sentences as will fit.
+    ///
+    /// ```
+    /// use text_splitter::TextSplitter;
+    ///
+    /// let splitter = TextSplitter::new(30).with_line_breaks(false);
+    /// let text = "Some text\n\nfrom a document. Another sentence.";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec!["Some text\n\nfrom a document.", "Another sentence."], chunks);
+    /// ```
+    #[must_use]
+    pub fn with_line_breaks(mut self, line_breaks: bool) -> Self {
+        self.separators = if line_breaks {
+            Separators::LineBreaks
+        } else {
+            Separators::None
+        };
+        self
+    }
+
+    /// Use a custom pattern to find paragraph separators, instead of sequences
+    /// of newlines.
+    ///
+    /// Each match of the pattern is a separator. Consecutive matches are
+    /// grouped together, and the number of matches in a group is its semantic
+    /// level, just like a sequence of two newlines is a higher level than a
+    /// single newline.
+    ///
+    /// ```
+    /// use regex::Regex;
+    /// use text_splitter::TextSplitter;
+    ///
+    /// let splitter = TextSplitter::new(20)
+    ///     .with_separators(Regex::new(r"\u{2029}|<PARA>").unwrap());
+    /// let text = "Some text<PARA>from a\ndocument\u{2029}More text";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec!["Some text<PARA>", "from a\ndocument", "More text"], chunks);
+    /// ```
+    #[must_use]
+    pub fn with_separators(mut self, separators: Regex) -> Self {
+        self.separators = Separators::Custom(separators);
+        self
+    }
+
+    /// Whether to find separators lazily while generating chunks, rather than
+    /// finding all of them up front. Defaults to `false`.
+    ///
+    /// Only the separators needed for the next chunk are kept in memory, which
+    /// reduces memory usage on very large texts, such as when chunking a
+    /// memory-mapped file. The chunks are the same either way, but more chunk
+    /// sizes are measured to know how far ahead to look, so this is mostly
+    /// useful with a cheap chunk sizer, such as [`Characters`](crate::Characters).
+    ///
+    /// ```
+    /// use text_splitter::TextSplitter;
+    ///
+    /// let splitter = TextSplitter::new(10).with_lazy_parsing(true);
+    /// let text = "Some text\n\nfrom a\ndocument";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec!["Some text", "from a", "document"], chunks);
+    /// ```
+    #[must_use]
+    pub fn with_lazy_parsing(mut self, lazy_parsing: bool) -> Self {
+        self.lazy_parsing = lazy_parsing;
+        self
+    }
+
+    /// The chunk configuration the splitter was created with, such as for
+    /// measuring text with the same sizer.
+    #[must_use]
+    pub fn chunk_config(&self) -> &ChunkConfig<Sizer> {
+        &self.chunk_config
+    }
+
     /// Generate a list of chunks from a given text. Each chunk will be up to the `chunk_capacity`.
     ///
     /// ## Method
@@ -104,6 +193,45 @@ where
     ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter {
         Splitter::<_>::chunk_indices(self, text)
     }
+
+    /// Returns chunks of hard-wrapped text, such as from e-books or extracted
+    /// PDFs, with the single line breaks within each paragraph joined, along
+    /// with the byte range of the original text that each chunk came from.
+    ///
+    /// A single line break, and any spaces or tabs around it, is replaced by a
+    /// space. If a word was hyphenated at the end of the line and continues in
+    /// lowercase on the next one, the hyphen is removed as well, so
+    /// `"para-\ngraph"` becomes `"paragraph"`. Two or more line breaks in a
+    /// row still separate paragraphs. Chunk sizes are measured on the joined
+    /// text.
+    ///
+    /// See [`TextSplitter::chunks`] for more information.
+    ///
+    /// ```
+    /// use text_splitter::{MappedChunk, TextSplitter};
+    ///
+    /// let splitter = TextSplitter::new(40);
+    /// let text = "A hard-wrapped para-\ngraph of\ntext.\n\nAnother one.";
+    /// let chunks = splitter.chunks_joining_soft_wraps(text);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         MappedChunk { range: 0..35, text: "A hard-wrapped paragraph of text.".to_string() },
+    ///         MappedChunk { range: 37..49, text: "Another one.".to_string() },
+    ///     ],
+    ///     chunks
+    /// );
+    /// ```
+    #[must_use]
+    pub fn chunks_joining_soft_wraps(&self, text: &str) -> Vec<MappedChunk> {
+        let (joined, offsets) = OffsetMap::replace(text, soft_wraps(text));
+        self.chunk_indices(&joined)
+            .map(|(offset, chunk)| MappedChunk {
+                range: offsets.original_range(offset..offset + chunk.len()),
+                text: chunk.to_string(),
+            })
+            .collect()
+    }
 }
 
 impl<Sizer> Splitter<Sizer> for TextSplitter<Sizer>
@@ -117,47 +245,171 @@ where
     }
 
     fn parse(&self, text: &str) -> Vec<(Self::Level, Range<usize>)> {
-        CAPTURE_LINEBREAKS
-            .find_iter(text)
-            .map(|m| {
-                let range = m.range();
-                let level = GRAPHEME_SEGMENTER
-                    .segment_str(text.get(range.start..range.end).unwrap())
-                    .tuple_windows::<(usize, usize)>()
-                    .count();
-                (
-                    match level {
-                        0 => unreachable!("regex should always match at least one newline"),
-                        n => LineBreaks(n),
-                    },
-                    range,
-                )
-            })
-            .collect()
+        self.ranges(text).collect()
+    }
+
+    fn lazy_ranges<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> Option<RangeIter<'splitter, Self::Level>> {
+        Some(Box::new(self.ranges(text)))
+    }
+
+    fn parse_lazily(&self) -> bool {
+        self.lazy_parsing
     }
 }
 
+impl<Sizer> TextSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Ranges of the separators in the text, in order.
+    #[auto_enum(Iterator)]
+    fn ranges<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (LineBreaks, Range<usize>)> + 'splitter {
+        match &self.separators {
+            Separators::LineBreaks => linebreaks(text),
+            Separators::Custom(regex) => regex
+                .find_iter(text)
+                .filter(|m| !m.is_empty())
+                .map(|m| (LineBreaks(1), m.range()))
+                .coalesce(|(a_level, a_range), (b_level, b_range)| {
+                    if a_range.end == b_range.start {
+                        Ok((
+                            LineBreaks(a_level.0 + b_level.0),
+                            a_range.start..b_range.end,
+                        ))
+                    } else {
+                        Err(((a_level, a_range), (b_level, b_range)))
+                    }
+                }),
+            Separators::None => std::iter::empty(),
+        }
+    }
+}
+
+/// Source of the separators used as semantic levels above sentences.
+#[derive(Debug)]
+enum Separators {
+    /// Sequences of newlines
+    LineBreaks,
+    /// Matches of a user-provided pattern
+    Custom(Regex),
+    /// Only split by sentences and below
+    None,
+}
+
 /// Different semantic levels that text can be split by.
 /// Each level provides a method of splitting text into chunks of a given level
 /// as well as a fallback in case a given fallback is too large.
 ///
-/// Split by given number of linebreaks, either `\n`, `\r`, or `\r\n`.
+/// Split by given number of linebreaks, either `\n`, `\r`, or `\r\n`, or by
+/// the number of consecutive custom separators.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub struct LineBreaks(usize);
 
 // Lazy so that we don't have to compile them more than once
-static CAPTURE_LINEBREAKS: LazyLock<Regex> =
+pub static CAPTURE_LINEBREAKS: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"(\r\n)+|\r+|\n+").unwrap());
 
 impl SemanticLevel for LineBreaks {}
 
+/// Find all sequences of newlines in the text, with the number of newlines in
+/// each sequence as its level.
+#[cfg(not(feature = "simd"))]
+fn linebreaks(text: &str) -> impl Iterator<Item = (LineBreaks, Range<usize>)> + '_ {
+    CAPTURE_LINEBREAKS.find_iter(text).map(|m| {
+        let range = m.range();
+        let level = GRAPHEME_SEGMENTER
+            .segment_str(text.get(range.start..range.end).unwrap())
+            .tuple_windows::<(usize, usize)>()
+            .count();
+        (
+            match level {
+                0 => unreachable!("regex should always match at least one newline"),
+                n => LineBreaks(n),
+            },
+            range,
+        )
+    })
+}
+
+/// Find all sequences of newlines in the text, with the number of newlines in
+/// each sequence as its level.
+///
+/// Finds the same sequences as [`CAPTURE_LINEBREAKS`], but searches for the
+/// next newline with SIMD, and counts newlines by their bytes instead of
+/// segmenting them into graphemes.
+#[cfg(feature = "simd")]
+fn linebreaks(text: &str) -> impl Iterator<Item = (LineBreaks, Range<usize>)> + '_ {
+    let bytes = text.as_bytes();
+    let mut cursor = 0;
+
+    std::iter::from_fn(move || {
+        let start = cursor + memchr::memchr2(b'\n', b'\r', &bytes[cursor..])?;
+        // Same precedence as the regex: `\r\n` pairs, then `\r`s, then `\n`s
+        let (newline, width): (&[u8], usize) = if bytes[start..].starts_with(b"\r\n") {
+            (b"\r\n", 2)
+        } else if bytes[start] == b'\r' {
+            (b"\r", 1)
+        } else {
+            (b"\n", 1)
+        };
+        let mut end = start;
+        while bytes[end..].starts_with(newline) {
+            end += width;
+        }
+        cursor = end;
+        Some((LineBreaks((end - start) / width), start..end))
+    })
+}
+
+/// Ranges of the single line breaks within paragraphs, along with any spaces
+/// or tabs around them, and what to replace them with to join the lines. The
+/// hyphen of a word that is split across the lines is removed as well.
+fn soft_wraps(text: &str) -> Vec<(Range<usize>, &'static str)> {
+    let is_space = |c: char| c == ' ' || c == '\t';
+    linebreaks(text)
+        .filter(|(level, _)| level.0 == 1)
+        .filter_map(|(_, range)| {
+            let before = text[..range.start].trim_end_matches(is_space);
+            let after = text[range.end..].trim_start_matches(is_space);
+            let start = before.len();
+            let end = text.len() - after.len();
+            let last = before.chars().next_back()?;
+            let next = after.chars().next()?;
+            // Blank lines separate paragraphs
+            if matches!(last, '\n' | '\r') || matches!(next, '\n' | '\r') {
+                return None;
+            }
+            let hyphenated = last == '-'
+                && next.is_lowercase()
+                && before[..start - 1]
+                    .chars()
+                    .next_back()
+                    .is_some_and(char::is_alphabetic);
+            Some(if hyphenated {
+                (start - 1..end, "")
+            } else {
+                (start..end, " ")
+            })
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use std::cmp::min;
 
     use fake::{Fake, Faker};
 
+    #[cfg(feature = "simd")]
+    use crate::splitter::fallback::GRAPHEME_SEGMENTER;
     use crate::splitter::SemanticSplitRanges;
+    use crate::{ChunkDiagnostic, SplitterExt};
 
     use super::*;
 
@@ -246,6 +498,29 @@ mod tests {
         assert_eq!(vec!["é", "é"], chunks);
     }
 
+    #[test]
+    fn diagnostics_for_char_bigger_than_len() {
+        let text = "éé"; // Char that is two bytes each
+        let chunks = TextSplitter::new(ChunkConfig::new(1).with_sizer(Str))
+            .chunks_with_diagnostics(text)
+            .collect::<Vec<_>>();
+
+        let oversized = || vec![ChunkDiagnostic::Oversized { size: 2, max: 1 }];
+        assert_eq!(vec![(0, "é", oversized()), (2, "é", oversized())], chunks);
+    }
+
+    #[test]
+    fn no_diagnostics_for_chunks_that_fit() {
+        let text = "Some text\n\nfrom a\ndocument";
+        let chunks = TextSplitter::new(10)
+            .chunks_with_diagnostics(text)
+            .collect::<Vec<_>>();
+
+        assert!(chunks
+            .iter()
+            .all(|(_, _, diagnostics)| diagnostics.is_empty()));
+    }
+
     #[test]
     fn chunk_by_graphemes() {
         let text = "a̐éö̲\r\n";
@@ -384,4 +659,63 @@ mod tests {
             linebreaks.ranges
         );
     }
+
+    #[test]
+    fn disabled_line_breaks() {
+        let text = "\r\n\r\ntext\n\n\ntext2";
+        let splitter = TextSplitter::new(10).with_line_breaks(false);
+        let linebreaks = SemanticSplitRanges::new(splitter.parse(text));
+        assert!(linebreaks.ranges.is_empty());
+    }
+
+    #[test]
+    fn custom_separators() {
+        let text = "a<PARA>b<PARA><PARA>c\u{2029}d";
+        let splitter =
+            TextSplitter::new(10).with_separators(Regex::new(r"\u{2029}|<PARA>").unwrap());
+        let separators = SemanticSplitRanges::new(splitter.parse(text));
+        assert_eq!(
+            vec![
+                (LineBreaks(1), 1..7),
+                (LineBreaks(2), 8..20),
+                (LineBreaks(1), 21..24)
+            ],
+            separators.ranges
+        );
+    }
+
+    #[test]
+    fn finds_soft_wraps() {
+        let text = "Some para-  \n  graph\r\nwith Jean-\nPaul\n\nNext -\nline\n";
+
+        assert_eq!(
+            vec![(9..15, ""), (20..22, " "), (32..33, " "), (45..46, " ")],
+            soft_wraps(text)
+        );
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn simd_linebreaks_match_regex() {
+        for text in [
+            "\r\n\r\ntext\n\n\ntext2",
+            "\r\r\n\n\r",
+            "a\n\r\nb\r\n\r\rc\r",
+            "\n",
+            "no newlines",
+            "",
+        ] {
+            let expected = CAPTURE_LINEBREAKS
+                .find_iter(text)
+                .map(|m| {
+                    let level = GRAPHEME_SEGMENTER
+                        .segment_str(m.as_str())
+                        .tuple_windows::<(usize, usize)>()
+                        .count();
+                    (LineBreaks(level), m.range())
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(expected, linebreaks(text).collect::<Vec<_>>(), "{text:?}");
+        }
+    }
 }
@@ -0,0 +1,494 @@
+/*!
+# [`TranscriptSplitter`]
+Semantic splitting of speaker-diarized transcripts, such as the output of a
+speech-to-text service, where each turn starts with a timestamp and the name
+of the speaker.
+*/
+
+use std::{ops::Range, sync::LazyLock, time::Duration};
+
+use itertools::Itertools;
+use regex::Regex;
+use thiserror::Error;
+
+use crate::{
+    splitter::{SemanticLevel, Splitter},
+    ChunkConfig, ChunkSizer,
+};
+
+use super::{fallback::GRAPHEME_SEGMENTER, text::CAPTURE_LINEBREAKS};
+
+/// Matches turns like `[00:12:01] SPEAKER A: ...` or `[12:01.5] Ana: ...`
+static DEFAULT_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?m)^\[(?P<start>\d+(?::\d{1,2}){1,2}(?:[.,]\d+)?)\][ \t]*(?P<speaker>[^:\r\n]+?):",
+    )
+    .unwrap()
+});
+
+/// Indicates there was an error with the pattern of a `TranscriptSplitter`.
+/// The `Display` implementation will provide a human-readable error message to
+/// help debug the issue that caused the error.
+#[derive(Error, Debug)]
+#[error(transparent)]
+#[allow(clippy::module_name_repetitions)]
+pub struct TranscriptSplitterError(#[from] TranscriptSplitterErrorRepr);
+
+/// Private error and free to change across minor version of the crate.
+#[derive(Error, Debug)]
+enum TranscriptSplitterErrorRepr {
+    #[error("Invalid pattern for the start of a turn")]
+    Regex(#[source] regex::Error),
+    #[error("The pattern for the start of a turn has no `start` group")]
+    MissingStartGroup,
+}
+
+/// Splitter for speaker-diarized transcripts, where each turn starts with a
+/// timestamp and the name of the speaker, like `[00:12:01] SPEAKER A: ...`.
+///
+/// Consecutive turns of the same speaker are kept together if they fit, then
+/// single turns, and then lines and sentences within a turn. The timestamp
+/// and speaker at the start of a turn are never split.
+///
+/// Use [`TranscriptSplitter::chunks_with_metadata`] to get the timestamps and
+/// speakers of each chunk, such as to link a chunk back to the audio it came
+/// from.
+#[derive(Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub struct TranscriptSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Method of determining chunk sizes.
+    chunk_config: ChunkConfig<Sizer>,
+    /// Matches the start of each turn
+    pattern: Regex,
+}
+
+/// Timestamps and speakers of a chunk of a transcript, as returned by
+/// [`TranscriptSplitter::chunks_with_metadata`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TranscriptChunkMetadata<'text> {
+    /// Timestamp of the turn the chunk starts in, if the chunk starts within
+    /// a turn and its timestamp could be read.
+    pub start: Option<Duration>,
+    /// End timestamp of the turn the chunk ends in, if the pattern has an
+    /// `end` group, otherwise the timestamp of the turn after the chunk.
+    /// `None` for the last turn of a transcript without end timestamps.
+    pub end: Option<Duration>,
+    /// Speakers of the turns in the chunk, in the order they first speak.
+    pub speakers: Vec<&'text str>,
+}
+
+impl<Sizer> TranscriptSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Creates a new [`TranscriptSplitter`], for turns that start like
+    /// `[00:12:01] SPEAKER A:` at the start of a line. Timestamps can be
+    /// `MM:SS` or `HH:MM:SS`, with optional fractions of a second.
+    ///
+    /// ```
+    /// use text_splitter::TranscriptSplitter;
+    ///
+    /// // By default, the chunk sizer is based on characters.
+    /// let splitter = TranscriptSplitter::new(512);
+    /// ```
+    #[must_use]
+    pub fn new(chunk_config: impl Into<ChunkConfig<Sizer>>) -> Self {
+        Self {
+            chunk_config: chunk_config.into(),
+            pattern: DEFAULT_PATTERN.clone(),
+        }
+    }
+
+    /// Use a different pattern to find the start of each turn, for other
+    /// transcript formats. The pattern needs a `start` group with the
+    /// timestamp, and can have an `end` group with the end timestamp of the
+    /// turn, and a `speaker` group with the name of the speaker.
+    ///
+    /// Timestamps are read as `HH:MM:SS`, `MM:SS`, or seconds, with optional
+    /// fractions of a second after a `.` or `,`.
+    ///
+    /// ```
+    /// use text_splitter::TranscriptSplitter;
+    ///
+    /// let splitter = TranscriptSplitter::new(512)
+    ///     .with_pattern(r"(?m)^(?P<speaker>\w+) \((?P<start>[\d:.]+) - (?P<end>[\d:.]+)\):")
+    ///     .expect("Invalid pattern");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the pattern is invalid, or doesn't have a
+    /// `start` group.
+    pub fn with_pattern(mut self, pattern: &str) -> Result<Self, TranscriptSplitterError> {
+        let pattern = Regex::new(pattern).map_err(TranscriptSplitterErrorRepr::Regex)?;
+        if !pattern.capture_names().any(|name| name == Some("start")) {
+            return Err(TranscriptSplitterErrorRepr::MissingStartGroup.into());
+        }
+        self.pattern = pattern;
+        Ok(self)
+    }
+
+    /// Generate a list of chunks from a given text. Each chunk will be up to
+    /// the `max_chunk_size`.
+    ///
+    /// ## Method
+    ///
+    /// To preserve as much semantic meaning within a chunk as possible, each chunk is composed of the largest semantic units that can fit in the next given chunk. For each splitter type, there is a defined set of semantic levels. Here is an example of the steps used:
+    ///
+    /// 1. Characters
+    /// 2. [Unicode Grapheme Cluster Boundaries](https://www.unicode.org/reports/tr29/#Grapheme_Cluster_Boundaries)
+    /// 3. [Unicode Word Boundaries](https://www.unicode.org/reports/tr29/#Word_Boundaries)
+    /// 4. [Unicode Sentence Boundaries](https://www.unicode.org/reports/tr29/#Sentence_Boundaries)
+    /// 5. Ascending sequence length of newlines. (Newline is `\r\n`, `\n`, or `\r`)
+    /// 6. Turns, from the timestamp and speaker of a turn up to the next turn
+    /// 7. Consecutive turns of the same speaker
+    ///
+    /// Splitting doesn't occur below the character level, otherwise you could get partial bytes of a char, which may not be a valid unicode str.
+    ///
+    /// The timestamp and speaker at the start of a turn are never split.
+    ///
+    /// ```
+    /// use text_splitter::TranscriptSplitter;
+    ///
+    /// let splitter = TranscriptSplitter::new(60);
+    /// let text = "[00:00:01] ANA: Hi there.\n[00:00:03] BEN: Hello!\n[00:00:05] BEN: How are you?";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "[00:00:01] ANA: Hi there.",
+    ///         "[00:00:03] BEN: Hello!\n[00:00:05] BEN: How are you?"
+    ///     ],
+    ///     chunks
+    /// );
+    /// ```
+    pub fn chunks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = &'text str> + 'splitter {
+        Splitter::<_>::chunks(self, text)
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets.
+    /// Each chunk will be up to the `max_chunk_size`.
+    ///
+    /// See [`TranscriptSplitter::chunks`] for more information.
+    pub fn chunk_indices<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter {
+        Splitter::<_>::chunk_indices(self, text)
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets,
+    /// along with the timestamps and speakers of each chunk.
+    ///
+    /// See [`TranscriptSplitter::chunks`] for more information.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use text_splitter::{TranscriptChunkMetadata, TranscriptSplitter};
+    ///
+    /// let splitter = TranscriptSplitter::new(60);
+    /// let text = "[00:00:01] ANA: Hi there.\n[00:00:03] BEN: Hello!\n[00:00:05] BEN: How are you?";
+    /// let (_, _, metadata) = splitter.chunks_with_metadata(text).next().unwrap();
+    ///
+    /// assert_eq!(
+    ///     TranscriptChunkMetadata {
+    ///         start: Some(Duration::from_secs(1)),
+    ///         end: Some(Duration::from_secs(3)),
+    ///         speakers: vec!["ANA"],
+    ///     },
+    ///     metadata
+    /// );
+    /// ```
+    pub fn chunks_with_metadata<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str, TranscriptChunkMetadata<'text>)> + 'splitter {
+        let turns = self.turns(text);
+        self.chunk_indices(text).map(move |(offset, chunk)| {
+            let range = offset..offset + chunk.len();
+            let overlapping = turns
+                .iter()
+                .enumerate()
+                .filter(|(_, turn)| turn.range.start < range.end && range.start < turn.range.end)
+                .collect::<Vec<_>>();
+            let start = overlapping
+                .first()
+                .filter(|(_, turn)| turn.range.start <= range.start)
+                .and_then(|(_, turn)| turn.start);
+            let end = overlapping.last().and_then(|(index, turn)| {
+                if turn.has_end {
+                    turn.end
+                } else {
+                    turns.get(index + 1).and_then(|next| next.start)
+                }
+            });
+            let speakers = overlapping
+                .iter()
+                .filter_map(|(_, turn)| turn.speaker)
+                .unique()
+                .collect();
+            (
+                offset,
+                chunk,
+                TranscriptChunkMetadata {
+                    start,
+                    end,
+                    speakers,
+                },
+            )
+        })
+    }
+}
+
+impl<Sizer> TranscriptSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Find the turns of the transcript, in order.
+    fn turns<'text>(&self, text: &'text str) -> Vec<Turn<'text>> {
+        let captures = self.pattern.captures_iter(text).collect::<Vec<_>>();
+        let mut turns = captures
+            .iter()
+            .map(|captures| {
+                let header = captures.get(0).expect("Always a full match").range();
+                let timestamp = |name| {
+                    captures
+                        .name(name)
+                        .and_then(|m| parse_timestamp(m.as_str()))
+                };
+                Turn {
+                    range: header.start..text.len(),
+                    header,
+                    start: timestamp("start"),
+                    end: timestamp("end"),
+                    has_end: captures.name("end").is_some(),
+                    speaker: captures.name("speaker").map(|m| m.as_str().trim()),
+                }
+            })
+            .collect::<Vec<_>>();
+        // Each turn runs until the next one starts, without trailing whitespace
+        let starts = turns
+            .iter()
+            .skip(1)
+            .map(|turn| turn.range.start)
+            .collect::<Vec<_>>();
+        for (turn, next) in turns.iter_mut().zip(starts.into_iter().chain([text.len()])) {
+            turn.range.end = turn.range.start + text[turn.range.start..next].trim_end().len();
+        }
+        turns
+    }
+}
+
+impl<Sizer> Splitter<Sizer> for TranscriptSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    type Level = Element;
+
+    fn chunk_config(&self) -> &ChunkConfig<Sizer> {
+        &self.chunk_config
+    }
+
+    fn parse(&self, text: &str) -> Vec<(Self::Level, Range<usize>)> {
+        let turns = self.turns(text);
+        let speaker_runs = turns
+            .iter()
+            .chunk_by(|turn| turn.speaker)
+            .into_iter()
+            .filter_map(|(speaker, mut run)| {
+                speaker?;
+                let first = run.next()?;
+                let end = run.last().map_or(first.range.end, |last| last.range.end);
+                Some((Element::SpeakerRun, first.range.start..end))
+            })
+            .collect::<Vec<_>>();
+
+        CAPTURE_LINEBREAKS
+            .find_iter(text)
+            .map(|m| {
+                let count = GRAPHEME_SEGMENTER
+                    .segment_str(m.as_str())
+                    .tuple_windows::<(usize, usize)>()
+                    .count();
+                (Element::LineBreak(count), m.range())
+            })
+            .chain(turns.into_iter().map(|turn| (Element::Turn, turn.range)))
+            .chain(speaker_runs)
+            .collect()
+    }
+
+    fn atomic_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        self.turns(text)
+            .into_iter()
+            .map(|turn| turn.header)
+            .collect()
+    }
+}
+
+/// Different semantic levels that text can be split by.
+/// Each level provides a method of splitting text into chunks of a given level
+/// as well as a fallback in case a given fallback is too large.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Element {
+    /// Sequence of a given number of linebreaks
+    LineBreak(usize),
+    /// A single turn, from its timestamp up to the next turn
+    Turn,
+    /// Consecutive turns of the same speaker
+    SpeakerRun,
+}
+
+impl SemanticLevel for Element {}
+
+/// A turn of the transcript
+#[derive(Debug)]
+struct Turn<'text> {
+    /// Byte range from the start of the timestamp up to the next turn
+    range: Range<usize>,
+    /// Byte range of the timestamp and speaker
+    header: Range<usize>,
+    /// Start timestamp, if it could be read
+    start: Option<Duration>,
+    /// End timestamp, if the pattern has one and it could be read
+    end: Option<Duration>,
+    /// Whether the pattern matched an end timestamp
+    has_end: bool,
+    /// Name of the speaker
+    speaker: Option<&'text str>,
+}
+
+/// Read a timestamp like `01:02:03.5`, `02:03,5` or `123.5`.
+fn parse_timestamp(timestamp: &str) -> Option<Duration> {
+    let timestamp = timestamp.trim().replace(',', ".");
+    let mut parts = timestamp.rsplit(':');
+    let seconds = parts.next()?.parse::<f64>().ok()?;
+    let mut total = seconds;
+    for (part, unit) in parts.zip([60.0, 3600.0]) {
+        total += f64::from(part.parse::<u32>().ok()?) * unit;
+    }
+    Duration::try_from_secs_f64(total).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRANSCRIPT: &str = "Meeting notes\n\n[00:00:01] ANA: Welcome everyone. Let's start.\n[00:00:04] ANA: First item.\n[00:00:09] BEN: Sounds good.\nI have an update.\n[00:01:15.5] ANA: Thanks.";
+
+    #[test]
+    fn parses_timestamps() {
+        assert_eq!(parse_timestamp("01:02:03"), Some(Duration::from_secs(3723)));
+        assert_eq!(
+            parse_timestamp("02:03,5"),
+            Some(Duration::from_millis(123_500))
+        );
+        assert_eq!(
+            parse_timestamp("12.25"),
+            Some(Duration::from_millis(12_250))
+        );
+        assert_eq!(parse_timestamp("soon"), None);
+    }
+
+    #[test]
+    fn finds_turns() {
+        let splitter = TranscriptSplitter::new(100);
+        let turns = splitter.turns(TRANSCRIPT);
+
+        assert_eq!(
+            turns
+                .iter()
+                .map(|turn| (&TRANSCRIPT[turn.range.clone()], turn.speaker))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "[00:00:01] ANA: Welcome everyone. Let's start.",
+                    Some("ANA")
+                ),
+                ("[00:00:04] ANA: First item.", Some("ANA")),
+                (
+                    "[00:00:09] BEN: Sounds good.\nI have an update.",
+                    Some("BEN")
+                ),
+                ("[00:01:15.5] ANA: Thanks.", Some("ANA")),
+            ]
+        );
+        assert_eq!(&TRANSCRIPT[turns[3].header.clone()], "[00:01:15.5] ANA:");
+    }
+
+    #[test]
+    fn metadata_of_chunks() {
+        let splitter = TranscriptSplitter::new(80);
+        let chunks = splitter
+            .chunks_with_metadata(TRANSCRIPT)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|(_, chunk, metadata)| (
+                    *chunk,
+                    metadata.start,
+                    metadata.end,
+                    metadata.speakers.clone()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Meeting notes", None, None, vec![]),
+                (
+                    "[00:00:01] ANA: Welcome everyone. Let's start.\n[00:00:04] ANA: First item.",
+                    Some(Duration::from_secs(1)),
+                    Some(Duration::from_secs(9)),
+                    vec!["ANA"]
+                ),
+                (
+                    "[00:00:09] BEN: Sounds good.\nI have an update.\n[00:01:15.5] ANA: Thanks.",
+                    Some(Duration::from_secs(9)),
+                    None,
+                    vec!["BEN", "ANA"]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_pattern_with_end() {
+        let splitter = TranscriptSplitter::new(25)
+            .with_pattern(r"(?m)^(?P<speaker>\w+) \((?P<start>[\d:.]+)-(?P<end>[\d:.]+)\):")
+            .unwrap();
+        let text = "Ana (0:01-0:03): Hi.\nBen (0:04-0:09): Hello.";
+        let chunks = splitter.chunks_with_metadata(text).collect::<Vec<_>>();
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].2.end, Some(Duration::from_secs(3)));
+        assert_eq!(chunks[1].2.start, Some(Duration::from_secs(4)));
+        assert_eq!(chunks[1].2.end, Some(Duration::from_secs(9)));
+    }
+
+    #[test]
+    fn pattern_needs_start_group() {
+        let err = TranscriptSplitter::new(20)
+            .with_pattern(r"(?m)^(?P<speaker>\w+):")
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "The pattern for the start of a turn has no `start` group"
+        );
+    }
+
+    #[test]
+    fn headers_are_never_split() {
+        let splitter = TranscriptSplitter::new(5);
+
+        assert!(splitter
+            .chunks(TRANSCRIPT)
+            .any(|chunk| chunk == "[00:00:09] BEN:"));
+    }
+}
@@ -0,0 +1,300 @@
+/*!
+# [`TypstSplitter`]
+Semantic splitting of Typst documents. Tries to use as many semantic units
+from Typst markup as possible, based on the syntax tree from `typst-syntax`.
+*/
+
+use std::{cmp::Reverse, ops::Range};
+
+use typst_syntax::{ast, SyntaxKind, SyntaxNode};
+
+use crate::{
+    splitter::{SemanticLevel, SemanticSplitPosition, Splitter},
+    trim::Trim,
+    ChunkConfig, ChunkSizer,
+};
+
+/// Typst splitter. Recursively splits chunks into the largest
+/// semantic units that fit within the chunk size. Also will
+/// attempt to merge neighboring chunks if they can fit within the
+/// given chunk size.
+#[derive(Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub struct TypstSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Method of determining chunk sizes.
+    chunk_config: ChunkConfig<Sizer>,
+}
+
+impl<Sizer> TypstSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Creates a new [`TypstSplitter`].
+    ///
+    /// ```
+    /// use text_splitter::TypstSplitter;
+    ///
+    /// // By default, the chunk sizer is based on characters.
+    /// let splitter = TypstSplitter::new(512);
+    /// ```
+    #[must_use]
+    pub fn new(chunk_config: impl Into<ChunkConfig<Sizer>>) -> Self {
+        Self {
+            chunk_config: chunk_config.into(),
+        }
+    }
+
+    /// Generate a list of chunks from a given text. Each chunk will be up to
+    /// the `max_chunk_size`.
+    ///
+    /// ## Method
+    ///
+    /// To preserve as much semantic meaning within a chunk as possible, each chunk is composed of the largest semantic units that can fit in the next given chunk. For each splitter type, there is a defined set of semantic levels. Here is an example of the steps used:
+    ///
+    /// 1. Characters
+    /// 2. [Unicode Grapheme Cluster Boundaries](https://www.unicode.org/reports/tr29/#Grapheme_Cluster_Boundaries)
+    /// 3. [Unicode Word Boundaries](https://www.unicode.org/reports/tr29/#Word_Boundaries)
+    /// 4. [Unicode Sentence Boundaries](https://www.unicode.org/reports/tr29/#Sentence_Boundaries)
+    /// 5. Soft line breaks (single newline) which isn't necessarily a new element in Typst.
+    /// 6. Inline elements such as: strong, emphasis, links, references, line breaks, inline raw text, and inline equations.
+    /// 7. Block elements such as: paragraph breaks, list, enum, and term items, content blocks (`[...]`), code blocks (`{...}`), raw blocks, and block equations.
+    /// 8. Headings by level
+    ///
+    /// Splitting doesn't occur below the character level, otherwise you could get partial bytes of a char, which may not be a valid unicode str.
+    ///
+    /// ```
+    /// use text_splitter::TypstSplitter;
+    ///
+    /// let splitter = TypstSplitter::new(10);
+    /// let text = "= Header\n\nfrom a\ndocument";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec!["= Header", "from a", "document"], chunks);
+    /// ```
+    pub fn chunks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = &'text str> + 'splitter {
+        Splitter::<_>::chunks(self, text)
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets.
+    /// Each chunk will be up to the `max_chunk_size`.
+    ///
+    /// See [`TypstSplitter::chunks`] for more information.
+    ///
+    /// ```
+    /// use text_splitter::TypstSplitter;
+    ///
+    /// let splitter = TypstSplitter::new(10);
+    /// let text = "= Header\n\nfrom a\ndocument";
+    /// let chunks = splitter.chunk_indices(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec![(0, "= Header"), (10, "from a"), (17, "document")], chunks);
+    /// ```
+    pub fn chunk_indices<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter {
+        Splitter::<_>::chunk_indices(self, text)
+    }
+}
+
+impl<Sizer> Splitter<Sizer> for TypstSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    type Level = Element;
+
+    const TRIM: Trim = Trim::PreserveIndentation;
+
+    fn chunk_config(&self) -> &ChunkConfig<Sizer> {
+        &self.chunk_config
+    }
+
+    fn parse(&self, text: &str) -> Vec<(Self::Level, Range<usize>)> {
+        let root = typst_syntax::parse(text);
+        let mut ranges = vec![];
+        let mut nodes = vec![(&root, 0)];
+
+        while let Some((node, offset)) = nodes.pop() {
+            if let Some(level) = Element::from_node(node) {
+                let range = offset..offset + node.len();
+                // Nodes recovered from malformed markup, such as unclosed
+                // math, can report lengths that run past the end of the text.
+                if text.get(range.clone()).is_some() {
+                    ranges.push((level, range));
+                }
+            }
+            let mut child_offset = offset;
+            for child in node.children() {
+                nodes.push((child, child_offset));
+                child_offset += child.len();
+            }
+        }
+
+        ranges
+    }
+}
+
+/// Different semantic levels that text can be split by.
+/// Each level provides a method of splitting text into chunks of a given level
+/// as well as a fallback in case a given fallback is too large.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Element {
+    /// Single line break, which isn't necessarily a new element in Typst
+    SoftBreak,
+    /// An inline element that is within a larger element such as a paragraph,
+    /// like strong or emphasized text, links, or inline equations.
+    Inline,
+    /// Paragraph break, list/enum/term item, content or code block, raw block,
+    /// or block equation.
+    Block,
+    /// Heading by depth. Headings with fewer `=` are higher levels.
+    Heading(Reverse<usize>),
+}
+
+impl Element {
+    /// The semantic level of a syntax node, if it is one we split by.
+    fn from_node(node: &SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            SyntaxKind::Space if node.text().matches('\n').count() == 1 => Some(Self::SoftBreak),
+            SyntaxKind::Strong
+            | SyntaxKind::Emph
+            | SyntaxKind::Link
+            | SyntaxKind::Ref
+            | SyntaxKind::Linebreak => Some(Self::Inline),
+            SyntaxKind::Raw => Some(if node.cast::<ast::Raw<'_>>()?.block() {
+                Self::Block
+            } else {
+                Self::Inline
+            }),
+            SyntaxKind::Equation => Some(if node.cast::<ast::Equation<'_>>()?.block() {
+                Self::Block
+            } else {
+                Self::Inline
+            }),
+            SyntaxKind::Parbreak
+            | SyntaxKind::ListItem
+            | SyntaxKind::EnumItem
+            | SyntaxKind::TermItem
+            | SyntaxKind::ContentBlock
+            | SyntaxKind::CodeBlock => Some(Self::Block),
+            SyntaxKind::Heading => Some(Self::Heading(Reverse(
+                node.cast::<ast::Heading<'_>>()?.depth().get(),
+            ))),
+            _ => None,
+        }
+    }
+}
+
+impl SemanticLevel for Element {
+    fn split_position(self) -> SemanticSplitPosition {
+        match self {
+            Self::SoftBreak | Self::Inline | Self::Block => SemanticSplitPosition::Own,
+            // Attach it to the next text
+            Self::Heading(_) => SemanticSplitPosition::Next,
+        }
+    }
+
+    fn treat_whitespace_as_previous(self) -> bool {
+        match self {
+            Self::SoftBreak | Self::Inline | Self::Heading(_) => false,
+            Self::Block => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::splitter::SemanticSplitRanges;
+
+    use super::*;
+
+    #[test]
+    fn empty_string() {
+        let chunks = TypstSplitter::new(ChunkConfig::new(100).with_trim(false))
+            .chunks("")
+            .collect::<Vec<_>>();
+
+        assert!(chunks.is_empty());
+    }
+
+    #[test]
+    fn heading_levels() {
+        let splitter = TypstSplitter::new(10);
+        let ranges = SemanticSplitRanges::new(splitter.parse("= One\n== Two"));
+
+        assert_eq!(
+            vec![
+                (Element::Heading(Reverse(1)), 0..5),
+                (Element::SoftBreak, 5..6),
+                (Element::Heading(Reverse(2)), 6..12),
+            ],
+            ranges.ranges_after_offset(0).collect::<Vec<_>>()
+        );
+        assert!(Element::Heading(Reverse(1)) > Element::Heading(Reverse(2)));
+        assert!(Element::Heading(Reverse(2)) > Element::Block);
+    }
+
+    #[test]
+    fn block_and_inline_levels() {
+        let splitter = TypstSplitter::new(10);
+        let text = "- *one*\n- two\n\n```rust\nfn main() {}\n```";
+        let ranges = SemanticSplitRanges::new(splitter.parse(text));
+
+        assert_eq!(
+            vec![
+                (Element::Block, 0..7),
+                (Element::Inline, 2..7),
+                (Element::SoftBreak, 7..8),
+                (Element::Block, 8..13),
+                (Element::Block, 13..15),
+                (Element::Block, 15..39),
+            ],
+            ranges.ranges_after_offset(0).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn headings_attach_to_content() {
+        let splitter = TypstSplitter::new(ChunkConfig::new(30).with_trim(false));
+        let text = "= Intro\nSome text here.\n\n= Usage\nMore text here.";
+        let chunks = splitter.chunks(text).collect::<Vec<_>>();
+
+        assert_eq!(
+            vec!["= Intro\nSome text here.\n\n", "= Usage\nMore text here."],
+            chunks
+        );
+    }
+
+    #[test]
+    fn keeps_raw_blocks_together() {
+        let splitter = TypstSplitter::new(30);
+        let text = "Some text.\n\n```rust\nfn main() {}\n```\n\nMore text.";
+        let chunks = splitter.chunks(text).collect::<Vec<_>>();
+
+        assert_eq!(
+            vec!["Some text.", "```rust\nfn main() {}\n```", "More text."],
+            chunks
+        );
+    }
+
+    #[test]
+    fn ignores_ranges_past_end_of_malformed_math() {
+        let text = "\\$$$$$$$$de(}]\n";
+        let splitter = TypstSplitter::new(3);
+
+        assert!(splitter
+            .parse(text)
+            .iter()
+            .all(|(_, range)| text.get(range.clone()).is_some()));
+        assert_eq!(
+            splitter.chunks(text).collect::<String>(),
+            text.split_whitespace().collect::<String>()
+        );
+    }
+}
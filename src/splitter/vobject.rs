@@ -0,0 +1,413 @@
+/*!
+# [`VObjectSplitter`]
+Semantic splitting of iCalendar (`.ics`) and vCard (`.vcf`) files, which share
+the same `BEGIN:`/`END:` block structure. Each record, like an event or a
+contact, is the primary semantic unit.
+*/
+
+use std::{cmp::Reverse, ops::Range};
+
+use crate::{
+    splitter::{SemanticLevel, Splitter},
+    ChunkConfig, ChunkSizer,
+};
+
+/// Splitter for iCalendar and vCard files, such as calendar and contact
+/// exports.
+///
+/// Components (`BEGIN:VEVENT` ... `END:VEVENT`) are split by their nesting
+/// depth, so the splitter will first try to fit whole records (events, to-dos,
+/// contacts, etc.) into a chunk, then nested components like alarms, and then
+/// individual properties. Properties, including folded continuation lines, are
+/// never split.
+///
+/// Use [`VObjectSplitter::chunks_with_metadata`] to get the `UID` and summary
+/// of the records in each chunk.
+///
+/// ```
+/// use text_splitter::VObjectSplitter;
+///
+/// let splitter = VObjectSplitter::new(60);
+/// let text = "BEGIN:VCARD\nFN:Ada Lovelace\nEND:VCARD\nBEGIN:VCARD\nFN:Alan Turing\nEND:VCARD";
+/// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+///
+/// assert_eq!(
+///     vec!["BEGIN:VCARD\nFN:Ada Lovelace\nEND:VCARD", "BEGIN:VCARD\nFN:Alan Turing\nEND:VCARD"],
+///     chunks
+/// );
+/// ```
+#[derive(Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub struct VObjectSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Method of determining chunk sizes.
+    chunk_config: ChunkConfig<Sizer>,
+}
+
+/// A record of an iCalendar or vCard file, as returned by
+/// [`VObjectSplitter::chunks_with_metadata`].
+///
+/// Records are the outermost components, other than the `VCALENDAR` wrapper.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct VObjectRecord<'text> {
+    /// Name of the component, such as `VEVENT` or `VCARD`, as written in the
+    /// text.
+    pub component: &'text str,
+    /// Value of the `UID` property, if there is one.
+    pub uid: Option<&'text str>,
+    /// Value of the `SUMMARY` property for calendar components, or the `FN`
+    /// property for contacts, if there is one. Folded lines are returned as
+    /// written in the text.
+    pub summary: Option<&'text str>,
+}
+
+impl<Sizer> VObjectSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Creates a new [`VObjectSplitter`].
+    ///
+    /// ```
+    /// use text_splitter::VObjectSplitter;
+    ///
+    /// // By default, the chunk sizer is based on characters.
+    /// let splitter = VObjectSplitter::new(512);
+    /// ```
+    #[must_use]
+    pub fn new(chunk_config: impl Into<ChunkConfig<Sizer>>) -> Self {
+        Self {
+            chunk_config: chunk_config.into(),
+        }
+    }
+
+    /// Generate a list of chunks from a given text. Each chunk will be up to
+    /// the `max_chunk_size`.
+    ///
+    /// ## Method
+    ///
+    /// To preserve as much semantic meaning within a chunk as possible, each chunk is composed of the largest semantic units that can fit in the next given chunk. For each splitter type, there is a defined set of semantic levels. Here is an example of the steps used:
+    ///
+    /// 1. Properties, including folded continuation lines
+    /// 2. Components by descending depth. So an event would have a higher level than an alarm inside of it, and so on.
+    ///
+    /// Properties are never split, even if they are larger than the chunk
+    /// capacity.
+    ///
+    /// ```
+    /// use text_splitter::VObjectSplitter;
+    ///
+    /// let splitter = VObjectSplitter::new(40);
+    /// let text = "BEGIN:VEVENT\nUID:1\nSUMMARY:Launch\nEND:VEVENT";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec!["BEGIN:VEVENT\nUID:1\nSUMMARY:Launch", "END:VEVENT"], chunks);
+    /// ```
+    pub fn chunks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = &'text str> + 'splitter {
+        Splitter::<_>::chunks(self, text)
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets.
+    /// Each chunk will be up to the `max_chunk_size`.
+    ///
+    /// See [`VObjectSplitter::chunks`] for more information.
+    pub fn chunk_indices<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter {
+        Splitter::<_>::chunk_indices(self, text)
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets,
+    /// along with the records that are part of each chunk.
+    ///
+    /// See [`VObjectSplitter::chunks`] for more information.
+    ///
+    /// ```
+    /// use text_splitter::{VObjectRecord, VObjectSplitter};
+    ///
+    /// let splitter = VObjectSplitter::new(512);
+    /// let text = "BEGIN:VCALENDAR\nBEGIN:VEVENT\nUID:42\nSUMMARY:Launch\nEND:VEVENT\nEND:VCALENDAR";
+    /// let (_, _, records) = splitter.chunks_with_metadata(text).next().unwrap();
+    ///
+    /// assert_eq!(
+    ///     vec![VObjectRecord { component: "VEVENT", uid: Some("42"), summary: Some("Launch") }],
+    ///     records
+    /// );
+    /// ```
+    pub fn chunks_with_metadata<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str, Vec<VObjectRecord<'text>>)> + 'splitter {
+        let records = parse_vobject(text)
+            .components
+            .into_iter()
+            .filter_map(|component| Some((component.range, component.record?)))
+            .collect::<Vec<_>>();
+        self.chunk_indices(text).map(move |(offset, chunk)| {
+            let range = offset..offset + chunk.len();
+            let metadata = records
+                .iter()
+                .filter(|(r, _)| r.start < range.end && range.start < r.end)
+                .map(|(_, record)| *record)
+                .collect();
+            (offset, chunk, metadata)
+        })
+    }
+}
+
+impl<Sizer> Splitter<Sizer> for VObjectSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    type Level = Element;
+
+    fn chunk_config(&self) -> &ChunkConfig<Sizer> {
+        &self.chunk_config
+    }
+
+    fn parse(&self, text: &str) -> Vec<(Self::Level, Range<usize>)> {
+        let vobject = parse_vobject(text);
+        vobject
+            .components
+            .into_iter()
+            .map(|component| {
+                (
+                    Element::Component(Reverse(component.depth)),
+                    component.range,
+                )
+            })
+            .chain(
+                vobject
+                    .properties
+                    .into_iter()
+                    .map(|range| (Element::Property, range)),
+            )
+            .collect()
+    }
+
+    fn atomic_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        parse_vobject(text).properties
+    }
+}
+
+/// Different semantic levels that text can be split by.
+/// Each level provides a method of splitting text into chunks of a given level
+/// as well as a fallback in case a given fallback is too large.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Element {
+    /// A single property, including folded continuation lines
+    Property,
+    /// A component by depth. Components with a lower depth are higher levels.
+    Component(Reverse<usize>),
+}
+
+impl SemanticLevel for Element {}
+
+/// A component of the file, with its byte range in the text
+#[derive(Debug)]
+struct ParsedComponent<'text> {
+    /// Byte range from the start of `BEGIN:` to the end of `END:`
+    range: Range<usize>,
+    /// Nesting depth, with top-level components at 0
+    depth: usize,
+    /// Metadata, if this component is a record
+    record: Option<VObjectRecord<'text>>,
+}
+
+/// Components and properties of a file
+#[derive(Debug)]
+struct VObject<'text> {
+    components: Vec<ParsedComponent<'text>>,
+    properties: Vec<Range<usize>>,
+}
+
+/// Find the components and properties of an iCalendar or vCard file. Lines
+/// starting with a space or tab continue the previous property.
+fn parse_vobject(text: &str) -> VObject<'_> {
+    let mut components = vec![];
+    let mut properties: Vec<Range<usize>> = vec![];
+    // Components that haven't been closed yet
+    let mut stack: Vec<ParsedComponent<'_>> = vec![];
+    let mut offset = 0;
+    // End of the last line that isn't blank
+    let mut last_end = 0;
+
+    for line in text.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let line = line.trim_end_matches(['\n', '\r']);
+        let end = start + line.len();
+        if line.trim().is_empty() {
+            continue;
+        }
+        last_end = end;
+
+        // Folded continuation of the previous property
+        if line.starts_with([' ', '\t']) {
+            if let Some(property) = properties.last_mut() {
+                property.end = end;
+            }
+            continue;
+        }
+
+        properties.push(start..end);
+        let (name, value) = property_name_value(line);
+        if name.eq_ignore_ascii_case("BEGIN") {
+            let depth = stack.len();
+            // Records are the outermost components other than the wrapper
+            let is_record = !value.eq_ignore_ascii_case("VCALENDAR")
+                && stack.iter().all(|c| c.record.is_none());
+            stack.push(ParsedComponent {
+                range: start..end,
+                depth,
+                record: is_record.then_some(VObjectRecord {
+                    component: value,
+                    uid: None,
+                    summary: None,
+                }),
+            });
+        } else if name.eq_ignore_ascii_case("END") {
+            // Close any components that were left open inside of this one
+            while let Some(mut component) = stack.pop() {
+                component.range.end = end;
+                let matches = component
+                    .record
+                    .map_or(true, |r| r.component.eq_ignore_ascii_case(value));
+                components.push(component);
+                if matches {
+                    break;
+                }
+            }
+        }
+    }
+
+    // Components without an `END:` line run until the end of the text
+    for mut component in stack.into_iter().rev() {
+        component.range.end = last_end;
+        components.push(component);
+    }
+    // Fill in record metadata, now that folded properties are complete
+    for component in &mut components {
+        let Some(record) = component.record.as_mut() else {
+            continue;
+        };
+        for property in properties
+            .iter()
+            .filter(|p| component.range.start <= p.start && p.end <= component.range.end)
+        {
+            let (name, value) = property_name_value(&text[property.clone()]);
+            if name.eq_ignore_ascii_case("UID") {
+                record.uid.get_or_insert(value);
+            } else if name.eq_ignore_ascii_case("SUMMARY") || name.eq_ignore_ascii_case("FN") {
+                record.summary.get_or_insert(value);
+            }
+        }
+    }
+
+    VObject {
+        components,
+        properties,
+    }
+}
+
+/// Split a property into its name (without parameters) and value.
+fn property_name_value(property: &str) -> (&str, &str) {
+    let (head, value) = property.split_once(':').unwrap_or((property, ""));
+    let name = head.split(';').next().unwrap_or(head);
+    (name.trim(), value.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ICS: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+UID:event-1@example.com\r
+SUMMARY;LANGUAGE=en:Team meeting\r
+DESCRIPTION:A long description that is folded\r
+  onto a second line\r
+BEGIN:VALARM\r
+ACTION:DISPLAY\r
+END:VALARM\r
+END:VEVENT\r
+BEGIN:VTODO\r
+UID:todo-1\r
+END:VTODO\r
+END:VCALENDAR\r
+";
+
+    #[test]
+    fn empty_string() {
+        let chunks = VObjectSplitter::new(ChunkConfig::new(100).with_trim(false))
+            .chunks("")
+            .collect::<Vec<_>>();
+
+        assert!(chunks.is_empty());
+    }
+
+    #[test]
+    fn parses_components() {
+        let vobject = parse_vobject(ICS);
+        let mut components = vobject
+            .components
+            .iter()
+            .map(|c| (c.depth, &ICS[c.range.clone()], c.record))
+            .collect::<Vec<_>>();
+        components.sort_by_key(|(_, c, _)| c.as_ptr());
+
+        assert_eq!(components.len(), 4);
+        assert!(components[0].1.starts_with("BEGIN:VCALENDAR"));
+        assert!(components[0].1.ends_with("END:VCALENDAR"));
+        assert_eq!(components[0].2, None);
+        assert_eq!(
+            components[1].2,
+            Some(VObjectRecord {
+                component: "VEVENT",
+                uid: Some("event-1@example.com"),
+                summary: Some("Team meeting"),
+            })
+        );
+        assert_eq!(
+            (components[2].0, components[2].1, components[2].2),
+            (2, "BEGIN:VALARM\r\nACTION:DISPLAY\r\nEND:VALARM", None)
+        );
+        assert_eq!(components[3].2.unwrap().uid, Some("todo-1"));
+    }
+
+    #[test]
+    fn folded_properties() {
+        let vobject = parse_vobject(ICS);
+
+        assert!(vobject.properties.iter().any(|p| &ICS[p.clone()]
+            == "DESCRIPTION:A long description that is folded\r\n  onto a second line"));
+    }
+
+    #[test]
+    fn unclosed_components() {
+        let vobject = parse_vobject("BEGIN:VCARD\nFN:Ada\n");
+
+        assert_eq!(vobject.components[0].range, 0..18);
+        assert_eq!(vobject.components[0].record.unwrap().summary, Some("Ada"));
+    }
+
+    #[test]
+    fn splits_by_record() {
+        let splitter = VObjectSplitter::new(200);
+        let chunks = splitter.chunks_with_metadata(ICS).collect::<Vec<_>>();
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].1, "BEGIN:VCALENDAR\r\nVERSION:2.0");
+        assert!(chunks[0].2.is_empty());
+        assert!(chunks[1].1.starts_with("BEGIN:VEVENT"));
+        assert!(chunks[1].1.ends_with("END:VEVENT"));
+        assert_eq!(chunks[1].2[0].component, "VEVENT");
+        assert_eq!(chunks[2].2[0].component, "VTODO");
+    }
+}
@@ -0,0 +1,592 @@
+/*!
+# [`WikitextSplitter`]
+Semantic splitting of `MediaWiki` wikitext, such as Wikipedia dumps. Uses the
+same semantic levels as the [`crate::MarkdownSplitter`], based on wikitext
+headings, tables, lists, and inline markup.
+*/
+
+use std::{cmp::Reverse, ops::Range};
+
+use crate::{
+    splitter::{SemanticLevel, SemanticSplitPosition, Splitter},
+    trim::Trim,
+    ChunkConfig, ChunkSizer,
+};
+
+/// Wikitext splitter. Recursively splits chunks into the largest
+/// semantic units that fit within the chunk size. Also will
+/// attempt to merge neighboring chunks if they can fit within the
+/// given chunk size.
+///
+/// Templates (`{{...}}`) are never split internally, even if they are larger
+/// than the chunk capacity, since a partial template is meaningless.
+#[derive(Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub struct WikitextSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Method of determining chunk sizes.
+    chunk_config: ChunkConfig<Sizer>,
+}
+
+impl<Sizer> WikitextSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    /// Creates a new [`WikitextSplitter`].
+    ///
+    /// ```
+    /// use text_splitter::WikitextSplitter;
+    ///
+    /// // By default, the chunk sizer is based on characters.
+    /// let splitter = WikitextSplitter::new(512);
+    /// ```
+    #[must_use]
+    pub fn new(chunk_config: impl Into<ChunkConfig<Sizer>>) -> Self {
+        Self {
+            chunk_config: chunk_config.into(),
+        }
+    }
+
+    /// Generate a list of chunks from a given text. Each chunk will be up to
+    /// the `max_chunk_size`.
+    ///
+    /// ## Method
+    ///
+    /// To preserve as much semantic meaning within a chunk as possible, each chunk is composed of the largest semantic units that can fit in the next given chunk. For each splitter type, there is a defined set of semantic levels. Here is an example of the steps used:
+    ///
+    /// 1. Characters
+    /// 2. [Unicode Grapheme Cluster Boundaries](https://www.unicode.org/reports/tr29/#Grapheme_Cluster_Boundaries)
+    /// 3. [Unicode Word Boundaries](https://www.unicode.org/reports/tr29/#Word_Boundaries)
+    /// 4. [Unicode Sentence Boundaries](https://www.unicode.org/reports/tr29/#Sentence_Boundaries)
+    /// 5. Soft line breaks (single newline) within a paragraph.
+    /// 6. Inline elements such as: templates, internal and external links, bold and italic text, references, comments, and table cells.
+    /// 7. Block elements such as: paragraphs, preformatted text, block tags (like `<pre>` or `<gallery>`), and templates on their own lines. Also, a row of a table or an item of a list, and a list or table that contains items.
+    /// 8. Horizontal rules (`----`).
+    /// 9. Headings by level (`== Section ==`)
+    ///
+    /// Splitting doesn't occur below the character level, otherwise you could get partial bytes of a char, which may not be a valid unicode str.
+    ///
+    /// ```
+    /// use text_splitter::WikitextSplitter;
+    ///
+    /// let splitter = WikitextSplitter::new(25);
+    /// let text = "== History ==\nSome text.\n\n== Usage ==\nMore text.";
+    /// let chunks = splitter.chunks(text).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(vec!["== History ==\nSome text.", "== Usage ==\nMore text."], chunks);
+    /// ```
+    pub fn chunks<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = &'text str> + 'splitter {
+        Splitter::<_>::chunks(self, text)
+    }
+
+    /// Returns an iterator over chunks of the text and their byte offsets.
+    /// Each chunk will be up to the `max_chunk_size`.
+    ///
+    /// See [`WikitextSplitter::chunks`] for more information.
+    pub fn chunk_indices<'splitter, 'text: 'splitter>(
+        &'splitter self,
+        text: &'text str,
+    ) -> impl Iterator<Item = (usize, &'text str)> + 'splitter {
+        Splitter::<_>::chunk_indices(self, text)
+    }
+}
+
+impl<Sizer> Splitter<Sizer> for WikitextSplitter<Sizer>
+where
+    Sizer: ChunkSizer,
+{
+    type Level = Element;
+
+    const TRIM: Trim = Trim::PreserveIndentation;
+
+    fn chunk_config(&self) -> &ChunkConfig<Sizer> {
+        &self.chunk_config
+    }
+
+    fn parse(&self, text: &str) -> Vec<(Self::Level, Range<usize>)> {
+        let templates = templates(text);
+        let mut ranges = blocks(text, &templates);
+        ranges.extend(inlines(text, &templates));
+        ranges
+    }
+
+    fn atomic_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        templates(text)
+    }
+}
+
+/// Different semantic levels that text can be split by.
+/// Each level provides a method of splitting text into chunks of a given level
+/// as well as a fallback in case a given fallback is too large.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Element {
+    /// Single line break within a paragraph
+    SoftBreak,
+    /// An inline element that is within a larger element such as a paragraph,
+    /// like a template, link, bold or italic text, or reference.
+    Inline,
+    /// Paragraph, preformatted text, block tag, template on its own lines,
+    /// a row/item within a table or list, or a list or table that contains
+    /// items.
+    Block,
+    /// Horizontal rule (`----`)
+    Rule,
+    /// Heading by depth. Headings with fewer `=` are higher levels.
+    Heading(Reverse<usize>),
+}
+
+impl SemanticLevel for Element {
+    fn split_position(self) -> SemanticSplitPosition {
+        match self {
+            Self::SoftBreak | Self::Inline | Self::Block | Self::Rule => SemanticSplitPosition::Own,
+            // Attach it to the next text
+            Self::Heading(_) => SemanticSplitPosition::Next,
+        }
+    }
+
+    fn treat_whitespace_as_previous(self) -> bool {
+        match self {
+            Self::SoftBreak | Self::Inline | Self::Rule | Self::Heading(_) => false,
+            Self::Block => true,
+        }
+    }
+}
+
+/// Tags whose contents are a block of their own, when they start a line.
+const BLOCK_TAGS: [&str; 7] = [
+    "pre",
+    "syntaxhighlight",
+    "source",
+    "gallery",
+    "blockquote",
+    "math",
+    "poem",
+];
+
+/// Ranges of all outermost templates (`{{...}}`), including parser functions
+/// and template parameters (`{{{...}}}`).
+fn templates(text: &str) -> Vec<Range<usize>> {
+    let mut templates = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut i = 0;
+    while let Some(offset) = text[i..].find(['{', '}']) {
+        i += offset;
+        if text[i..].starts_with("{{") {
+            if depth == 0 {
+                start = i;
+            }
+            depth += 1;
+            i += 2;
+        } else if text[i..].starts_with("}}") && depth > 0 {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                templates.push(start..i);
+            }
+        } else {
+            i += 1;
+        }
+    }
+    templates
+}
+
+/// Each line of the text along with its byte offset, without line endings.
+fn lines(text: &str) -> Vec<(usize, &str)> {
+    text.split_inclusive('\n')
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some((start, line.trim_end_matches(['\n', '\r'])))
+        })
+        .collect()
+}
+
+/// Kinds of blocks that are made up of consecutive lines
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum LineBlock {
+    Paragraph,
+    List,
+    Preformatted,
+}
+
+/// Heading depth of a line, if it is a heading (`== Section ==`).
+fn heading_depth(line: &str) -> Option<usize> {
+    let line = line.trim_end();
+    let leading = line.bytes().take_while(|&b| b == b'=').count();
+    let trailing = line.bytes().rev().take_while(|&b| b == b'=').count();
+    let depth = leading.min(trailing).min(6);
+    (depth > 0 && line.len() > depth * 2).then_some(depth)
+}
+
+/// Find the block-level elements of the document, line by line.
+#[allow(clippy::too_many_lines)]
+fn blocks(text: &str, templates: &[Range<usize>]) -> Vec<(Element, Range<usize>)> {
+    let lines = lines(text);
+    let mut ranges = vec![];
+    // Block of consecutive lines that is still open
+    let mut current: Option<(LineBlock, Range<usize>)> = None;
+    // Start of each table (and its current row) that is still open
+    let mut tables: Vec<(usize, Option<usize>)> = vec![];
+    let mut row_end = 0;
+    let mut index = 0;
+
+    let flush = |ranges: &mut Vec<(Element, Range<usize>)>,
+                 current: &mut Option<(LineBlock, Range<usize>)>| {
+        if let Some((_, range)) = current.take() {
+            ranges.push((Element::Block, range));
+        }
+    };
+
+    while let Some(&(start, line)) = lines.get(index) {
+        index += 1;
+        let end = start + line.len();
+        let trimmed = line.trim_start();
+
+        // Tables, which can be nested
+        if trimmed.starts_with("{|") {
+            flush(&mut ranges, &mut current);
+            tables.push((start, Some(start)));
+            row_end = end;
+            continue;
+        }
+        if let Some((table_start, row_start)) = tables.last_mut() {
+            if trimmed.starts_with("|}") {
+                if let Some(row_start) = row_start.take() {
+                    ranges.push((Element::Block, row_start..row_end));
+                }
+                ranges.push((Element::Block, *table_start..end));
+                tables.pop();
+                row_end = end;
+            } else if trimmed.starts_with("|-") {
+                if let Some(row_start) = row_start.replace(start) {
+                    ranges.push((Element::Block, row_start..row_end));
+                }
+                row_end = end;
+            } else {
+                if (trimmed.starts_with(['|', '!'])) && !trimmed.starts_with("|+") {
+                    ranges.push((Element::Inline, start + line.len() - trimmed.len()..end));
+                }
+                if !trimmed.is_empty() {
+                    row_end = end;
+                }
+            }
+            continue;
+        }
+
+        if line.trim().is_empty() {
+            flush(&mut ranges, &mut current);
+            continue;
+        }
+
+        // Templates and block tags that span multiple lines are their own block
+        let multiline_end = templates
+            .iter()
+            .find(|t| t.start == start + line.len() - trimmed.len())
+            .map(|t| t.end)
+            .or_else(|| block_tag_end(text, start + line.len() - trimmed.len()))
+            .filter(|&block_end| block_end > end);
+        if let Some(block_end) = multiline_end {
+            flush(&mut ranges, &mut current);
+            while lines
+                .get(index)
+                .is_some_and(|&(next_start, _)| next_start < block_end)
+            {
+                index += 1;
+            }
+            let (last_start, last_line) = lines[index - 1];
+            ranges.push((Element::Block, start..last_start + last_line.len()));
+            continue;
+        }
+
+        if let Some(depth) = heading_depth(line) {
+            flush(&mut ranges, &mut current);
+            ranges.push((Element::Heading(Reverse(depth)), start..end));
+            continue;
+        }
+        if line.starts_with("----") && line.trim_start_matches('-').trim().is_empty() {
+            flush(&mut ranges, &mut current);
+            ranges.push((Element::Rule, start..end));
+            continue;
+        }
+
+        let kind = if line.starts_with(['*', '#', ':', ';']) {
+            LineBlock::List
+        } else if line.starts_with(' ') {
+            LineBlock::Preformatted
+        } else {
+            LineBlock::Paragraph
+        };
+        if kind == LineBlock::List {
+            ranges.push((Element::Block, start..end));
+        }
+        match current.as_mut() {
+            Some((current_kind, range)) if *current_kind == kind => {
+                if kind == LineBlock::Paragraph {
+                    ranges.push((Element::SoftBreak, range.end..start));
+                }
+                range.end = end;
+            }
+            _ => {
+                flush(&mut ranges, &mut current);
+                current = Some((kind, start..end));
+            }
+        }
+    }
+    flush(&mut ranges, &mut current);
+
+    // Close any tables that were never closed
+    while let Some((table_start, row_start)) = tables.pop() {
+        if let Some(row_start) = row_start {
+            ranges.push((Element::Block, row_start..row_end));
+        }
+        ranges.push((Element::Block, table_start..row_end));
+    }
+
+    ranges
+}
+
+/// End of a block tag (like `<pre>...</pre>`) starting at `start`, if there is
+/// one.
+fn block_tag_end(text: &str, start: usize) -> Option<usize> {
+    let rest = text[start..].strip_prefix('<')?;
+    let name = BLOCK_TAGS.iter().find(|name| {
+        rest.get(..name.len())
+            .is_some_and(|n| n.eq_ignore_ascii_case(name))
+            && rest[name.len()..].starts_with(['>', ' ', '\t', '\n'])
+    })?;
+    let close = format!("</{name}>");
+    let end = text[start..]
+        .to_ascii_lowercase()
+        .find(&close)
+        .map_or(text.len(), |i| start + i + close.len());
+    Some(end)
+}
+
+/// Find the inline elements of the document: templates, links, references,
+/// comments, and bold or italic text.
+fn inlines(text: &str, templates: &[Range<usize>]) -> Vec<(Element, Range<usize>)> {
+    let mut ranges = templates
+        .iter()
+        .map(|range| (Element::Inline, range.clone()))
+        .collect::<Vec<_>>();
+    let bytes = text.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        // Template contents are atomic, so there is nothing to find in them
+        if let Some(template) = templates.iter().find(|t| t.start == i) {
+            i = template.end;
+            continue;
+        }
+        let rest = &text[i..];
+        let end = if rest.starts_with("[[") {
+            matching_brackets(rest).map(|len| i + len)
+        } else if rest.starts_with("[http://")
+            || rest.starts_with("[https://")
+            || rest.starts_with("[//")
+        {
+            rest[..rest.find('\n').unwrap_or(rest.len())]
+                .find(']')
+                .map(|len| i + len + 1)
+        } else if rest.starts_with("<!--") {
+            Some(rest.find("-->").map_or(text.len(), |len| i + len + 3))
+        } else if rest
+            .get(..4)
+            .is_some_and(|tag| tag.eq_ignore_ascii_case("<ref"))
+        {
+            ref_end(rest).map(|len| i + len)
+        } else if rest.starts_with("''") {
+            let marker = if rest.starts_with("'''") { "'''" } else { "''" };
+            let line = &rest[marker.len()..rest.find('\n').unwrap_or(rest.len())];
+            line.find(marker).map(|len| i + marker.len() * 2 + len)
+        } else {
+            None
+        };
+        match end {
+            Some(end) => {
+                ranges.push((Element::Inline, i..end));
+                i = end;
+            }
+            None => i += rest.chars().next().map_or(1, char::len_utf8),
+        }
+    }
+
+    ranges
+}
+
+/// Length of a `[[...]]` link at the start of `text`, which can contain nested
+/// links, like in image captions.
+fn matching_brackets(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < text.len() {
+        if text[i..].starts_with("[[") {
+            depth += 1;
+            i += 2;
+        } else if text[i..].starts_with("]]") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return Some(i);
+            }
+        } else if text[i..].starts_with("\n\n") {
+            return None;
+        } else {
+            i += text[i..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    None
+}
+
+/// Length of a `<ref>...</ref>` or `<ref ... />` at the start of `text`.
+fn ref_end(text: &str) -> Option<usize> {
+    if !text[4..].starts_with(['>', ' ', '/']) {
+        return None;
+    }
+    let tag_end = text.find('>')? + 1;
+    if text[..tag_end].ends_with("/>") {
+        return Some(tag_end);
+    }
+    text[tag_end..]
+        .to_ascii_lowercase()
+        .find("</ref>")
+        .map(|len| tag_end + len + "</ref>".len())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::splitter::SemanticSplitRanges;
+
+    use super::*;
+
+    fn levels(text: &str) -> Vec<(Element, &str)> {
+        let splitter = WikitextSplitter::new(10);
+        SemanticSplitRanges::new(splitter.parse(text))
+            .ranges
+            .into_iter()
+            .map(|(level, range)| (level, &text[range]))
+            .collect()
+    }
+
+    #[test]
+    fn empty_string() {
+        let chunks = WikitextSplitter::new(ChunkConfig::new(100).with_trim(false))
+            .chunks("")
+            .collect::<Vec<_>>();
+
+        assert!(chunks.is_empty());
+    }
+
+    #[test]
+    fn headings_and_rules() {
+        assert_eq!(
+            levels("= Title =\n== Section ==\n----\n=== Sub ==="),
+            vec![
+                (Element::Heading(Reverse(1)), "= Title ="),
+                (Element::Heading(Reverse(2)), "== Section =="),
+                (Element::Rule, "----"),
+                (Element::Heading(Reverse(3)), "=== Sub ==="),
+            ]
+        );
+        assert!(Element::Heading(Reverse(2)) > Element::Heading(Reverse(3)));
+        assert_eq!(heading_depth("==="), None);
+    }
+
+    #[test]
+    fn paragraphs_and_lists() {
+        assert_eq!(
+            levels("Line one\nline two\n\n* item\n** nested"),
+            vec![
+                (Element::Block, "Line one\nline two"),
+                (Element::SoftBreak, "\n"),
+                (Element::Block, "* item\n** nested"),
+                (Element::Block, "* item"),
+                (Element::Block, "** nested"),
+            ]
+        );
+    }
+
+    #[test]
+    fn inline_elements() {
+        assert_eq!(
+            levels("'''Rust''' is [[programming language|a language]] {{cite|x}}<ref name=\"a\">[https://example.com site]</ref><!-- c -->"),
+            vec![
+                (Element::Block, "'''Rust''' is [[programming language|a language]] {{cite|x}}<ref name=\"a\">[https://example.com site]</ref><!-- c -->"),
+                (Element::Inline, "'''Rust'''"),
+                (Element::Inline, "[[programming language|a language]]"),
+                (Element::Inline, "{{cite|x}}"),
+                (Element::Inline, "<ref name=\"a\">[https://example.com site]</ref>"),
+                (Element::Inline, "<!-- c -->"),
+            ]
+        );
+    }
+
+    #[test]
+    fn tables() {
+        let text = "{| class=\"wikitable\"\n! A !! B\n|-\n| 1 || 2\n|}";
+
+        assert_eq!(
+            levels(text),
+            vec![
+                (Element::Block, text),
+                (Element::Block, "{| class=\"wikitable\"\n! A !! B"),
+                (Element::Inline, "! A !! B"),
+                (Element::Block, "|-\n| 1 || 2"),
+                (Element::Inline, "| 1 || 2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn multiline_templates_are_blocks() {
+        let text = "{{Infobox\n| name = Rust\n| {{nested}}\n}}\nText";
+
+        assert_eq!(
+            levels(text),
+            vec![
+                (Element::Block, "{{Infobox\n| name = Rust\n| {{nested}}\n}}"),
+                (
+                    Element::Inline,
+                    "{{Infobox\n| name = Rust\n| {{nested}}\n}}"
+                ),
+                (Element::Block, "Text"),
+            ]
+        );
+    }
+
+    #[test]
+    fn templates_are_atomic() {
+        let splitter = WikitextSplitter::new(10);
+        let text = "Text {{cite web|url=x|title=y}} more";
+        let chunks = splitter.chunks(text).collect::<Vec<_>>();
+
+        assert_eq!(vec!["Text", "{{cite web|url=x|title=y}}", "more"], chunks);
+    }
+
+    #[test]
+    fn block_tags() {
+        let text = "<pre>\na\n\nb\n</pre>\nText";
+
+        assert_eq!(
+            levels(text),
+            vec![
+                (Element::Block, "<pre>\na\n\nb\n</pre>"),
+                (Element::Block, "Text"),
+            ]
+        );
+    }
+
+    #[test]
+    fn non_ascii_after_table_start() {
+        let splitter = WikitextSplitter::new(ChunkConfig::new(4));
+        let text = "{|\u{1F1EF}\u{1F1F5}";
+
+        assert_eq!(splitter.chunks(text).collect::<String>(), text);
+    }
+}
@@ -0,0 +1,231 @@
+/*!
+Templates for the text of each chunk, such as to add the headings a chunk is
+under or a reference to its source.
+*/
+
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+/// Indicates there was an error with a chunk template.
+/// The `Display` implementation will provide a human-readable error message to
+/// help debug the issue that caused the error.
+#[derive(Error, Debug)]
+#[error(transparent)]
+#[allow(clippy::module_name_repetitions)]
+pub struct ChunkTemplateError(#[from] ChunkTemplateErrorRepr);
+
+/// Private error and free to change across minor version of the crate.
+#[derive(Error, Debug)]
+enum ChunkTemplateErrorRepr {
+    #[error("The template has an unclosed placeholder starting at byte {0}")]
+    UnclosedPlaceholder(usize),
+    #[error("The template has an unmatched `}}` at byte {0}")]
+    UnmatchedBrace(usize),
+    #[error("The template has an empty placeholder at byte {0}")]
+    EmptyPlaceholder(usize),
+    #[error("The template has to contain `{{chunk}}` exactly once, but contains it {0} times")]
+    ChunkCount(usize),
+    #[error("The template field `{0}` has no value")]
+    MissingField(String),
+}
+
+/// Part of a parsed template
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Segment {
+    /// Text that is copied as is
+    Literal(String),
+    /// The text of the chunk
+    Chunk,
+    /// The index of the chunk, starting at 0
+    Index,
+    /// The titles of the headings the chunk is under, separated by ` > `
+    Breadcrumbs,
+    /// A value set with [`ChunkTemplate::with_field`]
+    Field(String),
+}
+
+/// A template that the text of each chunk is rendered with, such as
+/// `"{breadcrumbs}\n\n{chunk}\n\n[source: {doc_id}#{index}]"`, for use with
+/// methods like `chunks_with_template`.
+///
+/// The template can contain these placeholders:
+///
+/// - `{chunk}`: the text of the chunk, which is required exactly once
+/// - `{index}`: the index of the chunk, starting at 0
+/// - `{breadcrumbs}`: the titles of the headings the chunk is under,
+///   separated by ` > `, for splitters that know about headings. Empty
+///   otherwise. Long breadcrumbs are shortened, see `chunks_with_template`.
+/// - Any other name, whose value is set with [`ChunkTemplate::with_field`]
+///
+/// Use `{{` and `}}` for literal braces.
+///
+/// The size of everything around the chunk counts towards the chunk
+/// capacity, so the rendered text fits within the max capacity, unless the
+/// template by itself is already too large.
+///
+/// ```
+/// use text_splitter::ChunkTemplate;
+///
+/// let template = ChunkTemplate::new("{chunk}\n\n[source: {doc_id}#{index}]")
+///     .unwrap()
+///     .with_field("doc_id", "guide.md");
+/// ```
+#[derive(Clone, Debug)]
+pub struct ChunkTemplate {
+    /// Parts of the template, in order
+    segments: Vec<Segment>,
+    /// Values of the custom fields
+    fields: HashMap<String, String>,
+}
+
+impl ChunkTemplate {
+    /// Parse a template.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the template doesn't contain `{chunk}` exactly
+    /// once, or if a brace isn't matched.
+    pub fn new(template: &str) -> Result<Self, ChunkTemplateError> {
+        let mut segments = vec![];
+        let mut literal = String::new();
+        let mut chars = template.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '{' if chars.next_if(|(_, c)| *c == '{').is_some() => literal.push('{'),
+                '}' if chars.next_if(|(_, c)| *c == '}').is_some() => literal.push('}'),
+                '}' => return Err(ChunkTemplateErrorRepr::UnmatchedBrace(i).into()),
+                '{' => {
+                    let rest = &template[i + 1..];
+                    let end = rest
+                        .find(['{', '}'])
+                        .filter(|end| rest[*end..].starts_with('}'))
+                        .ok_or(ChunkTemplateErrorRepr::UnclosedPlaceholder(i))?;
+                    let name = rest[..end].trim();
+                    if name.is_empty() {
+                        return Err(ChunkTemplateErrorRepr::EmptyPlaceholder(i).into());
+                    }
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(match name {
+                        "chunk" => Segment::Chunk,
+                        "index" => Segment::Index,
+                        "breadcrumbs" => Segment::Breadcrumbs,
+                        _ => Segment::Field(name.to_string()),
+                    });
+                    // Skip the name and the closing brace
+                    while chars.next_if(|(j, _)| *j <= i + 1 + end).is_some() {}
+                }
+                _ => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        let chunks = segments.iter().filter(|s| **s == Segment::Chunk).count();
+        if chunks != 1 {
+            return Err(ChunkTemplateErrorRepr::ChunkCount(chunks).into());
+        }
+
+        Ok(Self {
+            segments,
+            fields: HashMap::new(),
+        })
+    }
+
+    /// Template of the breadcrumbs directly followed by the chunk, the same
+    /// as `"{breadcrumbs}{chunk}"`.
+    #[cfg(feature = "markdown")]
+    pub(crate) fn breadcrumbs_and_chunk() -> Self {
+        Self {
+            segments: vec![Segment::Breadcrumbs, Segment::Chunk],
+            fields: HashMap::new(),
+        }
+    }
+
+    /// Set the value of a custom field, such as the id of the document.
+    #[must_use]
+    pub fn with_field(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.fields.insert(name.into(), value.into());
+        self
+    }
+
+    /// Check that every custom field in the template has a value.
+    pub(crate) fn check_fields(&self) -> Result<(), ChunkTemplateError> {
+        match self.segments.iter().find_map(|segment| match segment {
+            Segment::Field(name) if !self.fields.contains_key(name) => Some(name),
+            _ => None,
+        }) {
+            Some(name) => Err(ChunkTemplateErrorRepr::MissingField(name.clone()).into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Render the template for a chunk. Custom fields without a value are
+    /// left empty.
+    pub(crate) fn render(&self, chunk: &str, index: usize, breadcrumbs: &str) -> String {
+        let mut rendered = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => rendered.push_str(literal),
+                Segment::Chunk => rendered.push_str(chunk),
+                Segment::Index => rendered.push_str(&index.to_string()),
+                Segment::Breadcrumbs => rendered.push_str(breadcrumbs),
+                Segment::Field(name) => {
+                    rendered.push_str(self.fields.get(name).map_or("", String::as_str));
+                }
+            }
+        }
+        rendered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_placeholders() {
+        let template = ChunkTemplate::new("{breadcrumbs}\n\n{chunk}\n\n[source: {doc_id}#{index}]")
+            .unwrap()
+            .with_field("doc_id", "guide.md");
+
+        assert!(template.check_fields().is_ok());
+        assert_eq!(
+            template.render("Some text", 3, "Guide > Install"),
+            "Guide > Install\n\nSome text\n\n[source: guide.md#3]"
+        );
+    }
+
+    #[test]
+    fn escaped_braces() {
+        let template = ChunkTemplate::new("{{{chunk}}}").unwrap();
+
+        assert_eq!(template.render("a", 0, ""), "{a}");
+    }
+
+    #[test]
+    fn invalid_templates() {
+        for template in [
+            "no chunk",
+            "{chunk}{chunk}",
+            "{chunk",
+            "{chunk}}",
+            "{}{chunk}",
+        ] {
+            assert!(ChunkTemplate::new(template).is_err(), "{template}");
+        }
+    }
+
+    #[test]
+    fn missing_field() {
+        let template = ChunkTemplate::new("{chunk} {doc_id}").unwrap();
+
+        assert_eq!(
+            template.check_fields().unwrap_err().to_string(),
+            "The template field `doc_id` has no value"
+        );
+    }
+}
@@ -0,0 +1,161 @@
+/*!
+# Test vectors
+
+Canonical inputs, configurations, and the chunks they are expected to produce,
+so that integrators can check programmatically that chunking behaves the same
+in their environment, such as the Python bindings on a given platform, as it
+does in the Rust crate.
+
+The vectors are stored as JSON in `data/test_vectors.json`, which is also
+available from [`json`] for loading in other languages. Chunk sizes are
+measured in characters.
+
+```
+use text_splitter::test_vectors::test_vectors;
+
+for vector in test_vectors() {
+    assert_eq!(vector.chunks, vector.run(), "{}", vector.name);
+}
+```
+*/
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    output::ChunkingRun, Characters, ChunkCapacity, ChunkConfig, DocumentSplitter,
+    MarkdownSplitter, TextSplitter,
+};
+
+/// The test vectors, as JSON.
+const TEST_VECTORS: &str = include_str!("../data/test_vectors.json");
+
+/// A text and configuration, along with the chunks they are expected to
+/// produce.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TestVector {
+    /// Unique name of the vector
+    pub name: String,
+    /// Format of the text, which determines the splitter
+    pub format: TestVectorFormat,
+    /// Text to chunk
+    pub text: String,
+    /// How to chunk the text
+    pub config: TestVectorConfig,
+    /// Chunks the text is expected to produce, in order
+    pub chunks: Vec<TestVectorChunk>,
+}
+
+/// Format of the text of a [`TestVector`].
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestVectorFormat {
+    /// Plain text, split with a [`TextSplitter`](crate::TextSplitter)
+    Text,
+    /// Markdown, split with a [`MarkdownSplitter`](crate::MarkdownSplitter)
+    Markdown,
+}
+
+/// Configuration of a [`TestVector`], mirroring the options of a
+/// [`ChunkConfig`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TestVectorConfig {
+    /// Desired chunk capacity, in characters
+    pub capacity: usize,
+    /// Max chunk capacity, in characters, if different from the desired
+    /// capacity
+    pub max_capacity: Option<usize>,
+    /// Overlap between chunks, in characters
+    pub overlap: usize,
+    /// Whether whitespace is trimmed from each chunk
+    pub trim: bool,
+}
+
+/// A chunk a [`TestVector`] is expected to produce.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TestVectorChunk {
+    /// Byte offset of the start of the chunk within the text
+    pub start: usize,
+    /// Character offset of the start of the chunk within the text, as
+    /// returned by `chunk_indices` in the language bindings
+    pub char_start: usize,
+    /// Text of the chunk
+    pub text: String,
+}
+
+/// The test vectors, as JSON, such as for loading them in other languages.
+#[must_use]
+pub fn json() -> &'static str {
+    TEST_VECTORS
+}
+
+/// The test vectors.
+///
+/// # Panics
+///
+/// Will panic if the JSON of the vectors is invalid, which is checked when
+/// the crate is tested.
+#[must_use]
+pub fn test_vectors() -> Vec<TestVector> {
+    serde_json::from_str(TEST_VECTORS).expect("Invalid test vectors")
+}
+
+impl TestVector {
+    /// Chunk the text of the vector with its configuration, to compare with
+    /// the expected [`Self::chunks`].
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the configuration of the vector is invalid.
+    #[must_use]
+    pub fn run(&self) -> Vec<TestVectorChunk> {
+        let capacity = ChunkCapacity::new(self.config.capacity)
+            .with_max(self.config.max_capacity.unwrap_or(self.config.capacity))
+            .expect("Invalid capacity");
+        let chunk_config = ChunkConfig::new(capacity)
+            .with_overlap(self.config.overlap)
+            .expect("Invalid overlap")
+            .with_trim(self.config.trim);
+        let splitter: DocumentSplitter<Characters> = match self.format {
+            TestVectorFormat::Text => TextSplitter::new(chunk_config).into(),
+            TestVectorFormat::Markdown => MarkdownSplitter::new(chunk_config).into(),
+        };
+
+        ChunkingRun::new(
+            &self.text,
+            splitter.chunk_indices(&self.text),
+            splitter.chunk_config(),
+        )
+        .chunks
+        .into_iter()
+        .map(|chunk| TestVectorChunk {
+            start: chunk.metadata.start,
+            char_start: chunk.metadata.char_start,
+            text: chunk.text,
+        })
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ahash::AHashSet;
+
+    use super::*;
+
+    #[test]
+    fn vectors_match_current_chunks() {
+        let vectors = test_vectors();
+
+        assert_eq!(
+            vectors
+                .iter()
+                .map(|v| &v.name)
+                .collect::<AHashSet<_>>()
+                .len(),
+            vectors.len()
+        );
+        for vector in vectors {
+            assert_eq!(vector.chunks, vector.run(), "{}", vector.name);
+        }
+    }
+}
@@ -15,13 +15,11 @@ pub enum Trim {
     /// leading whitespace will be trimmed.
     /// Useful for text like Markdown or code, where indentation is important to
     /// the meaning of the text.
-    #[cfg(any(feature = "markdown", feature = "code"))]
     PreserveIndentation,
     /// Apply no trimming
     None,
 }
 
-#[cfg(any(feature = "markdown", feature = "code"))]
 const NEWLINES: [char; 2] = ['\n', '\r'];
 
 impl Trim {
@@ -32,7 +30,6 @@ impl Trim {
                 let diff = chunk.len() - chunk.trim_start().len();
                 (offset + diff, chunk.trim())
             }
-            #[cfg(any(feature = "markdown", feature = "code"))]
             Self::PreserveIndentation => {
                 // Preserve indentation if we have newlines inside the element
                 if chunk.trim().contains(NEWLINES) {
@@ -59,7 +56,6 @@ mod tests {
         assert_eq!(chunk, "hello world");
     }
 
-    #[cfg(any(feature = "markdown", feature = "code"))]
     #[test]
     fn trim_indentation_fallback() {
         let chunk = "  hello world  ";
@@ -68,7 +64,6 @@ mod tests {
         assert_eq!(chunk, "hello world");
     }
 
-    #[cfg(any(feature = "markdown", feature = "code"))]
     #[test]
     fn trim_indentation_preserved() {
         let chunk = "\n  hello\n  world  ";
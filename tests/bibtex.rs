@@ -0,0 +1,70 @@
+//! Test for `BibtexSplitter` behavior.
+use fake::{Fake, Faker};
+use itertools::Itertools;
+use text_splitter::{BibtexSplitter, ChunkConfig};
+
+const BIB: &str = r#"% Books
+@book{knuth1997,
+  author = {Donald E. Knuth},
+  title = {The Art of Computer Programming},
+  publisher = {Addison-Wesley},
+  year = 1997
+}
+
+@book{abelson1996,
+  author = {Harold Abelson and Gerald Jay Sussman},
+  title = "Structure and {I}nterpretation of Computer Programs",
+  year = 1996
+}
+
+% Articles
+@article{turing1936,
+  author = {Alan M. Turing},
+  title = {On Computable Numbers},
+  journal = {Proceedings of the London Mathematical Society},
+  year = 1936
+}
+"#;
+
+#[test]
+fn chunks_roundtrip() {
+    for _ in 0..10 {
+        let max_characters = Faker.fake::<usize>() % 300 + 1;
+        let splitter = BibtexSplitter::new(ChunkConfig::new(max_characters).with_trim(false));
+        let chunks = splitter.chunk_indices(BIB).collect::<Vec<_>>();
+
+        assert_eq!(chunks.iter().map(|(_, c)| *c).join(""), BIB);
+        assert!(chunks
+            .iter()
+            .map(|(i, _)| i)
+            .tuple_windows()
+            .all(|(a, b)| a < b));
+    }
+}
+
+#[test]
+fn entries_are_never_split() {
+    for _ in 0..10 {
+        let max_characters = Faker.fake::<usize>() % 300 + 1;
+        let splitter = BibtexSplitter::new(max_characters);
+
+        for (_, chunk, entries) in splitter.chunks_with_metadata(BIB) {
+            assert!(chunk.ends_with('}'), "entry was split: {chunk:?}");
+            assert_eq!(chunk.matches('@').count(), entries.len());
+        }
+    }
+}
+
+#[test]
+fn groups_by_comment() {
+    let splitter = BibtexSplitter::new(320);
+    let chunks = splitter.chunks_with_metadata(BIB).collect::<Vec<_>>();
+
+    assert_eq!(
+        chunks
+            .iter()
+            .map(|(_, _, entries)| entries.iter().map(|e| e.key).collect::<Vec<_>>())
+            .collect::<Vec<_>>(),
+        vec![vec!["knuth1997", "abelson1996"], vec!["turing1936"]]
+    );
+}
@@ -0,0 +1,70 @@
+//! Test for `ClauseSplitter` behavior.
+use fake::{Fake, Faker};
+use itertools::Itertools;
+use text_splitter::{ChunkConfig, ClauseSplitter};
+
+const CONTRACT: &str = "SERVICES AGREEMENT
+
+1. Definitions
+1.1 \"Services\" means the services described in Schedule 1.
+1.2 \"Fees\" means the amounts payable under clause 3.
+
+2. Obligations
+2.1 The Supplier shall:
+  (a) perform the Services with reasonable skill and care;
+  (b) comply with all applicable laws, including:
+    (i) data protection laws; and
+    (ii) anti-bribery laws;
+  (c) keep the Customer informed of progress.
+2.2 The Customer shall pay the Fees on time.
+
+3. Payment
+3.1 Invoices are payable within 30 days. The agreement started in
+2024. Late payments accrue interest.
+";
+
+#[test]
+fn chunks_roundtrip() {
+    for _ in 0..10 {
+        let max_characters = Faker.fake::<usize>() % 200 + 1;
+        let splitter = ClauseSplitter::new(ChunkConfig::new(max_characters).with_trim(false));
+        let chunks = splitter.chunk_indices(CONTRACT).collect::<Vec<_>>();
+
+        assert_eq!(chunks.iter().map(|(_, c)| *c).join(""), CONTRACT);
+        assert!(chunks
+            .iter()
+            .map(|(i, _)| i)
+            .tuple_windows()
+            .all(|(a, b)| a < b));
+    }
+}
+
+#[test]
+fn top_level_clauses_are_chunks() {
+    let splitter = ClauseSplitter::new(400);
+    let chunks = splitter.chunks(CONTRACT).collect::<Vec<_>>();
+
+    assert!(chunks
+        .iter()
+        .any(|chunk| chunk.starts_with("2. Obligations")
+            && chunk.ends_with("2.2 The Customer shall pay the Fees on time.")));
+    assert!(chunks.iter().any(|chunk| chunk.starts_with("3. Payment")));
+}
+
+#[test]
+fn chunks_align_with_nested_clauses() {
+    let splitter = ClauseSplitter::new(100);
+    let chunks = splitter.chunks(CONTRACT).collect::<Vec<_>>();
+
+    assert!(chunks.contains(
+        &"(b) comply with all applicable laws, including:\n    (i) data protection laws; and"
+    ));
+}
+
+#[test]
+fn wrapped_numbers_are_not_clauses() {
+    let splitter = ClauseSplitter::new(120);
+    let chunks = splitter.chunks(CONTRACT).collect::<Vec<_>>();
+
+    assert!(chunks.contains(&"3. Payment\n3.1 Invoices are payable within 30 days. The agreement started in\n2024. Late payments accrue interest."));
+}
@@ -0,0 +1,111 @@
+//! Test for `ConfigSplitter` behavior.
+use fake::{Fake, Faker};
+use itertools::Itertools;
+use text_splitter::{ChunkConfig, ConfigFormat, ConfigSplitter};
+
+const YAML: &str = r"# Service configuration
+service:
+  name: example
+  replicas: 3
+  ports:
+    - name: http
+      port: 80
+    - name: https
+      port: 443
+  description: >
+    A long description of the service
+    that spans multiple lines.
+
+# Logging
+logging:
+  level: info
+  outputs:
+  - stdout
+  - file
+";
+
+const TOML: &str = r#"title = "Example"
+
+[owner]
+name = "Someone"
+# When they joined
+joined = 1979-05-27T07:32:00-08:00
+
+[database]
+ports = [
+  8000,
+  8001,
+  8002,
+]
+notes = """
+A multi-line string
+[not.a.table]
+"""
+
+[[products]]
+name = "Hammer"
+sku = 738594937
+"#;
+
+/// As long as each line fits in a chunk, every chunk should start at the
+/// beginning or end of a line (ignoring indentation), since scalar values are
+/// never split.
+fn assert_chunks_start_on_lines(format: ConfigFormat, text: &str) {
+    for _ in 0..10 {
+        let max_characters = Faker.fake::<usize>() % 100 + 40;
+        let splitter =
+            ConfigSplitter::new(format, ChunkConfig::new(max_characters).with_trim(false));
+        let chunks = splitter.chunk_indices(text).collect::<Vec<_>>();
+
+        assert_eq!(chunks.iter().map(|(_, c)| *c).join(""), text);
+        assert!(chunks
+            .iter()
+            .map(|(i, _)| i)
+            .tuple_windows()
+            .all(|(a, b)| a < b));
+        for (offset, _) in chunks {
+            let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = text[offset..].find('\n').map_or(text.len(), |i| offset + i);
+            assert!(
+                text[line_start..offset].trim().is_empty()
+                    || text[offset..line_end].trim().is_empty(),
+                "chunk starts in the middle of a line at {offset}"
+            );
+        }
+    }
+}
+
+#[test]
+fn yaml_chunks_start_on_lines() {
+    assert_chunks_start_on_lines(ConfigFormat::Yaml, YAML);
+}
+
+#[test]
+fn toml_chunks_start_on_lines() {
+    assert_chunks_start_on_lines(ConfigFormat::Toml, TOML);
+}
+
+#[test]
+fn yaml_top_level_keys() {
+    let splitter = ConfigSplitter::new(ConfigFormat::Yaml, 250);
+    let chunks = splitter.chunks(YAML).collect::<Vec<_>>();
+
+    assert_eq!(chunks.len(), 2);
+    assert!(chunks[0].starts_with("# Service configuration\nservice:"));
+    assert!(chunks[1].starts_with("# Logging\nlogging:"));
+}
+
+#[test]
+fn toml_tables() {
+    let splitter = ConfigSplitter::new(ConfigFormat::Toml, 100);
+    let chunks = splitter.chunks(TOML).collect::<Vec<_>>();
+
+    assert_eq!(
+        chunks,
+        vec![
+            "title = \"Example\"\n\n[owner]\nname = \"Someone\"\n# When they joined\njoined = 1979-05-27T07:32:00-08:00",
+            "[database]\nports = [\n  8000,\n  8001,\n  8002,\n]\nnotes = \"\"\"\nA multi-line string\n[not.a.table]\n\"\"\"",
+            "[[products]]\nname = \"Hammer\"\nsku = 738594937",
+        ]
+    );
+}
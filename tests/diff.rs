@@ -0,0 +1,77 @@
+//! Test for `DiffSplitter` behavior.
+use fake::{Fake, Faker};
+use itertools::Itertools;
+use text_splitter::{ChunkConfig, DiffSplitter};
+
+const DIFF: &str = r#"diff --git a/src/main.rs b/src/main.rs
+index 1111111..2222222 100644
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1,5 +1,6 @@
+ use std::io;
++use std::fs;
+ 
+ fn main() {
+-    println!("Hello");
++    println!("Hello, world!");
+ }
+@@ -20,3 +21,3 @@ fn helper() {
+     let a = 1;
+-    let b = 2;
++    let b = 3;
+diff --git a/docs/new.md b/docs/new.md
+new file mode 100644
+index 0000000..3333333
+--- /dev/null
++++ b/docs/new.md
+@@ -0,0 +1,2 @@
++# New docs
++Some text.
+"#;
+
+#[test]
+fn chunks_roundtrip() {
+    for _ in 0..10 {
+        let max_characters = Faker.fake::<usize>() % 200 + 1;
+        let splitter = DiffSplitter::new(ChunkConfig::new(max_characters).with_trim(false));
+        let chunks = splitter.chunk_indices(DIFF).collect::<Vec<_>>();
+
+        assert_eq!(chunks.iter().map(|(_, c)| *c).join(""), DIFF);
+        assert!(chunks
+            .iter()
+            .map(|(i, _)| i)
+            .tuple_windows()
+            .all(|(a, b)| a < b));
+    }
+}
+
+#[test]
+fn hunk_headers_are_never_separated_from_their_body() {
+    for _ in 0..10 {
+        let max_characters = Faker.fake::<usize>() % 200 + 1;
+        let splitter = DiffSplitter::new(max_characters);
+
+        for chunk in splitter.chunks(DIFF) {
+            let last_line = chunk.lines().last().unwrap();
+            assert!(
+                !last_line.starts_with("@@") && !last_line.starts_with("+++"),
+                "chunk ends with a header: {chunk:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn files_and_hunks() {
+    let splitter = DiffSplitter::new(200);
+    let chunks = splitter.chunks_with_metadata(DIFF).collect::<Vec<_>>();
+
+    assert_eq!(chunks.len(), 3);
+    assert!(chunks[0].1.starts_with("diff --git a/src/main.rs"));
+    assert!(chunks[1].1.starts_with("@@ -20,3 +21,3 @@"));
+    assert!(chunks[2].1.starts_with("diff --git a/docs/new.md"));
+    assert_eq!(chunks[1].2[0].new_path, Some("src/main.rs"));
+    assert_eq!(chunks[1].2[0].hunks[0].new_start, 21);
+    assert_eq!(chunks[2].2[0].old_path, None);
+    assert_eq!(chunks[2].2[0].new_path, Some("docs/new.md"));
+}
@@ -0,0 +1,45 @@
+//! Test for `FixedWidthSplitter` behavior.
+use fake::{Fake, Faker};
+use itertools::Itertools;
+use text_splitter::{ChunkConfig, FixedWidthSplitter, RecordLength};
+
+const RECORD_LENGTH: usize = 24;
+const FEED: &str = "000001JOHNSON   19840312000002MÜLLER    19790921000003GARCÍA    20011105000004NAKAMURA  19950630000005O'BRIEN   1968";
+
+#[test]
+fn chunks_roundtrip() {
+    for _ in 0..10 {
+        let max_characters = Faker.fake::<usize>() % 200 + 1;
+        let splitter = FixedWidthSplitter::new(
+            RecordLength::Chars(RECORD_LENGTH),
+            ChunkConfig::new(max_characters).with_trim(false),
+        );
+        let chunks = splitter.chunk_indices(FEED).collect::<Vec<_>>();
+
+        assert_eq!(chunks.iter().map(|(_, c)| *c).join(""), FEED);
+        assert!(chunks
+            .iter()
+            .map(|(i, _)| i)
+            .tuple_windows()
+            .all(|(a, b)| a < b));
+    }
+}
+
+#[test]
+fn chunks_are_whole_records() {
+    for max_characters in 1..150 {
+        let splitter = FixedWidthSplitter::new(
+            RecordLength::Chars(RECORD_LENGTH),
+            ChunkConfig::new(max_characters).with_trim(false),
+        );
+
+        for (offset, chunk) in splitter.chunk_indices(FEED) {
+            assert_eq!(FEED[..offset].chars().count() % RECORD_LENGTH, 0);
+            let records = chunk.chars().count().div_ceil(RECORD_LENGTH);
+            assert!(
+                records == 1 || chunk.chars().count() <= max_characters,
+                "{max_characters} {chunk:?}"
+            );
+        }
+    }
+}
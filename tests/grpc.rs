@@ -0,0 +1,33 @@
+//! Tests for the gRPC service definition.
+#![cfg(feature = "grpc")]
+use std::{fs, path::Path};
+
+/// The generated code for `proto/text_splitter/v1/chunking.proto` is checked
+/// in, so that using the `grpc` feature doesn't require `protoc`. If the proto
+/// definition changes, this regenerates the code and fails, so that the new
+/// code can be reviewed and committed.
+#[test]
+fn generated_code_is_up_to_date() {
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("grpc");
+    fs::create_dir_all(&out_dir).unwrap();
+    let mut config = tonic_build::Config::new();
+    config.protoc_executable(protoc_bin_vendored::protoc_bin_path().unwrap());
+    tonic_build::configure()
+        .build_client(false)
+        .build_transport(false)
+        .emit_rerun_if_changed(false)
+        .out_dir(&out_dir)
+        .compile_protos_with_config(
+            config,
+            &["proto/text_splitter/v1/chunking.proto"],
+            &["proto"],
+        )
+        .unwrap();
+
+    let generated = fs::read_to_string(out_dir.join("text_splitter.v1.rs")).unwrap();
+    let checked_in = Path::new("src/grpc/text_splitter.v1.rs");
+    if fs::read_to_string(checked_in).ok().as_deref() != Some(generated.as_str()) {
+        fs::write(checked_in, generated).unwrap();
+        panic!("Generated gRPC code was out of date and has been updated, please commit it");
+    }
+}
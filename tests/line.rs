@@ -0,0 +1,88 @@
+//! Test for `LineSplitter` behavior.
+use fake::{Fake, Faker};
+use itertools::Itertools;
+use text_splitter::{ChunkCapacity, ChunkConfig, LineSplitter, TieBreaking};
+
+const LOG: &str = "2024-05-01T10:00:00Z INFO server started on port 8080
+2024-05-01T10:00:01Z INFO connected to database
+2024-05-01T10:00:05Z WARN slow request: GET /api/users took 1532ms
+
+2024-05-01T10:01:00Z ERROR request failed: connection reset by peer
+    at handler (src/api.rs:42)
+    at router (src/main.rs:17)
+
+2024-05-01T10:02:00Z INFO shutting down
+";
+
+#[test]
+fn chunks_roundtrip() {
+    for _ in 0..10 {
+        let max_characters = Faker.fake::<usize>() % 200 + 1;
+        let splitter = LineSplitter::new(ChunkConfig::new(max_characters).with_trim(false));
+        let chunks = splitter.chunk_indices(LOG).collect::<Vec<_>>();
+
+        assert_eq!(chunks.iter().map(|(_, c)| *c).join(""), LOG);
+        assert!(chunks
+            .iter()
+            .map(|(i, _)| i)
+            .tuple_windows()
+            .all(|(a, b)| a < b));
+    }
+}
+
+#[test]
+fn lines_are_kept_whole_if_they_fit() {
+    let max_line = LOG.lines().map(|line| line.chars().count()).max().unwrap();
+    for max_characters in max_line..max_line + 100 {
+        let splitter = LineSplitter::new(max_characters);
+
+        for (offset, chunk) in splitter.chunk_indices(LOG) {
+            let before = &LOG[..offset];
+            let after = &LOG[offset + chunk.len()..];
+            assert!(
+                before.is_empty() || before.ends_with('\n') || before.ends_with(' '),
+                "{chunk:?}"
+            );
+            assert!(after.is_empty() || after.starts_with('\n'), "{chunk:?}");
+        }
+    }
+}
+
+#[test]
+fn sticky_punctuation_with_overlap() {
+    let text = "!'type Q {}}[2,3]]]## ==[table]## //+add+add#set🇺🇸!";
+    let splitter = LineSplitter::new(
+        ChunkConfig::new(ChunkCapacity::new(13).with_max(29).unwrap())
+            .with_overlap(3)
+            .unwrap()
+            .with_trim(false)
+            .with_sticky_punctuation(true)
+            .with_tie_breaking(TieBreaking::PreferLarger),
+    );
+
+    let chunks = splitter.chunk_indices(text).collect::<Vec<_>>();
+    assert_eq!(
+        chunks.last().map(|(offset, chunk)| offset + chunk.len()),
+        Some(text.len())
+    );
+    for (offset, chunk) in chunks {
+        assert_eq!(&text[offset..offset + chunk.len()], chunk);
+        assert!(chunk.chars().count() <= 29);
+    }
+}
+
+#[test]
+fn line_aligned_with_overlap() {
+    let text = "\n\naaaaaaaa\n🇺🇸aa\n";
+    let splitter = LineSplitter::new(
+        ChunkConfig::new(ChunkCapacity::new(8).with_max(15).unwrap())
+            .with_overlap(5)
+            .unwrap()
+            .with_trim(false)
+            .with_line_aligned(true)
+            .with_tie_breaking(TieBreaking::PreferLarger),
+    );
+
+    let chunks = splitter.chunk_indices(text).collect::<Vec<_>>();
+    assert_eq!(chunks, vec![(0, "\n\n"), (2, "aaaaaaaa\n🇺🇸aa\n")]);
+}
@@ -5,7 +5,7 @@ use fake::{Fake, Faker};
 use itertools::Itertools;
 use more_asserts::assert_le;
 #[cfg(feature = "markdown")]
-use text_splitter::{ChunkConfig, MarkdownSplitter};
+use text_splitter::{ChunkConfig, MarkdownSplitter, SplitterExt};
 
 #[cfg(feature = "markdown")]
 #[test]
@@ -137,3 +137,52 @@ fn trimming_does_trim_block_level_indentation_if_only_one_item() {
         chunks
     );
 }
+
+#[cfg(feature = "markdown")]
+#[test]
+fn parse_ranges_rank_headings_highest() {
+    let text = "# Title\n\nSome *text* here.\n\n## Section\n\nMore text.";
+    let splitter = MarkdownSplitter::new(100);
+    let ranges = splitter.parse_ranges(text);
+
+    let max_level = ranges.iter().map(|range| range.level).max().unwrap();
+    let highest = ranges
+        .iter()
+        .filter(|range| range.level == max_level)
+        .map(|range| &text[range.range.clone()])
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["# Title\n"], highest);
+    assert!(ranges
+        .iter()
+        .any(|range| &text[range.range.clone()] == "*text*" && range.level == 0));
+    assert!(ranges
+        .iter()
+        .map(|range| range.range.start)
+        .tuple_windows()
+        .all(|(a, b)| a <= b));
+}
+
+#[cfg(feature = "markdown")]
+#[test]
+fn take_chunks_matches_first_chunks() {
+    let text = fs::read_to_string("tests/inputs/markdown/commonmark_spec.md").unwrap();
+    let splitter = MarkdownSplitter::new(200);
+
+    assert_eq!(
+        splitter.chunk_indices(&text).take(10).collect::<Vec<_>>(),
+        splitter.take_chunks(&text, 10).collect::<Vec<_>>()
+    );
+}
+
+#[cfg(feature = "markdown")]
+#[test]
+fn tail_chunks_match_last_chunks() {
+    let text = fs::read_to_string("tests/inputs/markdown/commonmark_spec.md").unwrap();
+    let splitter = MarkdownSplitter::new(200);
+    let chunks = splitter.chunk_indices(&text).collect::<Vec<_>>();
+
+    assert_eq!(
+        chunks[chunks.len() - 10..].to_vec(),
+        splitter.tail_chunks(&text, 10)
+    );
+}
@@ -0,0 +1,129 @@
+//! Tests that chunks are the same on every platform. The pieces that rely on
+//! other crates, like Unicode segmentation and regexes, are checked against
+//! fixed expected chunks, so running these tests on each platform shows they
+//! agree.
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use text_splitter::{
+    chunking_fingerprint, Characters, ChunkConfig, SentenceBoundaries, TextSplitter,
+};
+
+fn chunks(text: &str, chunk_config: ChunkConfig<Characters>) -> Vec<&str> {
+    TextSplitter::new(chunk_config.with_trim(false))
+        .chunks(text)
+        .collect()
+}
+
+#[test]
+fn grapheme_clusters() {
+    assert_eq!(
+        chunks("👨‍👩‍👧‍👦🇯🇵", ChunkConfig::new(7)),
+        vec!["👨\u{200d}👩\u{200d}👧\u{200d}👦", "🇯🇵"]
+    );
+    assert_eq!(
+        chunks(
+            "e\u{301}e\u{301}\u{1100}\u{1161}\u{11A8}",
+            ChunkConfig::new(3)
+        ),
+        vec!["e\u{301}", "e\u{301}", "\u{1100}\u{1161}\u{11A8}"]
+    );
+}
+
+#[test]
+fn words_without_spaces() {
+    assert_eq!(
+        chunks("สวัสดีครับ ยินดีต้อนรับ", ChunkConfig::new(7)),
+        vec!["สวัสดี", "ครับ ", "ยินดี", "ต้อนรับ"]
+    );
+    assert_eq!(
+        chunks(
+            "東京都は日本の首都です。中文没有空格！",
+            ChunkConfig::new(4)
+        ),
+        vec!["東京都は", "日本の", "首都です", "。", "中文没有", "空格！"]
+    );
+}
+
+#[test]
+fn sentences() {
+    assert_eq!(
+        chunks(
+            "Mr. Smith paid 1.5 million, i.e. a lot. Did he mind? No.",
+            ChunkConfig::new(30)
+        ),
+        vec![
+            "Mr. ",
+            "Smith paid 1.5 million, i.e. a",
+            " lot. Did he mind? No."
+        ]
+    );
+
+    let text = "As shown in Fig. 3 by Smith et al. [12, 13]. It works.";
+    assert_eq!(
+        chunks(text, ChunkConfig::new(40)),
+        vec!["As shown in Fig. 3 by Smith et al. ", "[12, 13]. It works."]
+    );
+    assert_eq!(
+        chunks(
+            text,
+            ChunkConfig::new(40).with_sentence_boundaries(SentenceBoundaries::Scientific)
+        ),
+        vec!["As shown in Fig. 3 by Smith et al. [12, ", "13]. It works."]
+    );
+}
+
+#[test]
+fn line_breaks() {
+    assert_eq!(
+        chunks("One\r\nTwo\r\n\r\nThree\u{2029}Four", ChunkConfig::new(8)),
+        vec!["One\r\nTwo", "\r\n\r\n", "Three\u{2029}", "Four"]
+    );
+}
+
+#[cfg(feature = "markdown")]
+#[test]
+fn markdown() {
+    let text = "# Title\n\nSome *text* here.\n\n- one\n- two\n\n```\ncode\n```\n";
+    assert_eq!(
+        text_splitter::MarkdownSplitter::new(12)
+            .chunks(text)
+            .collect::<Vec<_>>(),
+        vec![
+            "# Title",
+            "Some *text*",
+            "here.",
+            "- one\n- two",
+            "```\ncode\n```"
+        ]
+    );
+}
+
+#[test]
+fn chunks_do_not_depend_on_hash_seeds() {
+    let text = std::fs::read_to_string("tests/inputs/text/room_with_a_view.txt").unwrap();
+    // Each splitter, and each thread, gets its own randomly seeded hash maps.
+    let runs = (0..8)
+        .into_par_iter()
+        .map(|_| {
+            TextSplitter::new(200)
+                .chunk_indices(&text)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    assert!(runs.windows(2).all(|pair| pair[0] == pair[1]));
+}
+
+#[test]
+fn fingerprint_matches_expected_chunks() {
+    // If this changes, chunks changed as well. Update it along with a note in
+    // the changelog that previously stored chunks may need to be recomputed.
+    #[cfg(not(feature = "markdown"))]
+    let expected = "da249322defbdcd1";
+    #[cfg(feature = "markdown")]
+    let expected = "5075c68a2d97f019";
+
+    assert_eq!(
+        chunking_fingerprint(),
+        format!("{}-{expected}", env!("CARGO_PKG_VERSION"))
+    );
+}
@@ -0,0 +1,103 @@
+//! Tests that the work done to chunk adversarial inputs, such as many
+//! identical short lines, grows linearly with the text, where it used to grow
+//! quadratically. The work is counted instead of timed, so the tests are
+//! deterministic.
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use text_splitter::{ChunkConfig, ChunkSizer, ChunkingCounters, SplitterExt, TextSplitter};
+
+/// Counts chars, and how many bytes it was asked to measure in total
+#[derive(Debug, Default)]
+struct CountingSizer(AtomicUsize);
+
+impl ChunkSizer for CountingSizer {
+    fn size(&self, chunk: &str) -> usize {
+        self.0.fetch_add(chunk.len(), Ordering::Relaxed);
+        chunk.chars().count()
+    }
+}
+
+/// Work done to chunk a text: the sections examined, and the bytes measured
+fn work(counters: ChunkingCounters, sizer: &CountingSizer) -> (usize, usize) {
+    (counters.sections_examined, sizer.0.load(Ordering::Relaxed))
+}
+
+/// Work to chunk texts of `n` and `4 * n` repetitions must grow by less than
+/// 5 times, which quadratic behavior (16 times) wouldn't meet.
+fn assert_linear(n: usize, chunk: impl Fn(usize) -> (usize, usize)) {
+    let (small_sections, small_bytes) = chunk(n);
+    let (large_sections, large_bytes) = chunk(n * 4);
+
+    assert!(
+        large_sections < small_sections * 5,
+        "{n} repetitions examined {small_sections} sections, {} examined {large_sections}",
+        n * 4
+    );
+    assert!(
+        large_bytes < small_bytes * 5,
+        "{n} repetitions measured {small_bytes} bytes, {} measured {large_bytes}",
+        n * 4
+    );
+}
+
+#[test]
+fn identical_lines_scale_linearly() {
+    assert_linear(2_000, |n| {
+        let splitter =
+            TextSplitter::new(ChunkConfig::new(200).with_sizer(CountingSizer::default()));
+        let (_, counters) = splitter.chunk_indices_with_counters(&"abc def\n".repeat(n));
+        work(counters, splitter.chunk_config().sizer())
+    });
+}
+
+#[cfg(feature = "markdown")]
+#[test]
+fn paragraphs_before_one_heading_scale_linearly() {
+    use text_splitter::MarkdownSplitter;
+
+    assert_linear(2_000, |n| {
+        let splitter =
+            MarkdownSplitter::new(ChunkConfig::new(200).with_sizer(CountingSizer::default()));
+        let text = format!("{}# Heading\n\nend", "abc def\n\n".repeat(n));
+        let (_, counters) = splitter.chunk_indices_with_counters(&text);
+        work(counters, splitter.chunk_config().sizer())
+    });
+}
+
+#[cfg(feature = "markdown")]
+#[test]
+fn v1_measures_whole_sections() {
+    use text_splitter::{ChunkingVersion, MarkdownSplitter};
+
+    let measured = |n| {
+        let splitter = MarkdownSplitter::new(
+            ChunkConfig::new(200)
+                .with_sizer(CountingSizer::default())
+                .with_algorithm_version(ChunkingVersion::V1),
+        );
+        let text = format!("{}# Heading\n\nend", "abc def\n\n".repeat(n));
+        let (chunks, _) = splitter.chunk_indices_with_counters(&text);
+        (
+            chunks
+                .into_iter()
+                .map(|(offset, chunk)| offset..offset + chunk.len())
+                .collect::<Vec<_>>(),
+            splitter.chunk_config().sizer().0.load(Ordering::Relaxed),
+        )
+    };
+    let (chunks, small_bytes) = measured(500);
+    let (_, large_bytes) = measured(2_000);
+
+    // Same chunks, but the section before the heading is measured in full
+    // for every chunk, so the work grows quadratically.
+    let latest = MarkdownSplitter::new(200);
+    let text = format!("{}# Heading\n\nend", "abc def\n\n".repeat(500));
+    assert_eq!(
+        chunks,
+        latest
+            .chunk_indices(&text)
+            .map(|(offset, chunk)| offset..offset + chunk.len())
+            .collect::<Vec<_>>()
+    );
+    assert!(large_bytes > small_bytes * 10);
+}
@@ -0,0 +1,124 @@
+//! Test for the `CustomSplitter` schema presets.
+use fake::{Fake, Faker};
+use itertools::Itertools;
+use text_splitter::{ChunkConfig, CustomSplitter, SchemaLanguage};
+
+const PROTO: &str = r#"syntax = "proto3";
+
+package example.v1;
+
+// A user of the service
+message User {
+  string id = 1;
+  string name = 2; // Display name
+  repeated Address addresses = 3;
+
+  message Address {
+    string street = 1;
+    string city = 2;
+  }
+}
+
+// Manages users
+service UserService {
+  // Get a single user
+  rpc GetUser(GetUserRequest) returns (User);
+  rpc ListUsers(ListUsersRequest)
+    returns (stream User);
+}
+"#;
+
+const GRAPHQL: &str = r#""""
+A user of the service
+"""
+type User {
+  id: ID!
+  "Display name"
+  name: String
+  posts(first: Int = 10, after: String): [Post!]!
+}
+
+type Post {
+  id: ID!
+  title: String!
+}
+
+union SearchResult = User | Post
+"#;
+
+const THRIFT: &str = r"namespace java example
+
+/** A user of the service */
+struct User {
+  1: required string id,
+  2: optional string name,
+  3: map<string, string> attributes
+}
+
+service UserService {
+  User getUser(1: string id)
+}
+";
+
+#[test]
+fn chunks_roundtrip() {
+    for (language, text) in [
+        (SchemaLanguage::Protobuf, PROTO),
+        (SchemaLanguage::GraphQl, GRAPHQL),
+        (SchemaLanguage::Thrift, THRIFT),
+    ] {
+        for _ in 0..10 {
+            let max_characters = Faker.fake::<usize>() % 100 + 1;
+            let splitter =
+                CustomSplitter::schema(language, ChunkConfig::new(max_characters).with_trim(false));
+            let chunks = splitter.chunk_indices(text).collect::<Vec<_>>();
+
+            assert_eq!(chunks.iter().map(|(_, c)| *c).join(""), text);
+            assert!(chunks
+                .iter()
+                .map(|(i, _)| i)
+                .tuple_windows()
+                .all(|(a, b)| a < b));
+        }
+    }
+}
+
+#[test]
+fn protobuf_definitions() {
+    let splitter = CustomSplitter::schema(SchemaLanguage::Protobuf, 200);
+    let chunks = splitter.chunks(PROTO).collect::<Vec<_>>();
+
+    assert_eq!(chunks.len(), 3);
+    assert!(chunks[0].starts_with("syntax"));
+    assert!(chunks[1].starts_with("// A user of the service\nmessage User {"));
+    assert!(chunks[2].starts_with("// Manages users\nservice UserService {"));
+}
+
+#[test]
+fn graphql_definitions() {
+    let splitter = CustomSplitter::schema(SchemaLanguage::GraphQl, 140);
+    let chunks = splitter.chunks(GRAPHQL).collect::<Vec<_>>();
+
+    assert_eq!(
+        chunks,
+        vec![
+            "\"\"\"\nA user of the service\n\"\"\"\ntype User {\n  id: ID!\n  \"Display name\"\n  name: String\n  posts(first: Int = 10, after: String): [Post!]!\n}",
+            "type Post {\n  id: ID!\n  title: String!\n}\n\nunion SearchResult = User | Post",
+        ]
+    );
+}
+
+#[test]
+fn thrift_definitions() {
+    let splitter = CustomSplitter::schema(SchemaLanguage::Thrift, 140);
+    let chunks = splitter.chunks(THRIFT).collect::<Vec<_>>();
+
+    assert_eq!(
+        chunks,
+        vec![
+            "namespace java example",
+            "/** A user of the service */\nstruct User {\n  1: required string id,\n  2: optional string name,\n  3: map<string, string> attributes\n}",
+            "service UserService {\n  User getUser(1: string id)\n}",
+        ]
+    );
+}
@@ -1,7 +1,6 @@
 //! Snapshot tests for regressions in chunk output.
-use std::{fs, ops::RangeInclusive, path::PathBuf};
+use std::{fs, ops::RangeInclusive};
 
-use cached_path::Cache;
 use rayon::prelude::*;
 #[cfg(feature = "rust-tokenizers")]
 use rust_tokenizers::tokenizer::BertTokenizer;
@@ -18,15 +17,23 @@ use tokenizers::Tokenizer;
 
 const CHUNK_SIZES: [usize; 3] = [32, 512, 8192];
 const RANGE_CHUNK_SIZES: [RangeInclusive<usize>; 2] = [64..=512, 512..=4096];
+/// The right-to-left inputs are short, so smaller chunks exercise more of them
+const RTL_CHUNK_SIZES: [usize; 3] = [16, 64, 256];
+/// Bidi control characters that apply to the text after them
+const BIDI_CONTROLS: &[char] = &[
+    '\u{200E}', '\u{200F}', '\u{061C}', '\u{202A}', '\u{202B}', '\u{202D}', '\u{202E}', '\u{2066}',
+    '\u{2067}', '\u{2068}',
+];
 
 /// Downloads a remote file to the cache directory if it doensn't already exist,
 /// and returns the path to the cached file.
-fn download_file_to_cache(src: &str) -> PathBuf {
+#[cfg(feature = "rust-tokenizers")]
+fn download_file_to_cache(src: &str) -> std::path::PathBuf {
     let mut cache_dir = dirs::home_dir().unwrap();
     cache_dir.push(".cache");
     cache_dir.push(".text-splitter");
 
-    Cache::builder()
+    cached_path::Cache::builder()
         .dir(cache_dir)
         .build()
         .unwrap()
@@ -139,6 +146,60 @@ fn trim() {
     });
 }
 
+#[test]
+fn rtl_trim_false() {
+    insta::glob!("inputs/rtl/*.txt", |path| {
+        let text = fs::read_to_string(path).unwrap();
+
+        RTL_CHUNK_SIZES.into_par_iter().for_each(|chunk_size| {
+            let config = ChunkConfig::new(chunk_size).with_trim(false);
+            let capacity = *config.capacity();
+            let splitter = TextSplitter::new(config);
+            let chunks = splitter.chunks(&text).collect::<Vec<_>>();
+
+            assert_eq!(chunks.join(""), text);
+            for chunk in &chunks {
+                assert!(capacity.fits(Characters.size(chunk)).is_le());
+            }
+            for chunk in &chunks[..chunks.len() - 1] {
+                assert!(!chunk.ends_with(BIDI_CONTROLS));
+            }
+            insta::assert_yaml_snapshot!(
+                format!(
+                    "{}_Characters_trim_false_{chunk_size}",
+                    path.file_stem().unwrap().to_string_lossy()
+                ),
+                chunks
+            );
+        });
+    });
+}
+
+#[test]
+fn rtl_trim() {
+    insta::glob!("inputs/rtl/*.txt", |path| {
+        let text = fs::read_to_string(path).unwrap();
+
+        RTL_CHUNK_SIZES.into_par_iter().for_each(|chunk_size| {
+            let config = ChunkConfig::new(chunk_size);
+            let capacity = *config.capacity();
+            let splitter = TextSplitter::new(config);
+            let chunks = splitter.chunks(&text).collect::<Vec<_>>();
+
+            for chunk in &chunks {
+                assert!(capacity.fits(Characters.size(chunk)).is_le());
+            }
+            insta::assert_yaml_snapshot!(
+                format!(
+                    "{}_Characters_trim_{chunk_size}",
+                    path.file_stem().unwrap().to_string_lossy()
+                ),
+                chunks
+            );
+        });
+    });
+}
+
 #[test]
 fn range_trim_false() {
     insta::glob!("inputs/text/*.txt", |path| {
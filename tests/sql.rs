@@ -0,0 +1,69 @@
+//! Test for `SqlSplitter` behavior.
+use fake::{Fake, Faker};
+use itertools::Itertools;
+use text_splitter::{ChunkConfig, SqlSplitter};
+
+const SQL: &str = r"-- Schema
+CREATE TABLE users (
+  id SERIAL PRIMARY KEY,
+  name TEXT NOT NULL DEFAULT 'unknown; user'
+);
+
+CREATE FUNCTION touch() RETURNS trigger AS $$
+BEGIN
+  NEW.updated_at = now();
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+BEGIN;
+INSERT INTO users (id, name) VALUES
+  (1, 'Alice'),
+  (2, 'Bob'),
+  (3, 'Carol (admin)'),
+  (4, 'Dave');
+UPDATE users SET name = 'Eve' WHERE id = 4;
+COMMIT;
+";
+
+#[test]
+fn chunks_roundtrip() {
+    for _ in 0..10 {
+        let max_characters = Faker.fake::<usize>() % 200 + 1;
+        let splitter = SqlSplitter::new(ChunkConfig::new(max_characters).with_trim(false));
+        let chunks = splitter.chunk_indices(SQL).collect::<Vec<_>>();
+
+        assert_eq!(chunks.iter().map(|(_, c)| *c).join(""), SQL);
+        assert!(chunks
+            .iter()
+            .map(|(i, _)| i)
+            .tuple_windows()
+            .all(|(a, b)| a < b));
+    }
+}
+
+#[test]
+fn chunks_only_split_between_statements_and_rows() {
+    for _ in 0..10 {
+        let max_characters = Faker.fake::<usize>() % 200 + 1;
+        let splitter = SqlSplitter::new(max_characters);
+
+        for chunk in splitter.chunks(SQL) {
+            assert!(
+                chunk.ends_with([';', ')', ',']) || chunk.ends_with("VALUES"),
+                "chunk ends in the middle of a statement: {chunk:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn transaction_is_kept_together() {
+    let splitter = SqlSplitter::new(250);
+    let chunks = splitter.chunks(SQL).collect::<Vec<_>>();
+
+    assert_eq!(chunks.len(), 2);
+    assert!(chunks[0].starts_with("-- Schema\nCREATE TABLE users"));
+    assert!(chunks[1].starts_with("BEGIN;\nINSERT INTO users"));
+    assert!(chunks[1].ends_with("COMMIT;"));
+}
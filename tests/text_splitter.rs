@@ -5,7 +5,10 @@ use fake::{Fake, Faker};
 use itertools::Itertools;
 use more_asserts::assert_le;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use text_splitter::{ChunkConfig, TextSplitter};
+use text_splitter::{
+    ChunkConfig, ChunkNode, ChunkSizer, ChunkingVersion, Redactions, SizeCheckpoint, SplitterExt,
+    SplitterSession, TextSplitter,
+};
 
 #[test]
 fn chunk_by_paragraphs() {
@@ -241,3 +244,513 @@ fn random_chunk_overlap() {
         }
     });
 }
+
+#[test]
+fn chunks_never_span_documents() {
+    let text = "Mr. Fox jumped.\n===DOC===\nThe dog was too lazy.\n===DOC===\nIt just sat there.";
+    let splitter = TextSplitter::new(100);
+
+    let chunks = splitter
+        .chunk_indices_by_document(text, "\n===DOC===\n")
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            (0, 0, "Mr. Fox jumped."),
+            (1, 26, "The dog was too lazy."),
+            (2, 58, "It just sat there.")
+        ],
+        chunks
+    );
+    for (_, offset, chunk) in chunks {
+        assert_eq!(&text[offset..offset + chunk.len()], chunk);
+    }
+}
+
+#[test]
+fn hierarchical_chunks_are_within_parents() {
+    let text = fs::read_to_string("tests/inputs/text/room_with_a_view.txt").unwrap();
+    let splitter = TextSplitter::new(1);
+    let parent_config = ChunkConfig::new(2000);
+    let child_config = ChunkConfig::new(256);
+
+    let chunks = splitter.hierarchical_chunks(&text, &parent_config, &child_config);
+
+    assert_eq!(
+        chunks.parents,
+        TextSplitter::new(2000)
+            .chunk_indices(&text)
+            .collect::<Vec<_>>()
+    );
+    assert!(chunks.children.len() > chunks.parents.len());
+    for child in &chunks.children {
+        let (parent_offset, parent) = chunks.parents[child.parent];
+        assert!(child.text.chars().count() <= 256);
+        assert_eq!(child.offset, parent_offset + child.parent_offset);
+        assert_eq!(
+            &text[child.offset..child.offset + child.text.len()],
+            child.text
+        );
+        assert!(child.parent_offset + child.text.len() <= parent.len());
+    }
+    assert!(chunks
+        .children
+        .iter()
+        .map(|child| child.offset)
+        .tuple_windows()
+        .all(|(a, b)| a < b));
+}
+
+/// Check that every child is within its parent and its level's capacity
+fn check_chunk_node(node: &ChunkNode<'_>, text: &str, capacities: &[usize]) {
+    for child in &node.children {
+        assert_le!(child.text.chars().count(), capacities[0]);
+        assert_eq!(
+            &text[child.offset..child.offset + child.text.len()],
+            child.text
+        );
+        assert!(child.offset >= node.offset);
+        assert_le!(
+            child.offset + child.text.len(),
+            node.offset + node.text.len()
+        );
+        check_chunk_node(child, text, &capacities[1..]);
+    }
+    if capacities.is_empty() {
+        assert!(node.children.is_empty());
+    }
+}
+
+#[test]
+fn chunk_tree_levels_match_capacities() {
+    let text = fs::read_to_string("tests/inputs/text/room_with_a_view.txt").unwrap();
+    let splitter = TextSplitter::new(1);
+    let configs = [
+        ChunkConfig::new(4000),
+        ChunkConfig::new(1000),
+        ChunkConfig::new(100),
+    ];
+
+    let tree = splitter.chunk_tree(&text, &configs);
+
+    assert_eq!((tree.offset, tree.text), (0, text.as_str()));
+    assert_eq!(
+        tree.children
+            .iter()
+            .map(|node| (node.offset, node.text))
+            .collect::<Vec<_>>(),
+        TextSplitter::new(4000)
+            .chunk_indices(&text)
+            .collect::<Vec<_>>()
+    );
+    check_chunk_node(&tree, &text, &[4000, 1000, 100]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn chunk_tree_serializes() {
+    let splitter = TextSplitter::new(1);
+    let tree = splitter.chunk_tree("One. Two.", &[ChunkConfig::new(5)]);
+
+    assert_eq!(
+        serde_json::to_string(&tree).unwrap(),
+        r#"{"offset":0,"text":"One. Two.","children":[{"offset":0,"text":"One.","children":[]},{"offset":5,"text":"Two.","children":[]}]}"#
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn parse_ranges_serialize() {
+    let splitter = TextSplitter::new(1);
+    let ranges = splitter.parse_ranges("One\nTwo");
+
+    assert_eq!(
+        serde_json::to_string(&ranges).unwrap(),
+        r#"[{"level":0,"name":"LineBreaks(1)","range":{"start":3,"end":4}}]"#
+    );
+}
+
+#[test]
+fn pinned_algorithm_version_matches_latest() {
+    let text = fs::read_to_string("tests/inputs/text/room_with_a_view.txt").unwrap();
+    let latest =
+        TextSplitter::new(ChunkConfig::new(500).with_algorithm_version(ChunkingVersion::Latest));
+    let pinned =
+        TextSplitter::new(ChunkConfig::new(500).with_algorithm_version(ChunkingVersion::V3));
+
+    assert_eq!(
+        latest.chunk_indices(&text).collect::<Vec<_>>(),
+        pinned.chunk_indices(&text).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn bidi_controls_stay_with_following_text() {
+    // A right-to-left isolate around a Hebrew word in English text
+    let text = "ab \u{2067}\u{5E9}\u{5DC}\u{5D5}\u{5DD}\u{2069} cd";
+    let latest = TextSplitter::new(ChunkConfig::new(6).with_trim(false));
+    let pinned = TextSplitter::new(
+        ChunkConfig::new(6)
+            .with_trim(false)
+            .with_algorithm_version(ChunkingVersion::V1),
+    );
+
+    assert_eq!(
+        latest.chunks(text).collect::<Vec<_>>(),
+        ["ab ", "\u{2067}\u{5E9}\u{5DC}\u{5D5}\u{5DD}\u{2069}", " cd"]
+    );
+    assert_eq!(
+        pinned.chunks(text).collect::<Vec<_>>(),
+        ["ab \u{2067}", "\u{5E9}\u{5DC}\u{5D5}\u{5DD}\u{2069} ", "cd"]
+    );
+}
+
+#[test]
+fn char_fallback_keeps_emoji_sequences_whole() {
+    // A letter with more combining marks than fit in a chunk, followed by a
+    // ZWJ family emoji and a flag
+    let text = "a\u{301}\u{302}\u{303}\u{304}\u{305}\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\u{1F1EF}\u{1F1F5}";
+    let splitter = TextSplitter::new(5);
+
+    assert_eq!(
+        splitter.chunks(text).collect::<Vec<_>>(),
+        [
+            "a\u{301}\u{302}\u{303}\u{304}",
+            "\u{305}",
+            "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}",
+            "\u{1F1EF}\u{1F1F5}"
+        ]
+    );
+}
+
+#[test]
+fn char_fallback_by_algorithm_version() {
+    // Counts chars, but a chunk ending in a dangling combining mark costs more,
+    // so that the size isn't monotonic, as it can be for tokenizers
+    struct DanglingMarkSizer;
+
+    impl ChunkSizer for DanglingMarkSizer {
+        fn size(&self, chunk: &str) -> usize {
+            chunk.chars().count() + if chunk.ends_with('\u{301}') { 3 } else { 0 }
+        }
+    }
+
+    let text = "a\u{301}\u{301}\u{301}\u{301}\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}x";
+    let splitter = |version| {
+        TextSplitter::new(
+            ChunkConfig::new(5)
+                .with_sizer(DanglingMarkSizer)
+                .with_algorithm_version(version),
+        )
+    };
+
+    assert_eq!(
+        splitter(ChunkingVersion::V3)
+            .chunks(text)
+            .collect::<Vec<_>>(),
+        [
+            "a\u{301}",
+            "\u{301}\u{301}",
+            "\u{301}",
+            "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}",
+            "x"
+        ]
+    );
+    assert_eq!(
+        splitter(ChunkingVersion::V2)
+            .chunks(text)
+            .collect::<Vec<_>>(),
+        [
+            "a\u{301}",
+            "\u{301}\u{301}\u{301}\u{1F468}",
+            "\u{200D}\u{1F469}\u{200D}\u{1F467}x"
+        ]
+    );
+}
+
+#[test]
+fn session_matches_chunk_indices_across_documents() {
+    let splitter = TextSplitter::new(ChunkConfig::new(200).with_overlap(20).unwrap());
+    let mut session = SplitterSession::new();
+
+    for filename in ["romeo_and_juliet", "room_with_a_view"] {
+        let text = fs::read_to_string(format!("tests/inputs/text/{filename}.txt")).unwrap();
+        // Stop partway through the first pass to make sure buffers are still reused
+        let partial = splitter
+            .chunk_indices_with_session(&text, &mut session)
+            .take(3)
+            .collect::<Vec<_>>();
+        let chunks = splitter
+            .chunk_indices_with_session(&text, &mut session)
+            .collect::<Vec<_>>();
+
+        assert_eq!(splitter.chunk_indices(&text).collect::<Vec<_>>(), chunks);
+        assert_eq!(chunks[..3], partial);
+    }
+}
+
+#[test]
+fn lazy_parsing_matches_eager_parsing() {
+    for filename in ["romeo_and_juliet", "room_with_a_view"] {
+        let text = fs::read_to_string(format!("tests/inputs/text/{filename}.txt")).unwrap();
+        for (capacity, trim) in [(64, false), (500, true), (2000, true)] {
+            let config = || {
+                ChunkConfig::new(capacity)
+                    .with_overlap(5)
+                    .unwrap()
+                    .with_trim(trim)
+            };
+            let eager = TextSplitter::new(config());
+            let lazy = TextSplitter::new(config()).with_lazy_parsing(true);
+
+            assert_eq!(
+                eager.chunk_indices(&text).collect::<Vec<_>>(),
+                lazy.chunk_indices(&text).collect::<Vec<_>>()
+            );
+        }
+    }
+}
+
+#[test]
+fn take_chunks_matches_first_chunks() {
+    let text = fs::read_to_string("tests/inputs/text/room_with_a_view.txt").unwrap();
+    let splitter = TextSplitter::new(ChunkConfig::new(100).with_overlap(10).unwrap());
+
+    for n in [0, 1, 25] {
+        assert_eq!(
+            splitter.chunk_indices(&text).take(n).collect::<Vec<_>>(),
+            splitter.take_chunks(&text, n).collect::<Vec<_>>()
+        );
+    }
+}
+
+#[test]
+fn tail_chunks_match_last_chunks() {
+    let text = fs::read_to_string("tests/inputs/text/room_with_a_view.txt").unwrap();
+    for capacity in [50, 500, 5000] {
+        let splitter = TextSplitter::new(capacity);
+        let chunks = splitter.chunk_indices(&text).collect::<Vec<_>>();
+
+        assert_eq!(
+            chunks[chunks.len() - 5..].to_vec(),
+            splitter.tail_chunks(&text, 5)
+        );
+    }
+}
+
+#[test]
+fn counters_track_chunking_work() {
+    let text = fs::read_to_string("tests/inputs/text/room_with_a_view.txt").unwrap();
+    let splitter = TextSplitter::new(500);
+    let (chunks, counters) = splitter.chunk_indices_with_counters(&text);
+
+    assert_eq!(splitter.chunk_indices(&text).collect::<Vec<_>>(), chunks);
+    assert_eq!(chunks.len(), counters.chunks);
+    assert_le!(counters.sizer_calls, counters.size_lookups);
+    assert_le!(counters.chunks, counters.sections_examined);
+    assert_le!(counters.chunks, counters.search_iterations);
+}
+
+#[test]
+fn chunks_never_cross_forced_breaks() {
+    let text = fs::read_to_string("tests/inputs/text/room_with_a_view.txt").unwrap();
+    let splitter = TextSplitter::new(1000);
+    let breaks = (1..text.len())
+        .step_by(2500)
+        .filter(|&offset| text.is_char_boundary(offset))
+        .collect::<Vec<_>>();
+
+    for (offset, chunk) in splitter.chunk_indices_with_breaks(&text, &breaks) {
+        let end = offset + chunk.len();
+        assert!(breaks.iter().all(|&b| b <= offset || b >= end));
+    }
+    assert_eq!(
+        splitter.chunk_indices(&text).collect::<Vec<_>>(),
+        splitter
+            .chunk_indices_with_breaks(&text, &[])
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn chunks_never_split_protected_ranges() {
+    let text = fs::read_to_string("tests/inputs/text/room_with_a_view.txt").unwrap();
+    let splitter = TextSplitter::new(200);
+    let protected = text
+        .match_indices("Miss Bartlett")
+        .map(|(offset, name)| offset..offset + name.len())
+        .collect::<Vec<_>>();
+
+    for (offset, chunk) in splitter.chunk_indices_with_protected_ranges(&text, &protected) {
+        let end = offset + chunk.len();
+        for range in &protected {
+            assert!(!range.contains(&offset) || range.start == offset);
+            assert!(!(range.start < end && end < range.end));
+        }
+    }
+}
+
+#[test]
+fn ignored_ranges_are_left_out_of_chunks() {
+    let text = fs::read_to_string("tests/inputs/text/room_with_a_view.txt").unwrap();
+    let splitter = TextSplitter::new(300);
+    let ignore = text
+        .match_indices("Miss Bartlett")
+        .map(|(offset, name)| offset..offset + name.len())
+        .collect::<Vec<_>>();
+
+    let chunks = splitter.chunks_ignoring_ranges(&text, &ignore);
+    assert!(!chunks.is_empty());
+    for chunk in chunks {
+        let mut expected = String::new();
+        let mut cursor = chunk.range.start;
+        for range in ignore
+            .iter()
+            .filter(|range| chunk.range.start <= range.start && range.end <= chunk.range.end)
+        {
+            expected.push_str(&text[cursor..range.start]);
+            cursor = range.end;
+        }
+        expected.push_str(&text[cursor..chunk.range.end]);
+
+        assert_eq!(expected, chunk.text);
+        assert!(!chunk.text.contains("Miss Bartlett"));
+    }
+}
+
+#[test]
+fn redacted_spans_are_masked_in_chunks() {
+    let text = fs::read_to_string("tests/inputs/text/room_with_a_view.txt").unwrap();
+    let capacity = 300;
+    let splitter = TextSplitter::new(capacity);
+    let redactions =
+        Redactions::new("<NAME>").with_pattern(regex::Regex::new("Miss Bartlett").unwrap());
+
+    let chunks = splitter.chunks_redacting(&text, &redactions);
+    assert!(!chunks.is_empty());
+    for chunk in chunks {
+        assert_le!(chunk.text.chars().count(), capacity);
+        assert!(!chunk.text.contains("Miss Bartlett"));
+        assert_eq!(
+            text[chunk.range.clone()].replace("Miss Bartlett", "<NAME>"),
+            chunk.text
+        );
+    }
+}
+
+#[test]
+fn soft_wraps_are_joined_in_chunks() {
+    let text = fs::read_to_string("tests/inputs/text/room_with_a_view.txt").unwrap();
+    // Hard-wrap each line after every eighth word
+    let wrapped = text
+        .lines()
+        .map(|line| {
+            line.split(' ')
+                .chunks(8)
+                .into_iter()
+                .map(|mut words| words.join(" "))
+                .join("\n")
+        })
+        .join("\n");
+    let capacity = 300;
+    let splitter = TextSplitter::new(capacity);
+    let letters = |s: &str| {
+        s.chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .collect::<String>()
+    };
+
+    let chunks = splitter.chunks_joining_soft_wraps(&wrapped);
+    assert!(!chunks.is_empty());
+    for chunk in chunks {
+        assert_le!(chunk.text.chars().count(), capacity);
+        // Only paragraph breaks are left
+        assert!(chunk
+            .text
+            .split('\n')
+            .tuple_windows()
+            .all(|(a, b)| a.trim().is_empty() || b.trim().is_empty()));
+        assert_eq!(letters(&wrapped[chunk.range.clone()]), letters(&chunk.text));
+    }
+}
+
+#[test]
+fn size_checkpoints_are_cumulative() {
+    let text = fs::read_to_string("tests/inputs/text/room_with_a_view.txt").unwrap();
+    let splitter = TextSplitter::new(300);
+
+    let checkpoints = splitter.size_checkpoints(&text);
+    assert!(checkpoints
+        .iter()
+        .tuple_windows()
+        .all(|(a, b)| a.offset < b.offset && a.size <= b.size));
+    for SizeCheckpoint { offset, size, .. } in &checkpoints {
+        assert_eq!(text[..*offset].chars().count(), *size);
+    }
+    assert_eq!(Some(text.len()), checkpoints.last().map(|c| c.offset));
+}
+
+#[test]
+fn sticky_punctuation_stays_with_previous_chunk() {
+    let text = fs::read_to_string("tests/inputs/text/room_with_a_view.txt").unwrap();
+    let max = 12;
+    let splitter = TextSplitter::new(
+        ChunkConfig::new(6..=max)
+            .with_trim(false)
+            .with_sticky_punctuation(true),
+    );
+    let closing = ['.', ',', ';', ':', '!', '?', '"', '”', '’', ')'];
+
+    let chunks = splitter.chunks(&text).collect::<Vec<_>>();
+    assert_eq!(chunks.join(""), text);
+    for (prev, chunk) in chunks.iter().tuple_windows() {
+        let punctuation = chunk.len() - chunk.trim_start_matches(closing).len();
+        if punctuation > 0 && !prev.ends_with(char::is_whitespace) {
+            // Only allowed if the punctuation doesn't fit in the previous chunk
+            assert!(
+                prev.chars().count() + punctuation > max,
+                "{prev:?} {chunk:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn whole_words_only_split_when_oversized() {
+    let text = fs::read_to_string("tests/inputs/text/room_with_a_view.txt").unwrap();
+    let max = 12;
+    let splitter = TextSplitter::new(
+        ChunkConfig::new(max)
+            .with_trim(false)
+            .with_whole_words(true),
+    );
+
+    let chunks = splitter.chunk_indices(&text).collect::<Vec<_>>();
+    assert_eq!(chunks.iter().map(|(_, chunk)| *chunk).join(""), text);
+    for (offset, _) in chunks.into_iter().skip(1) {
+        let (before, after) = text.split_at(offset);
+        if !before.ends_with(char::is_whitespace) && !after.starts_with(char::is_whitespace) {
+            // Only allowed within a word that doesn't fit in a chunk by itself
+            let start = before.trim_end_matches(|c: char| !c.is_whitespace()).len();
+            let end = offset + after.find(char::is_whitespace).unwrap_or(after.len());
+            let word = &text[start..end];
+            assert!(word.chars().count() > max, "{word:?}");
+        }
+    }
+}
+
+#[test]
+fn line_aligned_chunks_end_at_newlines() {
+    let text = fs::read_to_string("tests/inputs/text/room_with_a_view.txt").unwrap();
+    let splitter = TextSplitter::new(
+        ChunkConfig::new(50..=80)
+            .with_trim(false)
+            .with_line_aligned(true),
+    );
+
+    let chunks = splitter.chunks(&text).collect::<Vec<_>>();
+    assert_eq!(chunks.join(""), text);
+    for chunk in chunks.iter().rev().skip(1) {
+        // Only allowed if there is no newline to end at
+        assert!(chunk.ends_with('\n') || !chunk.contains('\n'), "{chunk:?}");
+    }
+}
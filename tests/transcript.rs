@@ -0,0 +1,49 @@
+//! Test for `TranscriptSplitter` behavior.
+use fake::{Fake, Faker};
+use itertools::Itertools;
+use text_splitter::{ChunkConfig, TranscriptSplitter};
+
+const TRANSCRIPT: &str = "[00:00:00] SPEAKER A: Good morning, and welcome to the quarterly review.
+[00:00:06] SPEAKER A: Let's start with the numbers.
+[00:00:09] SPEAKER B: Thanks. Revenue is up twelve percent compared to last quarter.
+Most of that comes from the new subscription plans.
+[00:00:21] SPEAKER C: What about churn?
+[00:00:23] SPEAKER B: Churn is flat, which is better than we expected.
+";
+
+#[test]
+fn chunks_roundtrip() {
+    for _ in 0..10 {
+        let max_characters = Faker.fake::<usize>() % 200 + 1;
+        let splitter = TranscriptSplitter::new(ChunkConfig::new(max_characters).with_trim(false));
+        let chunks = splitter.chunk_indices(TRANSCRIPT).collect::<Vec<_>>();
+
+        assert_eq!(chunks.iter().map(|(_, c)| *c).join(""), TRANSCRIPT);
+        assert!(chunks
+            .iter()
+            .map(|(i, _)| i)
+            .tuple_windows()
+            .all(|(a, b)| a < b));
+    }
+}
+
+#[test]
+fn timestamps_are_in_order() {
+    for _ in 0..10 {
+        let max_characters = Faker.fake::<usize>() % 200 + 1;
+        let splitter = TranscriptSplitter::new(max_characters);
+
+        for (_, chunk, metadata) in splitter.chunks_with_metadata(TRANSCRIPT) {
+            if let (Some(start), Some(end)) = (metadata.start, metadata.end) {
+                assert!(start < end, "{chunk:?}");
+            }
+            assert!(
+                metadata
+                    .speakers
+                    .iter()
+                    .all(|speaker| speaker.starts_with("SPEAKER ")),
+                "{chunk:?}"
+            );
+        }
+    }
+}
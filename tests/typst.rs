@@ -0,0 +1,71 @@
+//! Test for `TypstSplitter` behavior.
+#![cfg(feature = "typst")]
+
+use fake::{Fake, Faker};
+use itertools::Itertools;
+use more_asserts::assert_le;
+use text_splitter::{ChunkConfig, TypstSplitter};
+
+const TEXT: &str = r#"= Introduction
+Typst is a *markup-based* typesetting system, with _emphasis_ and `raw text`.
+
+== Lists
+- First item
+- Second item with a #link("https://typst.app")[link]
+  - Nested item
+
++ Numbered
++ Items
+
+/ Term: Its description
+
+== Code and math
+```rust
+fn main() {
+    println!("Hello, world!");
+}
+```
+
+The area of a circle is $A = pi r^2$, and:
+$ sum_(k=0)^n k = (n(n+1)) / 2 $
+
+#figure(
+  [A content block with some text inside of it.],
+  caption: [A caption],
+)
+"#;
+
+#[test]
+fn random_chunk_size() {
+    for _ in 0..10 {
+        let max_characters = Faker.fake::<usize>() % 200 + 1;
+        let splitter = TypstSplitter::new(ChunkConfig::new(max_characters).with_trim(false));
+        let chunks = splitter.chunks(TEXT).collect::<Vec<_>>();
+
+        assert_eq!(chunks.join(""), TEXT);
+        for chunk in chunks {
+            assert_le!(chunk.chars().count(), max_characters);
+        }
+    }
+}
+
+#[test]
+fn random_chunk_indices_increase() {
+    for _ in 0..10 {
+        let max_characters = Faker.fake::<usize>() % 200 + 1;
+        let splitter = TypstSplitter::new(ChunkConfig::new(max_characters).with_trim(false));
+        let indices = splitter.chunk_indices(TEXT).map(|(i, _)| i);
+
+        assert!(indices.tuple_windows().all(|(a, b)| a < b));
+    }
+}
+
+#[test]
+fn splits_by_headings() {
+    let splitter = TypstSplitter::new(120);
+    let chunks = splitter.chunks(TEXT).collect::<Vec<_>>();
+
+    assert!(chunks[0].starts_with("= Introduction"));
+    assert!(chunks.iter().any(|c| c.starts_with("== Lists")));
+    assert!(chunks.iter().any(|c| c.starts_with("== Code and math")));
+}
@@ -0,0 +1,75 @@
+//! Test for `VObjectSplitter` behavior.
+use fake::{Fake, Faker};
+use itertools::Itertools;
+use text_splitter::{ChunkConfig, VObjectSplitter};
+
+const VCF: &str = "BEGIN:VCARD
+VERSION:4.0
+UID:urn:uuid:1
+FN:Ada Lovelace
+N:Lovelace;Ada;;;
+EMAIL;TYPE=work:ada@example.com
+NOTE:Wrote the first published algorithm intended to be carried out by a
+  machine.
+END:VCARD
+BEGIN:VCARD
+VERSION:4.0
+UID:urn:uuid:2
+FN:Alan Turing
+EMAIL;TYPE=home:alan@example.com
+END:VCARD
+";
+
+#[test]
+fn chunks_roundtrip() {
+    for _ in 0..10 {
+        let max_characters = Faker.fake::<usize>() % 200 + 1;
+        let splitter = VObjectSplitter::new(ChunkConfig::new(max_characters).with_trim(false));
+        let chunks = splitter.chunk_indices(VCF).collect::<Vec<_>>();
+
+        assert_eq!(chunks.iter().map(|(_, c)| *c).join(""), VCF);
+        assert!(chunks
+            .iter()
+            .map(|(i, _)| i)
+            .tuple_windows()
+            .all(|(a, b)| a < b));
+    }
+}
+
+#[test]
+fn properties_are_never_split() {
+    for _ in 0..10 {
+        let max_characters = Faker.fake::<usize>() % 200 + 1;
+        let splitter = VObjectSplitter::new(max_characters);
+
+        for chunk in splitter.chunks(VCF) {
+            assert!(
+                !chunk.starts_with(' ')
+                    && chunk
+                        .lines()
+                        .all(|line| line.contains(':') || line.starts_with("  ")),
+                "property was split: {chunk:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn record_metadata() {
+    let splitter = VObjectSplitter::new(200);
+    let chunks = splitter.chunks_with_metadata(VCF).collect::<Vec<_>>();
+
+    assert_eq!(
+        chunks
+            .iter()
+            .map(|(_, _, records)| records
+                .iter()
+                .map(|r| (r.uid, r.summary))
+                .collect::<Vec<_>>())
+            .collect::<Vec<_>>(),
+        vec![
+            vec![(Some("urn:uuid:1"), Some("Ada Lovelace"))],
+            vec![(Some("urn:uuid:2"), Some("Alan Turing"))],
+        ]
+    );
+}
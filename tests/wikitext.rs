@@ -0,0 +1,82 @@
+//! Test for `WikitextSplitter` behavior.
+use fake::{Fake, Faker};
+use itertools::Itertools;
+use text_splitter::{ChunkConfig, WikitextSplitter};
+
+const ARTICLE: &str = "{{Infobox programming language
+| name = Rust
+| paradigm = {{hlist|Concurrent|Functional|Generic}}
+}}
+'''Rust''' is a [[General-purpose programming language|general-purpose]] language.<ref>{{cite web|url=https://www.rust-lang.org|title=Rust}}</ref>
+It emphasizes performance.
+
+== History ==
+Rust began as a personal project.
+
+=== Early years ===
+* First item
+** Nested item with {{lang|fr|texte}}
+# Numbered
+
+----
+{| class=\"wikitable\"
+! Version !! Date
+|-
+| 1.0 || 2015
+|-
+| 1.80 || 2024
+|}
+";
+
+#[test]
+fn chunks_roundtrip() {
+    for _ in 0..10 {
+        let max_characters = Faker.fake::<usize>() % 200 + 1;
+        let splitter = WikitextSplitter::new(ChunkConfig::new(max_characters).with_trim(false));
+        let chunks = splitter.chunk_indices(ARTICLE).collect::<Vec<_>>();
+
+        assert_eq!(chunks.iter().map(|(_, c)| *c).join(""), ARTICLE);
+        assert!(chunks
+            .iter()
+            .map(|(i, _)| i)
+            .tuple_windows()
+            .all(|(a, b)| a < b));
+    }
+}
+
+#[test]
+fn templates_are_never_split() {
+    for _ in 0..10 {
+        let max_characters = Faker.fake::<usize>() % 200 + 1;
+        let splitter = WikitextSplitter::new(max_characters);
+
+        for chunk in splitter.chunks(ARTICLE) {
+            assert_eq!(
+                chunk.matches("{{").count(),
+                chunk.matches("}}").count(),
+                "template was split: {chunk:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn sections_are_kept_with_their_heading() {
+    let splitter = WikitextSplitter::new(100);
+    let chunks = splitter.chunks(ARTICLE).collect::<Vec<_>>();
+
+    assert!(chunks
+        .iter()
+        .any(|chunk| chunk.starts_with("== History ==\nRust began")));
+    assert!(chunks
+        .iter()
+        .any(|chunk| chunk.starts_with("=== Early years ===\n* First item")));
+}
+
+#[test]
+fn table_rows_are_split() {
+    let splitter = WikitextSplitter::new(40);
+    let chunks = splitter.chunks(ARTICLE).collect::<Vec<_>>();
+
+    assert!(chunks.contains(&"|-\n| 1.0 || 2015\n|-\n| 1.80 || 2024\n|}"));
+}